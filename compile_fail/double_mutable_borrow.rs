@@ -0,0 +1,14 @@
+// 4. REFERENCES, BORROWING, AND OWNERSHIP - References and Borrowing
+// expected: E0499
+//
+// Only one mutable reference to a value may exist at a time.
+
+fn main() {
+    let mut token_supply = String::from("1000000");
+
+    let supply_ref = &mut token_supply;
+    let supply_ref2 = &mut token_supply;
+
+    supply_ref.push_str(" SPL");
+    supply_ref2.push_str(" SPL");
+}
@@ -0,0 +1,13 @@
+// 1. BASIC CONCEPTS - Variables and Mutability
+// expected: E0384
+//
+// Variables are immutable by default, so a wallet address cannot be
+// reassigned unless it is declared with `let mut`.
+
+fn main() {
+    let wallet_address = "8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa";
+    println!("Wallet address: {}", wallet_address);
+
+    wallet_address = "different_address";
+    println!("Wallet address: {}", wallet_address);
+}
@@ -0,0 +1,17 @@
+// 4. REFERENCES, BORROWING, AND OWNERSHIP - Lifetime Parameters
+// expected: E0106
+//
+// Without a lifetime parameter the compiler cannot tell whether the
+// returned reference borrows from s1 or s2.
+
+fn longest(s1: &str, s2: &str) -> &str {
+    if s1.len() > s2.len() {
+        s1
+    } else {
+        s2
+    }
+}
+
+fn main() {
+    println!("Longest: {}", longest("solana", "blockchain"));
+}
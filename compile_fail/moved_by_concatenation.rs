@@ -0,0 +1,15 @@
+// 5. STRINGS AND SLICES - Owned Strings
+// expected: E0382
+//
+// The + operator takes ownership of its left-hand String, so greeting
+// cannot be used after the concatenation.
+
+fn main() {
+    let greeting = String::from("Hello ");
+    let audience = String::from("Solana Developers");
+
+    let message = greeting + &audience;
+
+    println!("Message: {}", message);
+    println!("Greeting: {}", greeting);
+}
@@ -0,0 +1,16 @@
+// 4. REFERENCES, BORROWING, AND OWNERSHIP - Ownership Basics
+// expected: E0382
+//
+// Passing a String by value moves it into the function, so the caller
+// cannot use it afterwards.
+
+fn takes_ownership(program_id: String) {
+    println!("Now I own: {}", program_id);
+}
+
+fn main() {
+    let program_id = String::from("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+    takes_ownership(program_id);
+
+    println!("Program ID: {}", program_id);
+}
@@ -0,0 +1,15 @@
+// 4. REFERENCES, BORROWING, AND OWNERSHIP - References and Borrowing
+// expected: E0502
+//
+// An immutable borrow cannot be taken while a mutable borrow is still
+// in use.
+
+fn main() {
+    let mut token_supply = String::from("1000000");
+
+    let supply_ref = &mut token_supply;
+    let supply_ref3 = &token_supply;
+
+    supply_ref.push_str(" SPL");
+    println!("Supply: {}", supply_ref3);
+}
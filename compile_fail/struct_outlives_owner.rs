@@ -0,0 +1,24 @@
+// 4. REFERENCES, BORROWING, AND OWNERSHIP - Lifetime Parameters
+// expected: E0597
+//
+// A struct holding a reference cannot outlive the data it points to:
+// owner_name is dropped at the end of the inner block while account is
+// still used afterwards.
+
+struct TokenAccount<'a> {
+    amount: u64,
+    owner: &'a str,
+}
+
+fn main() {
+    let account;
+    {
+        let owner_name = String::from("Alice");
+        account = TokenAccount {
+            amount: 100,
+            owner: &owner_name,
+        };
+    }
+
+    println!("Account owner: {} ({})", account.owner, account.amount);
+}
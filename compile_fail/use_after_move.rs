@@ -0,0 +1,13 @@
+// 4. REFERENCES, BORROWING, AND OWNERSHIP - Ownership Basics
+// expected: E0382
+//
+// Assigning a String moves it: token_name no longer owns the data once
+// token_name2 takes it over.
+
+fn main() {
+    let token_name = String::from("Solana");
+    let token_name2 = token_name;
+
+    println!("Token: {}", token_name);
+    println!("Token: {}", token_name2);
+}
@@ -0,0 +1,18 @@
+// 9. ITERATORS - Iterator Adaptors
+// expected: E0277
+//
+// `accounts.iter()` yields `&&str`, not `&str`, so the zipped pairs cannot
+// be collected into a Vec<(&str, &i32)> without dereferencing first.
+
+fn main() {
+    let token_balances = vec![100, 200, 300, 400, 500];
+    let accounts = vec!["Alice", "Bob", "Charlie", "Dave"];
+
+    let account_info: Vec<(&str, &i32)> = accounts
+        .iter()
+        .zip(token_balances.iter())
+        .take(3)
+        .collect();
+
+    println!("Account info: {:?}", account_info);
+}
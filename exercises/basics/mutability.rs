@@ -0,0 +1,21 @@
+// Exercise: Variables and Mutability
+//
+// `add_rewards` should add every reward to the starting balance and return
+// the total, but it does not compile. Fix it without removing the loop.
+//
+// Then finish `whole_sol`, which converts lamports into whole SOL
+// (rounding down).
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+pub fn add_rewards(start: u64, rewards: &[u64]) -> u64 {
+    let balance = start;
+    for reward in rewards {
+        balance += reward;
+    }
+    balance
+}
+
+pub fn whole_sol(lamports: u64) -> u64 {
+    todo!("convert {} lamports into whole SOL", lamports)
+}
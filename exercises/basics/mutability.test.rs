@@ -0,0 +1,16 @@
+#[test]
+fn adds_every_reward() {
+    assert_eq!(add_rewards(100, &[1, 2, 3]), 106);
+}
+
+#[test]
+fn keeps_balance_without_rewards() {
+    assert_eq!(add_rewards(5, &[]), 5);
+}
+
+#[test]
+fn converts_to_whole_sol() {
+    assert_eq!(whole_sol(2_500_000_000), 2);
+    assert_eq!(whole_sol(999_999_999), 0);
+    assert_eq!(whole_sol(LAMPORTS_PER_SOL), 1);
+}
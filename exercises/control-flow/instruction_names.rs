@@ -0,0 +1,15 @@
+// Exercise: Control Flow
+//
+// Finish both functions using `match` (guards are allowed).
+
+// 0 => "Initialize", 1 => "Transfer", 2 => "Mint", 3 => "Burn",
+// anything else => "Unknown"
+pub fn instruction_name(tag: u8) -> &'static str {
+    todo!("name instruction {}", tag)
+}
+
+// "whale" for at least 1_000 SOL, "holder" for at least 1 SOL,
+// "dust" for anything smaller (1 SOL = 1_000_000_000 lamports)
+pub fn classify(lamports: u64) -> &'static str {
+    todo!("classify {} lamports", lamports)
+}
@@ -0,0 +1,22 @@
+#[test]
+fn names_known_instructions() {
+    assert_eq!(instruction_name(0), "Initialize");
+    assert_eq!(instruction_name(1), "Transfer");
+    assert_eq!(instruction_name(2), "Mint");
+    assert_eq!(instruction_name(3), "Burn");
+}
+
+#[test]
+fn unknown_instructions() {
+    assert_eq!(instruction_name(4), "Unknown");
+    assert_eq!(instruction_name(255), "Unknown");
+}
+
+#[test]
+fn classifies_balances() {
+    assert_eq!(classify(1_000_000_000_000), "whale");
+    assert_eq!(classify(999_999_999_999), "holder");
+    assert_eq!(classify(1_000_000_000), "holder");
+    assert_eq!(classify(999_999_999), "dust");
+    assert_eq!(classify(0), "dust");
+}
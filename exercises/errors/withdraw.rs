@@ -0,0 +1,37 @@
+// Exercise: Error Handling
+//
+// `withdraw` takes `amount` from `owner`'s balance and returns what is
+// left. Return an error instead of panicking:
+//
+// - TokenError::InvalidAmount when amount is zero
+// - TokenError::AccountNotFound when the owner has no balance entry
+// - TokenError::InsufficientBalance when the balance is too small
+//
+// `withdraw_all` withdraws every (owner, amount) pair in order and stops at
+// the first error. Use the `?` operator.
+
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq)]
+pub enum TokenError {
+    InsufficientBalance,
+    AccountNotFound,
+    InvalidAmount,
+}
+
+pub fn withdraw(
+    balances: &mut HashMap<String, u64>,
+    owner: &str,
+    amount: u64,
+) -> Result<u64, TokenError> {
+    let balance = balances.get_mut(owner).unwrap();
+    *balance -= amount;
+    Ok(*balance)
+}
+
+pub fn withdraw_all(
+    balances: &mut HashMap<String, u64>,
+    requests: &[(&str, u64)],
+) -> Result<(), TokenError> {
+    todo!("process {} withdrawals", requests.len())
+}
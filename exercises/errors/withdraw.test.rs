@@ -0,0 +1,31 @@
+fn balances() -> HashMap<String, u64> {
+    let mut balances = HashMap::new();
+    balances.insert("alice".to_string(), 100);
+    balances.insert("bob".to_string(), 5);
+    balances
+}
+
+#[test]
+fn withdraws_and_returns_remaining() {
+    let mut balances = balances();
+    assert_eq!(withdraw(&mut balances, "alice", 30), Ok(70));
+    assert_eq!(balances["alice"], 70);
+}
+
+#[test]
+fn reports_each_error() {
+    let mut balances = balances();
+    assert_eq!(withdraw(&mut balances, "alice", 0), Err(TokenError::InvalidAmount));
+    assert_eq!(withdraw(&mut balances, "carol", 1), Err(TokenError::AccountNotFound));
+    assert_eq!(withdraw(&mut balances, "bob", 6), Err(TokenError::InsufficientBalance));
+    assert_eq!(balances["bob"], 5);
+}
+
+#[test]
+fn stops_at_first_error() {
+    let mut balances = balances();
+    let result = withdraw_all(&mut balances, &[("alice", 10), ("bob", 50), ("alice", 10)]);
+    assert_eq!(result, Err(TokenError::InsufficientBalance));
+    assert_eq!(balances["alice"], 90);
+    assert_eq!(withdraw_all(&mut balances, &[("alice", 90), ("bob", 5)]), Ok(()));
+}
@@ -0,0 +1,15 @@
+// Exercise: HashMaps
+//
+// - `apply_updates` adds up the balance changes per account.
+// - `most_frequent` returns the token that appears most often; ties go to
+//   the alphabetically smallest token. None for an empty list.
+
+use std::collections::HashMap;
+
+pub fn apply_updates(ops: &[(&str, i64)]) -> HashMap<String, i64> {
+    todo!("apply {} updates", ops.len())
+}
+
+pub fn most_frequent<'a>(tokens: &[&'a str]) -> Option<&'a str> {
+    todo!("count {} tokens", tokens.len())
+}
@@ -0,0 +1,21 @@
+#[test]
+fn sums_changes_per_account() {
+    let updates = apply_updates(&[
+        ("Account1", 100),
+        ("Account2", -50),
+        ("Account1", 25),
+        ("Account3", 75),
+    ]);
+    assert_eq!(updates.len(), 3);
+    assert_eq!(updates["Account1"], 125);
+    assert_eq!(updates["Account2"], -50);
+    assert_eq!(updates["Account3"], 75);
+}
+
+#[test]
+fn finds_most_frequent_token() {
+    let tokens = ["SOL", "USDC", "SOL", "BTC", "ETH", "USDC", "SOL", "USDT"];
+    assert_eq!(most_frequent(&tokens), Some("SOL"));
+    assert_eq!(most_frequent(&["USDC", "BTC", "USDC", "BTC"]), Some("BTC"));
+    assert_eq!(most_frequent(&[]), None);
+}
@@ -0,0 +1,27 @@
+// Exercise: Iterators
+//
+// Use iterator adaptors (filter, map, sum, max_by_key, ...) rather than
+// manual loops.
+//
+// - `total_for` adds up every account belonging to `owner`.
+// - `richest_owner` returns the owner of the single largest account, or
+//   None when there are no accounts.
+// - `owners_above` lists owners of accounts holding more than `min`, in
+//   the order they appear (duplicates allowed).
+
+pub struct TokenAcct {
+    pub owner: String,
+    pub amount: u64,
+}
+
+pub fn total_for(accounts: &[TokenAcct], owner: &str) -> u64 {
+    todo!("sum {} accounts for {}", accounts.len(), owner)
+}
+
+pub fn richest_owner(accounts: &[TokenAcct]) -> Option<&str> {
+    todo!("find the richest of {} accounts", accounts.len())
+}
+
+pub fn owners_above(accounts: &[TokenAcct], min: u64) -> Vec<&str> {
+    todo!("owners with more than {}", min)
+}
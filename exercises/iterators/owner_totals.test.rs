@@ -0,0 +1,28 @@
+fn accounts() -> Vec<TokenAcct> {
+    [("Alice", 100), ("Bob", 200), ("Alice", 150), ("Charlie", 300)]
+        .iter()
+        .map(|(owner, amount)| TokenAcct {
+            owner: owner.to_string(),
+            amount: *amount,
+        })
+        .collect()
+}
+
+#[test]
+fn totals_per_owner() {
+    let accounts = accounts();
+    assert_eq!(total_for(&accounts, "Alice"), 250);
+    assert_eq!(total_for(&accounts, "Dave"), 0);
+}
+
+#[test]
+fn finds_richest_owner() {
+    assert_eq!(richest_owner(&accounts()), Some("Charlie"));
+    assert_eq!(richest_owner(&[]), None);
+}
+
+#[test]
+fn lists_owners_above_minimum() {
+    assert_eq!(owners_above(&accounts(), 120), vec!["Bob", "Alice", "Charlie"]);
+    assert!(owners_above(&accounts(), 300).is_empty());
+}
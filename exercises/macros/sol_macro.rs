@@ -0,0 +1,14 @@
+// Exercise: Macros
+//
+// Finish the `sol!` macro so that:
+//
+//   sol!(2)              == 2_000_000_000 lamports
+//   sol!(1, 500_000_000) == 1_500_000_000 lamports (whole SOL + lamports)
+//
+// Both forms must accept any expression, e.g. sol!(1 + 1).
+
+macro_rules! sol {
+    ($sol:expr) => {
+        0u64
+    };
+}
@@ -0,0 +1,16 @@
+#[test]
+fn converts_whole_sol() {
+    assert_eq!(sol!(2), 2_000_000_000u64);
+    assert_eq!(sol!(0), 0u64);
+}
+
+#[test]
+fn accepts_expressions() {
+    assert_eq!(sol!(1 + 1), 2_000_000_000u64);
+}
+
+#[test]
+fn adds_extra_lamports() {
+    assert_eq!(sol!(1, 500_000_000), 1_500_000_000u64);
+    assert_eq!(sol!(0, 5), 5u64);
+}
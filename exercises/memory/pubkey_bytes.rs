@@ -0,0 +1,17 @@
+// Exercise: Stack vs Heap
+//
+// A public key is a fixed-size `[u8; 32]` that lives on the stack, while
+// account data arrives as a heap-allocated `Vec<u8>` of any length.
+//
+// `to_pubkey` should copy the first 32 bytes of `data` into a stack array,
+// or return None if there are fewer than 32 bytes.
+//
+// `boxed_total` should sum the balances and return the total on the heap.
+
+pub fn to_pubkey(data: &[u8]) -> Option<[u8; 32]> {
+    todo!("read a pubkey from {} bytes", data.len())
+}
+
+pub fn boxed_total(balances: Vec<u64>) -> Box<u64> {
+    todo!("box the total of {} balances", balances.len())
+}
@@ -0,0 +1,20 @@
+#[test]
+fn reads_first_32_bytes() {
+    let data: Vec<u8> = (0..40).collect();
+    let key = to_pubkey(&data).expect("40 bytes is enough");
+    assert_eq!(key[0], 0);
+    assert_eq!(key[31], 31);
+}
+
+#[test]
+fn rejects_short_data() {
+    assert_eq!(to_pubkey(&[1; 31]), None);
+    assert_eq!(to_pubkey(&[]), None);
+}
+
+#[test]
+fn boxes_the_total() {
+    let total = boxed_total(vec![10, 20, 30]);
+    assert_eq!(*total, 60);
+    assert_eq!(*boxed_total(Vec::new()), 0);
+}
@@ -0,0 +1,22 @@
+// Exercise: References and Borrowing
+//
+// Neither function compiles. Read the compiler errors, then fix them:
+//
+// - `add_alias` pushes "<first account><suffix>" onto the list and returns
+//   the new length. Do not clone the whole vector.
+// - `longer_key` returns whichever key is longer (the first one on a tie).
+//   The compiler needs to know how long the returned reference lives.
+
+pub fn add_alias(accounts: &mut Vec<String>, suffix: &str) -> usize {
+    let first = &accounts[0];
+    accounts.push(format!("{}{}", first, suffix));
+    accounts.len()
+}
+
+pub fn longer_key(a: &str, b: &str) -> &str {
+    if a.len() >= b.len() {
+        a
+    } else {
+        b
+    }
+}
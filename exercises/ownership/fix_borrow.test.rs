@@ -0,0 +1,19 @@
+#[test]
+fn pushes_alias_of_first_account() {
+    let mut accounts = vec!["treasury".to_string(), "user".to_string()];
+    assert_eq!(add_alias(&mut accounts, "-backup"), 3);
+    assert_eq!(accounts[2], "treasury-backup");
+    assert_eq!(accounts[0], "treasury");
+}
+
+#[test]
+fn picks_longer_key() {
+    let a = String::from("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+    let result;
+    {
+        let b = String::from("short");
+        result = longer_key(&a, &b).to_string();
+    }
+    assert_eq!(result, a);
+    assert_eq!(longer_key("abc", "xyz"), "abc");
+}
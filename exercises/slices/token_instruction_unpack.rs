@@ -0,0 +1,39 @@
+// Exercise: Slices
+//
+// Complete `TokenInstruction::unpack`, which decodes instruction data.
+// The first byte is the instruction tag, followed by its payload:
+//
+//   0 => Initialize, then one byte of decimals
+//   1 => Transfer,   then a little-endian u64 amount
+//   2 => Mint,       then a little-endian u64 amount
+//   3 => Burn,       then a little-endian u64 amount
+//
+// An unknown tag or a payload that is too short is InvalidInstruction.
+// Extra trailing bytes are ignored.
+
+#[derive(Debug, PartialEq)]
+pub enum TokenInstruction {
+    Initialize { decimals: u8 },
+    Transfer { amount: u64 },
+    Mint { amount: u64 },
+    Burn { amount: u64 },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ProgramError {
+    InvalidInstruction,
+}
+
+impl TokenInstruction {
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidInstruction)?;
+
+        match tag {
+            0 => todo!("decode Initialize from {:?}", rest),
+            1 => todo!("decode Transfer"),
+            _ => Err(ProgramError::InvalidInstruction),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+fn with_amount(tag: u8, amount: u64) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+#[test]
+fn unpacks_initialize() {
+    assert_eq!(
+        TokenInstruction::unpack(&[0, 9]),
+        Ok(TokenInstruction::Initialize { decimals: 9 })
+    );
+}
+
+#[test]
+fn unpacks_amount_instructions() {
+    assert_eq!(
+        TokenInstruction::unpack(&with_amount(1, 100)),
+        Ok(TokenInstruction::Transfer { amount: 100 })
+    );
+    assert_eq!(
+        TokenInstruction::unpack(&with_amount(2, u64::MAX)),
+        Ok(TokenInstruction::Mint { amount: u64::MAX })
+    );
+    assert_eq!(
+        TokenInstruction::unpack(&with_amount(3, 1 << 40)),
+        Ok(TokenInstruction::Burn { amount: 1 << 40 })
+    );
+}
+
+#[test]
+fn ignores_trailing_bytes() {
+    let mut data = with_amount(1, 7);
+    data.push(0xff);
+    assert_eq!(
+        TokenInstruction::unpack(&data),
+        Ok(TokenInstruction::Transfer { amount: 7 })
+    );
+}
+
+#[test]
+fn rejects_bad_input() {
+    let invalid = Err(ProgramError::InvalidInstruction);
+    assert_eq!(TokenInstruction::unpack(&[]), invalid);
+    assert_eq!(TokenInstruction::unpack(&[0]), invalid);
+    assert_eq!(TokenInstruction::unpack(&[1, 1, 2, 3]), invalid);
+    assert_eq!(TokenInstruction::unpack(&[4, 0, 0, 0, 0, 0, 0, 0, 0]), invalid);
+}
@@ -0,0 +1,14 @@
+// Exercise: Strings and Slices
+//
+// - `short_address` shortens a base58 address for display: the first four
+//   characters, "...", then the last four. Addresses of eight characters
+//   or fewer are returned unchanged.
+// - `pair_name` builds an upper-case market name such as "SOL/USDC".
+
+pub fn short_address(address: &str) -> String {
+    todo!("shorten {}", address)
+}
+
+pub fn pair_name(base: &str, quote: &str) -> String {
+    todo!("join {} and {}", base, quote)
+}
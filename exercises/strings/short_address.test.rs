@@ -0,0 +1,19 @@
+#[test]
+fn shortens_long_addresses() {
+    assert_eq!(
+        short_address("8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa"),
+        "8ZgM...2mKa"
+    );
+}
+
+#[test]
+fn keeps_short_addresses() {
+    assert_eq!(short_address("12345678"), "12345678");
+    assert_eq!(short_address(""), "");
+}
+
+#[test]
+fn builds_pair_names() {
+    assert_eq!(pair_name("sol", "usdc"), "SOL/USDC");
+    assert_eq!(pair_name("Bonk", "SOL"), "BONK/SOL");
+}
@@ -0,0 +1,40 @@
+// Exercise: Traits
+//
+// Implement `Transaction::verify` for `TokenTransfer`. A transfer is
+// verified when:
+//
+// - the signature is "0x" followed by at least 8 hex digits (0-9, a-f)
+// - the sender and the recipient are different accounts
+//
+// `is_valid` already has a default implementation; do not override it.
+
+pub trait Transaction {
+    fn signature(&self) -> String;
+    fn amount(&self) -> u64;
+    fn verify(&self) -> bool;
+
+    fn is_valid(&self) -> bool {
+        self.verify() && self.amount() > 0
+    }
+}
+
+pub struct TokenTransfer {
+    pub from: String,
+    pub to: String,
+    pub amount_lamports: u64,
+    pub sig: String,
+}
+
+impl Transaction for TokenTransfer {
+    fn signature(&self) -> String {
+        self.sig.clone()
+    }
+
+    fn amount(&self) -> u64 {
+        self.amount_lamports
+    }
+
+    fn verify(&self) -> bool {
+        todo!("check the signature and the accounts")
+    }
+}
@@ -0,0 +1,35 @@
+fn transfer(from: &str, to: &str, amount: u64, sig: &str) -> TokenTransfer {
+    TokenTransfer {
+        from: from.to_string(),
+        to: to.to_string(),
+        amount_lamports: amount,
+        sig: sig.to_string(),
+    }
+}
+
+#[test]
+fn accepts_well_formed_transfer() {
+    let tx = transfer("Alice", "Bob", 5, "0x123abc45");
+    assert!(tx.verify());
+    assert!(tx.is_valid());
+}
+
+#[test]
+fn rejects_bad_signatures() {
+    assert!(!transfer("Alice", "Bob", 5, "0x123").verify());
+    assert!(!transfer("Alice", "Bob", 5, "123abc4567").verify());
+    assert!(!transfer("Alice", "Bob", 5, "0x123abcXY").verify());
+    assert!(!transfer("Alice", "Bob", 5, "").verify());
+}
+
+#[test]
+fn rejects_self_transfer() {
+    assert!(!transfer("Alice", "Alice", 5, "0xdeadbeef").verify());
+}
+
+#[test]
+fn zero_amount_verifies_but_is_invalid() {
+    let tx = transfer("Alice", "Bob", 0, "0xdeadbeef");
+    assert!(tx.verify());
+    assert!(!tx.is_valid());
+}
@@ -0,0 +1,14 @@
+// Exercise: Arrays and Vectors
+//
+// - `top_balances` returns the `n` largest balances, largest first,
+//   without modifying the input.
+// - `remove_empty` removes every zero balance in place, keeping the order
+//   of the others.
+
+pub fn top_balances(balances: &[u64], n: usize) -> Vec<u64> {
+    todo!("pick the top {} of {:?}", n, balances)
+}
+
+pub fn remove_empty(balances: &mut Vec<u64>) {
+    todo!("remove zeros from {:?}", balances)
+}
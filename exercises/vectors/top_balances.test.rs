@@ -0,0 +1,19 @@
+#[test]
+fn returns_largest_first() {
+    let balances = [5, 100, 20, 100, 1];
+    assert_eq!(top_balances(&balances, 3), vec![100, 100, 20]);
+    assert_eq!(balances, [5, 100, 20, 100, 1]);
+}
+
+#[test]
+fn handles_short_input() {
+    assert_eq!(top_balances(&[3, 1], 5), vec![3, 1]);
+    assert_eq!(top_balances(&[], 2), Vec::<u64>::new());
+}
+
+#[test]
+fn removes_zero_balances() {
+    let mut balances = vec![0, 10, 0, 0, 5, 0];
+    remove_empty(&mut balances);
+    assert_eq!(balances, vec![10, 5]);
+}
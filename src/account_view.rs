@@ -0,0 +1,738 @@
+// ========================================================================
+// ZERO-COPY ACCOUNT VIEWS
+// ========================================================================
+//
+// `TokenAccount::unpack` copies every field out of an account's bytes
+// into a new struct, even when the caller only wants the balance. A view
+// instead borrows the bytes and decodes a field when it is asked for:
+//
+//     let view = TokenAccountView::new(&account.data)?;
+//     let owner: &Pubkey = view.owner();      // points into account.data
+//     let amount = view.amount();             // reads 8 bytes
+//
+// The same lifetime that ties `TokenAccount<'a>` in the cheat sheet to
+// the string it borrows ties a view to the account data: the data cannot
+// change or go away while the view is alive. `TokenAccountViewMut` and
+// `MintViewMut` write fields back in place over `&mut [u8]`.
+//
+// Underneath, `from_bytes` reinterprets a byte slice as a `#[repr(C)]`
+// struct describing the layout, which is only sound when:
+//
+//     the slice is exactly as long as the struct
+//     its address is a multiple of the struct's alignment
+//     every bit pattern is a valid value (the `Pod` trait)
+//
+// The first two are checked and fail with a ViewError. The layouts below
+// are made of byte arrays and `Pubkey`s, so their alignment is 1 and any
+// address will do; multi-byte numbers are stored little-endian, as on
+// chain, and decoded on access. Reading a `u64` directly needs an address
+// that is a multiple of 8, which an offset into account data often is
+// not. Fields with a limited set of values (an account state, an option
+// tag) are validated once, when the view is made.
+//
+// `simply_rust bench views` compares views with full deserialization.
+
+use std::fmt;
+use std::hint::black_box;
+use std::mem::{align_of, size_of};
+use std::time::{Duration, Instant};
+
+use crate::address_lookup_table_program::{
+    LookupTable, LOOKUP_TABLE_MAX_ADDRESSES, LOOKUP_TABLE_META_SIZE,
+};
+use crate::alloc_counter::{self, AllocStats};
+use crate::pubkey::Pubkey;
+use crate::token_program::{AccountState, Mint, TokenAccount, ACCOUNT_LEN, MINT_LEN};
+
+// ----------------------------------------
+// Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViewError {
+    WrongLength { expected: usize, actual: usize },
+    Misaligned { align: usize, address: usize },
+    // A field holds a value its type does not allow
+    InvalidField(&'static str),
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ViewError::WrongLength { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+            ViewError::Misaligned { align, address } => write!(
+                f,
+                "address {:#x} is not a multiple of the required alignment {}",
+                address, align
+            ),
+            ViewError::InvalidField(field) => write!(f, "invalid value in `{}`", field),
+        }
+    }
+}
+
+// ----------------------------------------
+// Casting Bytes
+// ----------------------------------------
+
+// Types that any bytes of the right length and alignment are a valid
+// value of: no padding, no references, no enums, no bools.
+//
+// SAFETY: implement only for such types, and only with #[repr(C)] or
+// #[repr(transparent)] so the layout is fixed
+#[allow(clippy::missing_safety_doc)]
+pub unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for u64 {}
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+unsafe impl Pod for Pubkey {}
+
+fn check_alignment<T>(data: &[u8]) -> Result<(), ViewError> {
+    let address = data.as_ptr() as usize;
+    if !address.is_multiple_of(align_of::<T>()) {
+        return Err(ViewError::Misaligned {
+            align: align_of::<T>(),
+            address,
+        });
+    }
+    Ok(())
+}
+
+fn check_length<T>(data: &[u8]) -> Result<(), ViewError> {
+    if data.len() != size_of::<T>() {
+        return Err(ViewError::WrongLength {
+            expected: size_of::<T>(),
+            actual: data.len(),
+        });
+    }
+    Ok(())
+}
+
+// `data` as a T, without copying
+pub fn from_bytes<T: Pod>(data: &[u8]) -> Result<&T, ViewError> {
+    check_length::<T>(data)?;
+    check_alignment::<T>(data)?;
+    // SAFETY: the length and alignment were just checked, and T is Pod,
+    // so every byte pattern is a valid T. The borrow keeps `data` alive
+    // and unchanged for as long as the result is used.
+    Ok(unsafe { &*data.as_ptr().cast::<T>() })
+}
+
+pub fn from_bytes_mut<T: Pod>(data: &mut [u8]) -> Result<&mut T, ViewError> {
+    check_length::<T>(data)?;
+    check_alignment::<T>(data)?;
+    // SAFETY: as in from_bytes; the exclusive borrow of `data` makes the
+    // result the only way to reach those bytes, and any T written through
+    // it is valid as bytes again
+    Ok(unsafe { &mut *data.as_mut_ptr().cast::<T>() })
+}
+
+// `data` as consecutive Ts; its length must be a multiple of T's size
+pub fn slice_from_bytes<T: Pod>(data: &[u8]) -> Result<&[T], ViewError> {
+    assert!(size_of::<T>() > 0, "zero-sized types have no bytes to view");
+    if !data.len().is_multiple_of(size_of::<T>()) {
+        return Err(ViewError::WrongLength {
+            expected: data.len() / size_of::<T>() * size_of::<T>(),
+            actual: data.len(),
+        });
+    }
+    check_alignment::<T>(data)?;
+    // SAFETY: as in from_bytes, for len / size_of::<T>() values in a row
+    Ok(unsafe {
+        std::slice::from_raw_parts(data.as_ptr().cast::<T>(), data.len() / size_of::<T>())
+    })
+}
+
+// ----------------------------------------
+// Layouts
+// ----------------------------------------
+
+// A COption<Pubkey>: a u32 tag, then a key that is zeros when absent
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct COptionPubkey {
+    tag: [u8; 4],
+    value: Pubkey,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct COptionU64 {
+    tag: [u8; 4],
+    value: [u8; 8],
+}
+
+// SPL Token's account layout, field for field (see token_program.rs)
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct TokenAccountLayout {
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: [u8; 8],
+    delegate: COptionPubkey,
+    state: u8,
+    is_native: COptionU64,
+    delegated_amount: [u8; 8],
+    close_authority: COptionPubkey,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct MintLayout {
+    mint_authority: COptionPubkey,
+    supply: [u8; 8],
+    decimals: u8,
+    is_initialized: u8,
+    freeze_authority: COptionPubkey,
+}
+
+// The 56-byte header of a lookup table (see
+// address_lookup_table_program.rs); the addresses follow it
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct LookupTableMetaLayout {
+    type_tag: [u8; 4],
+    deactivation_slot: [u8; 8],
+    last_extended_slot: [u8; 8],
+    last_extended_slot_start_index: u8,
+    has_authority: u8,
+    authority: Pubkey,
+    padding: [u8; 2],
+}
+
+unsafe impl Pod for COptionPubkey {}
+unsafe impl Pod for COptionU64 {}
+unsafe impl Pod for TokenAccountLayout {}
+unsafe impl Pod for MintLayout {}
+unsafe impl Pod for LookupTableMetaLayout {}
+
+// Checked when compiling: a layout that drifted from the real byte
+// count would not build
+const _: () = assert!(size_of::<TokenAccountLayout>() == ACCOUNT_LEN);
+const _: () = assert!(size_of::<MintLayout>() == MINT_LEN);
+const _: () = assert!(size_of::<LookupTableMetaLayout>() == LOOKUP_TABLE_META_SIZE);
+const _: () = assert!(align_of::<TokenAccountLayout>() == 1);
+const _: () = assert!(align_of::<MintLayout>() == 1);
+const _: () = assert!(align_of::<LookupTableMetaLayout>() == 1);
+
+impl COptionPubkey {
+    fn check(&self, field: &'static str) -> Result<(), ViewError> {
+        match u32::from_le_bytes(self.tag) {
+            0 | 1 => Ok(()),
+            _ => Err(ViewError::InvalidField(field)),
+        }
+    }
+
+    fn get(&self) -> Option<&Pubkey> {
+        (u32::from_le_bytes(self.tag) == 1).then_some(&self.value)
+    }
+
+    fn set(&mut self, value: Option<&Pubkey>) {
+        self.tag = (value.is_some() as u32).to_le_bytes();
+        self.value = value.copied().unwrap_or_default();
+    }
+}
+
+fn account_state(byte: u8) -> Result<AccountState, ViewError> {
+    match byte {
+        0 => Ok(AccountState::Uninitialized),
+        1 => Ok(AccountState::Initialized),
+        2 => Ok(AccountState::Frozen),
+        _ => Err(ViewError::InvalidField("state")),
+    }
+}
+
+// ----------------------------------------
+// Token Accounts
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+pub struct TokenAccountView<'a> {
+    layout: &'a TokenAccountLayout,
+}
+
+impl<'a> TokenAccountView<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, ViewError> {
+        let layout: &TokenAccountLayout = from_bytes(data)?;
+        account_state(layout.state)?;
+        layout.delegate.check("delegate")?;
+        layout.close_authority.check("close_authority")?;
+        if u32::from_le_bytes(layout.is_native.tag) > 1 {
+            return Err(ViewError::InvalidField("is_native"));
+        }
+        Ok(TokenAccountView { layout })
+    }
+
+    pub fn mint(&self) -> &'a Pubkey {
+        &self.layout.mint
+    }
+
+    pub fn owner(&self) -> &'a Pubkey {
+        &self.layout.owner
+    }
+
+    pub fn amount(&self) -> u64 {
+        u64::from_le_bytes(self.layout.amount)
+    }
+
+    pub fn delegate(&self) -> Option<&'a Pubkey> {
+        self.layout.delegate.get()
+    }
+
+    pub fn state(&self) -> AccountState {
+        account_state(self.layout.state).expect("checked by new")
+    }
+
+    pub fn is_native(&self) -> Option<u64> {
+        let is_native = &self.layout.is_native;
+        (u32::from_le_bytes(is_native.tag) == 1).then(|| u64::from_le_bytes(is_native.value))
+    }
+
+    pub fn delegated_amount(&self) -> u64 {
+        u64::from_le_bytes(self.layout.delegated_amount)
+    }
+
+    pub fn close_authority(&self) -> Option<&'a Pubkey> {
+        self.layout.close_authority.get()
+    }
+
+    // Copy everything out, as TokenAccount::unpack would have
+    pub fn to_account(self) -> TokenAccount {
+        TokenAccount {
+            mint: *self.mint(),
+            owner: *self.owner(),
+            amount: self.amount(),
+            delegate: self.delegate().copied(),
+            state: self.state(),
+            is_native: self.is_native(),
+            delegated_amount: self.delegated_amount(),
+            close_authority: self.close_authority().copied(),
+        }
+    }
+}
+
+pub struct TokenAccountViewMut<'a> {
+    layout: &'a mut TokenAccountLayout,
+}
+
+impl<'a> TokenAccountViewMut<'a> {
+    pub fn new(data: &'a mut [u8]) -> Result<Self, ViewError> {
+        TokenAccountView::new(data)?;
+        Ok(TokenAccountViewMut {
+            layout: from_bytes_mut(data)?,
+        })
+    }
+
+    // Reading while holding the mutable view
+    pub fn view(&self) -> TokenAccountView<'_> {
+        TokenAccountView {
+            layout: self.layout,
+        }
+    }
+
+    pub fn set_owner(&mut self, owner: &Pubkey) {
+        self.layout.owner = *owner;
+    }
+
+    pub fn set_amount(&mut self, amount: u64) {
+        self.layout.amount = amount.to_le_bytes();
+    }
+
+    // The delegate and how much it may still move
+    pub fn set_delegate(&mut self, delegate: Option<&Pubkey>, amount: u64) {
+        self.layout.delegate.set(delegate);
+        self.layout.delegated_amount = amount.to_le_bytes();
+    }
+
+    pub fn set_state(&mut self, state: AccountState) {
+        self.layout.state = state as u8;
+    }
+
+    pub fn set_close_authority(&mut self, authority: Option<&Pubkey>) {
+        self.layout.close_authority.set(authority);
+    }
+}
+
+// ----------------------------------------
+// Mints
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+pub struct MintView<'a> {
+    layout: &'a MintLayout,
+}
+
+impl<'a> MintView<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, ViewError> {
+        let layout: &MintLayout = from_bytes(data)?;
+        layout.mint_authority.check("mint_authority")?;
+        layout.freeze_authority.check("freeze_authority")?;
+        if layout.is_initialized > 1 {
+            return Err(ViewError::InvalidField("is_initialized"));
+        }
+        Ok(MintView { layout })
+    }
+
+    pub fn mint_authority(&self) -> Option<&'a Pubkey> {
+        self.layout.mint_authority.get()
+    }
+
+    pub fn supply(&self) -> u64 {
+        u64::from_le_bytes(self.layout.supply)
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.layout.decimals
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.layout.is_initialized == 1
+    }
+
+    pub fn freeze_authority(&self) -> Option<&'a Pubkey> {
+        self.layout.freeze_authority.get()
+    }
+
+    pub fn to_mint(self) -> Mint {
+        Mint {
+            mint_authority: self.mint_authority().copied(),
+            supply: self.supply(),
+            decimals: self.decimals(),
+            is_initialized: self.is_initialized(),
+            freeze_authority: self.freeze_authority().copied(),
+        }
+    }
+}
+
+pub struct MintViewMut<'a> {
+    layout: &'a mut MintLayout,
+}
+
+impl<'a> MintViewMut<'a> {
+    pub fn new(data: &'a mut [u8]) -> Result<Self, ViewError> {
+        MintView::new(data)?;
+        Ok(MintViewMut {
+            layout: from_bytes_mut(data)?,
+        })
+    }
+
+    pub fn view(&self) -> MintView<'_> {
+        MintView {
+            layout: self.layout,
+        }
+    }
+
+    pub fn set_supply(&mut self, supply: u64) {
+        self.layout.supply = supply.to_le_bytes();
+    }
+
+    pub fn set_mint_authority(&mut self, authority: Option<&Pubkey>) {
+        self.layout.mint_authority.set(authority);
+    }
+
+    pub fn set_freeze_authority(&mut self, authority: Option<&Pubkey>) {
+        self.layout.freeze_authority.set(authority);
+    }
+}
+
+// ----------------------------------------
+// Lookup Tables
+// ----------------------------------------
+
+// Up to 256 addresses, 8 KiB, borrowed as a slice of Pubkeys
+#[derive(Clone, Copy)]
+pub struct LookupTableView<'a> {
+    meta: &'a LookupTableMetaLayout,
+    addresses: &'a [Pubkey],
+}
+
+impl<'a> LookupTableView<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, ViewError> {
+        if data.len() < LOOKUP_TABLE_META_SIZE {
+            return Err(ViewError::WrongLength {
+                expected: LOOKUP_TABLE_META_SIZE,
+                actual: data.len(),
+            });
+        }
+        let (meta, addresses) = data.split_at(LOOKUP_TABLE_META_SIZE);
+        let meta: &LookupTableMetaLayout = from_bytes(meta)?;
+        if u32::from_le_bytes(meta.type_tag) != 1 {
+            return Err(ViewError::InvalidField("type"));
+        }
+        if meta.has_authority > 1 {
+            return Err(ViewError::InvalidField("authority"));
+        }
+        let addresses: &[Pubkey] = slice_from_bytes(addresses)?;
+        if addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+            return Err(ViewError::InvalidField("addresses"));
+        }
+        Ok(LookupTableView { meta, addresses })
+    }
+
+    pub fn deactivation_slot(&self) -> u64 {
+        u64::from_le_bytes(self.meta.deactivation_slot)
+    }
+
+    pub fn last_extended_slot(&self) -> u64 {
+        u64::from_le_bytes(self.meta.last_extended_slot)
+    }
+
+    pub fn authority(&self) -> Option<&'a Pubkey> {
+        (self.meta.has_authority == 1).then_some(&self.meta.authority)
+    }
+
+    pub fn addresses(&self) -> &'a [Pubkey] {
+        self.addresses
+    }
+
+    pub fn get(&self, index: u8) -> Option<&'a Pubkey> {
+        self.addresses.get(index as usize)
+    }
+
+    pub fn to_table(self) -> LookupTable {
+        LookupTable {
+            deactivation_slot: self.deactivation_slot(),
+            last_extended_slot: self.last_extended_slot(),
+            last_extended_slot_start_index: self.meta.last_extended_slot_start_index,
+            authority: self.authority().copied(),
+            addresses: self.addresses.to_vec(),
+        }
+    }
+}
+
+// ----------------------------------------
+// Benchmark: `simply_rust bench views`
+// ----------------------------------------
+
+const BENCH_ACCOUNTS: usize = 10_000;
+const BENCH_ROUNDS: usize = 20;
+const BENCH_LOOKUPS: usize = 10_000;
+
+pub fn bench() -> Result<(), String> {
+    if cfg!(debug_assertions) {
+        println!("(a debug build: run with `cargo run --release` for real numbers)");
+    }
+    if !alloc_counter::enabled() {
+        println!("(add `--features count-allocations` to count heap allocations too)");
+    }
+    round_trip()?;
+    println!("Every view field agrees with pack and unpack");
+
+    // Token accounts back to back in one buffer, a third of them alice's
+    let alice = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let mut data = Vec::with_capacity(BENCH_ACCOUNTS * ACCOUNT_LEN);
+    for i in 0..BENCH_ACCOUNTS {
+        let account = TokenAccount {
+            mint,
+            owner: if i % 3 == 0 {
+                alice
+            } else {
+                Pubkey::new_unique()
+            },
+            amount: i as u64 * 1_000,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        };
+        data.extend_from_slice(&account.pack());
+    }
+
+    println!(
+        "\nAlice's balance across {} token accounts, {} rounds",
+        BENCH_ACCOUNTS, BENCH_ROUNDS
+    );
+    let (unpacked, baseline, allocs) = time(|| {
+        data.chunks_exact(ACCOUNT_LEN)
+            .map(|bytes| TokenAccount::unpack(black_box(bytes)).unwrap())
+            .filter(|account| account.owner == alice)
+            .map(|account| account.amount)
+            .sum::<u64>()
+    });
+    print_timing("TokenAccount::unpack", baseline, baseline, allocs);
+    let (viewed, elapsed, allocs) = time(|| {
+        data.chunks_exact(ACCOUNT_LEN)
+            .map(|bytes| TokenAccountView::new(black_box(bytes)).unwrap())
+            .filter(|view| *view.owner() == alice)
+            .map(|view| view.amount())
+            .sum::<u64>()
+    });
+    print_timing("TokenAccountView", elapsed, baseline, allocs);
+    if unpacked != viewed {
+        return Err(format!(
+            "the view summed {} where unpacking summed {}",
+            viewed, unpacked
+        ));
+    }
+
+    println!("\nCrediting every account 1 token, {} rounds", BENCH_ROUNDS);
+    let mut copy = data.clone();
+    let ((), baseline, allocs) = time(|| {
+        for bytes in copy.chunks_exact_mut(ACCOUNT_LEN) {
+            let mut account = TokenAccount::unpack(black_box(bytes)).unwrap();
+            account.amount += 1;
+            bytes.copy_from_slice(&account.pack());
+        }
+    });
+    print_timing("unpack + pack", baseline, baseline, allocs);
+    let ((), elapsed, allocs) = time(|| {
+        for bytes in data.chunks_exact_mut(ACCOUNT_LEN) {
+            let mut view = TokenAccountViewMut::new(black_box(bytes)).unwrap();
+            let amount = view.view().amount();
+            view.set_amount(amount + 1);
+        }
+    });
+    print_timing("TokenAccountViewMut", elapsed, baseline, allocs);
+    if copy != data {
+        return Err("writing through the view left different bytes".to_string());
+    }
+
+    // A full table, looked up by index the way v0 messages use it
+    let mut table = LookupTable::new(alice);
+    table.addresses = (0..LOOKUP_TABLE_MAX_ADDRESSES)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    let table_data = table.pack();
+    println!(
+        "\n{} lookups in a {}-byte table of {} addresses, {} rounds",
+        BENCH_LOOKUPS,
+        table_data.len(),
+        LOOKUP_TABLE_MAX_ADDRESSES,
+        BENCH_ROUNDS
+    );
+    let index = |i: usize| (i * 7 % LOOKUP_TABLE_MAX_ADDRESSES) as u8;
+    let (unpacked, baseline, allocs) = time(|| {
+        (0..BENCH_LOOKUPS)
+            .map(|i| {
+                LookupTable::unpack(black_box(&table_data))
+                    .unwrap()
+                    .addresses[index(i) as usize]
+            })
+            .fold(0u8, |acc, key| acc ^ key.0[0])
+    });
+    print_timing("LookupTable::unpack", baseline, baseline, allocs);
+    let (viewed, elapsed, allocs) = time(|| {
+        (0..BENCH_LOOKUPS)
+            .map(|i| {
+                *LookupTableView::new(black_box(&table_data))
+                    .unwrap()
+                    .get(index(i))
+                    .unwrap()
+            })
+            .fold(0u8, |acc, key| acc ^ key.0[0])
+    });
+    print_timing("LookupTableView", elapsed, baseline, allocs);
+    let view = LookupTableView::new(&table_data).map_err(|err| err.to_string())?;
+    if unpacked != viewed || view.addresses() != table.addresses || view.to_table() != table {
+        return Err("the lookup table view disagrees with unpacking".to_string());
+    }
+    Ok(())
+}
+
+// Not timed: every getter and setter against pack and unpack, with
+// each optional field both set and cleared
+fn round_trip() -> Result<(), String> {
+    let [owner, mint, delegate, authority] = [(); 4].map(|()| Pubkey::new_unique());
+    let account = TokenAccount {
+        mint,
+        owner,
+        amount: 5_000,
+        delegate: Some(delegate),
+        state: AccountState::Frozen,
+        is_native: Some(2_039_280),
+        delegated_amount: 1_000,
+        close_authority: Some(authority),
+    };
+    let mut data = account.pack();
+    let view = TokenAccountView::new(&data).map_err(|err| err.to_string())?;
+    if view.to_account() != account || *view.mint() != mint {
+        return Err("TokenAccountView disagrees with TokenAccount::unpack".to_string());
+    }
+    let cleared = TokenAccount {
+        owner: authority,
+        amount: 0,
+        delegate: None,
+        state: AccountState::Initialized,
+        delegated_amount: 0,
+        close_authority: None,
+        ..account
+    };
+    let mut view = TokenAccountViewMut::new(&mut data).map_err(|err| err.to_string())?;
+    view.set_owner(&authority);
+    view.set_amount(0);
+    view.set_delegate(None, 0);
+    view.set_state(AccountState::Initialized);
+    view.set_close_authority(None);
+    if data != cleared.pack() {
+        return Err("TokenAccountViewMut disagrees with TokenAccount::pack".to_string());
+    }
+    let mut view = TokenAccountViewMut::new(&mut data).map_err(|err| err.to_string())?;
+    view.set_owner(&owner);
+    view.set_amount(5_000);
+    view.set_delegate(Some(&delegate), 1_000);
+    view.set_state(AccountState::Frozen);
+    view.set_close_authority(Some(&authority));
+    if data != account.pack() {
+        return Err("TokenAccountViewMut disagrees with TokenAccount::pack".to_string());
+    }
+
+    let mint = Mint {
+        mint_authority: Some(owner),
+        supply: 1_000_000,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: Some(authority),
+    };
+    let mut data = mint.pack();
+    if MintView::new(&data).map(|view| view.to_mint()) != Ok(mint) {
+        return Err("MintView disagrees with Mint::unpack".to_string());
+    }
+    let cleared = Mint {
+        mint_authority: None,
+        supply: 0,
+        freeze_authority: None,
+        ..mint
+    };
+    let mut view = MintViewMut::new(&mut data).map_err(|err| err.to_string())?;
+    view.set_mint_authority(None);
+    view.set_supply(0);
+    view.set_freeze_authority(None);
+    if view.view().to_mint() != cleared || data != cleared.pack() {
+        return Err("MintViewMut disagrees with Mint::pack".to_string());
+    }
+    let mut view = MintViewMut::new(&mut data).map_err(|err| err.to_string())?;
+    view.set_mint_authority(Some(&owner));
+    view.set_supply(1_000_000);
+    view.set_freeze_authority(Some(&authority));
+    if data != mint.pack() {
+        return Err("MintViewMut disagrees with Mint::pack".to_string());
+    }
+    Ok(())
+}
+
+// Run `f` once to warm up and count its allocations, then time
+// BENCH_ROUNDS more runs. Returns the last result.
+fn time<T>(mut f: impl FnMut() -> T) -> (T, Duration, AllocStats) {
+    let (mut result, allocs) = alloc_counter::measure(&mut f);
+    let start = Instant::now();
+    for _ in 0..BENCH_ROUNDS {
+        result = black_box(f());
+    }
+    (result, start.elapsed(), allocs)
+}
+
+fn print_timing(label: &str, elapsed: Duration, baseline: Duration, allocs: AllocStats) {
+    print!(
+        "  {:<22} {:>8.2} ms  {:>6.2}x",
+        label,
+        elapsed.as_secs_f64() * 1000.0,
+        baseline.as_secs_f64() / elapsed.as_secs_f64()
+    );
+    if alloc_counter::enabled() {
+        print!("  {} per round", allocs);
+    }
+    println!();
+}
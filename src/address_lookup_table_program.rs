@@ -0,0 +1,744 @@
+// ========================================================================
+// THE ADDRESS LOOKUP TABLE PROGRAM
+// ========================================================================
+//
+// An address lookup table is an on-chain list of up to 256 addresses that
+// v0 messages (see transaction.rs) refer to by position, one byte instead
+// of 32. Its life cycle:
+//
+//     create      at find_program_address([authority, recent slot]), so
+//                 an authority gets a fresh address per slot
+//     extend      the authority appends addresses; ones added in the
+//                 current slot only become usable in the next
+//     freeze      (optional) drop the authority, making the table
+//                 permanent
+//     deactivate  start a cooldown; lookups still work for 512 slots,
+//                 the length of the recent slot history, so a
+//                 transaction cannot load different accounts depending
+//                 on when it lands
+//     close       once fully deactivated, return the rent to a recipient
+//
+// The account is a 56-byte header followed by the addresses:
+//
+//     u32   type (1 = lookup table)
+//     u64   deactivation slot (u64::MAX while active)
+//     u64   last extended slot
+//     u8    how many addresses there were before that slot's extends
+//     u8    1 if there is an authority, then its 32-byte key
+//     u16   padding
+
+use crate::bank::{print_outcome, Bank, TransactionError};
+use crate::ledger::AccountStore;
+use crate::program::{InstructionError, InvokeContext, Program};
+use crate::pubkey::{Pubkey, SYSTEM_PROGRAM_ID};
+use crate::system_program;
+use crate::transaction::{
+    AccountMeta, AddressLookupTableAccount, DemoSigner, Instruction, LoadedAddresses,
+    LoadedMessage, MessageAddressTableLookup, MessageV0, Signer, Transaction, VersionedMessage,
+    VersionedTransaction, PACKET_DATA_SIZE,
+};
+
+pub const ID: Pubkey = Pubkey::from_str_const("AddressLookupTab1e1111111111111111111111111");
+
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+pub const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+// Slots the runtime remembers, which sets both how recent a creation
+// slot must be and how long deactivation takes
+pub const MAX_ENTRIES: u64 = 512;
+
+const LOOKUP_TABLE_TYPE: u32 = 1;
+
+pub fn derive_lookup_table_address(authority: &Pubkey, recent_slot: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[authority.as_ref(), &recent_slot.to_le_bytes()], &ID)
+}
+
+// ----------------------------------------
+// Table State
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupTableStatus {
+    Activated,
+    // Still usable, and not yet closable
+    Deactivating { remaining_slots: u64 },
+    Deactivated,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTable {
+    pub deactivation_slot: u64,
+    pub last_extended_slot: u64,
+    pub last_extended_slot_start_index: u8,
+    // None once frozen
+    pub authority: Option<Pubkey>,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl LookupTable {
+    pub fn new(authority: Pubkey) -> Self {
+        LookupTable {
+            deactivation_slot: u64::MAX,
+            last_extended_slot: 0,
+            last_extended_slot_start_index: 0,
+            authority: Some(authority),
+            addresses: Vec::new(),
+        }
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(LOOKUP_TABLE_META_SIZE + 32 * self.addresses.len());
+        data.extend_from_slice(&LOOKUP_TABLE_TYPE.to_le_bytes());
+        data.extend_from_slice(&self.deactivation_slot.to_le_bytes());
+        data.extend_from_slice(&self.last_extended_slot.to_le_bytes());
+        data.push(self.last_extended_slot_start_index);
+        match &self.authority {
+            Some(authority) => {
+                data.push(1);
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.push(0),
+        }
+        data.resize(LOOKUP_TABLE_META_SIZE, 0);
+        for address in &self.addresses {
+            data.extend_from_slice(address.as_ref());
+        }
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        if data.len() < LOOKUP_TABLE_META_SIZE {
+            return Err(InstructionError::UninitializedAccount);
+        }
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        if data[..4] != LOOKUP_TABLE_TYPE.to_le_bytes() {
+            return Err(InstructionError::UninitializedAccount);
+        }
+        let authority = match data[21] {
+            0 => None,
+            1 => Some(Pubkey::new(data[22..54].try_into().unwrap())),
+            _ => return Err(InstructionError::InvalidAccountData),
+        };
+        let addresses = &data[LOOKUP_TABLE_META_SIZE..];
+        if !addresses.len().is_multiple_of(32) || addresses.len() / 32 > LOOKUP_TABLE_MAX_ADDRESSES
+        {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        Ok(LookupTable {
+            deactivation_slot: u64_at(4),
+            last_extended_slot: u64_at(12),
+            last_extended_slot_start_index: data[20],
+            authority,
+            addresses: addresses
+                .chunks_exact(32)
+                .map(|chunk| Pubkey::new(chunk.try_into().unwrap()))
+                .collect(),
+        })
+    }
+
+    pub fn status(&self, current_slot: u64) -> LookupTableStatus {
+        if self.deactivation_slot == u64::MAX {
+            return LookupTableStatus::Activated;
+        }
+        let age = current_slot.saturating_sub(self.deactivation_slot);
+        if age <= MAX_ENTRIES {
+            LookupTableStatus::Deactivating {
+                remaining_slots: MAX_ENTRIES + 1 - age,
+            }
+        } else {
+            LookupTableStatus::Deactivated
+        }
+    }
+
+    // The addresses lookups may use at `current_slot`: everything but
+    // what was appended during it
+    pub fn active_addresses(&self, current_slot: u64) -> &[Pubkey] {
+        if current_slot > self.last_extended_slot {
+            &self.addresses
+        } else {
+            &self.addresses[..self.last_extended_slot_start_index as usize]
+        }
+    }
+}
+
+// ----------------------------------------
+// Resolving Lookups
+// ----------------------------------------
+
+// Expand a message's lookups into addresses using the tables in
+// `accounts` as they are at `current_slot`. Errors are the ones a
+// validator reports for the whole transaction.
+pub fn load_addresses(
+    accounts: &AccountStore,
+    lookups: &[MessageAddressTableLookup],
+    current_slot: u64,
+) -> Result<LoadedAddresses, TransactionError> {
+    let mut loaded = LoadedAddresses::default();
+    for lookup in lookups {
+        let account = accounts
+            .get(&lookup.account_key)
+            .ok_or(TransactionError::AddressLookupTableNotFound)?;
+        if account.owner != ID {
+            return Err(TransactionError::InvalidAddressLookupTableOwner);
+        }
+        let table = LookupTable::unpack(&account.data)
+            .map_err(|_| TransactionError::InvalidAddressLookupTableData)?;
+        if table.status(current_slot) == LookupTableStatus::Deactivated {
+            return Err(TransactionError::AddressLookupTableNotFound);
+        }
+
+        let active = table.active_addresses(current_slot);
+        let resolve = |indexes: &[u8]| {
+            indexes
+                .iter()
+                .map(|&i| active.get(i as usize).copied())
+                .collect::<Option<Vec<Pubkey>>>()
+                .ok_or(TransactionError::InvalidAddressLookupTableIndex)
+        };
+        loaded.writable.extend(resolve(&lookup.writable_indexes)?);
+        loaded.readonly.extend(resolve(&lookup.readonly_indexes)?);
+    }
+    Ok(loaded)
+}
+
+// ----------------------------------------
+// Instructions
+// ----------------------------------------
+
+// Bincode, like the real program: a u32 tag, then the fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupTableInstruction {
+    // Accounts: [writable] table, authority, [signer, writable] payer,
+    // system program
+    Create { recent_slot: u64, bump_seed: u8 },
+    // Accounts: [writable] table, [signer] authority
+    Freeze,
+    // Accounts: [writable] table, [signer] authority, and if the table
+    // needs more rent: [signer, writable] payer, system program
+    Extend { new_addresses: Vec<Pubkey> },
+    // Accounts: [writable] table, [signer] authority
+    Deactivate,
+    // Accounts: [writable] table, [signer] authority, [writable] recipient
+    Close,
+}
+
+impl LookupTableInstruction {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            LookupTableInstruction::Create {
+                recent_slot,
+                bump_seed,
+            } => {
+                data.extend_from_slice(&0u32.to_le_bytes());
+                data.extend_from_slice(&recent_slot.to_le_bytes());
+                data.push(*bump_seed);
+            }
+            LookupTableInstruction::Freeze => data.extend_from_slice(&1u32.to_le_bytes()),
+            LookupTableInstruction::Extend { new_addresses } => {
+                data.extend_from_slice(&2u32.to_le_bytes());
+                data.extend_from_slice(&(new_addresses.len() as u64).to_le_bytes());
+                for address in new_addresses {
+                    data.extend_from_slice(address.as_ref());
+                }
+            }
+            LookupTableInstruction::Deactivate => data.extend_from_slice(&3u32.to_le_bytes()),
+            LookupTableInstruction::Close => data.extend_from_slice(&4u32.to_le_bytes()),
+        }
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let (tag, rest) = data
+            .split_first_chunk::<4>()
+            .ok_or(InstructionError::InvalidInstructionData)?;
+        let instruction = match (u32::from_le_bytes(*tag), rest) {
+            (0, [slot @ .., bump_seed]) if slot.len() == 8 => LookupTableInstruction::Create {
+                recent_slot: u64::from_le_bytes(slot.try_into().unwrap()),
+                bump_seed: *bump_seed,
+            },
+            (1, []) => LookupTableInstruction::Freeze,
+            (2, rest) => {
+                let (len, addresses) = rest
+                    .split_first_chunk::<8>()
+                    .ok_or(InstructionError::InvalidInstructionData)?;
+                if u64::from_le_bytes(*len) != addresses.len() as u64 / 32
+                    || !addresses.len().is_multiple_of(32)
+                {
+                    return Err(InstructionError::InvalidInstructionData);
+                }
+                LookupTableInstruction::Extend {
+                    new_addresses: addresses
+                        .chunks_exact(32)
+                        .map(|chunk| Pubkey::new(chunk.try_into().unwrap()))
+                        .collect(),
+                }
+            }
+            (3, []) => LookupTableInstruction::Deactivate,
+            (4, []) => LookupTableInstruction::Close,
+            _ => return Err(InstructionError::InvalidInstructionData),
+        };
+        Ok(instruction)
+    }
+}
+
+// ----------------------------------------
+// Instruction Constructors
+// ----------------------------------------
+
+// The instruction and the address of the table it creates. `recent_slot`
+// must be no older than MAX_ENTRIES slots when it runs.
+pub fn create_lookup_table(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+) -> (Instruction, Pubkey) {
+    let (table, bump_seed) = derive_lookup_table_address(authority, recent_slot);
+    let data = LookupTableInstruction::Create {
+        recent_slot,
+        bump_seed,
+    }
+    .pack();
+    let instruction = Instruction::new(
+        ID,
+        &data,
+        vec![
+            AccountMeta::new(table, false),
+            AccountMeta::new_readonly(*authority, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+    );
+    (instruction, table)
+}
+
+pub fn freeze_lookup_table(table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new(
+        ID,
+        &LookupTableInstruction::Freeze.pack(),
+        vec![
+            AccountMeta::new(*table, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+pub fn extend_lookup_table(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: Option<&Pubkey>,
+    new_addresses: Vec<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*table, false),
+        AccountMeta::new_readonly(*authority, true),
+    ];
+    if let Some(payer) = payer {
+        accounts.push(AccountMeta::new(*payer, true));
+        accounts.push(AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false));
+    }
+    let data = LookupTableInstruction::Extend { new_addresses }.pack();
+    Instruction::new(ID, &data, accounts)
+}
+
+pub fn deactivate_lookup_table(table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new(
+        ID,
+        &LookupTableInstruction::Deactivate.pack(),
+        vec![
+            AccountMeta::new(*table, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+pub fn close_lookup_table(table: &Pubkey, authority: &Pubkey, recipient: &Pubkey) -> Instruction {
+    Instruction::new(
+        ID,
+        &LookupTableInstruction::Close.pack(),
+        vec![
+            AccountMeta::new(*table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+        ],
+    )
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct AddressLookupTableProgram;
+
+impl Program for AddressLookupTableProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "address_lookup_table_program"
+    }
+
+    fn compute_units(&self) -> u64 {
+        750
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        match LookupTableInstruction::unpack(data)? {
+            LookupTableInstruction::Create {
+                recent_slot,
+                bump_seed,
+            } => create(ctx, recent_slot, bump_seed),
+            LookupTableInstruction::Freeze => freeze(ctx),
+            LookupTableInstruction::Extend { new_addresses } => extend(ctx, new_addresses),
+            LookupTableInstruction::Deactivate => deactivate(ctx),
+            LookupTableInstruction::Close => close(ctx),
+        }
+    }
+}
+
+fn create(
+    ctx: &mut InvokeContext,
+    recent_slot: u64,
+    bump_seed: u8,
+) -> Result<(), InstructionError> {
+    ctx.log("Instruction: CreateLookupTable");
+    let table = *ctx.key(0)?;
+    let authority = *ctx.key(1)?;
+    let payer = *ctx.signer(2)?;
+    if ctx.account(0)?.owner == ID {
+        ctx.log("Table account must not be allocated");
+        return Err(InstructionError::AccountAlreadyInitialized);
+    }
+
+    let slot = ctx.clock().slot;
+    if recent_slot > slot || slot - recent_slot >= MAX_ENTRIES {
+        ctx.log(&format!("{} is not a recent slot", recent_slot));
+        return Err(InstructionError::InvalidInstructionData);
+    }
+    let slot_seed = recent_slot.to_le_bytes();
+    let bump = [bump_seed];
+    let seeds: &[&[u8]] = &[authority.as_ref(), &slot_seed, &bump];
+    if Pubkey::create_program_address(seeds, &ID) != Ok(table) {
+        ctx.log("Table address must match derived address");
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    system_program::create_pda_account(ctx, 0, &payer, LOOKUP_TABLE_META_SIZE, &ID, seeds)?;
+    ctx.account_mut(0)?.data = LookupTable::new(authority).pack();
+    Ok(())
+}
+
+// The table at account 0, checked against the authority at account 1
+fn load_for_update(ctx: &mut InvokeContext) -> Result<LookupTable, InstructionError> {
+    let account = ctx.account(0)?;
+    if account.owner != ID {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+    let table = LookupTable::unpack(&account.data)?;
+    let authority = *ctx.signer(1)?;
+    match table.authority {
+        None => {
+            ctx.log("Lookup table is frozen");
+            Err(InstructionError::Immutable)
+        }
+        Some(expected) if expected != authority => Err(InstructionError::IncorrectAuthority),
+        Some(_) => Ok(table),
+    }
+}
+
+fn freeze(ctx: &mut InvokeContext) -> Result<(), InstructionError> {
+    ctx.log("Instruction: FreezeLookupTable");
+    let mut table = load_for_update(ctx)?;
+    if table.deactivation_slot != u64::MAX {
+        ctx.log("Deactivated tables cannot be frozen");
+        return Err(InstructionError::InvalidArgument);
+    }
+    if table.addresses.is_empty() {
+        ctx.log("Empty lookup tables cannot be frozen");
+        return Err(InstructionError::InvalidInstructionData);
+    }
+    table.authority = None;
+    ctx.account_mut(0)?.data = table.pack();
+    Ok(())
+}
+
+fn extend(ctx: &mut InvokeContext, new_addresses: Vec<Pubkey>) -> Result<(), InstructionError> {
+    ctx.log("Instruction: ExtendLookupTable");
+    let mut table = load_for_update(ctx)?;
+    if table.deactivation_slot != u64::MAX {
+        ctx.log("Deactivated tables cannot be extended");
+        return Err(InstructionError::InvalidArgument);
+    }
+    if new_addresses.is_empty() {
+        ctx.log("Must extend with at least one address");
+        return Err(InstructionError::InvalidInstructionData);
+    }
+    let len = table.addresses.len() + new_addresses.len();
+    if len > LOOKUP_TABLE_MAX_ADDRESSES {
+        ctx.log(&format!(
+            "Extended lookup table length {} would exceed max capacity of {}",
+            len, LOOKUP_TABLE_MAX_ADDRESSES
+        ));
+        return Err(InstructionError::InvalidInstructionData);
+    }
+
+    // The first extend in a slot marks where this slot's additions start
+    let slot = ctx.clock().slot;
+    if slot != table.last_extended_slot {
+        table.last_extended_slot = slot;
+        table.last_extended_slot_start_index = table.addresses.len() as u8;
+    }
+    table.addresses.extend(new_addresses);
+    let data = table.pack();
+    let required = system_program::rent_exempt_lamports(data.len());
+    ctx.account_mut(0)?.data = data;
+
+    let funded = ctx.account(0)?.lamports;
+    if funded < required {
+        let table_key = *ctx.key(0)?;
+        let payer = *ctx.signer(2)?;
+        ctx.invoke(&system_program::transfer(
+            &payer,
+            &table_key,
+            required - funded,
+        ))?;
+    }
+    Ok(())
+}
+
+fn deactivate(ctx: &mut InvokeContext) -> Result<(), InstructionError> {
+    ctx.log("Instruction: DeactivateLookupTable");
+    let mut table = load_for_update(ctx)?;
+    if table.deactivation_slot != u64::MAX {
+        ctx.log("Lookup table is already deactivated");
+        return Err(InstructionError::InvalidArgument);
+    }
+    table.deactivation_slot = ctx.clock().slot;
+    ctx.account_mut(0)?.data = table.pack();
+    Ok(())
+}
+
+fn close(ctx: &mut InvokeContext) -> Result<(), InstructionError> {
+    ctx.log("Instruction: CloseLookupTable");
+    let table = load_for_update(ctx)?;
+    if ctx.key(2)? == ctx.key(0)? {
+        ctx.log("Lookup table cannot be the recipient of reclaimed lamports");
+        return Err(InstructionError::InvalidArgument);
+    }
+    match table.status(ctx.clock().slot) {
+        LookupTableStatus::Activated => {
+            ctx.log("Lookup table is not deactivated");
+            return Err(InstructionError::InvalidArgument);
+        }
+        LookupTableStatus::Deactivating { remaining_slots } => {
+            ctx.log(&format!(
+                "Table cannot be closed until it's fully deactivated in {} blocks",
+                remaining_slots
+            ));
+            return Err(InstructionError::InvalidArgument);
+        }
+        LookupTableStatus::Deactivated => {}
+    }
+
+    let lamports = ctx.account(0)?.lamports;
+    ctx.transfer_lamports(0, 2, lamports)?;
+    ctx.account_mut(0)?.data.clear();
+    Ok(())
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo alt`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    let alice = DemoSigner::new("alice");
+    bank.airdrop(&alice.pubkey(), 10_000_000_000)
+        .map_err(|err| err.to_string())?;
+    let recipients: Vec<Pubkey> = (0..30)
+        .map(|i| DemoSigner::new(&format!("recipient {}", i)).pubkey())
+        .collect();
+
+    let send = |bank: &mut Bank, title: &str, instructions: &[Instruction]| {
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&alice.pubkey()),
+            &[&alice],
+            bank.last_blockhash(),
+        );
+        let outcome = bank.execute_transaction(&tx);
+        print_outcome(title, &outcome.status, &outcome.logs);
+    };
+
+    // Two batches of 15, since a transaction cannot carry all 30 keys
+    let (create, table) = create_lookup_table(&alice.pubkey(), &alice.pubkey(), bank.slot());
+    println!("Lookup table {}", table);
+    send(&mut bank, "Create the table", &[create]);
+    bank.advance_slots(1);
+    for (i, batch) in recipients.chunks(15).enumerate() {
+        send(
+            &mut bank,
+            &format!("Extend it with recipients {}..{}", i * 15, i * 15 + 14),
+            &[extend_lookup_table(
+                &table,
+                &alice.pubkey(),
+                Some(&alice.pubkey()),
+                batch.to_vec(),
+            )],
+        );
+    }
+
+    // 0.001 SOL to each of the 30 recipients
+    let payments: Vec<Instruction> = recipients
+        .iter()
+        .map(|recipient| system_program::transfer(&alice.pubkey(), recipient, 1_000_000))
+        .collect();
+    let table_account = |bank: &Bank| -> Result<AddressLookupTableAccount, String> {
+        let account = bank.get_account(&table).ok_or("lookup table missing")?;
+        let state = LookupTable::unpack(&account.data).map_err(|err| err.to_string())?;
+        Ok(AddressLookupTableAccount {
+            key: table,
+            addresses: state.addresses,
+        })
+    };
+    let send_v0 = |bank: &mut Bank, title: &str| -> Result<VersionedTransaction, String> {
+        let message = MessageV0::compile(
+            &alice.pubkey(),
+            &payments,
+            &[table_account(bank)?],
+            &bank.last_blockhash(),
+        );
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&alice])
+            .map_err(|err| err.to_string())?;
+        let outcome = bank.execute_versioned_transaction(&tx);
+        print_outcome(title, &outcome.status, &[]);
+        Ok(tx)
+    };
+
+    // Still the slot the second batch went in: those addresses are not
+    // usable yet
+    send_v0(&mut bank, "Pay all 30 through the table, same slot")?;
+    bank.advance_slots(1);
+    let v0 = send_v0(&mut bank, "Pay all 30 through the table, next slot")?;
+
+    let legacy = Transaction::new_signed_with_payer(
+        &payments,
+        Some(&alice.pubkey()),
+        &[&alice],
+        bank.last_blockhash(),
+    );
+    println!("\nThe same 30 payments:");
+    for (name, size) in [
+        ("legacy", legacy.serialize().len()),
+        ("v0", v0.serialize().len()),
+    ] {
+        let fits = if size <= PACKET_DATA_SIZE {
+            "fits"
+        } else {
+            "too large for"
+        };
+        println!(
+            "  {:<7} {:>5} bytes, {} a {}-byte packet",
+            name, size, fits, PACKET_DATA_SIZE
+        );
+    }
+
+    let loaded = bank
+        .resolve_message(&v0.message)
+        .map_err(|err| err.to_string())?;
+    print_accounts(&loaded);
+    println!(
+        "recipient 29 has {} lamports",
+        bank.get_balance(&recipients[29])
+    );
+
+    // Deactivation leaves the table usable for a while, then it can be
+    // closed and its rent returned
+    send(
+        &mut bank,
+        "Deactivate the table",
+        &[deactivate_lookup_table(&table, &alice.pubkey())],
+    );
+    bank.advance_slots(1);
+    send(
+        &mut bank,
+        "Close it right away",
+        &[close_lookup_table(&table, &alice.pubkey(), &alice.pubkey())],
+    );
+    send_v0(&mut bank, "Pay through the deactivating table")?;
+    bank.advance_slots(MAX_ENTRIES);
+    send_v0(&mut bank, "Pay through the table after the cooldown")?;
+    let rent = bank.get_balance(&table);
+    send(
+        &mut bank,
+        "Close it after the cooldown",
+        &[close_lookup_table(&table, &alice.pubkey(), &alice.pubkey())],
+    );
+    println!(
+        "  {} lamports of rent went back to alice; the table is {}",
+        rent,
+        if bank.get_account(&table).is_none() {
+            "gone"
+        } else {
+            "still there"
+        }
+    );
+
+    // A frozen table has no authority left, so it can never change or
+    // be closed
+    let (create, frozen) = create_lookup_table(&alice.pubkey(), &alice.pubkey(), bank.slot());
+    send(
+        &mut bank,
+        "Create a table with 3 recipients and freeze it",
+        &[
+            create,
+            extend_lookup_table(
+                &frozen,
+                &alice.pubkey(),
+                Some(&alice.pubkey()),
+                recipients[..3].to_vec(),
+            ),
+            freeze_lookup_table(&frozen, &alice.pubkey()),
+        ],
+    );
+    send(
+        &mut bank,
+        "Extend the frozen table",
+        &[extend_lookup_table(
+            &frozen,
+            &alice.pubkey(),
+            Some(&alice.pubkey()),
+            recipients[3..4].to_vec(),
+        )],
+    );
+    Ok(())
+}
+
+fn print_accounts(message: &LoadedMessage) {
+    let loaded = message.loaded_addresses();
+    println!(
+        "\nResolved: {} static keys, {} writable and {} readonly from the table",
+        message.message().account_keys.len(),
+        loaded.writable.len(),
+        loaded.readonly.len()
+    );
+    for (i, key) in message.account_keys().iter().enumerate().take(4) {
+        println!(
+            "  {:>2} {}{}{}",
+            i,
+            key,
+            if message.is_signer(i) { "  signer" } else { "" },
+            if message.is_writable(i) {
+                "  writable"
+            } else {
+                ""
+            }
+        );
+    }
+    println!("  ... {} more", message.account_keys().len() - 4);
+}
@@ -0,0 +1,158 @@
+// ========================================================================
+// COUNTING ALLOCATIONS
+// ========================================================================
+//
+// Whether a value lives on the stack or the heap can be measured: every
+// heap allocation goes through the global allocator. Building with
+//
+//     cargo run --features count-allocations
+//
+// installs `CountingAllocator`, which hands each request to the system
+// allocator and counts it for the calling thread. `measure` runs a closure
+// and returns what it allocated:
+//
+//     let (v, stats) = alloc_counter::measure(|| Vec::<u8>::with_capacity(5));
+//     // stats.allocations == 1, stats.bytes == 5
+//
+// Counts are per thread, so other threads allocating at the same time do
+// not show up, and scopes may nest. Without the feature nothing is
+// installed, `enabled()` is false and every count is zero.
+
+#[cfg(feature = "count-allocations")]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+pub fn enabled() -> bool {
+    cfg!(feature = "count-allocations")
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    pub allocations: u64,
+    pub deallocations: u64,
+    // Growing or shrinking a block in place or by moving it
+    pub reallocations: u64,
+    // Requested by allocations and by reallocations that grew a block
+    pub bytes: u64,
+}
+
+impl AllocStats {
+    const ZERO: AllocStats = AllocStats {
+        allocations: 0,
+        deallocations: 0,
+        reallocations: 0,
+        bytes: 0,
+    };
+
+    fn since(self, start: AllocStats) -> AllocStats {
+        AllocStats {
+            allocations: self.allocations - start.allocations,
+            deallocations: self.deallocations - start.deallocations,
+            reallocations: self.reallocations - start.reallocations,
+            bytes: self.bytes - start.bytes,
+        }
+    }
+}
+
+impl fmt::Display for AllocStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |n: u64| if n == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} allocation{}",
+            self.allocations,
+            plural(self.allocations)
+        )?;
+        if self.reallocations > 0 {
+            write!(
+                f,
+                " + {} reallocation{}",
+                self.reallocations,
+                plural(self.reallocations)
+            )?;
+        }
+        if self.bytes > 0 {
+            write!(f, ", {} bytes", self.bytes)?;
+        }
+        Ok(())
+    }
+}
+
+// A const-initialized Cell needs no lazy setup and no destructor, so the
+// allocator can use it without allocating itself
+thread_local! {
+    static COUNTS: Cell<AllocStats> = const { Cell::new(AllocStats::ZERO) };
+}
+
+#[cfg(feature = "count-allocations")]
+fn record(update: impl FnOnce(&mut AllocStats)) {
+    // Fails only while the thread is being torn down; those go uncounted
+    let _ = COUNTS.try_with(|counts| {
+        let mut stats = counts.get();
+        update(&mut stats);
+        counts.set(stats);
+    });
+}
+
+// What this thread has allocated so far
+pub fn current() -> AllocStats {
+    COUNTS.try_with(Cell::get).unwrap_or_default()
+}
+
+// Run `f` and count what it allocates on this thread, including anything
+// it frees again before returning
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, AllocStats) {
+    let start = current();
+    let value = f();
+    (value, current().since(start))
+}
+
+// Only compiled with the feature, like the `GLOBAL` that installs it
+#[cfg(feature = "count-allocations")]
+pub struct CountingAllocator;
+
+// SAFETY: every call is passed unchanged to the system allocator, which
+// upholds GlobalAlloc's contract; counting touches no allocated memory
+#[cfg(feature = "count-allocations")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(|stats| {
+            stats.allocations += 1;
+            stats.bytes += layout.size() as u64;
+        });
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(|stats| {
+            stats.allocations += 1;
+            stats.bytes += layout.size() as u64;
+        });
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record(|stats| stats.deallocations += 1);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(|stats| {
+            stats.reallocations += 1;
+            stats.bytes += new_size.saturating_sub(layout.size()) as u64;
+        });
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+// One line of a measured example: what ran and what it allocated
+pub fn print_allocations<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    let (value, stats) = measure(f);
+    println!("  {:<40} {}", label, stats);
+    value
+}
@@ -0,0 +1,1222 @@
+// ========================================================================
+// THE CONSTANT-PRODUCT AMM
+// ========================================================================
+//
+// slice_examples in main.rs decodes a "SOL/USDC" pair name; this is the
+// market behind such a name. A pool holds reserves of two tokens, A and
+// B, in token accounts (vaults) owned by the pool's program derived
+// authority, and prices every swap so that the product of the reserves,
+// k = A * B, never goes down:
+//
+//     in_after_fee = in * (10_000 - fee_bps) / 10_000
+//     out          = B * in_after_fee / (A + in_after_fee)
+//
+// The fee stays in the pool, so k grows a little with every swap, and
+// that growth is what liquidity providers earn. Their share of the pool
+// is a balance of LP tokens: a deposit adds both tokens in the current
+// ratio and mints LP tokens, a withdrawal burns them and pays out both.
+//
+// Every intermediate value is a u128 (a product of two u64 always fits),
+// and every division rounds in the pool's favour: amounts paid out are
+// rounded down, amounts paid in are rounded up. The processor checks the
+// result anyway and fails the instruction if a swap made k smaller or a
+// deposit or withdrawal made an LP token worth less.
+//
+// As in Uniswap v2, the first MINIMUM_LIQUIDITY LP tokens of a pool are
+// never minted to anyone. They count towards the supply in every
+// calculation, so the last real LP tokens can never take the reserves all
+// the way to zero, where the price would be undefined.
+//
+// Wrapped SOL is an ordinary 9-decimal mint here.
+//
+//     Pool (164 bytes)
+//     is_initialized  u8
+//     bump_seed       u8       of the authority, find_program_address([pool])
+//     fee_bps         u16
+//     mint_a          Pubkey
+//     mint_b          Pubkey
+//     vault_a         Pubkey
+//     vault_b         Pubkey
+//     lp_mint         Pubkey
+
+use std::fmt;
+
+use crate::amount;
+use crate::associated_token_program::{self, AssociatedTokenProgram};
+use crate::bank::{print_outcome, Bank};
+use crate::hash::hashv;
+use crate::program::{InstructionError, InvokeContext, Program};
+use crate::pubkey::{Pubkey, PUBKEY_BYTES};
+use crate::system_program;
+use crate::token_program::{self, Mint, TokenAccount, TokenProgram};
+use crate::transaction::{AccountMeta, DemoSigner, Instruction, Signer, Transaction};
+
+pub const ID: Pubkey = Pubkey::from_str_const("ConstantProduct1111111111111111111111111111");
+
+pub const POOL_LEN: usize = 4 + 5 * PUBKEY_BYTES;
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+// ----------------------------------------
+// Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmmError {
+    InvalidFee(u16),
+    IdenticalMints,
+    // Vaults must be empty, undelegated accounts owned by the authority
+    InvalidVault,
+    // The LP mint must be new and only mintable by the authority
+    InvalidLpMint,
+    // An account is not the one the pool's state names
+    PoolMismatch,
+    // The token account holds neither of the pool's tokens
+    MintMismatch,
+    ZeroAmount,
+    // The first deposit has to be worth more than MINIMUM_LIQUIDITY
+    InsufficientLiquidity,
+    // The swap is too small to pay out anything
+    ZeroOutput,
+    SlippageExceeded,
+    InvariantViolated,
+}
+
+impl fmt::Display for AmmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AmmError::InvalidFee(bps) => {
+                write!(
+                    f,
+                    "a fee of {} basis points is not below {}",
+                    bps, MAX_FEE_BPS
+                )
+            }
+            AmmError::IdenticalMints => write!(f, "both sides of the pool are the same mint"),
+            AmmError::InvalidVault => {
+                write!(
+                    f,
+                    "vault must be an empty token account of the pool authority"
+                )
+            }
+            AmmError::InvalidLpMint => {
+                write!(
+                    f,
+                    "LP mint must be empty and mintable only by the pool authority"
+                )
+            }
+            AmmError::PoolMismatch => write!(f, "account does not belong to this pool"),
+            AmmError::MintMismatch => write!(f, "token account is for neither of the pool's mints"),
+            AmmError::ZeroAmount => write!(f, "amount must be above zero"),
+            AmmError::InsufficientLiquidity => write!(
+                f,
+                "initial deposit must be worth more than {} LP tokens",
+                MINIMUM_LIQUIDITY
+            ),
+            AmmError::ZeroOutput => write!(f, "swap too small to pay anything out"),
+            AmmError::SlippageExceeded => write!(f, "price moved past the slippage limit"),
+            AmmError::InvariantViolated => write!(f, "pool invariant violated"),
+        }
+    }
+}
+
+// ----------------------------------------
+// Account State
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pool {
+    pub is_initialized: bool,
+    pub bump_seed: u8,
+    pub fee_bps: u16,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+impl Pool {
+    pub fn pack(&self) -> [u8; POOL_LEN] {
+        let mut data = [0u8; POOL_LEN];
+        data[0] = self.is_initialized as u8;
+        data[1] = self.bump_seed;
+        data[2..4].copy_from_slice(&self.fee_bps.to_le_bytes());
+        let keys = [
+            self.mint_a,
+            self.mint_b,
+            self.vault_a,
+            self.vault_b,
+            self.lp_mint,
+        ];
+        for (chunk, key) in data[4..].chunks_exact_mut(PUBKEY_BYTES).zip(keys) {
+            chunk.copy_from_slice(key.as_ref());
+        }
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        if data.len() != POOL_LEN {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        let key = |n: usize| {
+            let start = 4 + n * PUBKEY_BYTES;
+            Pubkey::new(data[start..start + PUBKEY_BYTES].try_into().unwrap())
+        };
+        Ok(Pool {
+            is_initialized: match data[0] {
+                0 => false,
+                1 => true,
+                _ => return Err(InstructionError::InvalidAccountData),
+            },
+            bump_seed: data[1],
+            fee_bps: u16::from_le_bytes([data[2], data[3]]),
+            mint_a: key(0),
+            mint_b: key(1),
+            vault_a: key(2),
+            vault_b: key(3),
+            lp_mint: key(4),
+        })
+    }
+
+    pub fn unpack_initialized(data: &[u8]) -> Result<Self, InstructionError> {
+        let pool = Pool::unpack(data)?;
+        if !pool.is_initialized {
+            return Err(InstructionError::UninitializedAccount);
+        }
+        Ok(pool)
+    }
+}
+
+// The address that owns a pool's vaults and mints its LP tokens
+pub fn pool_authority(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[pool.as_ref()], &ID)
+}
+
+// ----------------------------------------
+// Pool Math
+// ----------------------------------------
+
+const BPS: u128 = MAX_FEE_BPS as u128;
+
+// What a swap of `amount_in` pays out, rounded down. The fee is taken
+// from the input first, also rounded in the pool's favour.
+pub fn swap_output(amount_in: u64, reserve_in: u64, reserve_out: u64, fee_bps: u16) -> u64 {
+    let in_after_fee = amount_in as u128 * (BPS - fee_bps as u128) / BPS;
+    let denominator = reserve_in as u128 + in_after_fee;
+    if denominator == 0 {
+        return 0;
+    }
+    // Below reserve_out, since in_after_fee < denominator
+    (reserve_out as u128 * in_after_fee / denominator) as u64
+}
+
+// LP tokens for a pool's first deposit: the geometric mean of the two
+// amounts, less the MINIMUM_LIQUIDITY nobody gets
+pub fn initial_liquidity(amount_a: u64, amount_b: u64) -> Option<u64> {
+    let shares = (amount_a as u128 * amount_b as u128).isqrt() as u64;
+    shares
+        .checked_sub(MINIMUM_LIQUIDITY)
+        .filter(|&shares| shares > 0)
+}
+
+// Both tokens needed to mint `lp_amount` LP tokens, rounded up. `supply`
+// includes MINIMUM_LIQUIDITY.
+pub fn deposit_amounts(lp_amount: u64, reserves: (u64, u64), supply: u64) -> Option<(u64, u64)> {
+    let share = |reserve: u64| {
+        let numerator = lp_amount as u128 * reserve as u128;
+        u64::try_from(numerator.div_ceil(supply as u128)).ok()
+    };
+    Some((share(reserves.0)?, share(reserves.1)?))
+}
+
+// Both tokens paid out for burning `lp_amount` LP tokens, rounded down
+pub fn withdraw_amounts(lp_amount: u64, reserves: (u64, u64), supply: u64) -> (u64, u64) {
+    let share = |reserve: u64| (lp_amount as u128 * reserve as u128 / supply as u128) as u64;
+    (share(reserves.0), share(reserves.1))
+}
+
+// A snapshot of a pool's reserves and LP supply (with MINIMUM_LIQUIDITY)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolBalances {
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub supply: u64,
+}
+
+impl PoolBalances {
+    pub fn k(&self) -> u128 {
+        self.reserve_a as u128 * self.reserve_b as u128
+    }
+
+    // After a swap: k may only grow
+    pub fn check_swap(&self, after: &PoolBalances) -> Result<(), AmmError> {
+        if after.k() < self.k() || after.supply != self.supply {
+            return Err(AmmError::InvariantViolated);
+        }
+        Ok(())
+    }
+
+    // After a deposit or withdrawal: neither reserve per LP token may
+    // shrink, that is reserve' / supply' >= reserve / supply
+    pub fn check_liquidity(&self, after: &PoolBalances) -> Result<(), AmmError> {
+        let holds = |before: u64, after_reserve: u64| {
+            after_reserve as u128 * self.supply as u128 >= before as u128 * after.supply as u128
+        };
+        if !holds(self.reserve_a, after.reserve_a) || !holds(self.reserve_b, after.reserve_b) {
+            return Err(AmmError::InvariantViolated);
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------
+// Instructions
+// ----------------------------------------
+
+// Deposits, withdrawals and pool creation take the same accounts:
+//   [writable] pool (only written by InitializePool), pool authority,
+//   [writable] vault A, [writable] vault B, [writable] LP mint,
+//   [writable] owner's token A, [writable] owner's token B,
+//   [writable] owner's LP tokens, [signer] owner, token program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmmInstruction {
+    // Moves the first deposit into the vaults
+    InitializePool {
+        fee_bps: u16,
+        amount_a: u64,
+        amount_b: u64,
+    },
+    Deposit {
+        lp_amount: u64,
+        max_amount_a: u64,
+        max_amount_b: u64,
+    },
+    Withdraw {
+        lp_amount: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    },
+    // pool, pool authority, [writable] vault A, [writable] vault B,
+    // [writable] source, [writable] destination, [signer] owner,
+    // token program. The source's mint picks the direction.
+    Swap {
+        amount_in: u64,
+        min_amount_out: u64,
+    },
+}
+
+impl AmmInstruction {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        let (tag, amounts): (u8, &[u64]) = match self {
+            AmmInstruction::InitializePool {
+                fee_bps,
+                amount_a,
+                amount_b,
+            } => {
+                data.push(0);
+                data.extend_from_slice(&fee_bps.to_le_bytes());
+                return [data, pack_u64s(&[*amount_a, *amount_b])].concat();
+            }
+            AmmInstruction::Deposit {
+                lp_amount,
+                max_amount_a,
+                max_amount_b,
+            } => (1, &[*lp_amount, *max_amount_a, *max_amount_b]),
+            AmmInstruction::Withdraw {
+                lp_amount,
+                min_amount_a,
+                min_amount_b,
+            } => (2, &[*lp_amount, *min_amount_a, *min_amount_b]),
+            AmmInstruction::Swap {
+                amount_in,
+                min_amount_out,
+            } => (3, &[*amount_in, *min_amount_out]),
+        };
+        data.push(tag);
+        data.extend_from_slice(&pack_u64s(amounts));
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let (&tag, rest) = data
+            .split_first()
+            .ok_or(InstructionError::InvalidInstructionData)?;
+        let instruction = match (tag, rest.len()) {
+            (0, 18) => {
+                let amounts = unpack_u64s(&rest[2..]);
+                AmmInstruction::InitializePool {
+                    fee_bps: u16::from_le_bytes([rest[0], rest[1]]),
+                    amount_a: amounts[0],
+                    amount_b: amounts[1],
+                }
+            }
+            (1, 24) => {
+                let amounts = unpack_u64s(rest);
+                AmmInstruction::Deposit {
+                    lp_amount: amounts[0],
+                    max_amount_a: amounts[1],
+                    max_amount_b: amounts[2],
+                }
+            }
+            (2, 24) => {
+                let amounts = unpack_u64s(rest);
+                AmmInstruction::Withdraw {
+                    lp_amount: amounts[0],
+                    min_amount_a: amounts[1],
+                    min_amount_b: amounts[2],
+                }
+            }
+            (3, 16) => {
+                let amounts = unpack_u64s(rest);
+                AmmInstruction::Swap {
+                    amount_in: amounts[0],
+                    min_amount_out: amounts[1],
+                }
+            }
+            _ => return Err(InstructionError::InvalidInstructionData),
+        };
+        Ok(instruction)
+    }
+}
+
+fn pack_u64s(values: &[u64]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn unpack_u64s(data: &[u8]) -> Vec<u64> {
+    data.chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+// ----------------------------------------
+// Instruction Constructors
+// ----------------------------------------
+
+// The pool's own accounts, as instructions need them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+impl PoolKeys {
+    // The vaults are the authority's associated token accounts
+    pub fn new(pool: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, lp_mint: &Pubkey) -> Self {
+        let authority = pool_authority(pool).0;
+        PoolKeys {
+            pool: *pool,
+            vault_a: associated_token_program::get_associated_token_address(&authority, mint_a),
+            vault_b: associated_token_program::get_associated_token_address(&authority, mint_b),
+            lp_mint: *lp_mint,
+        }
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        pool_authority(&self.pool).0
+    }
+}
+
+// A liquidity provider's token accounts and the wallet that owns them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderAccounts {
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp: Pubkey,
+    pub owner: Pubkey,
+}
+
+fn liquidity_instruction(
+    keys: &PoolKeys,
+    provider: &ProviderAccounts,
+    instruction: AmmInstruction,
+) -> Instruction {
+    let pool = match instruction {
+        AmmInstruction::InitializePool { .. } => AccountMeta::new(keys.pool, false),
+        _ => AccountMeta::new_readonly(keys.pool, false),
+    };
+    Instruction::new(
+        ID,
+        &instruction.pack(),
+        vec![
+            pool,
+            AccountMeta::new_readonly(keys.authority(), false),
+            AccountMeta::new(keys.vault_a, false),
+            AccountMeta::new(keys.vault_b, false),
+            AccountMeta::new(keys.lp_mint, false),
+            AccountMeta::new(provider.token_a, false),
+            AccountMeta::new(provider.token_b, false),
+            AccountMeta::new(provider.lp, false),
+            AccountMeta::new_readonly(provider.owner, true),
+            AccountMeta::new_readonly(token_program::ID, false),
+        ],
+    )
+}
+
+pub fn initialize_pool(
+    keys: &PoolKeys,
+    provider: &ProviderAccounts,
+    fee_bps: u16,
+    amount_a: u64,
+    amount_b: u64,
+) -> Instruction {
+    liquidity_instruction(
+        keys,
+        provider,
+        AmmInstruction::InitializePool {
+            fee_bps,
+            amount_a,
+            amount_b,
+        },
+    )
+}
+
+pub fn deposit(
+    keys: &PoolKeys,
+    provider: &ProviderAccounts,
+    lp_amount: u64,
+    max_amount_a: u64,
+    max_amount_b: u64,
+) -> Instruction {
+    liquidity_instruction(
+        keys,
+        provider,
+        AmmInstruction::Deposit {
+            lp_amount,
+            max_amount_a,
+            max_amount_b,
+        },
+    )
+}
+
+pub fn withdraw(
+    keys: &PoolKeys,
+    provider: &ProviderAccounts,
+    lp_amount: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Instruction {
+    liquidity_instruction(
+        keys,
+        provider,
+        AmmInstruction::Withdraw {
+            lp_amount,
+            min_amount_a,
+            min_amount_b,
+        },
+    )
+}
+
+pub fn swap(
+    keys: &PoolKeys,
+    source: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let data = AmmInstruction::Swap {
+        amount_in,
+        min_amount_out,
+    }
+    .pack();
+    Instruction::new(
+        ID,
+        &data,
+        vec![
+            AccountMeta::new_readonly(keys.pool, false),
+            AccountMeta::new_readonly(keys.authority(), false),
+            AccountMeta::new(keys.vault_a, false),
+            AccountMeta::new(keys.vault_b, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(token_program::ID, false),
+        ],
+    )
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct AmmProgram;
+
+impl Program for AmmProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "constant_product_amm"
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        match AmmInstruction::unpack(data)? {
+            AmmInstruction::InitializePool {
+                fee_bps,
+                amount_a,
+                amount_b,
+            } => {
+                ctx.log("Instruction: InitializePool");
+                process_initialize_pool(ctx, fee_bps, amount_a, amount_b)
+            }
+            AmmInstruction::Deposit {
+                lp_amount,
+                max_amount_a,
+                max_amount_b,
+            } => {
+                ctx.log("Instruction: Deposit");
+                process_deposit(ctx, lp_amount, max_amount_a, max_amount_b)
+            }
+            AmmInstruction::Withdraw {
+                lp_amount,
+                min_amount_a,
+                min_amount_b,
+            } => {
+                ctx.log("Instruction: Withdraw");
+                process_withdraw(ctx, lp_amount, min_amount_a, min_amount_b)
+            }
+            AmmInstruction::Swap {
+                amount_in,
+                min_amount_out,
+            } => {
+                ctx.log("Instruction: Swap");
+                process_swap(ctx, amount_in, min_amount_out)
+            }
+        }
+    }
+}
+
+// The pool at account 0, after checking that the authority and vaults
+// passed with it are its own
+fn load_pool(ctx: &InvokeContext) -> Result<Pool, InstructionError> {
+    let account = ctx.account(0)?;
+    if account.owner != ID {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+    let pool = Pool::unpack_initialized(&account.data)?;
+    let authority = Pubkey::create_program_address(&[ctx.key(0)?.as_ref(), &[pool.bump_seed]], &ID)
+        .map_err(|_| InstructionError::InvalidSeeds)?;
+    if *ctx.key(1)? != authority || *ctx.key(2)? != pool.vault_a || *ctx.key(3)? != pool.vault_b {
+        return Err(AmmError::PoolMismatch.into());
+    }
+    Ok(pool)
+}
+
+// Reserves from the vaults at 2 and 3, supply from the LP mint at 4
+fn balances(ctx: &InvokeContext) -> Result<PoolBalances, InstructionError> {
+    let supply = token_program::mint(ctx, 4)?
+        .supply
+        .checked_add(MINIMUM_LIQUIDITY)
+        .ok_or(InstructionError::ArithmeticOverflow)?;
+    Ok(PoolBalances {
+        reserve_a: token_program::token_account(ctx, 2)?.amount,
+        reserve_b: token_program::token_account(ctx, 3)?.amount,
+        supply,
+    })
+}
+
+// Move tokens out of a vault, signing as the pool authority
+fn pay_out(
+    ctx: &mut InvokeContext,
+    vault: usize,
+    destination: usize,
+    bump_seed: u8,
+    amount: u64,
+) -> Result<(), InstructionError> {
+    let pool = *ctx.key(0)?;
+    let authority = *ctx.key(1)?;
+    let instruction = token_program::transfer(
+        ctx.key(vault)?,
+        ctx.key(destination)?,
+        &authority,
+        &[],
+        amount,
+    );
+    ctx.invoke_signed(&instruction, &[&[pool.as_ref(), &[bump_seed]]])
+}
+
+fn pay_in(
+    ctx: &mut InvokeContext,
+    source: usize,
+    vault: usize,
+    owner: usize,
+    amount: u64,
+) -> Result<(), InstructionError> {
+    let instruction = token_program::transfer(
+        ctx.key(source)?,
+        ctx.key(vault)?,
+        ctx.key(owner)?,
+        &[],
+        amount,
+    );
+    ctx.invoke(&instruction)
+}
+
+fn mint_lp(ctx: &mut InvokeContext, bump_seed: u8, amount: u64) -> Result<(), InstructionError> {
+    let pool = *ctx.key(0)?;
+    let instruction = token_program::mint_to(ctx.key(4)?, ctx.key(7)?, ctx.key(1)?, &[], amount);
+    ctx.invoke_signed(&instruction, &[&[pool.as_ref(), &[bump_seed]]])
+}
+
+fn process_initialize_pool(
+    ctx: &mut InvokeContext,
+    fee_bps: u16,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<(), InstructionError> {
+    let account = ctx.account(0)?;
+    if account.owner != ID {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+    if Pool::unpack(&account.data)?.is_initialized {
+        return Err(InstructionError::AccountAlreadyInitialized);
+    }
+    if fee_bps >= MAX_FEE_BPS {
+        return Err(AmmError::InvalidFee(fee_bps).into());
+    }
+    let (authority, bump_seed) = pool_authority(ctx.key(0)?);
+    if *ctx.key(1)? != authority {
+        return Err(InstructionError::InvalidSeeds);
+    }
+
+    let vault_a = token_program::token_account(ctx, 2)?;
+    let vault_b = token_program::token_account(ctx, 3)?;
+    for vault in [&vault_a, &vault_b] {
+        if vault.owner != authority
+            || vault.amount != 0
+            || vault.delegate.is_some()
+            || vault.close_authority.is_some()
+        {
+            return Err(AmmError::InvalidVault.into());
+        }
+    }
+    if vault_a.mint == vault_b.mint {
+        return Err(AmmError::IdenticalMints.into());
+    }
+    let lp_mint = token_program::mint(ctx, 4)?;
+    if lp_mint.mint_authority != Some(authority)
+        || lp_mint.freeze_authority.is_some()
+        || lp_mint.supply != 0
+    {
+        return Err(AmmError::InvalidLpMint.into());
+    }
+
+    if amount_a == 0 || amount_b == 0 {
+        return Err(AmmError::ZeroAmount.into());
+    }
+    let shares = initial_liquidity(amount_a, amount_b).ok_or(AmmError::InsufficientLiquidity)?;
+    pay_in(ctx, 5, 2, 8, amount_a)?;
+    pay_in(ctx, 6, 3, 8, amount_b)?;
+    mint_lp(ctx, bump_seed, shares)?;
+
+    let pool = Pool {
+        is_initialized: true,
+        bump_seed,
+        fee_bps,
+        mint_a: vault_a.mint,
+        mint_b: vault_b.mint,
+        vault_a: *ctx.key(2)?,
+        vault_b: *ctx.key(3)?,
+        lp_mint: *ctx.key(4)?,
+    };
+    ctx.account_mut(0)?.data = pool.pack().to_vec();
+    ctx.log(&format!(
+        "Pool opened with {} and {}, {} LP tokens minted",
+        amount_a, amount_b, shares
+    ));
+    Ok(())
+}
+
+fn process_deposit(
+    ctx: &mut InvokeContext,
+    lp_amount: u64,
+    max_amount_a: u64,
+    max_amount_b: u64,
+) -> Result<(), InstructionError> {
+    let pool = load_pool(ctx)?;
+    if *ctx.key(4)? != pool.lp_mint {
+        return Err(AmmError::PoolMismatch.into());
+    }
+    if lp_amount == 0 {
+        return Err(AmmError::ZeroAmount.into());
+    }
+    let before = balances(ctx)?;
+    let (amount_a, amount_b) = deposit_amounts(
+        lp_amount,
+        (before.reserve_a, before.reserve_b),
+        before.supply,
+    )
+    .ok_or(InstructionError::ArithmeticOverflow)?;
+    if amount_a > max_amount_a || amount_b > max_amount_b {
+        return Err(AmmError::SlippageExceeded.into());
+    }
+
+    pay_in(ctx, 5, 2, 8, amount_a)?;
+    pay_in(ctx, 6, 3, 8, amount_b)?;
+    mint_lp(ctx, pool.bump_seed, lp_amount)?;
+    ctx.log(&format!(
+        "Deposited {} and {} for {} LP tokens",
+        amount_a, amount_b, lp_amount
+    ));
+    before.check_liquidity(&balances(ctx)?)?;
+    Ok(())
+}
+
+fn process_withdraw(
+    ctx: &mut InvokeContext,
+    lp_amount: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Result<(), InstructionError> {
+    let pool = load_pool(ctx)?;
+    if *ctx.key(4)? != pool.lp_mint {
+        return Err(AmmError::PoolMismatch.into());
+    }
+    if lp_amount == 0 {
+        return Err(AmmError::ZeroAmount.into());
+    }
+    let before = balances(ctx)?;
+    let (amount_a, amount_b) = withdraw_amounts(
+        lp_amount,
+        (before.reserve_a, before.reserve_b),
+        before.supply,
+    );
+    if amount_a < min_amount_a || amount_b < min_amount_b {
+        return Err(AmmError::SlippageExceeded.into());
+    }
+
+    // Burning first means the owner must really hold the LP tokens
+    let burn = token_program::burn(ctx.key(7)?, ctx.key(4)?, ctx.key(8)?, &[], lp_amount);
+    ctx.invoke(&burn)?;
+    pay_out(ctx, 2, 5, pool.bump_seed, amount_a)?;
+    pay_out(ctx, 3, 6, pool.bump_seed, amount_b)?;
+    ctx.log(&format!(
+        "Withdrew {} and {} for {} LP tokens",
+        amount_a, amount_b, lp_amount
+    ));
+    before.check_liquidity(&balances(ctx)?)?;
+    Ok(())
+}
+
+fn process_swap(
+    ctx: &mut InvokeContext,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Result<(), InstructionError> {
+    let pool = load_pool(ctx)?;
+    if amount_in == 0 {
+        return Err(AmmError::ZeroAmount.into());
+    }
+    let source = token_program::token_account(ctx, 4)?;
+    let (vault_in, vault_out) = if source.mint == pool.mint_a {
+        (2, 3)
+    } else if source.mint == pool.mint_b {
+        (3, 2)
+    } else {
+        return Err(AmmError::MintMismatch.into());
+    };
+
+    let reserve_in = token_program::token_account(ctx, vault_in)?.amount;
+    let reserve_out = token_program::token_account(ctx, vault_out)?.amount;
+    let k_before = reserve_in as u128 * reserve_out as u128;
+    let amount_out = swap_output(amount_in, reserve_in, reserve_out, pool.fee_bps);
+    if amount_out == 0 {
+        return Err(AmmError::ZeroOutput.into());
+    }
+    if amount_out < min_amount_out {
+        return Err(AmmError::SlippageExceeded.into());
+    }
+
+    pay_in(ctx, 4, vault_in, 6, amount_in)?;
+    pay_out(ctx, vault_out, 5, pool.bump_seed, amount_out)?;
+    ctx.log(&format!("Swapped {} for {}", amount_in, amount_out));
+
+    let k_after = token_program::token_account(ctx, vault_in)?.amount as u128
+        * token_program::token_account(ctx, vault_out)?.amount as u128;
+    if k_after < k_before {
+        return Err(AmmError::InvariantViolated.into());
+    }
+    Ok(())
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo amm`
+// ----------------------------------------
+
+const SOL: u64 = 1_000_000_000;
+const USDC: u64 = 1_000_000;
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(TokenProgram));
+    bank.add_program(Box::new(AssociatedTokenProgram));
+    bank.add_program(Box::new(AmmProgram));
+
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let carol = DemoSigner::new("carol");
+    let sol_mint = DemoSigner::new("wrapped sol mint");
+    let usdc_mint = DemoSigner::new("usdc mint");
+    let pool = DemoSigner::new("sol/usdc pool");
+    let lp_mint = DemoSigner::new("sol/usdc lp mint");
+    for wallet in [&alice, &bob, &carol] {
+        bank.airdrop(&wallet.pubkey(), 10 * SOL)
+            .map_err(|err| err.to_string())?;
+    }
+
+    let name_of = |signer: &DemoSigner| {
+        [(&alice, "alice"), (&bob, "bob"), (&carol, "carol")]
+            .iter()
+            .find(|(wallet, _)| wallet.pubkey() == signer.pubkey())
+            .map_or("?", |(_, name)| name)
+    };
+    let ata = |wallet: &DemoSigner, mint: &DemoSigner| {
+        associated_token_program::get_associated_token_address(&wallet.pubkey(), &mint.pubkey())
+    };
+    let keys = PoolKeys::new(
+        &pool.pubkey(),
+        &sol_mint.pubkey(),
+        &usdc_mint.pubkey(),
+        &lp_mint.pubkey(),
+    );
+    let alice_accounts = ProviderAccounts {
+        token_a: ata(&alice, &sol_mint),
+        token_b: ata(&alice, &usdc_mint),
+        lp: ata(&alice, &lp_mint),
+        owner: alice.pubkey(),
+    };
+
+    let send = |bank: &mut Bank,
+                title: &str,
+                instructions: &[Instruction],
+                payer: &DemoSigner,
+                signers: &[&dyn Signer]| {
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            signers,
+            bank.last_blockhash(),
+        );
+        let outcome = bank.execute_transaction(&tx);
+        print_outcome(title, &outcome.status, &amm_logs(&outcome.logs));
+        bank.advance_slots(1);
+    };
+
+    // Two mints and everyone's token accounts. alice is the faucet.
+    let mut setup = Vec::new();
+    for (mint, decimals) in [(&sol_mint, 9), (&usdc_mint, 6)] {
+        setup.push(system_program::create_account(
+            &alice.pubkey(),
+            &mint.pubkey(),
+            system_program::rent_exempt_lamports(token_program::MINT_LEN),
+            token_program::MINT_LEN as u64,
+            &token_program::ID,
+        ));
+        setup.push(token_program::initialize_mint(
+            &mint.pubkey(),
+            &alice.pubkey(),
+            None,
+            decimals,
+        ));
+    }
+    send(
+        &mut bank,
+        "Create the wrapped SOL and USDC mints",
+        &setup,
+        &alice,
+        &[&alice, &sol_mint, &usdc_mint],
+    );
+    let mut funding = Vec::new();
+    for (wallet, sol, usdc) in [
+        (&alice, 1_000, 200_000),
+        (&bob, 10, 2_000),
+        (&carol, 100, 0),
+    ] {
+        for (mint, amount) in [(&sol_mint, sol * SOL), (&usdc_mint, usdc * USDC)] {
+            funding.push(associated_token_program::create_associated_token_account(
+                &alice.pubkey(),
+                &wallet.pubkey(),
+                &mint.pubkey(),
+            ));
+            if amount > 0 {
+                funding.push(token_program::mint_to(
+                    &mint.pubkey(),
+                    &ata(wallet, mint),
+                    &alice.pubkey(),
+                    &[],
+                    amount,
+                ));
+            }
+        }
+    }
+    send(
+        &mut bank,
+        "Fund alice, bob and carol",
+        &funding,
+        &alice,
+        &[&alice],
+    );
+
+    // The pool account, an LP mint only the pool authority can mint from,
+    // vaults owned by that authority, and the first deposit at 150 USDC
+    // per SOL
+    let authority = keys.authority();
+    println!("\nPool authority {}", authority);
+    let create_pool = [
+        system_program::create_account(
+            &alice.pubkey(),
+            &pool.pubkey(),
+            system_program::rent_exempt_lamports(POOL_LEN),
+            POOL_LEN as u64,
+            &ID,
+        ),
+        system_program::create_account(
+            &alice.pubkey(),
+            &lp_mint.pubkey(),
+            system_program::rent_exempt_lamports(token_program::MINT_LEN),
+            token_program::MINT_LEN as u64,
+            &token_program::ID,
+        ),
+        token_program::initialize_mint(&lp_mint.pubkey(), &authority, None, 9),
+        associated_token_program::create_associated_token_account(
+            &alice.pubkey(),
+            &authority,
+            &sol_mint.pubkey(),
+        ),
+        associated_token_program::create_associated_token_account(
+            &alice.pubkey(),
+            &authority,
+            &usdc_mint.pubkey(),
+        ),
+        associated_token_program::create_associated_token_account(
+            &alice.pubkey(),
+            &alice.pubkey(),
+            &lp_mint.pubkey(),
+        ),
+        initialize_pool(&keys, &alice_accounts, 30, 100 * SOL, 15_000 * USDC),
+    ];
+    send(
+        &mut bank,
+        "Open a SOL/USDC pool with a 0.3% fee",
+        &create_pool,
+        &alice,
+        &[&alice, &pool, &lp_mint],
+    );
+    print_pool(&bank, &keys)?;
+
+    // bob sells 1 SOL, accepting up to 0.5% less than the quote
+    let sell = |bank: &Bank,
+                seller: &DemoSigner,
+                sol: u64,
+                tolerance_bps: u64|
+     -> Result<Instruction, String> {
+        let state = pool_balances(bank, &keys)?;
+        let quote = swap_output(sol, state.reserve_a, state.reserve_b, 30);
+        let min_out = quote - quote * tolerance_bps / 10_000;
+        println!(
+            "\n{} asks to sell {} SOL for at least {} USDC",
+            name_of(seller),
+            amount::format_decimal(sol, 9),
+            amount::format_decimal(min_out, 6)
+        );
+        Ok(swap(
+            &keys,
+            &ata(seller, &sol_mint),
+            &ata(seller, &usdc_mint),
+            &seller.pubkey(),
+            sol,
+            min_out,
+        ))
+    };
+    let instruction = sell(&bank, &bob, SOL, 50)?;
+    send(&mut bank, "bob swaps 1 SOL", &[instruction], &bob, &[&bob]);
+    print_pool(&bank, &keys)?;
+
+    // bob signs another sale at the current price with no tolerance, but
+    // carol's big sale lands first and pushes the price down
+    let bobs_sale = sell(&bank, &bob, SOL, 0)?;
+    let carols_sale = sell(&bank, &carol, 20 * SOL, 100)?;
+    send(
+        &mut bank,
+        "carol swaps 20 SOL",
+        &[carols_sale],
+        &carol,
+        &[&carol],
+    );
+    send(&mut bank, "bob swaps 1 SOL", &[bobs_sale], &bob, &[&bob]);
+    print_pool(&bank, &keys)?;
+
+    // alice adds 10% to the pool. Both amounts are rounded up, so her
+    // limits allow a little over the exact quote.
+    let state = pool_balances(&bank, &keys)?;
+    let lp_amount = (state.supply - MINIMUM_LIQUIDITY) / 10;
+    let (amount_a, amount_b) =
+        deposit_amounts(lp_amount, (state.reserve_a, state.reserve_b), state.supply)
+            .ok_or("deposit overflows")?;
+    send(
+        &mut bank,
+        "alice deposits for 10% more LP tokens",
+        &[deposit(
+            &keys,
+            &alice_accounts,
+            lp_amount,
+            amount_a + amount_a / 100,
+            amount_b + amount_b / 100,
+        )],
+        &alice,
+        &[&alice],
+    );
+    print_pool(&bank, &keys)?;
+
+    // And takes half of her share back out, fees included
+    let held = token_balance(&bank, &alice_accounts.lp)?;
+    let state = pool_balances(&bank, &keys)?;
+    let (min_a, min_b) =
+        withdraw_amounts(held / 2, (state.reserve_a, state.reserve_b), state.supply);
+    send(
+        &mut bank,
+        "alice withdraws half her LP tokens",
+        &[withdraw(&keys, &alice_accounts, held / 2, min_a, min_b)],
+        &alice,
+        &[&alice],
+    );
+    print_pool(&bank, &keys)?;
+
+    // The pool math on its own, over a long run of random operations:
+    // every step must pass the same checks the processor makes. `simply_rust
+    // check amm-invariant` does the same over many short runs and shrinks
+    // any failure it finds.
+    let mut state = pool_balances(&bank, &keys)?;
+    let start = state;
+    let mut lowest = u64::MAX;
+    let steps = 10_000;
+    for i in 0..steps {
+        if let Some(next) = random_step(&state, random("amm invariant", i)) {
+            state = next.map_err(|err| format!("step {}: {}", i, err))?;
+            lowest = lowest.min(state.reserve_a).min(state.reserve_b);
+        }
+    }
+    // Floating point for the printout only; the checks are exact
+    let lp_value = |state: &PoolBalances| (state.k() as f64).sqrt() / state.supply as f64;
+    println!(
+        "\n{} random swaps, deposits and withdrawals: k never fell on a swap and no \
+         LP token lost value",
+        steps
+    );
+    // Fees and rounding both go to the pool. Once a reserve is down to a
+    // few units, rounding dwarfs the fee.
+    println!(
+        "  sqrt(k) per LP token {:.4} -> {:.4}, with a reserve as low as {} units",
+        lp_value(&start),
+        lp_value(&state),
+        lowest
+    );
+    Ok(())
+}
+
+// A swap, deposit or withdrawal of a random size up to a quarter of the
+// pool, checked the way the processor checks it. None if the amounts
+// would not fit in a u64.
+fn random_step(state: &PoolBalances, roll: u64) -> Option<Result<PoolBalances, AmmError>> {
+    let size = |total: u64| roll % (total / 4).max(1) + 1;
+    let reserves = (state.reserve_a, state.reserve_b);
+    let mut next = *state;
+    let checked = match roll % 4 {
+        0 => {
+            let amount_in = size(state.reserve_a);
+            next.reserve_a = state.reserve_a.checked_add(amount_in)?;
+            next.reserve_b -= swap_output(amount_in, state.reserve_a, state.reserve_b, 30);
+            state.check_swap(&next)
+        }
+        1 => {
+            let amount_in = size(state.reserve_b);
+            next.reserve_b = state.reserve_b.checked_add(amount_in)?;
+            next.reserve_a -= swap_output(amount_in, state.reserve_b, state.reserve_a, 30);
+            state.check_swap(&next)
+        }
+        2 => {
+            let lp_amount = size(state.supply);
+            let (amount_a, amount_b) = deposit_amounts(lp_amount, reserves, state.supply)?;
+            next.reserve_a = state.reserve_a.checked_add(amount_a)?;
+            next.reserve_b = state.reserve_b.checked_add(amount_b)?;
+            next.supply = state.supply.checked_add(lp_amount)?;
+            state.check_liquidity(&next)
+        }
+        _ => {
+            let lp_amount = size(state.supply - MINIMUM_LIQUIDITY);
+            let (amount_a, amount_b) = withdraw_amounts(lp_amount, reserves, state.supply);
+            next.reserve_a -= amount_a;
+            next.reserve_b -= amount_b;
+            next.supply -= lp_amount;
+            state.check_liquidity(&next)
+        }
+    };
+    Some(checked.map(|()| next))
+}
+
+// What the AMM itself logged, without the programs it called
+fn amm_logs(logs: &[String]) -> Vec<String> {
+    let mut depth = 0;
+    let mut lines = Vec::new();
+    for line in logs {
+        if line.starts_with(&format!("Program {} invoke", ID)) {
+            depth = 1;
+        } else if line.contains(" invoke [") {
+            depth += (depth > 0) as usize;
+        } else if line.ends_with(" success") || line.contains(" failed: ") {
+            depth = depth.saturating_sub(1);
+        } else if depth == 1 && !line.contains("Instruction: ") {
+            lines.push(line.clone());
+        }
+    }
+    lines
+}
+
+fn token_balance(bank: &Bank, key: &Pubkey) -> Result<u64, String> {
+    let account = bank.get_account(key).ok_or("token account missing")?;
+    let account = TokenAccount::unpack(&account.data).map_err(|err| err.to_string())?;
+    Ok(account.amount)
+}
+
+fn pool_balances(bank: &Bank, keys: &PoolKeys) -> Result<PoolBalances, String> {
+    let lp_mint = bank.get_account(&keys.lp_mint).ok_or("LP mint missing")?;
+    let lp_mint = Mint::unpack(&lp_mint.data).map_err(|err| err.to_string())?;
+    Ok(PoolBalances {
+        reserve_a: token_balance(bank, &keys.vault_a)?,
+        reserve_b: token_balance(bank, &keys.vault_b)?,
+        supply: lp_mint.supply + MINIMUM_LIQUIDITY,
+    })
+}
+
+fn print_pool(bank: &Bank, keys: &PoolKeys) -> Result<(), String> {
+    let state = pool_balances(bank, keys)?;
+    // Scaled up by 10^9 before dividing, which can pass u64::MAX
+    let price = state.reserve_b as u128 * SOL as u128 / state.reserve_a as u128;
+    let price = u64::try_from(price).map_err(|_| "the pool price does not fit in a u64")?;
+    println!(
+        "  pool: {} SOL, {} USDC, k = {}, {} USDC per SOL",
+        amount::format_decimal(state.reserve_a, 9),
+        amount::format_decimal(state.reserve_b, 6),
+        state.k(),
+        amount::format_decimal(price, 6)
+    );
+    Ok(())
+}
+
+fn random(seed: &str, i: u64) -> u64 {
+    let hash = hashv(&[seed.as_bytes(), &i.to_le_bytes()]).to_bytes();
+    u64::from_le_bytes(hash[..8].try_into().unwrap())
+}
@@ -0,0 +1,355 @@
+// ========================================================================
+// EXACT AMOUNTS: LAMPORTS AND TOKEN AMOUNTS
+// ========================================================================
+//
+// Balances on Solana are integers: 1 SOL is exactly 1_000_000_000
+// lamports, and an SPL token with 6 decimals stores 1.5 tokens as
+// 1_500_000. Floating-point numbers cannot represent most decimal
+// fractions exactly (0.1 + 0.2 != 0.3), so the types below keep the raw
+// integer and only turn it into a decimal string for display.
+//
+// - Lamports(u64)                        SOL balances
+// - TokenAmount { raw, decimals }        SPL token balances
+//
+// checked_* methods return None on overflow/underflow (like u64), while
+// the +, -, * operators panic on overflow instead of silently wrapping.
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use std::str::FromStr;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+pub const SOL_DECIMALS: u8 = 9;
+
+// ----------------------------------------
+// Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmountError {
+    Empty,
+    InvalidNumber(String),
+    TooManyDecimals { max: u8 },
+    UnknownUnit(String),
+    Overflow,
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AmountError::Empty => write!(f, "amount is empty"),
+            AmountError::InvalidNumber(text) => write!(f, "`{}` is not a valid amount", text),
+            AmountError::TooManyDecimals { max } => {
+                write!(f, "amount has more than {} decimal places", max)
+            }
+            AmountError::UnknownUnit(unit) => write!(f, "unknown unit `{}`", unit),
+            AmountError::Overflow => write!(f, "amount does not fit in a u64"),
+        }
+    }
+}
+
+// ----------------------------------------
+// Lamports
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lamports(pub u64);
+
+impl Lamports {
+    pub const ZERO: Lamports = Lamports(0);
+
+    // Whole SOL only; fractional SOL goes through parsing so it stays exact
+    pub fn from_sol(sol: u64) -> Option<Lamports> {
+        sol.checked_mul(LAMPORTS_PER_SOL).map(Lamports)
+    }
+
+    pub fn checked_add(self, other: Lamports) -> Option<Lamports> {
+        self.0.checked_add(other.0).map(Lamports)
+    }
+
+    pub fn checked_sub(self, other: Lamports) -> Option<Lamports> {
+        self.0.checked_sub(other.0).map(Lamports)
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Option<Lamports> {
+        self.0.checked_mul(factor).map(Lamports)
+    }
+
+    // Exact SOL value, e.g. "1.5" (trailing zeros trimmed)
+    pub fn to_sol_string(self) -> String {
+        format_decimal(self.0, SOL_DECIMALS)
+    }
+
+    // SOL value rounded to `places` decimal places, e.g. "1.50"
+    pub fn to_sol_rounded(self, places: u8) -> String {
+        format_rounded(self.0, SOL_DECIMALS, places)
+    }
+}
+
+// Accepts "1.5 SOL", "1.5" (SOL is assumed) and "1500 lamports"
+impl FromStr for Lamports {
+    type Err = AmountError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = text.split_whitespace();
+        let number = parts.next().ok_or(AmountError::Empty)?;
+        let unit = parts.next();
+        if let Some(extra) = parts.next() {
+            return Err(AmountError::InvalidNumber(extra.to_string()));
+        }
+
+        match unit.map(|unit| unit.to_ascii_lowercase()).as_deref() {
+            None | Some("sol") => parse_decimal(number, SOL_DECIMALS).map(Lamports),
+            Some("lamport") | Some("lamports") => parse_decimal(number, 0).map(Lamports),
+            Some(_) => Err(AmountError::UnknownUnit(
+                unit.unwrap_or_default().to_string(),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Lamports {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} SOL", self.to_sol_string())
+    }
+}
+
+impl Add for Lamports {
+    type Output = Lamports;
+
+    fn add(self, other: Lamports) -> Lamports {
+        self.checked_add(other).expect("lamports overflow")
+    }
+}
+
+impl Sub for Lamports {
+    type Output = Lamports;
+
+    fn sub(self, other: Lamports) -> Lamports {
+        self.checked_sub(other).expect("lamports underflow")
+    }
+}
+
+impl Mul<u64> for Lamports {
+    type Output = Lamports;
+
+    fn mul(self, factor: u64) -> Lamports {
+        self.checked_mul(factor).expect("lamports overflow")
+    }
+}
+
+impl AddAssign for Lamports {
+    fn add_assign(&mut self, other: Lamports) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Lamports {
+    fn sub_assign(&mut self, other: Lamports) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Lamports {
+    fn sum<I: Iterator<Item = Lamports>>(iter: I) -> Lamports {
+        iter.fold(Lamports::ZERO, |total, amount| total + amount)
+    }
+}
+
+// ----------------------------------------
+// Token Amounts
+// ----------------------------------------
+
+// Raw on-chain amount plus the mint's decimals (USDC uses 6)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenAmount {
+    pub raw: u64,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: u64, decimals: u8) -> Self {
+        TokenAmount { raw, decimals }
+    }
+
+    // Parse a UI amount such as "12.345" for a mint with `decimals`
+    pub fn parse_ui(text: &str, decimals: u8) -> Result<TokenAmount, AmountError> {
+        let raw = parse_decimal(text.trim(), decimals)?;
+        Ok(TokenAmount { raw, decimals })
+    }
+
+    // None on overflow or when the two amounts use different decimals
+    pub fn checked_add(self, other: TokenAmount) -> Option<TokenAmount> {
+        if self.decimals != other.decimals {
+            return None;
+        }
+        let raw = self.raw.checked_add(other.raw)?;
+        Some(TokenAmount::new(raw, self.decimals))
+    }
+
+    pub fn checked_sub(self, other: TokenAmount) -> Option<TokenAmount> {
+        if self.decimals != other.decimals {
+            return None;
+        }
+        let raw = self.raw.checked_sub(other.raw)?;
+        Some(TokenAmount::new(raw, self.decimals))
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Option<TokenAmount> {
+        let raw = self.raw.checked_mul(factor)?;
+        Some(TokenAmount::new(raw, self.decimals))
+    }
+
+    // Exact UI amount, e.g. "1.5" (trailing zeros trimmed)
+    pub fn to_ui_string(self) -> String {
+        format_decimal(self.raw, self.decimals)
+    }
+
+    // UI amount rounded half-up to `places` decimal places
+    pub fn to_ui_rounded(self, places: u8) -> String {
+        format_rounded(self.raw, self.decimals, places)
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_ui_string())
+    }
+}
+
+impl Add for TokenAmount {
+    type Output = TokenAmount;
+
+    fn add(self, other: TokenAmount) -> TokenAmount {
+        assert_eq!(self.decimals, other.decimals, "token decimals mismatch");
+        self.checked_add(other).expect("token amount overflow")
+    }
+}
+
+impl Sub for TokenAmount {
+    type Output = TokenAmount;
+
+    fn sub(self, other: TokenAmount) -> TokenAmount {
+        assert_eq!(self.decimals, other.decimals, "token decimals mismatch");
+        self.checked_sub(other).expect("token amount underflow")
+    }
+}
+
+impl Mul<u64> for TokenAmount {
+    type Output = TokenAmount;
+
+    fn mul(self, factor: u64) -> TokenAmount {
+        self.checked_mul(factor).expect("token amount overflow")
+    }
+}
+
+impl AddAssign for TokenAmount {
+    fn add_assign(&mut self, other: TokenAmount) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for TokenAmount {
+    fn sub_assign(&mut self, other: TokenAmount) {
+        *self = *self - other;
+    }
+}
+
+// ----------------------------------------
+// Decimal Conversion
+// ----------------------------------------
+
+// "1.25" with 9 decimals -> 1_250_000_000, using integer math only.
+// Extra fractional digits are accepted only if they are zeros, so parsing
+// never rounds. Underscores may separate digits, as in Rust literals, but
+// not start or end a number or follow each other.
+pub fn parse_decimal(text: &str, decimals: u8) -> Result<u64, AmountError> {
+    if text.is_empty() {
+        return Err(AmountError::Empty);
+    }
+    let invalid = || AmountError::InvalidNumber(text.to_string());
+
+    let (whole, fraction) = match text.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (text, ""),
+    };
+    // Either part may be empty ("5." or ".5"), but not both
+    let is_digits = |s: &str| {
+        s.is_empty()
+            || s.split('_')
+                .all(|group| !group.is_empty() && group.chars().all(|c| c.is_ascii_digit()))
+    };
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(invalid());
+    }
+
+    let fraction: String = fraction.chars().filter(|c| *c != '_').collect();
+    let significant = fraction.trim_end_matches('0');
+    if significant.len() > decimals as usize {
+        return Err(AmountError::TooManyDecimals { max: decimals });
+    }
+
+    let scale = 10u64
+        .checked_pow(decimals as u32)
+        .ok_or(AmountError::Overflow)?;
+    let mut raw = 0u64;
+    for digit in whole.chars().filter(|c| *c != '_') {
+        raw = raw
+            .checked_mul(10)
+            .and_then(|raw| raw.checked_add(digit as u64 - '0' as u64))
+            .ok_or(AmountError::Overflow)?;
+    }
+    raw = raw.checked_mul(scale).ok_or(AmountError::Overflow)?;
+
+    let mut fraction_raw = 0u64;
+    for (i, digit) in significant.chars().enumerate() {
+        let place = 10u64.pow(decimals as u32 - 1 - i as u32);
+        fraction_raw += (digit as u64 - '0' as u64) * place;
+    }
+    raw.checked_add(fraction_raw).ok_or(AmountError::Overflow)
+}
+
+// 1_500_000 with 6 decimals -> "1.5"
+pub fn format_decimal(raw: u64, decimals: u8) -> String {
+    let text = insert_point(&raw.to_string(), decimals as usize);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+// 1_234_567 with 6 decimals and 2 places -> "1.23"; halves round up
+// (1_235_000 -> "1.24"). Uses u128 so rounding up can never overflow.
+pub fn format_rounded(raw: u64, decimals: u8, places: u8) -> String {
+    if places >= decimals {
+        let digits = format!("{}{}", raw, "0".repeat((places - decimals) as usize));
+        return insert_point(&digits, places as usize);
+    }
+
+    let rounded = match 10u128.checked_pow((decimals - places) as u32) {
+        Some(divisor) => {
+            let quotient = raw as u128 / divisor;
+            let remainder = raw as u128 % divisor;
+            if remainder * 2 >= divisor {
+                quotient + 1
+            } else {
+                quotient
+            }
+        }
+        // The divisor is larger than any u64, so the value rounds to zero
+        None => 0,
+    };
+    insert_point(&rounded.to_string(), places as usize)
+}
+
+// "1500000" with 6 places -> "1.500000", "5" with 3 places -> "0.005"
+fn insert_point(digits: &str, places: usize) -> String {
+    if places == 0 {
+        return digits.to_string();
+    }
+    let digits = format!("{:0>width$}", digits, width = places + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - places);
+    format!("{}.{}", whole, fraction)
+}
@@ -0,0 +1,293 @@
+// ========================================================================
+// THE ASSOCIATED TOKEN ACCOUNT PROGRAM
+// ========================================================================
+//
+// A wallet may own any number of token accounts for the same mint, so
+// "send alice 5 USDC" needs an agreed answer to "which account?". The
+// associated token account is that answer: one canonical account per
+// (wallet, mint), at the program derived address
+//
+//     find_program_address([wallet, token program id, mint], ATA program id)
+//
+// Anyone can compute it, and anyone can create it by paying for it, so a
+// sender can open the recipient's account in the same transaction as the
+// payment. Only this program can sign for the address, which it does
+// when it asks the System Program to create the account, then has the
+// Token Program initialize it for the wallet.
+
+use crate::bank::{print_outcome, Bank};
+use crate::program::{InstructionError, InvokeContext, Program};
+use crate::pubkey::{Pubkey, SYSTEM_PROGRAM_ID};
+use crate::system_program;
+use crate::token_program::{self, TokenAccount, TokenProgram};
+use crate::transaction::{AccountMeta, DemoSigner, Instruction, Signer, Transaction};
+
+pub const ID: Pubkey = Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+pub const TOKEN_ACCOUNT_LAMPORTS: u64 =
+    system_program::rent_exempt_lamports(token_program::ACCOUNT_LEN);
+
+pub fn get_associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_and_bump(wallet, mint).0
+}
+
+pub fn get_associated_token_address_and_bump(wallet: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program::ID.as_ref(), mint.as_ref()],
+        &ID,
+    )
+}
+
+// ----------------------------------------
+// Instructions
+// ----------------------------------------
+
+// Both take the same accounts:
+//   [signer, writable] payer, [writable] associated account, wallet, mint,
+//   system program, token program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssociatedTokenInstruction {
+    // Fails if the account already exists
+    Create,
+    // Succeeds without changes if the account already exists
+    CreateIdempotent,
+}
+
+impl AssociatedTokenInstruction {
+    pub fn pack(self) -> Vec<u8> {
+        match self {
+            AssociatedTokenInstruction::Create => vec![0],
+            AssociatedTokenInstruction::CreateIdempotent => vec![1],
+        }
+    }
+
+    // Empty data also means Create, from before the instruction had a tag
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        match data {
+            [] | [0] => Ok(AssociatedTokenInstruction::Create),
+            [1] => Ok(AssociatedTokenInstruction::CreateIdempotent),
+            _ => Err(InstructionError::InvalidInstructionData),
+        }
+    }
+}
+
+fn build_create(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    instruction: AssociatedTokenInstruction,
+) -> Instruction {
+    let address = get_associated_token_address(wallet, mint);
+    Instruction::new(
+        ID,
+        &instruction.pack(),
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(address, false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(token_program::ID, false),
+        ],
+    )
+}
+
+pub fn create_associated_token_account(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    build_create(payer, wallet, mint, AssociatedTokenInstruction::Create)
+}
+
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    build_create(
+        payer,
+        wallet,
+        mint,
+        AssociatedTokenInstruction::CreateIdempotent,
+    )
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct AssociatedTokenProgram;
+
+impl Program for AssociatedTokenProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "spl_associated_token_account"
+    }
+
+    // Mostly deriving the address; the calls it makes are charged on top
+    fn compute_units(&self) -> u64 {
+        15_000
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        let instruction = AssociatedTokenInstruction::unpack(data)?;
+        ctx.log(match instruction {
+            AssociatedTokenInstruction::Create => "Create",
+            AssociatedTokenInstruction::CreateIdempotent => "CreateIdempotent",
+        });
+
+        let payer = *ctx.key(0)?;
+        let address = *ctx.key(1)?;
+        let wallet = *ctx.key(2)?;
+        let mint = *ctx.key(3)?;
+        let (expected, bump) = get_associated_token_address_and_bump(&wallet, &mint);
+        if address != expected {
+            ctx.log("Error: Associated address does not match seed derivation");
+            return Err(InstructionError::InvalidSeeds);
+        }
+        if ctx.account(3)?.owner != token_program::ID {
+            return Err(InstructionError::IncorrectProgramId);
+        }
+
+        let existing = ctx.account(1)?;
+        if instruction == AssociatedTokenInstruction::CreateIdempotent
+            && existing.owner == token_program::ID
+        {
+            let account = TokenAccount::unpack(&existing.data)?;
+            if account.owner != wallet || account.mint != mint {
+                ctx.log("Error: Existing account has the wrong owner or mint");
+                return Err(InstructionError::InvalidAccountData);
+            }
+            return Ok(());
+        }
+
+        let bump_seed = [bump];
+        let seeds: &[&[u8]] = &[
+            wallet.as_ref(),
+            token_program::ID.as_ref(),
+            mint.as_ref(),
+            &bump_seed,
+        ];
+        let space = token_program::ACCOUNT_LEN;
+        if existing.lamports == 0 {
+            ctx.invoke_signed(
+                &system_program::create_account(
+                    &payer,
+                    &address,
+                    TOKEN_ACCOUNT_LAMPORTS,
+                    space as u64,
+                    &token_program::ID,
+                ),
+                &[seeds],
+            )?;
+        } else {
+            // Someone already sent lamports to the address
+            system_program::create_pda_account(ctx, 1, &payer, space, &token_program::ID, seeds)?;
+        }
+
+        ctx.invoke(&token_program::initialize_account(&address, &mint, &wallet))
+    }
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo ata`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(TokenProgram));
+    bank.add_program(Box::new(AssociatedTokenProgram));
+
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let mint = DemoSigner::new("usdc mint");
+    bank.airdrop(&alice.pubkey(), 10_000_000_000)
+        .map_err(|err| err.to_string())?;
+
+    let alice_ata = get_associated_token_address(&alice.pubkey(), &mint.pubkey());
+    let (bob_ata, bump) = get_associated_token_address_and_bump(&bob.pubkey(), &mint.pubkey());
+    println!("Mint          {}", mint.pubkey());
+    println!("alice's ATA   {}", alice_ata);
+    println!("bob's ATA     {} (bump {})", bob_ata, bump);
+    println!("on curve:     {}", bob_ata.is_on_curve());
+
+    let mut send = |title: &str, instructions: &[Instruction], signers: &[&dyn Signer]| {
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&alice.pubkey()),
+            signers,
+            bank.last_blockhash(),
+        );
+        let outcome = bank.execute_transaction(&tx);
+        print_outcome(title, &outcome.status, &outcome.logs);
+        bank.advance_slots(1);
+    };
+
+    // Alice creates a mint and her own associated account, and mints to it
+    send(
+        "Create a mint and alice's associated account",
+        &[
+            system_program::create_account(
+                &alice.pubkey(),
+                &mint.pubkey(),
+                1_000_000,
+                token_program::MINT_LEN as u64,
+                &token_program::ID,
+            ),
+            token_program::initialize_mint(&mint.pubkey(), &alice.pubkey(), None, 6),
+            create_associated_token_account(&alice.pubkey(), &alice.pubkey(), &mint.pubkey()),
+            token_program::mint_to(&mint.pubkey(), &alice_ata, &alice.pubkey(), &[], 5_000_000),
+        ],
+        &[&alice, &mint],
+    );
+
+    // Paying bob: open his account if needed, then transfer. Running it
+    // twice is fine because the create is idempotent.
+    for attempt in 1..=2 {
+        send(
+            &format!("Pay bob 1.5 tokens (attempt {})", attempt),
+            &[
+                create_associated_token_account_idempotent(
+                    &alice.pubkey(),
+                    &bob.pubkey(),
+                    &mint.pubkey(),
+                ),
+                token_program::transfer(&alice_ata, &bob_ata, &alice.pubkey(), &[], 1_500_000),
+            ],
+            &[&alice],
+        );
+    }
+
+    // The non-idempotent create refuses an account that exists
+    send(
+        "Create bob's account again",
+        &[create_associated_token_account(
+            &alice.pubkey(),
+            &bob.pubkey(),
+            &mint.pubkey(),
+        )],
+        &[&alice],
+    );
+
+    // An address that is not the derived one is rejected
+    let mut wrong = create_associated_token_account(&alice.pubkey(), &bob.pubkey(), &mint.pubkey());
+    wrong.accounts[1].pubkey = Pubkey::new_unique();
+    send("Create at a made-up address", &[wrong], &[&alice]);
+
+    for (name, address) in [("alice", alice_ata), ("bob", bob_ata)] {
+        let account = bank
+            .get_account(&address)
+            .ok_or("associated account missing")?;
+        let state = TokenAccount::unpack(&account.data).map_err(|err| err.to_string())?;
+        println!("\n{} holds {} base units", name, state.amount);
+    }
+    Ok(())
+}
@@ -0,0 +1,816 @@
+// ========================================================================
+// THE BANK: EXECUTING TRANSACTIONS
+// ========================================================================
+//
+// A `Bank` ties the runtime together: the account store, the slot clock
+// and its recent blockhashes, and the registered programs. Processing a
+// transaction is all-or-nothing:
+//
+//     1. the message is well formed and every signature verifies
+//     2. its blockhash is still in the recent blockhash queue
+//     3. its signature has not already been processed (replay protection)
+//     4. the fee payer can cover the fee (see fee.rs), which is charged
+//        right away and kept whatever happens next
+//     5. every instruction runs in order against a working copy of the
+//        accounts; only if all of them succeed are the writable accounts
+//        written back
+//     6. the outcome, with balances before and after, goes into the
+//        transaction history
+//
+// A signature is remembered for as long as its blockhash is valid. After
+// that the blockhash check alone rejects the transaction, so the status
+// cache never grows without bound.
+//
+// The fee and the instructions are applied to a working copy first, and
+// the bank is only written once the transaction is done. That is what
+// lets execute_batch run transactions that lock different accounts on
+// several threads at once (see scheduler.rs).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::address_lookup_table_program::{self, AddressLookupTableProgram};
+use crate::clock::{self, BlockhashQueue, Clock, SlotClock, CLOCK_SYSVAR_ID, SYSVAR_OWNER_ID};
+use crate::compute_budget::{self, ComputeBudgetProgram};
+use crate::fee::{FeeDetails, FeeStructure};
+use crate::hash::Hash;
+use crate::history::TransactionHistory;
+use crate::ledger::{Account, AccountStore};
+use crate::program::{self, InstructionAccount, InstructionError, Program, TransactionContext};
+use crate::pubkey::{Pubkey, SYSTEM_PROGRAM_ID};
+use crate::scheduler::{self, TransactionLocks};
+use crate::simulation::Simulation;
+use crate::system_program::SystemProgram;
+use crate::transaction::{
+    LoadedMessage, Message, Signature, Transaction, VersionedMessage, VersionedTransaction,
+};
+
+pub const NATIVE_LOADER_ID: Pubkey =
+    Pubkey::from_str_const("NativeLoader1111111111111111111111111111111");
+
+// Balance of sysvar and builtin program accounts (the rent-exempt
+// minimum for a small account)
+const SYSVAR_LAMPORTS: u64 = 1_169_280;
+
+// ----------------------------------------
+// Transaction Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    // The message is malformed (bad header or account index)
+    SanitizeFailure,
+    SignatureFailure,
+    BlockhashNotFound,
+    AlreadyProcessed,
+    // The fee payer does not exist
+    AccountNotFound,
+    // The fee payer cannot pay the fee
+    InsufficientFundsForFee,
+    // The fee payer is not a plain system account
+    InvalidAccountForFee,
+    // A compute budget setting appears twice, the second at this index
+    DuplicateInstruction(u8),
+    // Instruction `index` failed; nothing was committed
+    InstructionError(u8, InstructionError),
+    // The same key appears twice among the message's accounts, counting
+    // the ones loaded from lookup tables
+    AccountLoadedTwice,
+    // A lookup names a table that does not exist or is fully deactivated
+    AddressLookupTableNotFound,
+    InvalidAddressLookupTableOwner,
+    InvalidAddressLookupTableData,
+    // A lookup index past the table's usable addresses
+    InvalidAddressLookupTableIndex,
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransactionError::SanitizeFailure => write!(f, "transaction failed to sanitize"),
+            TransactionError::SignatureFailure => {
+                write!(f, "transaction did not pass signature verification")
+            }
+            TransactionError::BlockhashNotFound => write!(f, "blockhash not found"),
+            TransactionError::AlreadyProcessed => {
+                write!(f, "this transaction has already been processed")
+            }
+            TransactionError::AccountNotFound => write!(
+                f,
+                "attempt to debit an account but found no record of a prior credit"
+            ),
+            TransactionError::InsufficientFundsForFee => {
+                write!(f, "insufficient funds for fee")
+            }
+            TransactionError::InvalidAccountForFee => {
+                write!(f, "this account may not be used to pay transaction fees")
+            }
+            TransactionError::DuplicateInstruction(index) => write!(
+                f,
+                "transaction contains a duplicate instruction ({}) that is not allowed",
+                index
+            ),
+            TransactionError::InstructionError(index, err) => {
+                write!(f, "error processing instruction {}: {}", index, err)
+            }
+            TransactionError::AccountLoadedTwice => write!(f, "account loaded twice"),
+            TransactionError::AddressLookupTableNotFound => write!(
+                f,
+                "transaction loads an address table account that doesn't exist"
+            ),
+            TransactionError::InvalidAddressLookupTableOwner => write!(
+                f,
+                "transaction loads an address table account with an invalid owner"
+            ),
+            TransactionError::InvalidAddressLookupTableData => write!(
+                f,
+                "transaction loads an address table account with invalid data"
+            ),
+            TransactionError::InvalidAddressLookupTableIndex => {
+                write!(f, "transaction address table lookup uses an invalid index")
+            }
+        }
+    }
+}
+
+// What happened to one transaction. Transactions rejected before
+// execution have no account keys or balances.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionOutcome {
+    pub signature: Signature,
+    pub slot: u64,
+    pub status: Result<(), TransactionError>,
+    // Zero for transactions rejected before execution
+    pub fee: FeeDetails,
+    // Balances line up with the message's account keys
+    pub account_keys: Vec<Pubkey>,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub logs: Vec<String>,
+    pub compute_units_consumed: u64,
+}
+
+impl TransactionOutcome {
+    // Whether it got as far as charging a fee and running, successfully
+    // or not
+    pub fn was_executed(&self) -> bool {
+        !self.account_keys.is_empty()
+    }
+}
+
+// A transaction run against a working copy of its accounts, waiting to be
+// committed
+struct ExecutedTransaction {
+    outcome: TransactionOutcome,
+    // None if it was rejected before execution
+    loaded: Option<(LoadedMessage, Vec<Account>)>,
+}
+
+// How the demos show a transaction's result and its program logs
+pub fn print_outcome(title: &str, status: &Result<(), TransactionError>, logs: &[String]) {
+    match status {
+        Ok(()) => println!("\n{}: ok", title),
+        Err(err) => println!("\n{}: {}", title, err),
+    }
+    for line in logs {
+        println!("  {}", line);
+    }
+}
+
+// ----------------------------------------
+// Bank
+// ----------------------------------------
+
+pub struct Bank {
+    accounts: AccountStore,
+    slot_clock: SlotClock,
+    blockhash_queue: BlockhashQueue,
+    programs: HashMap<Pubkey, Box<dyn Program>>,
+    // Processed signatures and the blockhash each one used
+    status_cache: HashMap<Signature, Hash>,
+    history: TransactionHistory,
+    fee_structure: FeeStructure,
+    // Every fee charged so far. They leave circulation here; mainnet burns
+    // half and pays the rest to the slot leader.
+    collected_fees: u64,
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        Bank::with_accounts(AccountStore::new())
+    }
+
+    // Start from existing accounts, such as a loaded snapshot
+    pub fn with_accounts(accounts: AccountStore) -> Self {
+        Bank::with_clock(accounts, SlotClock::default())
+    }
+
+    pub fn with_clock(accounts: AccountStore, slot_clock: SlotClock) -> Self {
+        let mut bank = Bank {
+            accounts,
+            slot_clock,
+            blockhash_queue: BlockhashQueue::default(),
+            programs: HashMap::new(),
+            status_cache: HashMap::new(),
+            history: TransactionHistory::new(),
+            fee_structure: FeeStructure::default(),
+            collected_fees: 0,
+        };
+        bank.blockhash_queue
+            .register(clock::genesis_blockhash(), bank.slot());
+        bank.update_clock_sysvar();
+        bank.add_program(Box::new(SystemProgram));
+        bank.add_program(Box::new(ComputeBudgetProgram));
+        bank.add_program(Box::new(AddressLookupTableProgram));
+        bank
+    }
+
+    // Register a builtin program and create its executable account
+    pub fn add_program(&mut self, program: Box<dyn Program>) {
+        let id = program.id();
+        let mut account = Account::new(SYSVAR_LAMPORTS, 0, &NATIVE_LOADER_ID);
+        account.data = program.name().as_bytes().to_vec();
+        account.executable = true;
+        self.accounts.store(id, account);
+        self.programs.insert(id, program);
+    }
+
+    pub fn accounts(&self) -> &AccountStore {
+        &self.accounts
+    }
+
+    pub fn get_account(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    pub fn get_balance(&self, pubkey: &Pubkey) -> u64 {
+        self.accounts.lamports(pubkey)
+    }
+
+    // Set an account directly, bypassing programs (genesis and test setup)
+    pub fn store_account(&mut self, pubkey: Pubkey, account: Account) {
+        self.accounts.store(pubkey, account);
+    }
+
+    // Credit lamports out of thin air, like a test validator's faucet.
+    // Fails rather than wrap past u64::MAX.
+    pub fn airdrop(&mut self, pubkey: &Pubkey, lamports: u64) -> Result<(), InstructionError> {
+        let balance = match self.accounts.get_mut(pubkey) {
+            Some(account) => {
+                account.lamports = account
+                    .lamports
+                    .checked_add(lamports)
+                    .ok_or(InstructionError::ArithmeticOverflow)?;
+                account.lamports
+            }
+            None => {
+                self.accounts
+                    .store(*pubkey, Account::new(lamports, 0, &SYSTEM_PROGRAM_ID));
+                lamports
+            }
+        };
+        self.history.record_balance(pubkey, self.slot(), balance);
+        Ok(())
+    }
+
+    pub fn history(&self) -> &TransactionHistory {
+        &self.history
+    }
+
+    // ----------------------------------------
+    // Fees
+    // ----------------------------------------
+
+    // What `message` would be charged (fails if its compute budget
+    // instructions are invalid)
+    pub fn get_fee_for_message(&self, message: &Message) -> Result<FeeDetails, TransactionError> {
+        let budget = compute_budget::process_compute_budget_instructions(message)?;
+        Ok(self.fee_structure.calculate_fee(message, &budget))
+    }
+
+    pub fn collected_fees(&self) -> u64 {
+        self.collected_fees
+    }
+
+    // ----------------------------------------
+    // Time
+    // ----------------------------------------
+
+    pub fn slot(&self) -> u64 {
+        self.slot_clock.slot()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.slot_clock.epoch()
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.slot_clock.clock()
+    }
+
+    pub fn last_blockhash(&self) -> Hash {
+        self.blockhash_queue
+            .latest()
+            .expect("the genesis blockhash is always registered")
+    }
+
+    pub fn blockhash_queue(&self) -> &BlockhashQueue {
+        &self.blockhash_queue
+    }
+
+    // Produce `slots` new slots, each with its own blockhash
+    pub fn advance_slots(&mut self, slots: u64) {
+        for _ in 0..slots {
+            let slot = self.slot_clock.advance(1);
+            let blockhash = clock::next_blockhash(&self.last_blockhash(), slot);
+            self.blockhash_queue.register(blockhash, slot);
+        }
+        self.update_clock_sysvar();
+
+        let queue = &self.blockhash_queue;
+        self.status_cache
+            .retain(|_, blockhash| queue.is_valid(blockhash));
+    }
+
+    fn update_clock_sysvar(&mut self) {
+        let mut account = Account::new(SYSVAR_LAMPORTS, 0, &SYSVAR_OWNER_ID);
+        account.data = self.clock().to_bytes().to_vec();
+        self.accounts.store(CLOCK_SYSVAR_ID, account);
+    }
+
+    // ----------------------------------------
+    // Processing
+    // ----------------------------------------
+
+    pub fn process_transaction(&mut self, tx: &Transaction) -> Result<(), TransactionError> {
+        self.execute_transaction(tx).status
+    }
+
+    pub fn execute_transaction(&mut self, tx: &Transaction) -> TransactionOutcome {
+        self.execute_versioned_transaction(&VersionedTransaction::from(tx.clone()))
+    }
+
+    // Legacy and v0 transactions alike. Lookup tables are read as they are
+    // before the transaction runs.
+    pub fn execute_versioned_transaction(
+        &mut self,
+        tx: &VersionedTransaction,
+    ) -> TransactionOutcome {
+        let executed = self.load_and_execute(tx);
+        let outcome = self.commit_transaction(tx, executed);
+        if outcome.was_executed() {
+            self.history.record(outcome.clone());
+        }
+        outcome
+    }
+
+    // Run a batch of transactions with up to `threads` at a time, leaving
+    // the bank and its history exactly as executing them one by one in
+    // order would (see scheduler.rs). Returns their outcomes in order.
+    pub fn execute_batch(
+        &mut self,
+        txs: &[VersionedTransaction],
+        threads: usize,
+    ) -> Vec<TransactionOutcome> {
+        let locks: Vec<_> = txs
+            .iter()
+            .map(|tx| TransactionLocks::new(self, tx))
+            .collect();
+        let mut outcomes: Vec<Option<TransactionOutcome>> = vec![None; txs.len()];
+        for wave in scheduler::schedule(&locks) {
+            let executed = self.execute_wave(txs, &wave, threads);
+            for (i, executed) in wave.into_iter().zip(executed) {
+                // The warmup rule (see scheduler.rs) keeps these current
+                if let (Some(locks), Some((message, _))) = (&locks[i], &executed.loaded) {
+                    assert_eq!(
+                        *locks,
+                        TransactionLocks::for_message(&txs[i], message),
+                        "transaction {} loaded accounts it was not scheduled with",
+                        i
+                    );
+                }
+                outcomes[i] = Some(self.commit_transaction(&txs[i], executed));
+            }
+        }
+
+        // Waves finish out of batch order, so the history is written last
+        let outcomes: Vec<_> = outcomes
+            .into_iter()
+            .map(|outcome| outcome.expect("every transaction is in a wave"))
+            .collect();
+        for outcome in &outcomes {
+            if outcome.was_executed() {
+                self.history.record(outcome.clone());
+            }
+        }
+        outcomes
+    }
+
+    // Transactions that lock no account in common, run side by side
+    // against the bank as it is. Threads only live for one wave: the next
+    // wave may need to see what this one commits.
+    fn execute_wave(
+        &self,
+        txs: &[VersionedTransaction],
+        wave: &[usize],
+        threads: usize,
+    ) -> Vec<ExecutedTransaction> {
+        if wave.len() == 1 || threads <= 1 {
+            return wave
+                .iter()
+                .map(|&i| self.load_and_execute(&txs[i]))
+                .collect();
+        }
+        let next = AtomicUsize::new(0);
+        let mut executed: Vec<Option<ExecutedTransaction>> = wave.iter().map(|_| None).collect();
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.min(wave.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let n = next.fetch_add(1, Ordering::Relaxed);
+                            match wave.get(n) {
+                                Some(&i) => done.push((n, self.load_and_execute(&txs[i]))),
+                                None => return done,
+                            }
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                for (n, result) in worker.join().expect("transaction worker panicked") {
+                    executed[n] = Some(result);
+                }
+            }
+        });
+        executed
+            .into_iter()
+            .map(|result| result.expect("every transaction in the wave ran"))
+            .collect()
+    }
+
+    // Checks and runs a transaction against a working copy of its accounts,
+    // with the fee already taken out of the payer's copy. Nothing in the
+    // bank changes until commit_transaction.
+    fn load_and_execute(&self, tx: &VersionedTransaction) -> ExecutedTransaction {
+        let mut outcome = self.empty_outcome(tx);
+        let (message, fee) = match self.check_transaction(tx) {
+            Ok(checked) => checked,
+            Err(err) => {
+                outcome.status = Err(err);
+                return ExecutedTransaction {
+                    outcome,
+                    loaded: None,
+                };
+            }
+        };
+
+        let keys = message.account_keys();
+        outcome.account_keys = keys.to_vec();
+        outcome.pre_balances = keys.iter().map(|key| self.get_balance(key)).collect();
+        outcome.fee = fee;
+
+        let mut accounts = self.load_accounts(&message);
+        accounts[0].lamports -= fee.total();
+        // A payer the fee empties is gone by the time the instructions run
+        if accounts[0].lamports == 0 {
+            accounts[0] = Account::default();
+        }
+        outcome.status = self.execute_instructions(
+            &message,
+            &mut accounts,
+            &mut outcome.logs,
+            &mut outcome.compute_units_consumed,
+        );
+        ExecutedTransaction {
+            outcome,
+            loaded: Some((message, accounts)),
+        }
+    }
+
+    // Charge the fee whatever happened, and write the accounts back if
+    // every instruction succeeded. The caller records the outcome in the
+    // history.
+    fn commit_transaction(
+        &mut self,
+        tx: &VersionedTransaction,
+        executed: ExecutedTransaction,
+    ) -> TransactionOutcome {
+        let mut outcome = executed.outcome;
+        let Some((message, accounts)) = executed.loaded else {
+            return outcome;
+        };
+        let keys = message.account_keys();
+        self.charge_fee(&keys[0], outcome.fee.total());
+        if outcome.status.is_ok() {
+            self.commit_accounts(&message, accounts);
+        }
+        outcome.post_balances = keys.iter().map(|key| self.get_balance(key)).collect();
+
+        self.status_cache
+            .insert(tx.signature(), *tx.message.recent_blockhash());
+        outcome
+    }
+
+    // Run a transaction as execute_transaction would, but commit nothing:
+    // no fee is charged, no account changes and nothing is recorded. The
+    // post balances are what the transaction would leave behind.
+    // Signatures are only checked if asked, so a client can try a
+    // transaction out before signing it. One the bank already processed
+    // fails with AlreadyProcessed, as sending it again would.
+    pub fn simulate_versioned_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        verify_signatures: bool,
+    ) -> TransactionOutcome {
+        self.simulate_execution(tx, verify_signatures).outcome
+    }
+
+    // The same simulation, reported with token balances and every account
+    // it would change (see simulation.rs)
+    pub fn simulate(&self, tx: &VersionedTransaction, verify_signatures: bool) -> Simulation {
+        let executed = self.simulate_execution(tx, verify_signatures);
+        let post_accounts = executed
+            .loaded
+            .map_or_else(Vec::new, |(_, accounts)| accounts);
+        Simulation::new(self, executed.outcome, post_accounts)
+    }
+
+    // Runs against a working copy of the message's accounts, so the rest
+    // of the bank is only read. The accounts returned are those the
+    // transaction would leave behind: after its instructions if they all
+    // succeed, otherwise as they were with only the fee taken.
+    fn simulate_execution(
+        &self,
+        tx: &VersionedTransaction,
+        verify_signatures: bool,
+    ) -> ExecutedTransaction {
+        let mut outcome = self.empty_outcome(tx);
+        let (message, fee) = match self.check_simulation(tx, verify_signatures) {
+            Ok(checked) => checked,
+            Err(err) => {
+                outcome.status = Err(err);
+                return ExecutedTransaction {
+                    outcome,
+                    loaded: None,
+                };
+            }
+        };
+
+        let keys = message.account_keys();
+        outcome.account_keys = keys.to_vec();
+        outcome.pre_balances = keys.iter().map(|key| self.get_balance(key)).collect();
+        outcome.fee = fee;
+
+        let mut charged = self.load_accounts(&message);
+        charged[0].lamports -= fee.total();
+        let mut accounts = charged.clone();
+        outcome.status = self.execute_instructions(
+            &message,
+            &mut accounts,
+            &mut outcome.logs,
+            &mut outcome.compute_units_consumed,
+        );
+        let after = if outcome.status.is_ok() {
+            accounts
+        } else {
+            charged
+        };
+        outcome.post_balances = after.iter().map(|account| account.lamports).collect();
+        ExecutedTransaction {
+            outcome,
+            loaded: Some((message, after)),
+        }
+    }
+
+    // Every account a message uses, looking up a v0 message's table
+    // addresses in the current slot
+    pub fn resolve_message(
+        &self,
+        message: &VersionedMessage,
+    ) -> Result<LoadedMessage, TransactionError> {
+        let loaded = address_lookup_table_program::load_addresses(
+            &self.accounts,
+            message.address_table_lookups(),
+            self.slot(),
+        )?;
+        let message = LoadedMessage::new(message, loaded);
+        if message.has_duplicate_keys() {
+            return Err(TransactionError::AccountLoadedTwice);
+        }
+        Ok(message)
+    }
+
+    // Rejected before execution: no keys, balances or fee
+    fn empty_outcome(&self, tx: &VersionedTransaction) -> TransactionOutcome {
+        TransactionOutcome {
+            signature: tx.signature(),
+            slot: self.slot(),
+            status: Ok(()),
+            fee: FeeDetails::default(),
+            account_keys: Vec::new(),
+            pre_balances: Vec::new(),
+            post_balances: Vec::new(),
+            logs: Vec::new(),
+            compute_units_consumed: 0,
+        }
+    }
+
+    // Everything that can reject a transaction before it runs. Returns the
+    // resolved message and the fee the payer is able to pay.
+    fn check_transaction(
+        &self,
+        tx: &VersionedTransaction,
+    ) -> Result<(LoadedMessage, FeeDetails), TransactionError> {
+        sanitize(tx)?;
+        if !self.blockhash_queue.is_valid(tx.message.recent_blockhash()) {
+            return Err(TransactionError::BlockhashNotFound);
+        }
+        if self.status_cache.contains_key(&tx.signature()) {
+            return Err(TransactionError::AlreadyProcessed);
+        }
+        if !tx.verify() {
+            return Err(TransactionError::SignatureFailure);
+        }
+        let message = self.resolve_message(&tx.message)?;
+        let fee = self.check_fee_payer(message.message())?;
+        Ok((message, fee))
+    }
+
+    // The same checks, with signatures optional
+    fn check_simulation(
+        &self,
+        tx: &VersionedTransaction,
+        verify_signatures: bool,
+    ) -> Result<(LoadedMessage, FeeDetails), TransactionError> {
+        sanitize(tx)?;
+        if !self.blockhash_queue.is_valid(tx.message.recent_blockhash()) {
+            return Err(TransactionError::BlockhashNotFound);
+        }
+        if self.status_cache.contains_key(&tx.signature()) {
+            return Err(TransactionError::AlreadyProcessed);
+        }
+        if verify_signatures && !tx.verify() {
+            return Err(TransactionError::SignatureFailure);
+        }
+        let message = self.resolve_message(&tx.message)?;
+        let fee = self.check_fee_payer(message.message())?;
+        Ok((message, fee))
+    }
+
+    fn check_fee_payer(&self, message: &Message) -> Result<FeeDetails, TransactionError> {
+        let fee = self.get_fee_for_message(message)?;
+        let payer = message
+            .fee_payer()
+            .and_then(|payer| self.accounts.get(payer))
+            .ok_or(TransactionError::AccountNotFound)?;
+        if payer.owner != SYSTEM_PROGRAM_ID || !payer.data.is_empty() {
+            return Err(TransactionError::InvalidAccountForFee);
+        }
+        if payer.lamports < fee.total() {
+            return Err(TransactionError::InsufficientFundsForFee);
+        }
+        Ok(fee)
+    }
+
+    fn charge_fee(&mut self, payer: &Pubkey, fee: u64) {
+        let remaining = self.accounts.lamports(payer) - fee;
+        if remaining == 0 {
+            self.accounts.remove(payer);
+        } else if let Some(account) = self.accounts.get_mut(payer) {
+            account.lamports = remaining;
+        }
+        self.collected_fees += fee;
+    }
+
+    // A working copy of the message's accounts. Missing accounts start out
+    // empty and owned by the System Program.
+    fn load_accounts(&self, message: &LoadedMessage) -> Vec<Account> {
+        message
+            .account_keys()
+            .iter()
+            .map(|key| self.accounts.get(key).cloned().unwrap_or_default())
+            .collect()
+    }
+
+    // Run every instruction in order against the working copy, stopping at
+    // the first failure
+    fn execute_instructions(
+        &self,
+        message: &LoadedMessage,
+        accounts: &mut [Account],
+        logs: &mut Vec<String>,
+        compute_units_consumed: &mut u64,
+    ) -> Result<(), TransactionError> {
+        let clock = self.clock();
+        let keys = message.account_keys();
+        let budget = compute_budget::process_compute_budget_instructions(message.message())?;
+
+        for (index, instruction) in message.message().instructions.iter().enumerate() {
+            let program_id = keys[instruction.program_id_index as usize];
+            let instruction_accounts = instruction
+                .accounts
+                .iter()
+                .map(|&i| InstructionAccount {
+                    index: i as usize,
+                    is_signer: message.is_signer(i as usize),
+                    is_writable: message.is_writable(i as usize),
+                })
+                .collect();
+
+            let result = match self.programs.get(&program_id) {
+                Some(program) => program::invoke_program(
+                    program.as_ref(),
+                    TransactionContext {
+                        keys,
+                        accounts,
+                        programs: &self.programs,
+                        clock: &clock,
+                        logs,
+                        compute_units_consumed,
+                        compute_unit_limit: budget.compute_unit_limit as u64,
+                    },
+                    instruction_accounts,
+                    &instruction.data,
+                    &[],
+                ),
+                None => Err(InstructionError::UnsupportedProgramId),
+            };
+            if let Err(err) = result {
+                return Err(TransactionError::InstructionError(index as u8, err));
+            }
+        }
+        Ok(())
+    }
+
+    // Write the writable accounts back once every instruction succeeded
+    fn commit_accounts(&mut self, message: &LoadedMessage, accounts: Vec<Account>) {
+        for (i, (key, account)) in message.account_keys().iter().zip(accounts).enumerate() {
+            if !message.is_writable(i) {
+                continue;
+            }
+            // Accounts left with no lamports are garbage collected
+            if account.lamports == 0 {
+                self.accounts.remove(key);
+            } else {
+                self.accounts.store(*key, account);
+            }
+        }
+    }
+}
+
+impl Default for Bank {
+    fn default() -> Self {
+        Bank::new()
+    }
+}
+
+// Structural checks that need no account state
+pub fn sanitize(tx: &VersionedTransaction) -> Result<(), TransactionError> {
+    let message = &tx.message;
+    let header = message.header();
+    let num_static_keys = message.static_account_keys().len();
+    let signers = header.num_required_signatures as usize;
+
+    if signers == 0
+        || tx.signatures.len() != signers
+        || signers > num_static_keys
+        || header.num_readonly_signed_accounts as usize >= signers
+        || header.num_readonly_unsigned_accounts as usize > num_static_keys - signers
+    {
+        return Err(TransactionError::SanitizeFailure);
+    }
+
+    // Every lookup loads something, and the total stays addressable by a u8
+    let lookups = message.address_table_lookups();
+    if lookups
+        .iter()
+        .any(|lookup| lookup.writable_indexes.is_empty() && lookup.readonly_indexes.is_empty())
+    {
+        return Err(TransactionError::SanitizeFailure);
+    }
+    let num_keys = num_static_keys
+        + lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+            .sum::<usize>();
+    if num_keys > 256 {
+        return Err(TransactionError::SanitizeFailure);
+    }
+
+    for instruction in message.instructions() {
+        // The fee payer can never be the program being invoked, and
+        // programs cannot come from a lookup table
+        let program_index = instruction.program_id_index as usize;
+        if program_index == 0 || program_index >= num_static_keys {
+            return Err(TransactionError::SanitizeFailure);
+        }
+        if instruction.accounts.iter().any(|&i| i as usize >= num_keys) {
+            return Err(TransactionError::SanitizeFailure);
+        }
+    }
+    Ok(())
+}
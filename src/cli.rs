@@ -0,0 +1,305 @@
+// ========================================================================
+// COMMAND LINE TOOLS
+// ========================================================================
+//
+// Running `simply_rust` with no arguments prints the whole cheat sheet.
+// Passing a command runs one of the tools below instead.
+
+use crate::account_view;
+use crate::address_lookup_table_program;
+use crate::amm_program;
+use crate::associated_token_program;
+use crate::bank::Bank;
+use crate::compile_fail;
+use crate::escrow_program;
+use crate::exercise;
+use crate::fee;
+use crate::grind;
+use crate::history;
+use crate::keypair;
+use crate::layout;
+use crate::ledger::{self, AccountStore};
+use crate::mnemonic;
+use crate::multisig;
+use crate::nft_program;
+use crate::property;
+use crate::rpc::{self, RpcServer};
+use crate::scheduler;
+use crate::schema::{self, FieldTree, Schema};
+use crate::simulation;
+use crate::timelock;
+use crate::transaction_builder;
+
+const USAGE: &str = "\
+Usage: simply_rust [COMMAND]
+
+With no command, prints the full cheat sheet.
+
+Commands:
+  inspect <schema-file> <hex | @file>   Decode account bytes with a layout schema
+  layout [Type]                         Show a type's size, alignment and field offsets
+  exercise [section | all]              Scaffold and check practice exercises
+  compile-fail [snippet]                Check (or show) the compile-fail examples
+  snapshot create <file>                Write the demo ledger to a snapshot file
+  snapshot show <file>                  List the accounts in a snapshot file
+  demo [name]                           Run (or list) the runtime demos
+  bench [name]                          Run (or list) the benchmarks
+  check [property] [--seed N] [--cases N]
+                                        Check the properties on random inputs
+  serve [--port N] [--snapshot <file>]  Answer Solana JSON-RPC calls on localhost
+  keygen [--outfile <file>] [--force]   Write a new keypair file (solana-keygen format)
+  keygen pubkey <file>                  Print the public key of a keypair file
+  grind [--starts-with <p>] [--ends-with <s>] [--threads N] [--count N]
+                                        Search for vanity addresses, writing <pubkey>.json
+  help                                  Show this message";
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let command = args[0].as_str();
+    let rest = &args[1..];
+
+    match command {
+        "inspect" => inspect(rest),
+        "layout" => layout::run(rest),
+        "exercise" => exercise::run(rest),
+        "compile-fail" => compile_fail::run(rest),
+        "snapshot" => snapshot(rest),
+        "demo" => demo(rest),
+        "bench" => bench(rest),
+        "check" => property::run(rest),
+        "serve" => serve(rest),
+        "keygen" => keypair::keygen(rest),
+        "grind" => grind::run(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(format!("unknown command `{}`\n\n{}", other, USAGE)),
+    }
+}
+
+// simply_rust inspect schemas/token_pair.layout 0100000000ffffff...
+// simply_rust inspect schemas/token_pair.layout @account.bin
+fn inspect(args: &[String]) -> Result<(), String> {
+    let (schema_path, input) = match args {
+        [schema_path, input] => (schema_path, input),
+        _ => return Err("usage: simply_rust inspect <schema-file> <hex | @file>".to_string()),
+    };
+
+    let schema = Schema::from_file(schema_path).map_err(|err| err.to_string())?;
+    let data = match input.strip_prefix('@') {
+        Some(path) => {
+            std::fs::read(path).map_err(|err| format!("cannot read {}: {}", path, err))?
+        }
+        None => schema::parse_hex(input)?,
+    };
+
+    println!("{} ({} bytes)", schema.name, data.len());
+    let fields = schema
+        .decode(&data)
+        .map_err(|err| format!("decode failed: {}", err))?;
+    print!("{}", FieldTree(&fields));
+    Ok(())
+}
+
+// simply_rust snapshot create demo.snapshot
+// simply_rust snapshot show demo.snapshot
+fn snapshot(args: &[String]) -> Result<(), String> {
+    match args {
+        [action, path] if action == "create" => {
+            let store = ledger::demo_store();
+            store.save_snapshot(path).map_err(|err| err.to_string())?;
+            println!("Wrote {} accounts to {}", store.len(), path);
+            Ok(())
+        }
+        [action, path] if action == "show" => {
+            let store = AccountStore::load_snapshot(path).map_err(|err| err.to_string())?;
+            let total = store
+                .total_lamports()
+                .ok_or("the accounts hold more than u64::MAX lamports in total")?;
+            println!("{} accounts, {} in total", store.len(), total);
+            for (pubkey, account) in store.sorted() {
+                println!(
+                    "  {:<44} {:>18} lamports  owner {}  data {} bytes{}",
+                    pubkey.to_string(),
+                    account.lamports,
+                    account.owner,
+                    account.data.len(),
+                    if account.executable {
+                        "  executable"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            Ok(())
+        }
+        _ => Err("usage: simply_rust snapshot <create | show> <file>".to_string()),
+    }
+}
+
+// Runtime walkthroughs, each defined next to the code it exercises
+struct Demo {
+    name: &'static str,
+    description: &'static str,
+    run: fn() -> Result<(), String>,
+}
+
+const DEMOS: &[Demo] = &[
+    Demo {
+        name: "timelock",
+        description: "Slots, the Clock sysvar, blockhash expiry and replay protection",
+        run: timelock::demo,
+    },
+    Demo {
+        name: "history",
+        description: "Transaction history: signatures by address, balances by slot",
+        run: history::demo,
+    },
+    Demo {
+        name: "multisig",
+        description: "A 2-of-3 multisig as mint authority and token account owner",
+        run: multisig::demo,
+    },
+    Demo {
+        name: "nft",
+        description: "NFT metadata, verified collections, limited prints and royalties",
+        run: nft_program::demo,
+    },
+    Demo {
+        name: "ata",
+        description: "Associated token accounts: derived addresses, idempotent creation",
+        run: associated_token_program::demo,
+    },
+    Demo {
+        name: "fees",
+        description: "Base and priority fees, charged even when a transaction fails",
+        run: fee::demo,
+    },
+    Demo {
+        name: "rpc",
+        description: "JSON-RPC requests and responses, without the HTTP server",
+        run: rpc::demo,
+    },
+    Demo {
+        name: "mnemonic",
+        description: "Seed phrases, wallet seeds and m/44'/501'/n'/0' derivation",
+        run: mnemonic::demo,
+    },
+    Demo {
+        name: "alt",
+        description: "Address lookup tables and v0 messages, legacy vs v0 sizes",
+        run: address_lookup_table_program::demo,
+    },
+    Demo {
+        name: "scheduler",
+        description: "Account locks and running non-conflicting transactions in parallel",
+        run: scheduler::demo,
+    },
+    Demo {
+        name: "amm",
+        description: "A constant-product SOL/USDC pool: swaps, slippage limits, liquidity",
+        run: amm_program::demo,
+    },
+    Demo {
+        name: "escrow",
+        description: "An escrow trading tokens through PDA-signed cross-program calls",
+        run: escrow_program::demo,
+    },
+    Demo {
+        name: "simulate",
+        description: "Simulation reports: compute units, token balances, changed accounts",
+        run: simulation::demo,
+    },
+    Demo {
+        name: "builder",
+        description: "Building transactions: account ordering, signer and size checks",
+        run: transaction_builder::demo,
+    },
+];
+
+// simply_rust demo timelock
+fn demo(args: &[String]) -> Result<(), String> {
+    let name = match args.first() {
+        Some(name) => name,
+        None => {
+            println!("Usage: simply_rust demo <name>\n\nDemos:");
+            for demo in DEMOS {
+                println!("  {:<12} {}", demo.name, demo.description);
+            }
+            return Ok(());
+        }
+    };
+    let demo = DEMOS.iter().find(|demo| demo.name == name).ok_or_else(|| {
+        format!(
+            "unknown demo `{}` (run `simply_rust demo` to list them)",
+            name
+        )
+    })?;
+    (demo.run)()
+}
+
+// Timing runs, best built with --release. Each one also checks that the
+// fast path gives the same answer as the plain one.
+const BENCHES: &[Demo] = &[
+    Demo {
+        name: "scheduler",
+        description: "Parallel batch execution against one transaction at a time",
+        run: scheduler::bench,
+    },
+    Demo {
+        name: "views",
+        description: "Zero-copy account views against full deserialization",
+        run: account_view::bench,
+    },
+];
+
+// simply_rust bench scheduler
+fn bench(args: &[String]) -> Result<(), String> {
+    let name = match args.first() {
+        Some(name) => name,
+        None => {
+            println!("Usage: simply_rust bench <name>\n\nBenchmarks:");
+            for bench in BENCHES {
+                println!("  {:<12} {}", bench.name, bench.description);
+            }
+            return Ok(());
+        }
+    };
+    let bench = BENCHES
+        .iter()
+        .find(|bench| bench.name == name)
+        .ok_or_else(|| {
+            format!(
+                "unknown benchmark `{}` (run `simply_rust bench` to list them)",
+                name
+            )
+        })?;
+    (bench.run)()
+}
+
+// simply_rust serve
+// simply_rust serve --port 8900 --snapshot demo.snapshot
+fn serve(args: &[String]) -> Result<(), String> {
+    const USAGE: &str = "usage: simply_rust serve [--port N] [--snapshot <file>]";
+    let mut port = rpc::DEFAULT_PORT;
+    let mut accounts = AccountStore::new();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(USAGE)?;
+        match flag.as_str() {
+            "--port" => {
+                port = value
+                    .parse()
+                    .map_err(|_| format!("invalid port `{}`", value))?
+            }
+            "--snapshot" => {
+                accounts = AccountStore::load_snapshot(value).map_err(|err| err.to_string())?
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let mut server =
+        RpcServer::new(Bank::with_accounts(accounts)).map_err(|err| err.to_string())?;
+    rpc::serve(&mut server, port).map_err(|err| format!("cannot serve on port {}: {}", port, err))
+}
@@ -0,0 +1,205 @@
+// ========================================================================
+// SLOTS, EPOCHS AND RECENT BLOCKHASHES
+// ========================================================================
+//
+// Solana measures time in slots (about 400ms each) grouped into epochs.
+// The simulated `SlotClock` only moves when told to, so every run of a
+// demo sees exactly the same slots, timestamps and blockhashes.
+//
+// Each slot produces a new blockhash. A transaction names a recent one,
+// and the `BlockhashQueue` only remembers the last MAX_PROCESSING_AGE of
+// them: once a transaction's blockhash ages out it is rejected, so an old
+// signed transaction cannot be replayed forever.
+
+use std::collections::VecDeque;
+
+use crate::hash::{hash, hashv, Hash};
+use crate::pubkey::Pubkey;
+
+pub const DEFAULT_MS_PER_SLOT: u64 = 400;
+
+// Mainnet epochs are 432,000 slots (about two days). Short epochs keep
+// epoch boundaries visible in demos.
+pub const DEFAULT_SLOTS_PER_EPOCH: u64 = 32;
+
+// How many newer blockhashes a transaction's blockhash may have before it
+// is considered stale (the same value Solana uses)
+pub const MAX_PROCESSING_AGE: usize = 150;
+
+// 2023-11-14 22:13:20 UTC, the timestamp of slot 0
+pub const GENESIS_UNIX_TIMESTAMP: i64 = 1_700_000_000;
+
+pub const SYSVAR_OWNER_ID: Pubkey =
+    Pubkey::from_str_const("Sysvar1111111111111111111111111111111111111");
+pub const CLOCK_SYSVAR_ID: Pubkey =
+    Pubkey::from_str_const("SysvarC1ock11111111111111111111111111111111");
+
+// ----------------------------------------
+// The Clock Sysvar
+// ----------------------------------------
+
+// What programs see of the current time. The bank keeps a copy in the
+// account CLOCK_SYSVAR_ID, serialized as five little-endian 8-byte fields
+// in this order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clock {
+    pub slot: u64,
+    pub epoch_start_timestamp: i64,
+    pub epoch: u64,
+    pub leader_schedule_epoch: u64,
+    pub unix_timestamp: i64,
+}
+
+impl Clock {
+    pub const SIZE: usize = 40;
+
+    pub fn to_bytes(self) -> [u8; Clock::SIZE] {
+        let mut out = [0u8; Clock::SIZE];
+        out[0..8].copy_from_slice(&self.slot.to_le_bytes());
+        out[8..16].copy_from_slice(&self.epoch_start_timestamp.to_le_bytes());
+        out[16..24].copy_from_slice(&self.epoch.to_le_bytes());
+        out[24..32].copy_from_slice(&self.leader_schedule_epoch.to_le_bytes());
+        out[32..40].copy_from_slice(&self.unix_timestamp.to_le_bytes());
+        out
+    }
+
+    pub fn from_account_data(data: &[u8]) -> Option<Clock> {
+        let data: &[u8; Clock::SIZE] = data.get(..Clock::SIZE)?.try_into().ok()?;
+        let field = |i: usize| -> [u8; 8] { data[i * 8..i * 8 + 8].try_into().unwrap() };
+        Some(Clock {
+            slot: u64::from_le_bytes(field(0)),
+            epoch_start_timestamp: i64::from_le_bytes(field(1)),
+            epoch: u64::from_le_bytes(field(2)),
+            leader_schedule_epoch: u64::from_le_bytes(field(3)),
+            unix_timestamp: i64::from_le_bytes(field(4)),
+        })
+    }
+}
+
+// ----------------------------------------
+// Slot Clock
+// ----------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct SlotClock {
+    slot: u64,
+    slots_per_epoch: u64,
+    ms_per_slot: u64,
+    genesis_unix_timestamp: i64,
+}
+
+impl SlotClock {
+    pub fn new(slots_per_epoch: u64, ms_per_slot: u64, genesis_unix_timestamp: i64) -> Self {
+        assert!(slots_per_epoch > 0, "an epoch needs at least one slot");
+        SlotClock {
+            slot: 0,
+            slots_per_epoch,
+            ms_per_slot,
+            genesis_unix_timestamp,
+        }
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.slot / self.slots_per_epoch
+    }
+
+    // Whole seconds, like the real sysvar
+    pub fn unix_timestamp_at(&self, slot: u64) -> i64 {
+        self.genesis_unix_timestamp + (slot * self.ms_per_slot / 1000) as i64
+    }
+
+    // Move forward and return the new slot
+    pub fn advance(&mut self, slots: u64) -> u64 {
+        self.slot += slots;
+        self.slot
+    }
+
+    pub fn clock(&self) -> Clock {
+        let epoch = self.epoch();
+        Clock {
+            slot: self.slot,
+            epoch_start_timestamp: self.unix_timestamp_at(epoch * self.slots_per_epoch),
+            epoch,
+            leader_schedule_epoch: epoch + 1,
+            unix_timestamp: self.unix_timestamp_at(self.slot),
+        }
+    }
+}
+
+impl Default for SlotClock {
+    fn default() -> Self {
+        SlotClock::new(
+            DEFAULT_SLOTS_PER_EPOCH,
+            DEFAULT_MS_PER_SLOT,
+            GENESIS_UNIX_TIMESTAMP,
+        )
+    }
+}
+
+// ----------------------------------------
+// Blockhashes
+// ----------------------------------------
+
+pub fn genesis_blockhash() -> Hash {
+    hash(b"simply_rust genesis")
+}
+
+// Each slot's blockhash chains from the previous one, so the sequence is
+// the same on every run
+pub fn next_blockhash(previous: &Hash, slot: u64) -> Hash {
+    hashv(&[previous.as_ref(), &slot.to_le_bytes()])
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockhashQueue {
+    max_age: usize,
+    // (blockhash, slot it was produced in), oldest first
+    entries: VecDeque<(Hash, u64)>,
+}
+
+impl BlockhashQueue {
+    pub fn new(max_age: usize) -> Self {
+        BlockhashQueue {
+            max_age,
+            entries: VecDeque::with_capacity(max_age + 1),
+        }
+    }
+
+    // Add the newest blockhash, forgetting any that are now too old
+    pub fn register(&mut self, blockhash: Hash, slot: u64) {
+        self.entries.push_back((blockhash, slot));
+        while self.entries.len() > self.max_age + 1 {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn latest(&self) -> Option<Hash> {
+        self.entries.back().map(|(blockhash, _)| *blockhash)
+    }
+
+    // Number of blockhashes registered after this one; None once expired
+    pub fn age(&self, blockhash: &Hash) -> Option<usize> {
+        self.entries
+            .iter()
+            .rev()
+            .position(|(entry, _)| entry == blockhash)
+    }
+
+    pub fn is_valid(&self, blockhash: &Hash) -> bool {
+        self.age(blockhash).is_some()
+    }
+
+    pub fn max_age(&self) -> usize {
+        self.max_age
+    }
+}
+
+impl Default for BlockhashQueue {
+    fn default() -> Self {
+        BlockhashQueue::new(MAX_PROCESSING_AGE)
+    }
+}
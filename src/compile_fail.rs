@@ -0,0 +1,203 @@
+// ========================================================================
+// COMPILE-FAIL DEMONSTRATIONS
+// ========================================================================
+//
+// The cheat sheet shows many mistakes as commented-out lines ("This would
+// cause an error: ..."). Each of those lives in `compile_fail/` as a
+// complete program that really fails to compile. The first lines of every
+// snippet name the section it belongs to and the error code rustc must
+// report:
+//
+//     // 1. BASIC CONCEPTS - Variables and Mutability
+//     // expected: E0384
+//
+// `simply_rust compile-fail` compiles every snippet with the local `rustc`
+// and checks that the expected code is the only error it reports, so a
+// snippet that breaks for some other reason shows up as a mismatch.
+// `simply_rust compile-fail <name>` prints one snippet together with the
+// compiler's actual diagnostic.
+
+use std::fs;
+use std::process::Command;
+
+struct Snippet {
+    name: &'static str,
+    source: &'static str,
+}
+
+macro_rules! snippet {
+    ($name:literal) => {
+        Snippet {
+            name: $name,
+            source: include_str!(concat!("../compile_fail/", $name, ".rs")),
+        }
+    };
+}
+
+// In cheat sheet order
+const SNIPPETS: &[Snippet] = &[
+    snippet!("immutable_reassign"),
+    snippet!("use_after_move"),
+    snippet!("moved_into_function"),
+    snippet!("double_mutable_borrow"),
+    snippet!("shared_while_mutable"),
+    snippet!("missing_lifetime"),
+    snippet!("struct_outlives_owner"),
+    snippet!("moved_by_concatenation"),
+    snippet!("zip_wrong_item_type"),
+];
+
+impl Snippet {
+    // Section title from the first comment line
+    fn section(&self) -> &str {
+        self.source
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("// "))
+            .unwrap_or("")
+    }
+
+    fn expected_code(&self) -> Option<&str> {
+        self.source
+            .lines()
+            .find_map(|line| line.strip_prefix("// expected: "))
+            .map(str::trim)
+    }
+}
+
+struct Diagnostics {
+    // One entry per error, "error" for those without a code
+    codes: Vec<String>,
+    stderr: String,
+}
+
+// ----------------------------------------
+// Command Entry Point
+// ----------------------------------------
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first() {
+        Some(name) => show(name),
+        None => check_all(),
+    }
+}
+
+// Compile every snippet and verify it fails with the expected code and
+// nothing else
+fn check_all() -> Result<(), String> {
+    let mut mismatches = 0;
+
+    for snippet in SNIPPETS {
+        let expected = snippet
+            .expected_code()
+            .ok_or_else(|| format!("{} has no `// expected:` line", snippet.name))?;
+        let diagnostics = compile(snippet)?;
+
+        if !diagnostics.codes.is_empty() && diagnostics.codes.iter().all(|code| code == expected) {
+            println!("ok       {:<24} {}", snippet.name, expected);
+        } else {
+            mismatches += 1;
+            let actual = if diagnostics.codes.is_empty() {
+                "no error code".to_string()
+            } else {
+                diagnostics.codes.join(", ")
+            };
+            println!(
+                "MISMATCH {:<24} expected {}, got {}",
+                snippet.name, expected, actual
+            );
+        }
+    }
+
+    if mismatches > 0 {
+        return Err(format!(
+            "{} of {} snippets did not fail as expected",
+            mismatches,
+            SNIPPETS.len()
+        ));
+    }
+    println!(
+        "\nAll {} snippets fail with their expected error",
+        SNIPPETS.len()
+    );
+    Ok(())
+}
+
+// Print one snippet followed by the compiler's real output
+fn show(name: &str) -> Result<(), String> {
+    let snippet = SNIPPETS
+        .iter()
+        .find(|snippet| snippet.name == name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = SNIPPETS.iter().map(|snippet| snippet.name).collect();
+            format!(
+                "unknown snippet `{}`; available: {}",
+                name,
+                names.join(", ")
+            )
+        })?;
+
+    println!(
+        "==> compile_fail/{}.rs ({})\n",
+        snippet.name,
+        snippet.section()
+    );
+    println!("{}", snippet.source.trim_end());
+    println!("\n==> rustc output\n");
+
+    let diagnostics = compile(snippet)?;
+    println!("{}", diagnostics.stderr.trim_end());
+    Ok(())
+}
+
+// ----------------------------------------
+// Running rustc
+// ----------------------------------------
+
+fn compile(snippet: &Snippet) -> Result<Diagnostics, String> {
+    let build_dir = std::env::temp_dir().join("simply_rust_compile_fail");
+    fs::create_dir_all(&build_dir)
+        .map_err(|err| format!("cannot create {}: {}", build_dir.display(), err))?;
+
+    // Write the snippet under its own name so diagnostics point at it
+    let source_dir = build_dir.join("compile_fail");
+    fs::create_dir_all(&source_dir)
+        .map_err(|err| format!("cannot create {}: {}", source_dir.display(), err))?;
+    let file_name = format!("{}.rs", snippet.name);
+    fs::write(source_dir.join(&file_name), snippet.source)
+        .map_err(|err| format!("cannot write {}: {}", file_name, err))?;
+
+    // Type and borrow checking is all we need, so skip code generation
+    let output = Command::new("rustc")
+        .current_dir(&build_dir)
+        .args([
+            "--edition",
+            "2021",
+            "--crate-type",
+            "lib",
+            "--emit=metadata",
+        ])
+        .args(["-A", "unused", "-A", "dead_code", "--out-dir", "."])
+        .arg(format!("compile_fail/{}", file_name))
+        .output()
+        .map_err(|err| format!("cannot run rustc (is it installed?): {}", err))?;
+
+    if output.status.success() {
+        return Ok(Diagnostics {
+            codes: Vec::new(),
+            stderr: "(compiled successfully)".to_string(),
+        });
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    let mut codes: Vec<String> = stderr
+        .lines()
+        .filter(|line| line.starts_with("error") && !line.starts_with("error: aborting"))
+        .map(|line| match line.strip_prefix("error[") {
+            Some(rest) => rest.split(']').next().unwrap_or(rest).to_string(),
+            None => "error".to_string(),
+        })
+        .collect();
+    codes.dedup();
+    Ok(Diagnostics { codes, stderr })
+}
@@ -0,0 +1,162 @@
+// ========================================================================
+// THE COMPUTE BUDGET PROGRAM
+// ========================================================================
+//
+// A transaction asks for compute units, and can bid a price per unit to
+// be scheduled ahead of others, with instructions addressed to this
+// program:
+//
+//     set_compute_unit_limit(units)           how much it may use
+//     set_compute_unit_price(micro_lamports)  what it pays per unit
+//
+// The program itself does nothing when executed. The bank reads these
+// instructions up front, before anything else runs, to work out the fee.
+
+use crate::bank::TransactionError;
+use crate::program::{InstructionError, InvokeContext, Program};
+use crate::pubkey::Pubkey;
+use crate::transaction::{Instruction, Message};
+
+pub const ID: Pubkey = Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
+
+// Granted per instruction when no limit is set
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+// ----------------------------------------
+// Instructions
+// ----------------------------------------
+
+// Tags 2 and 3 as on mainnet (0 is retired, 1 requests a bigger heap)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBudgetInstruction {
+    SetComputeUnitLimit(u32),
+    SetComputeUnitPrice(u64),
+}
+
+impl ComputeBudgetInstruction {
+    pub fn pack(self) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            ComputeBudgetInstruction::SetComputeUnitLimit(units) => {
+                data.push(2);
+                data.extend_from_slice(&units.to_le_bytes());
+            }
+            ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports) => {
+                data.push(3);
+                data.extend_from_slice(&micro_lamports.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        match data.split_first() {
+            Some((2, units)) => units
+                .try_into()
+                .map(|units| {
+                    ComputeBudgetInstruction::SetComputeUnitLimit(u32::from_le_bytes(units))
+                })
+                .map_err(|_| InstructionError::InvalidInstructionData),
+            Some((3, price)) => price
+                .try_into()
+                .map(|price| {
+                    ComputeBudgetInstruction::SetComputeUnitPrice(u64::from_le_bytes(price))
+                })
+                .map_err(|_| InstructionError::InvalidInstructionData),
+            _ => Err(InstructionError::InvalidInstructionData),
+        }
+    }
+}
+
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    let data = ComputeBudgetInstruction::SetComputeUnitLimit(units).pack();
+    Instruction::new(ID, &data, Vec::new())
+}
+
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let data = ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports).pack();
+    Instruction::new(ID, &data, Vec::new())
+}
+
+// ----------------------------------------
+// Reading a Message's Budget
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComputeBudgetLimits {
+    pub compute_unit_limit: u32,
+    // Micro-lamports per compute unit
+    pub compute_unit_price: u64,
+}
+
+// The limit and price a message asks for. Without an explicit limit each
+// other instruction is granted the default; either way the total is
+// capped. Setting the same thing twice is an error, as on mainnet.
+pub fn process_compute_budget_instructions(
+    message: &Message,
+) -> Result<ComputeBudgetLimits, TransactionError> {
+    let mut limit = None;
+    let mut price = None;
+    let mut other_instructions = 0u32;
+
+    for (index, instruction) in message.instructions.iter().enumerate() {
+        if message.program_id(instruction) != Some(&ID) {
+            other_instructions += 1;
+            continue;
+        }
+        let index = index as u8;
+        match ComputeBudgetInstruction::unpack(&instruction.data)
+            .map_err(|err| TransactionError::InstructionError(index, err))?
+        {
+            ComputeBudgetInstruction::SetComputeUnitLimit(units) => {
+                if limit.replace(units).is_some() {
+                    return Err(TransactionError::DuplicateInstruction(index));
+                }
+            }
+            ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports) => {
+                if price.replace(micro_lamports).is_some() {
+                    return Err(TransactionError::DuplicateInstruction(index));
+                }
+            }
+        }
+    }
+
+    let limit =
+        limit.unwrap_or(other_instructions.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT));
+    Ok(ComputeBudgetLimits {
+        compute_unit_limit: limit.min(MAX_COMPUTE_UNIT_LIMIT),
+        compute_unit_price: price.unwrap_or(0),
+    })
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct ComputeBudgetProgram;
+
+impl Program for ComputeBudgetProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "compute_budget"
+    }
+
+    fn compute_units(&self) -> u64 {
+        150
+    }
+
+    // Already applied by the bank; only check the data is well formed
+    fn process_instruction(
+        &self,
+        _ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        ComputeBudgetInstruction::unpack(data)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+// ========================================================================
+// CURVE25519 FIELD ARITHMETIC
+// ========================================================================
+//
+// Solana keypairs are ed25519 keys: points on the twisted Edwards curve
+//
+//     -x^2 + y^2 = 1 + d x^2 y^2      over the integers mod p = 2^255 - 19
+//
+// A public key stores y (255 bits) plus the sign of x in the top bit.
+// Program derived addresses must NOT be valid public keys, so that nobody
+// can hold a private key for them; telling the two apart needs the field
+// arithmetic below.
+//
+// Field elements are five 51-bit limbs, so products of two limbs fit in a
+// u128 with room to add up the partial products. This favours clarity
+// over speed and makes no attempt at constant time.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+const LOW_51_BITS: u64 = (1 << 51) - 1;
+
+// Exponents, as little-endian bytes
+const P_MINUS_2: [u8; 32] = exponent(0xeb, 0x7f);
+// (p - 5) / 8 = 2^252 - 3
+const P_MINUS_5_OVER_8: [u8; 32] = exponent(0xfd, 0x0f);
+
+// All 0xff bytes except the lowest and highest
+const fn exponent(low: u8, high: u8) -> [u8; 32] {
+    let mut bytes = [0xff; 32];
+    bytes[0] = low;
+    bytes[31] = high;
+    bytes
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FieldElement([u64; 5]);
+
+impl FieldElement {
+    pub const ZERO: FieldElement = FieldElement([0; 5]);
+    pub const ONE: FieldElement = FieldElement([1, 0, 0, 0, 0]);
+
+    // The top bit is ignored, as ed25519 uses it for the sign of x
+    pub fn from_bytes(bytes: &[u8; 32]) -> Self {
+        let load = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        FieldElement([
+            load(0) & LOW_51_BITS,
+            (load(6) >> 3) & LOW_51_BITS,
+            (load(12) >> 6) & LOW_51_BITS,
+            (load(19) >> 1) & LOW_51_BITS,
+            (load(24) >> 12) & LOW_51_BITS,
+        ])
+    }
+
+    // The canonical encoding, fully reduced below p
+    pub fn to_bytes(self) -> [u8; 32] {
+        let mut limbs = reduce(self.0);
+
+        // Work out whether limbs >= p by checking if limbs + 19 carries
+        // past 2^255, then subtract p by adding 19 and dropping bit 255
+        let mut q = (limbs[0] + 19) >> 51;
+        for limb in &limbs[1..] {
+            q = (limb + q) >> 51;
+        }
+        limbs[0] += 19 * q;
+        for i in 0..4 {
+            limbs[i + 1] += limbs[i] >> 51;
+            limbs[i] &= LOW_51_BITS;
+        }
+        limbs[4] &= LOW_51_BITS;
+
+        let mut out = [0u8; 32];
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        let mut pos = 0;
+        for limb in limbs {
+            acc |= (limb as u128) << bits;
+            bits += 51;
+            while bits >= 8 {
+                out[pos] = acc as u8;
+                acc >>= 8;
+                bits -= 8;
+                pos += 1;
+            }
+        }
+        out[pos] = acc as u8;
+        out
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.to_bytes() == [0; 32]
+    }
+
+    // The "sign" of x in a compressed point: its lowest bit
+    pub fn is_negative(self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    pub fn square(self) -> Self {
+        self * self
+    }
+
+    // self^exponent, square and multiply from the top bit down
+    pub fn pow(self, exponent: &[u8; 32]) -> Self {
+        let mut result = FieldElement::ONE;
+        for byte in exponent.iter().rev() {
+            for bit in (0..8).rev() {
+                result = result.square();
+                if (byte >> bit) & 1 == 1 {
+                    result = result * self;
+                }
+            }
+        }
+        result
+    }
+
+    // 1/self by Fermat's little theorem; zero stays zero
+    pub fn invert(self) -> Self {
+        self.pow(&P_MINUS_2)
+    }
+
+    // A square root of u/v, if there is one (RFC 8032, section 5.1.3)
+    pub fn sqrt_ratio(u: FieldElement, v: FieldElement) -> Option<FieldElement> {
+        let v3 = v.square() * v;
+        let v7 = v3.square() * v;
+        let x = u * v3 * (u * v7).pow(&P_MINUS_5_OVER_8);
+        let vx2 = v * x.square();
+        if vx2 == u {
+            Some(x)
+        } else if vx2 == -u {
+            Some(x * sqrt_minus_one())
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialEq for FieldElement {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for FieldElement {}
+
+// One round of carries, leaving every limb just over 51 bits at most
+fn reduce(mut limbs: [u64; 5]) -> [u64; 5] {
+    let carries = limbs.map(|limb| limb >> 51);
+    for limb in &mut limbs {
+        *limb &= LOW_51_BITS;
+    }
+    // 2^255 = 19 (mod p), so the carry out of the top limb wraps around
+    limbs[0] += carries[4] * 19;
+    for i in 1..5 {
+        limbs[i] += carries[i - 1];
+    }
+    limbs
+}
+
+impl Add for FieldElement {
+    type Output = FieldElement;
+
+    fn add(self, other: FieldElement) -> FieldElement {
+        let mut limbs = self.0;
+        for (limb, other) in limbs.iter_mut().zip(other.0) {
+            *limb += other;
+        }
+        FieldElement(reduce(limbs))
+    }
+}
+
+impl Sub for FieldElement {
+    type Output = FieldElement;
+
+    // Add 16p first so no limb underflows
+    fn sub(self, other: FieldElement) -> FieldElement {
+        let a = self.0;
+        let b = other.0;
+        FieldElement(reduce([
+            (a[0] + 36_028_797_018_963_664) - b[0],
+            (a[1] + 36_028_797_018_963_952) - b[1],
+            (a[2] + 36_028_797_018_963_952) - b[2],
+            (a[3] + 36_028_797_018_963_952) - b[3],
+            (a[4] + 36_028_797_018_963_952) - b[4],
+        ]))
+    }
+}
+
+impl Neg for FieldElement {
+    type Output = FieldElement;
+
+    fn neg(self) -> FieldElement {
+        FieldElement::ZERO - self
+    }
+}
+
+impl Mul for FieldElement {
+    type Output = FieldElement;
+
+    // Schoolbook multiplication; partial products that land past 2^255 are
+    // folded back in multiplied by 19
+    fn mul(self, other: FieldElement) -> FieldElement {
+        let a = self.0;
+        let b = other.0;
+        let m = |x: u64, y: u64| x as u128 * y as u128;
+        let b1 = b[1] * 19;
+        let b2 = b[2] * 19;
+        let b3 = b[3] * 19;
+        let b4 = b[4] * 19;
+
+        let c0 = m(a[0], b[0]) + m(a[4], b1) + m(a[3], b2) + m(a[2], b3) + m(a[1], b4);
+        let mut c1 = m(a[1], b[0]) + m(a[0], b[1]) + m(a[4], b2) + m(a[3], b3) + m(a[2], b4);
+        let mut c2 = m(a[2], b[0]) + m(a[1], b[1]) + m(a[0], b[2]) + m(a[4], b3) + m(a[3], b4);
+        let mut c3 = m(a[3], b[0]) + m(a[2], b[1]) + m(a[1], b[2]) + m(a[0], b[3]) + m(a[4], b4);
+        let mut c4 = m(a[4], b[0]) + m(a[3], b[1]) + m(a[2], b[2]) + m(a[1], b[3]) + m(a[0], b[4]);
+
+        c1 += c0 >> 51;
+        c2 += c1 >> 51;
+        c3 += c2 >> 51;
+        c4 += c3 >> 51;
+        let mut out = [
+            c0 as u64 & LOW_51_BITS,
+            c1 as u64 & LOW_51_BITS,
+            c2 as u64 & LOW_51_BITS,
+            c3 as u64 & LOW_51_BITS,
+            c4 as u64 & LOW_51_BITS,
+        ];
+        out[0] += (c4 >> 51) as u64 * 19;
+        out[1] += out[0] >> 51;
+        out[0] &= LOW_51_BITS;
+        FieldElement(out)
+    }
+}
+
+// ----------------------------------------
+// Curve Constants
+// ----------------------------------------
+
+// d = -121665 / 121666, worked out ahead of time since point addition
+// needs it every time
+const EDWARDS_D: [u8; 32] = [
+    0xa3, 0x78, 0x59, 0x13, 0xca, 0x4d, 0xeb, 0x75, 0xab, 0xd8, 0x41, 0x41, 0x4d, 0x0a, 0x70, 0x00,
+    0x98, 0xe8, 0x79, 0x77, 0x79, 0x40, 0xc7, 0x8c, 0x73, 0xfe, 0x6f, 0x2b, 0xee, 0x6c, 0x03, 0x52,
+];
+
+// 2^((p - 1) / 4), a square root of -1
+const SQRT_MINUS_ONE: [u8; 32] = [
+    0xb0, 0xa0, 0x0e, 0x4a, 0x27, 0x1b, 0xee, 0xc4, 0x78, 0xe4, 0x2f, 0xad, 0x06, 0x18, 0x43, 0x2f,
+    0xa7, 0xd7, 0xfb, 0x3d, 0x99, 0x00, 0x4d, 0x2b, 0x0b, 0xdf, 0xc1, 0x4f, 0x80, 0x24, 0x83, 0x2b,
+];
+
+pub fn edwards_d() -> FieldElement {
+    FieldElement::from_bytes(&EDWARDS_D)
+}
+
+pub fn sqrt_minus_one() -> FieldElement {
+    FieldElement::from_bytes(&SQRT_MINUS_ONE)
+}
+
+// ----------------------------------------
+// Point Validation
+// ----------------------------------------
+
+// Whether `bytes` is the compressed form of a curve point: there must be
+// an x with x^2 = (y^2 - 1) / (d y^2 + 1). Like the Solana runtime this
+// accepts non-canonical y and ignores the sign bit.
+pub fn is_on_curve(bytes: &[u8; 32]) -> bool {
+    let y = FieldElement::from_bytes(bytes);
+    let y2 = y.square();
+    let u = y2 - FieldElement::ONE;
+    let v = edwards_d() * y2 + FieldElement::ONE;
+    FieldElement::sqrt_ratio(u, v).is_some()
+}
@@ -0,0 +1,389 @@
+// ========================================================================
+// ED25519 SIGNATURES
+// ========================================================================
+//
+// A Solana keypair is an ed25519 keypair (RFC 8032):
+//
+//     secret   32 random bytes (the "seed")
+//     h        SHA-512(secret); the low half, clamped, is the scalar a,
+//              the high half a prefix for deriving nonces
+//     public   A = [a]B, the base point B added to itself a times,
+//              compressed to 32 bytes. This is the Pubkey.
+//
+// Signing message M:
+//
+//     r = SHA-512(prefix || M) mod L     a nonce only the signer can make
+//     R = [r]B
+//     k = SHA-512(R || A || M) mod L
+//     S = r + k * a mod L                signature = R || S
+//
+// and anyone can check that [S]B = R + [k]A, where L is the order of B.
+//
+// Points use extended coordinates (X : Y : Z : T) with x = X/Z, y = Y/Z
+// and xy = T/Z, for which one addition formula also covers doubling. As
+// with the field arithmetic, clarity wins over speed and nothing here is
+// constant time: fine for a cheat sheet, not for guarding real funds.
+
+use std::ops::{Add, Neg};
+
+use crate::curve25519::{edwards_d, FieldElement};
+use crate::hash::sha512v;
+
+pub const SECRET_KEY_BYTES: usize = 32;
+pub const PUBLIC_KEY_BYTES: usize = 32;
+pub const SIGNATURE_BYTES: usize = 64;
+
+// y = 4/5 with x even
+const BASEPOINT_BYTES: [u8; 32] = [
+    0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+];
+
+// L = 2^252 + 27742317777372353535851937790883648493, as little-endian
+// 64-bit limbs
+const L: [u64; 4] = [
+    0x5812_631a_5cf5_d3ed,
+    0x14de_f9de_a2f7_9cd6,
+    0,
+    0x1000_0000_0000_0000,
+];
+
+// ----------------------------------------
+// Points
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy)]
+pub struct EdwardsPoint {
+    x: FieldElement,
+    y: FieldElement,
+    z: FieldElement,
+    t: FieldElement,
+}
+
+impl EdwardsPoint {
+    // The neutral element, (0, 1)
+    pub const IDENTITY: EdwardsPoint = EdwardsPoint {
+        x: FieldElement::ZERO,
+        y: FieldElement::ONE,
+        z: FieldElement::ONE,
+        t: FieldElement::ZERO,
+    };
+
+    pub fn basepoint() -> Self {
+        EdwardsPoint::decompress(&BASEPOINT_BYTES).expect("the base point is on the curve")
+    }
+
+    // Recover x from y and its sign bit: x^2 = (y^2 - 1) / (d y^2 + 1)
+    pub fn decompress(bytes: &[u8; 32]) -> Option<Self> {
+        let y = FieldElement::from_bytes(bytes);
+        let y2 = y.square();
+        let u = y2 - FieldElement::ONE;
+        let v = edwards_d() * y2 + FieldElement::ONE;
+        let mut x = FieldElement::sqrt_ratio(u, v)?;
+
+        let x_negative = bytes[31] >> 7 == 1;
+        if x.is_zero() && x_negative {
+            return None;
+        }
+        if x.is_negative() != x_negative {
+            x = -x;
+        }
+        Some(EdwardsPoint {
+            x,
+            y,
+            z: FieldElement::ONE,
+            t: x * y,
+        })
+    }
+
+    // y, with the sign of x in the top bit
+    pub fn compress(self) -> [u8; 32] {
+        let z_inv = self.z.invert();
+        let x = self.x * z_inv;
+        let mut bytes = (self.y * z_inv).to_bytes();
+        bytes[31] |= (x.is_negative() as u8) << 7;
+        bytes
+    }
+
+    // [scalar]P by double and add, from the top bit down. The scalar is a
+    // little-endian 256-bit number.
+    pub fn mul(self, scalar: &[u8; 32]) -> Self {
+        let mut result = EdwardsPoint::IDENTITY;
+        for byte in scalar.iter().rev() {
+            for bit in (0..8).rev() {
+                result = result + result;
+                if (byte >> bit) & 1 == 1 {
+                    result = result + self;
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Add for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    // "add-2008-hwcd-3" from the Explicit-Formulas Database, complete for
+    // a = -1, so it doubles correctly too
+    fn add(self, other: EdwardsPoint) -> EdwardsPoint {
+        let d2 = edwards_d() + edwards_d();
+        let a = (self.y - self.x) * (other.y - other.x);
+        let b = (self.y + self.x) * (other.y + other.x);
+        let c = self.t * d2 * other.t;
+        let d = (self.z + self.z) * other.z;
+        let e = b - a;
+        let f = d - c;
+        let g = d + c;
+        let h = b + a;
+        EdwardsPoint {
+            x: e * f,
+            y: g * h,
+            z: f * g,
+            t: e * h,
+        }
+    }
+}
+
+impl Neg for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn neg(self) -> EdwardsPoint {
+        EdwardsPoint {
+            x: -self.x,
+            y: self.y,
+            z: self.z,
+            t: -self.t,
+        }
+    }
+}
+
+// ----------------------------------------
+// Scalars mod L
+// ----------------------------------------
+
+fn to_limbs(bytes: &[u8]) -> Vec<u64> {
+    bytes
+        .chunks(8)
+        .map(|chunk| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .collect()
+}
+
+fn to_bytes(limbs: [u64; 4]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (chunk, limb) in out.chunks_exact_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    out
+}
+
+fn less_than_l(limbs: &[u64; 4]) -> bool {
+    for i in (0..4).rev() {
+        if limbs[i] != L[i] {
+            return limbs[i] < L[i];
+        }
+    }
+    false
+}
+
+// Any little-endian number mod L, by binary long division: shift in one
+// bit at a time and subtract L whenever the remainder reaches it
+fn reduce(limbs: &[u64]) -> [u64; 4] {
+    let mut r = [0u64; 4];
+    for bit in (0..limbs.len() * 64).rev() {
+        let mut carry = (limbs[bit / 64] >> (bit % 64)) & 1;
+        for limb in &mut r {
+            let top = *limb >> 63;
+            *limb = *limb << 1 | carry;
+            carry = top;
+        }
+        if !less_than_l(&r) {
+            let mut borrow = false;
+            for (limb, l) in r.iter_mut().zip(L) {
+                let (diff, b1) = limb.overflowing_sub(l);
+                let (diff, b2) = diff.overflowing_sub(borrow as u64);
+                *limb = diff;
+                borrow = b1 || b2;
+            }
+        }
+    }
+    r
+}
+
+// A 64-byte hash as a scalar
+pub fn reduce_wide(bytes: &[u8; 64]) -> [u8; 32] {
+    to_bytes(reduce(&to_limbs(bytes)))
+}
+
+// a * b + c mod L
+pub fn mul_add(a: &[u8; 32], b: &[u8; 32], c: &[u8; 32]) -> [u8; 32] {
+    let (a, b, c) = (to_limbs(a), to_limbs(b), to_limbs(c));
+    let mut wide = [0u64; 9];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let sum = wide[i + j] as u128 + a[i] as u128 * b[j] as u128 + carry;
+            wide[i + j] = sum as u64;
+            carry = sum >> 64;
+        }
+        wide[i + 4] = carry as u64;
+    }
+    let mut carry = 0u128;
+    for (i, limb) in wide.iter_mut().enumerate() {
+        let sum = *limb as u128 + c.get(i).copied().unwrap_or(0) as u128 + carry;
+        *limb = sum as u64;
+        carry = sum >> 64;
+    }
+    to_bytes(reduce(&wide))
+}
+
+// A signature's S must be fully reduced, or a second valid signature
+// could be made from any first one by adding L
+fn is_canonical(scalar: &[u8; 32]) -> bool {
+    less_than_l(&to_limbs(scalar).try_into().unwrap())
+}
+
+// ----------------------------------------
+// Keys and Signatures
+// ----------------------------------------
+
+// The scalar a (clamped: a multiple of 8, with bit 254 set) and the nonce
+// prefix
+fn expand_secret(secret: &[u8; SECRET_KEY_BYTES]) -> ([u8; 32], [u8; 32]) {
+    let h = sha512v(&[secret]);
+    let mut scalar: [u8; 32] = h[..32].try_into().unwrap();
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+    (scalar, h[32..].try_into().unwrap())
+}
+
+pub fn public_key(secret: &[u8; SECRET_KEY_BYTES]) -> [u8; PUBLIC_KEY_BYTES] {
+    let (scalar, _) = expand_secret(secret);
+    EdwardsPoint::basepoint().mul(&scalar).compress()
+}
+
+pub fn sign(
+    secret: &[u8; SECRET_KEY_BYTES],
+    public: &[u8; PUBLIC_KEY_BYTES],
+    message: &[u8],
+) -> [u8; SIGNATURE_BYTES] {
+    let (scalar, prefix) = expand_secret(secret);
+    let r = reduce_wide(&sha512v(&[&prefix, message]));
+    let big_r = EdwardsPoint::basepoint().mul(&r).compress();
+    let k = reduce_wide(&sha512v(&[&big_r, public, message]));
+    let s = mul_add(&k, &scalar, &r);
+
+    let mut signature = [0u8; SIGNATURE_BYTES];
+    signature[..32].copy_from_slice(&big_r);
+    signature[32..].copy_from_slice(&s);
+    signature
+}
+
+// Checks [S]B - [k]A = R. Fails for a public key that is not a curve
+// point, which includes every program derived address.
+pub fn verify(
+    public: &[u8; PUBLIC_KEY_BYTES],
+    message: &[u8],
+    signature: &[u8; SIGNATURE_BYTES],
+) -> bool {
+    let a = match EdwardsPoint::decompress(public) {
+        Some(a) => a,
+        None => return false,
+    };
+    let big_r: [u8; 32] = signature[..32].try_into().unwrap();
+    let s: [u8; 32] = signature[32..].try_into().unwrap();
+    if !is_canonical(&s) {
+        return false;
+    }
+    let k = reduce_wide(&sha512v(&[&big_r, public, message]));
+    let check = EdwardsPoint::basepoint().mul(&s) + -a.mul(&k);
+    check.compress() == big_r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::sha512v;
+
+    fn hex<const N: usize>(text: &str) -> [u8; N] {
+        let bytes: Vec<u8> = (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect();
+        bytes.try_into().unwrap()
+    }
+
+    // RFC 8032 section 7.1: secret key, public key, message, signature
+    const VECTORS: [(&str, &str, &[u8], &str); 3] = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            b"",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+             5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            &[0x72],
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            &[0xaf, 0x82],
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+             18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        ),
+    ];
+
+    #[test]
+    fn rfc8032_vectors() {
+        for (secret, public, message, signature) in VECTORS {
+            let (secret, public, signature) = (hex(secret), hex(public), hex(signature));
+            assert_eq!(public_key(&secret), public);
+            assert_eq!(sign(&secret, &public, message), signature);
+            assert!(verify(&public, message, &signature));
+        }
+    }
+
+    // RFC 8032 "TEST SHA(abc)": the message is SHA-512 of "abc"
+    #[test]
+    fn rfc8032_sha_abc_vector() {
+        let secret = hex("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42");
+        let public = hex("ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf");
+        let message = sha512v(&[b"abc"]);
+        let signature = hex(
+            "dc2a4459e7369633a52b1bf277839a00201009a3efbf3ecb69bea2186c26b589\
+             09351fc9ac90b3ecfdfbc7c66431e0303dca179c138ac17ad9bef1177331a704",
+        );
+        assert_eq!(public_key(&secret), public);
+        assert_eq!(sign(&secret, &public, &message), signature);
+        assert!(verify(&public, &message, &signature));
+    }
+
+    #[test]
+    fn verify_rejects_changes() {
+        let (_, public, message, signature) = VECTORS[2];
+        let (public, signature): ([u8; 32], [u8; 64]) = (hex(public), hex(signature));
+        assert!(!verify(&public, &message[..1], &signature));
+        let mut flipped = signature;
+        flipped[0] ^= 1;
+        assert!(!verify(&public, message, &flipped));
+        // S + L passes the curve equation too, but is not canonical
+        let mut malleable = signature;
+        let mut carry = 0u16;
+        for (byte, l) in malleable[32..].iter_mut().zip(to_bytes(L)) {
+            let sum = *byte as u16 + l as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+        assert!(!verify(&public, message, &malleable));
+    }
+}
@@ -0,0 +1,897 @@
+// ========================================================================
+// THE ESCROW PROGRAM
+// ========================================================================
+//
+// Two people who do not trust each other want to swap tokens: alice gives
+// 100 GOLD, bob gives 250 USDC, and neither wants to send first. The
+// escrow program sends first for them. alice's offer moves her GOLD into
+// a vault only the program controls; whoever pays her the USDC she asked
+// for gets the vault's GOLD in the same instruction, and until then alice
+// can cancel and have it back.
+//
+// Every offer lives at a program derived address,
+//
+//     find_program_address(["escrow", maker, seed], escrow program id)
+//
+// which holds the offer's state and is also the owner of the vault, the
+// escrow address's associated token account for the offered mint. Making
+// an offer shows all three ways a program calls another:
+//
+//     escrow                       invoke [1]
+//       system CreateAccount        invoke_signed by the escrow address
+//       associated token Create     invoke, alice's signature passed on
+//         system CreateAccount      invoke_signed by the ATA program
+//         token InitializeAccount   invoke [3]
+//       token Transfer              invoke, alice's signature passed on
+//
+// Taking or cancelling pays out of the vault and closes it, with the
+// escrow program signing for the vault's owner, then closes the offer
+// and returns both accounts' rent to the maker.
+//
+//     Escrow (154 bytes)
+//     is_initialized  u8
+//     bump_seed       u8
+//     seed            u64      lets one maker have many offers open
+//     maker           Pubkey
+//     mint_a          Pubkey   offered
+//     mint_b          Pubkey   wanted
+//     maker_token_b   Pubkey   where the payment goes
+//     offer_amount    u64
+//     want_amount     u64
+
+use std::fmt;
+
+use crate::associated_token_program::{self, AssociatedTokenProgram};
+use crate::bank::{print_outcome, Bank};
+use crate::program::{InstructionError, InvokeContext, Program, MAX_INVOKE_DEPTH};
+use crate::pubkey::{Pubkey, PUBKEY_BYTES, SYSTEM_PROGRAM_ID};
+use crate::system_program;
+use crate::token_program::{self, TokenAccount, TokenProgram};
+use crate::transaction::{AccountMeta, DemoSigner, Instruction, Signer, Transaction};
+
+pub const ID: Pubkey = Pubkey::from_str_const("EscrowTokenSwap1111111111111111111111111111");
+
+pub const ESCROW_LEN: usize = 2 + 3 * 8 + 4 * PUBKEY_BYTES;
+
+// ----------------------------------------
+// Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EscrowError {
+    ZeroAmount,
+    // The token account holds a different token than the offer names
+    MintMismatch,
+    // An account is not the one the offer's state names
+    EscrowMismatch,
+}
+
+impl fmt::Display for EscrowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EscrowError::ZeroAmount => write!(f, "amount must be above zero"),
+            EscrowError::MintMismatch => write!(f, "token account is for the wrong mint"),
+            EscrowError::EscrowMismatch => write!(f, "account does not belong to this offer"),
+        }
+    }
+}
+
+// ----------------------------------------
+// Account State
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Escrow {
+    pub is_initialized: bool,
+    pub bump_seed: u8,
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub maker_token_b: Pubkey,
+    pub offer_amount: u64,
+    pub want_amount: u64,
+}
+
+impl Escrow {
+    pub fn pack(&self) -> [u8; ESCROW_LEN] {
+        let mut data = [0u8; ESCROW_LEN];
+        data[0] = self.is_initialized as u8;
+        data[1] = self.bump_seed;
+        data[2..10].copy_from_slice(&self.seed.to_le_bytes());
+        let keys = [self.maker, self.mint_a, self.mint_b, self.maker_token_b];
+        for (chunk, key) in data[10..].chunks_exact_mut(PUBKEY_BYTES).zip(keys) {
+            chunk.copy_from_slice(key.as_ref());
+        }
+        let amounts = 10 + 4 * PUBKEY_BYTES;
+        data[amounts..amounts + 8].copy_from_slice(&self.offer_amount.to_le_bytes());
+        data[amounts + 8..].copy_from_slice(&self.want_amount.to_le_bytes());
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        if data.len() != ESCROW_LEN {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        let key = |n: usize| {
+            let start = 10 + n * PUBKEY_BYTES;
+            Pubkey::new(data[start..start + PUBKEY_BYTES].try_into().unwrap())
+        };
+        let u64_at = |start: usize| u64::from_le_bytes(data[start..start + 8].try_into().unwrap());
+        let amounts = 10 + 4 * PUBKEY_BYTES;
+        Ok(Escrow {
+            is_initialized: match data[0] {
+                0 => false,
+                1 => true,
+                _ => return Err(InstructionError::InvalidAccountData),
+            },
+            bump_seed: data[1],
+            seed: u64_at(2),
+            maker: key(0),
+            mint_a: key(1),
+            mint_b: key(2),
+            maker_token_b: key(3),
+            offer_amount: u64_at(amounts),
+            want_amount: u64_at(amounts + 8),
+        })
+    }
+
+    pub fn unpack_initialized(data: &[u8]) -> Result<Self, InstructionError> {
+        let escrow = Escrow::unpack(data)?;
+        if !escrow.is_initialized {
+            return Err(InstructionError::UninitializedAccount);
+        }
+        Ok(escrow)
+    }
+}
+
+pub fn escrow_address(maker: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &ID)
+}
+
+// ----------------------------------------
+// Instructions
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscrowInstruction {
+    // [writable, signer] maker, [writable] escrow, [writable] vault,
+    // mint A, mint B, [writable] maker's token A, maker's token B,
+    // system program, token program, associated token program
+    Make {
+        seed: u64,
+        offer_amount: u64,
+        want_amount: u64,
+    },
+    // [writable, signer] taker, [writable] maker, [writable] escrow,
+    // [writable] vault, [writable] taker's token A,
+    // [writable] taker's token B, [writable] maker's token B,
+    // token program
+    Take,
+    // [writable, signer] maker, [writable] escrow, [writable] vault,
+    // [writable] maker's token A, token program
+    Cancel,
+}
+
+impl EscrowInstruction {
+    pub fn pack(&self) -> Vec<u8> {
+        match self {
+            EscrowInstruction::Make {
+                seed,
+                offer_amount,
+                want_amount,
+            } => {
+                let mut data = vec![0];
+                for value in [seed, offer_amount, want_amount] {
+                    data.extend_from_slice(&value.to_le_bytes());
+                }
+                data
+            }
+            EscrowInstruction::Take => vec![1],
+            EscrowInstruction::Cancel => vec![2],
+        }
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let (&tag, rest) = data
+            .split_first()
+            .ok_or(InstructionError::InvalidInstructionData)?;
+        let u64_at = |n: usize| u64::from_le_bytes(rest[n * 8..n * 8 + 8].try_into().unwrap());
+        let instruction = match (tag, rest.len()) {
+            (0, 24) => EscrowInstruction::Make {
+                seed: u64_at(0),
+                offer_amount: u64_at(1),
+                want_amount: u64_at(2),
+            },
+            (1, 0) => EscrowInstruction::Take,
+            (2, 0) => EscrowInstruction::Cancel,
+            _ => return Err(InstructionError::InvalidInstructionData),
+        };
+        Ok(instruction)
+    }
+}
+
+// ----------------------------------------
+// Instruction Constructors
+// ----------------------------------------
+
+// Everything that identifies an offer. The maker gives and receives
+// through their associated token accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Offer {
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub seed: u64,
+}
+
+impl Offer {
+    pub fn address(&self) -> Pubkey {
+        escrow_address(&self.maker, self.seed).0
+    }
+
+    pub fn vault(&self) -> Pubkey {
+        associated_token_program::get_associated_token_address(&self.address(), &self.mint_a)
+    }
+}
+
+fn ata(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    associated_token_program::get_associated_token_address(wallet, mint)
+}
+
+pub fn make_offer(offer: &Offer, offer_amount: u64, want_amount: u64) -> Instruction {
+    let data = EscrowInstruction::Make {
+        seed: offer.seed,
+        offer_amount,
+        want_amount,
+    }
+    .pack();
+    Instruction::new(
+        ID,
+        &data,
+        vec![
+            AccountMeta::new(offer.maker, true),
+            AccountMeta::new(offer.address(), false),
+            AccountMeta::new(offer.vault(), false),
+            AccountMeta::new_readonly(offer.mint_a, false),
+            AccountMeta::new_readonly(offer.mint_b, false),
+            AccountMeta::new(ata(&offer.maker, &offer.mint_a), false),
+            AccountMeta::new_readonly(ata(&offer.maker, &offer.mint_b), false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(token_program::ID, false),
+            AccountMeta::new_readonly(associated_token_program::ID, false),
+        ],
+    )
+}
+
+// The taker pays from and is paid into their associated token accounts
+pub fn take_offer(offer: &Offer, taker: &Pubkey) -> Instruction {
+    Instruction::new(
+        ID,
+        &EscrowInstruction::Take.pack(),
+        vec![
+            AccountMeta::new(*taker, true),
+            AccountMeta::new(offer.maker, false),
+            AccountMeta::new(offer.address(), false),
+            AccountMeta::new(offer.vault(), false),
+            AccountMeta::new(ata(taker, &offer.mint_a), false),
+            AccountMeta::new(ata(taker, &offer.mint_b), false),
+            AccountMeta::new(ata(&offer.maker, &offer.mint_b), false),
+            AccountMeta::new_readonly(token_program::ID, false),
+        ],
+    )
+}
+
+pub fn cancel_offer(offer: &Offer) -> Instruction {
+    Instruction::new(
+        ID,
+        &EscrowInstruction::Cancel.pack(),
+        vec![
+            AccountMeta::new(offer.maker, true),
+            AccountMeta::new(offer.address(), false),
+            AccountMeta::new(offer.vault(), false),
+            AccountMeta::new(ata(&offer.maker, &offer.mint_a), false),
+            AccountMeta::new_readonly(token_program::ID, false),
+        ],
+    )
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct EscrowProgram;
+
+impl Program for EscrowProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "escrow"
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        match EscrowInstruction::unpack(data)? {
+            EscrowInstruction::Make {
+                seed,
+                offer_amount,
+                want_amount,
+            } => {
+                ctx.log("Instruction: Make");
+                process_make(ctx, seed, offer_amount, want_amount)
+            }
+            EscrowInstruction::Take => {
+                ctx.log("Instruction: Take");
+                process_take(ctx)
+            }
+            EscrowInstruction::Cancel => {
+                ctx.log("Instruction: Cancel");
+                process_cancel(ctx)
+            }
+        }
+    }
+}
+
+// The offer at account `escrow`, after checking that `vault` is its vault
+fn load_escrow(
+    ctx: &InvokeContext,
+    escrow: usize,
+    vault: usize,
+) -> Result<Escrow, InstructionError> {
+    let account = ctx.account(escrow)?;
+    if account.owner != ID {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+    let state = Escrow::unpack_initialized(&account.data)?;
+    if *ctx.key(vault)? != ata(ctx.key(escrow)?, &state.mint_a) {
+        return Err(EscrowError::EscrowMismatch.into());
+    }
+    Ok(state)
+}
+
+fn process_make(
+    ctx: &mut InvokeContext,
+    seed: u64,
+    offer_amount: u64,
+    want_amount: u64,
+) -> Result<(), InstructionError> {
+    let maker = *ctx.signer(0)?;
+    if offer_amount == 0 || want_amount == 0 {
+        return Err(EscrowError::ZeroAmount.into());
+    }
+    let (escrow, bump_seed) = escrow_address(&maker, seed);
+    if *ctx.key(1)? != escrow {
+        return Err(InstructionError::InvalidSeeds);
+    }
+    let mint_a = *ctx.key(3)?;
+    let mint_b = *ctx.key(4)?;
+    if token_program::token_account(ctx, 5)?.mint != mint_a
+        || token_program::token_account(ctx, 6)?.mint != mint_b
+    {
+        return Err(EscrowError::MintMismatch.into());
+    }
+
+    // The offer's own account, which only this program can sign for
+    let seed_bytes = seed.to_le_bytes();
+    let signer_seeds: &[&[u8]] = &[b"escrow", maker.as_ref(), &seed_bytes, &[bump_seed]];
+    ctx.invoke_signed(
+        &system_program::create_account(
+            &maker,
+            &escrow,
+            system_program::rent_exempt_lamports(ESCROW_LEN),
+            ESCROW_LEN as u64,
+            &ID,
+        ),
+        &[signer_seeds],
+    )?;
+    // The vault, and the offered tokens moved into it. maker's signature
+    // on this instruction carries over to both calls.
+    ctx.invoke(&associated_token_program::create_associated_token_account(
+        &maker, &escrow, &mint_a,
+    ))?;
+    let vault = *ctx.key(2)?;
+    ctx.invoke(&token_program::transfer(
+        ctx.key(5)?,
+        &vault,
+        &maker,
+        &[],
+        offer_amount,
+    ))?;
+
+    let state = Escrow {
+        is_initialized: true,
+        bump_seed,
+        seed,
+        maker,
+        mint_a,
+        mint_b,
+        maker_token_b: *ctx.key(6)?,
+        offer_amount,
+        want_amount,
+    };
+    ctx.account_mut(1)?.data = state.pack().to_vec();
+    ctx.log(&format!("Offered {} for {}", offer_amount, want_amount));
+    Ok(())
+}
+
+fn process_take(ctx: &mut InvokeContext) -> Result<(), InstructionError> {
+    let taker = *ctx.signer(0)?;
+    let state = load_escrow(ctx, 2, 3)?;
+    if *ctx.key(1)? != state.maker || *ctx.key(6)? != state.maker_token_b {
+        return Err(EscrowError::EscrowMismatch.into());
+    }
+    if token_program::token_account(ctx, 4)?.mint != state.mint_a
+        || token_program::token_account(ctx, 5)?.mint != state.mint_b
+    {
+        return Err(EscrowError::MintMismatch.into());
+    }
+
+    ctx.invoke(&token_program::transfer(
+        ctx.key(5)?,
+        ctx.key(6)?,
+        &taker,
+        &[],
+        state.want_amount,
+    ))?;
+    close_escrow(ctx, &state, 2, 3, 4, 1)?;
+    ctx.log(&format!(
+        "Swapped {} for {}",
+        state.offer_amount, state.want_amount
+    ));
+    Ok(())
+}
+
+fn process_cancel(ctx: &mut InvokeContext) -> Result<(), InstructionError> {
+    let maker = *ctx.signer(0)?;
+    let state = load_escrow(ctx, 1, 2)?;
+    if maker != state.maker {
+        return Err(InstructionError::IncorrectAuthority);
+    }
+    if token_program::token_account(ctx, 3)?.mint != state.mint_a {
+        return Err(EscrowError::MintMismatch.into());
+    }
+
+    close_escrow(ctx, &state, 1, 2, 3, 0)?;
+    ctx.log("Offer cancelled");
+    Ok(())
+}
+
+// Empty the vault into `destination`, close it and the offer, and return
+// their rent to the maker at `maker`. The escrow address owns the vault,
+// so every token call here is signed with its seeds.
+fn close_escrow(
+    ctx: &mut InvokeContext,
+    state: &Escrow,
+    escrow: usize,
+    vault: usize,
+    destination: usize,
+    maker: usize,
+) -> Result<(), InstructionError> {
+    let seed_bytes = state.seed.to_le_bytes();
+    let signer_seeds: &[&[u8]] = &[
+        b"escrow",
+        state.maker.as_ref(),
+        &seed_bytes,
+        &[state.bump_seed],
+    ];
+    let authority = *ctx.key(escrow)?;
+    let vault_key = *ctx.key(vault)?;
+    // Everything in the vault, including anything sent to it since
+    let amount = token_program::token_account(ctx, vault)?.amount;
+    ctx.invoke_signed(
+        &token_program::transfer(&vault_key, ctx.key(destination)?, &authority, &[], amount),
+        &[signer_seeds],
+    )?;
+    ctx.invoke_signed(
+        &token_program::close_account(&vault_key, ctx.key(maker)?, &authority, &[]),
+        &[signer_seeds],
+    )?;
+
+    let lamports = ctx.account(escrow)?.lamports;
+    ctx.transfer_lamports(escrow, maker, lamports)?;
+    ctx.account_mut(escrow)?.data.fill(0);
+    Ok(())
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo escrow`
+// ----------------------------------------
+
+const GOLD: u64 = 1_000_000;
+const USDC: u64 = 1_000_000;
+
+pub fn demo() -> Result<(), String> {
+    let relay_a = DemoSigner::new("relay a").pubkey();
+    let relay_b = DemoSigner::new("relay b").pubkey();
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(TokenProgram));
+    bank.add_program(Box::new(AssociatedTokenProgram));
+    bank.add_program(Box::new(EscrowProgram));
+    bank.add_program(Box::new(RelayProgram(relay_a)));
+    bank.add_program(Box::new(RelayProgram(relay_b)));
+
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let carol = DemoSigner::new("carol");
+    let gold_mint = DemoSigner::new("gold mint");
+    let usdc_mint = DemoSigner::new("usdc mint");
+    for wallet in [&alice, &bob, &carol] {
+        bank.airdrop(&wallet.pubkey(), 10_000_000_000)
+            .map_err(|err| err.to_string())?;
+    }
+    let names = [
+        (SYSTEM_PROGRAM_ID, "system"),
+        (token_program::ID, "token"),
+        (associated_token_program::ID, "associated token"),
+        (ID, "escrow"),
+        (relay_a, "relay A"),
+        (relay_b, "relay B"),
+    ];
+
+    let send = |bank: &mut Bank,
+                title: &str,
+                instructions: &[Instruction],
+                payer: &DemoSigner,
+                signers: &[&dyn Signer]| {
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            signers,
+            bank.last_blockhash(),
+        );
+        let outcome = bank.execute_transaction(&tx);
+        print_outcome(title, &outcome.status, &call_tree(&outcome.logs, &names));
+        bank.advance_slots(1);
+    };
+
+    // Two mints, alice holding GOLD and bob USDC, and every associated
+    // token account the trades need
+    let mut setup = Vec::new();
+    for mint in [&gold_mint, &usdc_mint] {
+        setup.push(system_program::create_account(
+            &alice.pubkey(),
+            &mint.pubkey(),
+            system_program::rent_exempt_lamports(token_program::MINT_LEN),
+            token_program::MINT_LEN as u64,
+            &token_program::ID,
+        ));
+        setup.push(token_program::initialize_mint(
+            &mint.pubkey(),
+            &alice.pubkey(),
+            None,
+            6,
+        ));
+        for wallet in [&alice, &bob, &carol] {
+            setup.push(associated_token_program::create_associated_token_account(
+                &alice.pubkey(),
+                &wallet.pubkey(),
+                &mint.pubkey(),
+            ));
+        }
+    }
+    for (wallet, mint, amount) in [
+        (&alice, &gold_mint, 1_000 * GOLD),
+        (&bob, &usdc_mint, 500 * USDC),
+    ] {
+        setup.push(token_program::mint_to(
+            &mint.pubkey(),
+            &ata(&wallet.pubkey(), &mint.pubkey()),
+            &alice.pubkey(),
+            &[],
+            amount,
+        ));
+    }
+    let tx = Transaction::new_signed_with_payer(
+        &setup,
+        Some(&alice.pubkey()),
+        &[&alice, &gold_mint, &usdc_mint],
+        bank.last_blockhash(),
+    );
+    bank.execute_transaction(&tx)
+        .status
+        .map_err(|err| err.to_string())?;
+    let print_balances = |bank: &Bank| {
+        for (name, wallet) in [("alice", &alice), ("bob", &bob), ("carol", &carol)] {
+            let balance = |mint: &DemoSigner| {
+                let account = bank.get_account(&ata(&wallet.pubkey(), &mint.pubkey()));
+                account
+                    .and_then(|account| TokenAccount::unpack(&account.data).ok())
+                    .map_or(0, |account| account.amount)
+            };
+            println!(
+                "  {:<5} {:>5} GOLD {:>5} USDC",
+                name,
+                balance(&gold_mint) / GOLD,
+                balance(&usdc_mint) / USDC
+            );
+        }
+    };
+    println!("Set up GOLD and USDC mints:");
+    print_balances(&bank);
+
+    // alice offers 100 GOLD for 250 USDC. Her signature reaches the
+    // system and token programs three calls down.
+    let offer = Offer {
+        maker: alice.pubkey(),
+        mint_a: gold_mint.pubkey(),
+        mint_b: usdc_mint.pubkey(),
+        seed: 1,
+    };
+    println!("\nOffer address {}", offer.address());
+    println!("Vault         {}", offer.vault());
+    send(
+        &mut bank,
+        "alice offers 100 GOLD for 250 USDC",
+        &[make_offer(&offer, 100 * GOLD, 250 * USDC)],
+        &alice,
+        &[&alice],
+    );
+    send(
+        &mut bank,
+        "bob takes the offer",
+        &[take_offer(&offer, &bob.pubkey())],
+        &bob,
+        &[&bob],
+    );
+    print_balances(&bank);
+    println!(
+        "  offer account closed: {}",
+        bank.get_account(&offer.address()).is_none()
+    );
+
+    // A second offer, which only alice may call off
+    let offer = Offer { seed: 2, ..offer };
+    send(
+        &mut bank,
+        "alice offers 50 GOLD for 500 USDC",
+        &[make_offer(&offer, 50 * GOLD, 500 * USDC)],
+        &alice,
+        &[&alice],
+    );
+    let mut cancel = cancel_offer(&offer);
+    cancel.accounts[0] = AccountMeta::new(carol.pubkey(), true);
+    send(
+        &mut bank,
+        "carol tries to cancel it",
+        &[cancel],
+        &carol,
+        &[&carol],
+    );
+    send(
+        &mut bank,
+        "alice cancels it",
+        &[cancel_offer(&offer)],
+        &alice,
+        &[&alice],
+    );
+    print_balances(&bank);
+
+    // The call rules, with a program that only forwards what it is given
+    println!(
+        "\nCalls nest at most {} programs deep, and a program may call itself but \
+         not be re-entered through another",
+        MAX_INVOKE_DEPTH
+    );
+    let steal = token_program::transfer(
+        &ata(&alice.pubkey(), &gold_mint.pubkey()),
+        &ata(&carol.pubkey(), &gold_mint.pubkey()),
+        &alice.pubkey(),
+        &[],
+        GOLD,
+    );
+    let mut relayed = relay(&relay_a, Some(&steal));
+    // alice did not sign this transaction; the relay has no signature of
+    // hers to pass on
+    for meta in &mut relayed.accounts {
+        meta.is_signer = false;
+    }
+    send(
+        &mut bank,
+        "carol has relay A ask the token program for alice's GOLD",
+        &[relayed],
+        &carol,
+        &[&carol],
+    );
+    let chain = |hops: &[Pubkey]| {
+        hops.iter()
+            .rev()
+            .fold(None, |inner: Option<Instruction>, hop| {
+                Some(relay(hop, inner.as_ref()))
+            })
+            .unwrap()
+    };
+    send(
+        &mut bank,
+        "relay A calls itself to depth 5",
+        &[chain(&[relay_a; MAX_INVOKE_DEPTH])],
+        &carol,
+        &[&carol],
+    );
+    send(
+        &mut bank,
+        "relay A calls itself to depth 6",
+        &[chain(&[relay_a; MAX_INVOKE_DEPTH + 1])],
+        &carol,
+        &[&carol],
+    );
+    send(
+        &mut bank,
+        "relay A -> relay B -> relay A",
+        &[chain(&[relay_a, relay_b, relay_a])],
+        &carol,
+        &[&carol],
+    );
+    Ok(())
+}
+
+// The transaction's logs with program ids replaced by `names` and every
+// line indented by the depth of the call it belongs to
+fn call_tree(logs: &[String], names: &[(Pubkey, &str)]) -> Vec<String> {
+    let mut depth = 0;
+    let mut lines = Vec::new();
+    for line in logs {
+        let mut line = line.clone();
+        for (id, name) in names {
+            line = line.replace(&id.to_string(), name);
+        }
+        let returned = line.ends_with(" success") || line.contains(" failed: ");
+        if let Some(start) = line.rfind(" invoke [") {
+            depth = line[start + 9..line.len() - 1].parse().unwrap_or(depth);
+        }
+        lines.push(format!("{}{}", "  ".repeat(depth.max(1) - 1), line));
+        if returned {
+            depth = depth.saturating_sub(1);
+        }
+    }
+    lines
+}
+
+// A program that calls whatever instruction it is given, with the
+// accounts it was given. Its data is the instruction to call, empty to
+// stop: program id, account count, then each account's key, signer and
+// writable flags, then the instruction's own data.
+struct RelayProgram(Pubkey);
+
+impl Program for RelayProgram {
+    fn id(&self) -> Pubkey {
+        self.0
+    }
+
+    fn name(&self) -> &'static str {
+        "relay"
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        if data.is_empty() {
+            ctx.log(&format!("Reached depth {}", ctx.depth()));
+            return Ok(());
+        }
+        let invalid = InstructionError::InvalidInstructionData;
+        let key = |at: usize| -> Result<Pubkey, InstructionError> {
+            let bytes = data.get(at..at + PUBKEY_BYTES).ok_or(invalid.clone())?;
+            Ok(Pubkey::new(bytes.try_into().unwrap()))
+        };
+        let program_id = key(0)?;
+        let count = *data.get(PUBKEY_BYTES).ok_or(invalid.clone())? as usize;
+        let mut accounts = Vec::with_capacity(count);
+        let mut at = PUBKEY_BYTES + 1;
+        for _ in 0..count {
+            let flags = data.get(at + PUBKEY_BYTES..at + PUBKEY_BYTES + 2);
+            let flags = flags.ok_or(invalid.clone())?;
+            accounts.push(AccountMeta {
+                pubkey: key(at)?,
+                is_signer: flags[0] == 1,
+                is_writable: flags[1] == 1,
+            });
+            at += PUBKEY_BYTES + 2;
+        }
+        ctx.invoke(&Instruction::new(program_id, &data[at..], accounts))
+    }
+}
+
+// An instruction for the relay `relay_id` to call `inner`, passing on the
+// same accounts
+fn relay(relay_id: &Pubkey, inner: Option<&Instruction>) -> Instruction {
+    let Some(inner) = inner else {
+        return Instruction::new(*relay_id, &[], Vec::new());
+    };
+    let mut data = inner.program_id.as_ref().to_vec();
+    data.push(inner.accounts.len() as u8);
+    for meta in &inner.accounts {
+        data.extend_from_slice(meta.pubkey.as_ref());
+        data.push(meta.is_signer as u8);
+        data.push(meta.is_writable as u8);
+    }
+    data.extend_from_slice(&inner.data);
+    Instruction::new(*relay_id, &data, inner.accounts.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bank::TransactionError;
+
+    // Runs `instruction` paid and signed by carol, with two relays loaded
+    fn run(instruction: Instruction) -> Result<(), TransactionError> {
+        let mut bank = Bank::new();
+        bank.add_program(Box::new(TokenProgram));
+        bank.add_program(Box::new(RelayProgram(relay_a())));
+        bank.add_program(Box::new(RelayProgram(relay_b())));
+        let carol = DemoSigner::new("carol");
+        bank.airdrop(&carol.pubkey(), 10_000_000_000).unwrap();
+        bank.process_transaction(&Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&carol.pubkey()),
+            &[&carol],
+            bank.last_blockhash(),
+        ))
+    }
+
+    fn relay_a() -> Pubkey {
+        DemoSigner::new("relay a").pubkey()
+    }
+
+    fn relay_b() -> Pubkey {
+        DemoSigner::new("relay b").pubkey()
+    }
+
+    fn chain(hops: &[Pubkey]) -> Instruction {
+        hops.iter()
+            .rev()
+            .fold(None, |inner: Option<Instruction>, hop| {
+                Some(relay(hop, inner.as_ref()))
+            })
+            .unwrap()
+    }
+
+    fn failed_with(err: InstructionError) -> Result<(), TransactionError> {
+        Err(TransactionError::InstructionError(0, err))
+    }
+
+    #[test]
+    fn callees_cannot_sign_for_the_caller() {
+        let alice = DemoSigner::new("alice").pubkey();
+        let steal = token_program::transfer(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &alice,
+            &[],
+            GOLD,
+        );
+        let mut relayed = relay(&relay_a(), Some(&steal));
+        for meta in &mut relayed.accounts {
+            meta.is_signer = false;
+        }
+        assert_eq!(
+            run(relayed),
+            failed_with(InstructionError::PrivilegeEscalation)
+        );
+    }
+
+    #[test]
+    fn calls_nest_at_most_max_invoke_depth() {
+        assert_eq!(run(chain(&[relay_a(); MAX_INVOKE_DEPTH])), Ok(()));
+        assert_eq!(
+            run(chain(&[relay_a(); MAX_INVOKE_DEPTH + 1])),
+            failed_with(InstructionError::CallDepth)
+        );
+    }
+
+    #[test]
+    fn programs_cannot_be_reentered_through_another() {
+        assert_eq!(run(chain(&[relay_a(), relay_a()])), Ok(()));
+        assert_eq!(
+            run(chain(&[relay_a(), relay_b(), relay_a()])),
+            failed_with(InstructionError::ReentrancyNotAllowed)
+        );
+    }
+}
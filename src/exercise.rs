@@ -0,0 +1,314 @@
+// ========================================================================
+// EXERCISE MODE
+// ========================================================================
+//
+// `simply_rust exercise <section>` copies the section's exercise files
+// into `practice/<section>/`, compiles each one with the local `rustc`
+// together with a set of hidden tests, and reports which exercises pass.
+// Edit the files in `practice/` and run the command again until every
+// exercise passes.
+//
+// Exercise sources live in `exercises/<section>/<name>.rs`; the hidden
+// tests next to them (`<name>.test.rs`) are embedded in the binary and
+// never copied into `practice/`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+struct Exercise {
+    section: &'static str,
+    name: &'static str,
+    starter: &'static str,
+    tests: &'static str,
+    hint: &'static str,
+}
+
+macro_rules! exercise {
+    ($section:literal, $name:literal, $hint:literal) => {
+        Exercise {
+            section: $section,
+            name: $name,
+            starter: include_str!(concat!("../exercises/", $section, "/", $name, ".rs")),
+            tests: include_str!(concat!("../exercises/", $section, "/", $name, ".test.rs")),
+            hint: $hint,
+        }
+    };
+}
+
+// One entry per cheat sheet section, in the same order
+const EXERCISES: &[Exercise] = &[
+    exercise!(
+        "basics",
+        "mutability",
+        "Variables are immutable by default: `let mut balance`. \
+         Integer division by LAMPORTS_PER_SOL already rounds down."
+    ),
+    exercise!(
+        "control-flow",
+        "instruction_names",
+        "Use `_ =>` as the catch-all arm, and match guards such as \
+         `l if l >= 1_000 * 1_000_000_000 => ...` for the ranges."
+    ),
+    exercise!(
+        "memory",
+        "pubkey_bytes",
+        "`data.get(..32)` returns Option<&[u8]>, and `<[u8; 32]>::try_from(slice)` \
+         copies it onto the stack. `Box::new(value)` moves a value to the heap."
+    ),
+    exercise!(
+        "ownership",
+        "fix_borrow",
+        "Build the new String (which borrows `first`) before calling `push`, \
+         so the immutable borrow ends first. For `longer_key`, tie both inputs \
+         and the output to one lifetime: `fn longer_key<'a>(a: &'a str, b: &'a str) -> &'a str`."
+    ),
+    exercise!(
+        "strings",
+        "short_address",
+        "Base58 is ASCII, so byte slices like `&address[..4]` and \
+         `&address[address.len() - 4..]` are safe. `to_uppercase()` returns a new String."
+    ),
+    exercise!(
+        "slices",
+        "token_instruction_unpack",
+        "`rest.get(..8)` returns None when the slice is too short; \
+         `u64::from_le_bytes(bytes.try_into().unwrap())` converts 8 bytes to an amount."
+    ),
+    exercise!(
+        "traits",
+        "transaction_verify",
+        "`strip_prefix(\"0x\")` returns the digits after the prefix, and \
+         `chars().all(|c| c.is_ascii_hexdigit())` checks them. Compare `from` and `to` too."
+    ),
+    exercise!(
+        "vectors",
+        "top_balances",
+        "Copy with `to_vec()`, then `sort_unstable_by(|a, b| b.cmp(a))` and \
+         `truncate(n)`. `retain(|&b| b != 0)` filters a Vec in place."
+    ),
+    exercise!(
+        "iterators",
+        "owner_totals",
+        "`filter(..).map(|a| a.amount).sum()`, `max_by_key(|a| a.amount)` and \
+         `map(|a| a.owner.as_str())` cover all three functions."
+    ),
+    exercise!(
+        "errors",
+        "withdraw",
+        "`balances.get_mut(owner).ok_or(TokenError::AccountNotFound)?` replaces the unwrap, \
+         and `checked_sub` tells you when the balance is too small."
+    ),
+    exercise!(
+        "hashmaps",
+        "apply_updates",
+        "`*map.entry(key.to_string()).or_insert(0) += change` accumulates per account. \
+         For ties, compare (count, Reverse(token)) or sort the candidates."
+    ),
+    exercise!(
+        "macros",
+        "sol_macro",
+        "Add a second rule `($sol:expr, $lamports:expr) => { ... }` and multiply \
+         by 1_000_000_000u64. Wrap expressions in parentheses: `($sol) * ...`."
+    ),
+];
+
+const PRACTICE_DIR: &str = "practice";
+
+enum Outcome {
+    Passed {
+        tests: usize,
+    },
+    CompileError(String),
+    TestsFailed {
+        failed: usize,
+        total: usize,
+        output: String,
+    },
+}
+
+// ----------------------------------------
+// Command Entry Point
+// ----------------------------------------
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let section = match args.first() {
+        Some(section) => section.as_str(),
+        None => {
+            list_sections();
+            return Ok(());
+        }
+    };
+
+    let exercises: Vec<&Exercise> = EXERCISES
+        .iter()
+        .filter(|exercise| section == "all" || exercise.section == section)
+        .collect();
+    if exercises.is_empty() {
+        return Err(format!(
+            "no exercises for section `{}` (run `simply_rust exercise` to list sections)",
+            section
+        ));
+    }
+
+    let mut passed = 0;
+    for exercise in &exercises {
+        let path = scaffold(exercise)?;
+
+        match check(exercise, &path)? {
+            Outcome::Passed { tests } => {
+                passed += 1;
+                println!("PASS {} ({} hidden tests)", path.display(), tests);
+            }
+            Outcome::CompileError(diagnostics) => {
+                println!("FAIL {}: does not compile", path.display());
+                println!("{}", indent(&diagnostics));
+                println!("  hint: {}", exercise.hint);
+            }
+            Outcome::TestsFailed {
+                failed,
+                total,
+                output,
+            } => {
+                println!(
+                    "FAIL {}: {} of {} hidden tests failed",
+                    path.display(),
+                    failed,
+                    total
+                );
+                println!("{}", indent(&output));
+                println!("  hint: {}", exercise.hint);
+            }
+        }
+    }
+
+    println!("\n{}/{} exercises passing", passed, exercises.len());
+    Ok(())
+}
+
+fn list_sections() {
+    println!("Usage: simply_rust exercise <section | all>\n");
+    println!("Sections:");
+    for exercise in EXERCISES {
+        println!("  {:<14} {}", exercise.section, exercise.name);
+    }
+}
+
+// ----------------------------------------
+// Scaffolding and Checking
+// ----------------------------------------
+
+// Copy the starter file into practice/, keeping any existing work
+fn scaffold(exercise: &Exercise) -> Result<PathBuf, String> {
+    let dir = Path::new(PRACTICE_DIR).join(exercise.section);
+    let path = dir.join(format!("{}.rs", exercise.name));
+    if !path.exists() {
+        fs::create_dir_all(&dir)
+            .map_err(|err| format!("cannot create {}: {}", dir.display(), err))?;
+        fs::write(&path, exercise.starter)
+            .map_err(|err| format!("cannot write {}: {}", path.display(), err))?;
+        println!("Created {}", path.display());
+    }
+    Ok(path)
+}
+
+// Compile the learner's code plus the hidden tests as a test binary and
+// run it
+fn check(exercise: &Exercise, path: &Path) -> Result<Outcome, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+    let build_dir = std::env::temp_dir()
+        .join("simply_rust_exercises")
+        .join(exercise.section);
+    fs::create_dir_all(&build_dir)
+        .map_err(|err| format!("cannot create {}: {}", build_dir.display(), err))?;
+
+    let source_path = build_dir.join(format!("{}.rs", exercise.name));
+    let binary_path = build_dir.join(exercise.name);
+    let combined = format!(
+        "{}\n\n#[cfg(test)]\nmod hidden_tests {{\n    use super::*;\n\n{}\n}}\n",
+        source, exercise.tests
+    );
+    fs::write(&source_path, combined)
+        .map_err(|err| format!("cannot write {}: {}", source_path.display(), err))?;
+
+    let compile = Command::new("rustc")
+        .args([
+            "--edition",
+            "2021",
+            "--test",
+            "-A",
+            "unused",
+            "-A",
+            "dead_code",
+        ])
+        .arg("-o")
+        .arg(&binary_path)
+        .arg(&source_path)
+        .output()
+        .map_err(|err| format!("cannot run rustc (is it installed?): {}", err))?;
+    if !compile.status.success() {
+        // Point diagnostics at the learner's file rather than the build copy
+        let stderr = String::from_utf8_lossy(&compile.stderr).replace(
+            &source_path.display().to_string(),
+            &path.display().to_string(),
+        );
+        return Ok(Outcome::CompileError(first_error(&stderr)));
+    }
+
+    let run = Command::new(&binary_path)
+        .args(["--test-threads", "1"])
+        .output()
+        .map_err(|err| format!("cannot run {}: {}", binary_path.display(), err))?;
+    let stdout = String::from_utf8_lossy(&run.stdout).replace(
+        &source_path.display().to_string(),
+        &path.display().to_string(),
+    );
+    let total = exercise.tests.matches("#[test]").count();
+    if run.status.success() {
+        return Ok(Outcome::Passed { tests: total });
+    }
+
+    // Keep the failing test names and each panic location plus its message
+    let lines: Vec<&str> = stdout.lines().collect();
+    let mut failed = 0;
+    let mut report = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with("test ") && line.ends_with("FAILED") {
+            failed += 1;
+            report.push(line.replace("hidden_tests::", ""));
+        } else if line.contains("panicked at") {
+            report.push(line.replace("hidden_tests::", ""));
+            if let Some(message) = lines.get(i + 1) {
+                report.push(format!("  {}", message));
+            }
+        }
+    }
+    let output = report.join("\n");
+    Ok(Outcome::TestsFailed {
+        failed,
+        total,
+        output,
+    })
+}
+
+// The first error block of rustc's output is usually the one to fix first
+fn first_error(stderr: &str) -> String {
+    let mut lines = Vec::new();
+    for line in stderr.lines() {
+        if line.starts_with("error") && !lines.is_empty() {
+            break;
+        }
+        if !lines.is_empty() || line.starts_with("error") {
+            lines.push(line);
+        }
+    }
+    lines.join("\n").trim_end().to_string()
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,169 @@
+// ========================================================================
+// TRANSACTION FEES
+// ========================================================================
+//
+// Every transaction pays two things:
+//
+//     base fee            lamports_per_signature x required signatures
+//     prioritization fee  compute unit limit x compute unit price, where
+//                         the price is in micro-lamports (rounded up)
+//
+// The fee payer (the message's first account) is charged before any
+// instruction runs, and the fee is kept even if an instruction fails: the
+// validator did the work either way. A payer who cannot cover the fee has
+// the transaction rejected without it running at all.
+
+use std::fmt;
+
+use crate::bank::{print_outcome, Bank};
+use crate::compute_budget::{self, ComputeBudgetLimits, MICRO_LAMPORTS_PER_LAMPORT};
+use crate::system_program;
+use crate::transaction::{DemoSigner, Instruction, Message, Signer, Transaction};
+
+pub const DEFAULT_LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeStructure {
+    pub lamports_per_signature: u64,
+}
+
+impl Default for FeeStructure {
+    fn default() -> Self {
+        FeeStructure {
+            lamports_per_signature: DEFAULT_LAMPORTS_PER_SIGNATURE,
+        }
+    }
+}
+
+// What a transaction was charged, and why
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeDetails {
+    pub base_fee: u64,
+    pub prioritization_fee: u64,
+}
+
+impl FeeDetails {
+    pub fn total(self) -> u64 {
+        self.base_fee.saturating_add(self.prioritization_fee)
+    }
+}
+
+impl fmt::Display for FeeDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} lamports (base {} + priority {})",
+            self.total(),
+            self.base_fee,
+            self.prioritization_fee
+        )
+    }
+}
+
+impl FeeStructure {
+    pub fn calculate_fee(&self, message: &Message, budget: &ComputeBudgetLimits) -> FeeDetails {
+        let signatures = message.header.num_required_signatures as u64;
+        FeeDetails {
+            base_fee: signatures.saturating_mul(self.lamports_per_signature),
+            prioritization_fee: prioritization_fee(budget),
+        }
+    }
+}
+
+pub fn prioritization_fee(budget: &ComputeBudgetLimits) -> u64 {
+    let micro_lamports = budget.compute_unit_limit as u128 * budget.compute_unit_price as u128;
+    micro_lamports
+        .div_ceil(MICRO_LAMPORTS_PER_LAMPORT as u128)
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo fees`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let carol = DemoSigner::new("carol");
+    bank.airdrop(&alice.pubkey(), 1_000_000_000)
+        .map_err(|err| err.to_string())?;
+    bank.airdrop(&carol.pubkey(), 3_000)
+        .map_err(|err| err.to_string())?;
+
+    let mut send = |title: &str, instructions: &[Instruction], payer: &DemoSigner| {
+        let before = bank.get_balance(&payer.pubkey());
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            bank.last_blockhash(),
+        );
+        let outcome = bank.execute_transaction(&tx);
+        print_outcome(title, &outcome.status, &[]);
+        println!("  fee:     {}", outcome.fee);
+        println!(
+            "  payer:   {} -> {} lamports",
+            before,
+            bank.get_balance(&payer.pubkey())
+        );
+    };
+
+    send(
+        "Pay bob 0.1 SOL",
+        &[system_program::transfer(
+            &alice.pubkey(),
+            &bob.pubkey(),
+            100_000_000,
+        )],
+        &alice,
+    );
+
+    // 50,000 units at 20,000 micro-lamports each: 1,000 lamports extra
+    send(
+        "Pay bob 0.1 SOL with a priority fee",
+        &[
+            compute_budget::set_compute_unit_limit(50_000),
+            compute_budget::set_compute_unit_price(20_000),
+            system_program::transfer(&alice.pubkey(), &bob.pubkey(), 100_000_000),
+        ],
+        &alice,
+    );
+
+    // Without a limit every instruction is budgeted the default 200,000
+    send(
+        "Same price, default limit",
+        &[
+            compute_budget::set_compute_unit_price(20_000),
+            system_program::transfer(&alice.pubkey(), &bob.pubkey(), 100_000_000),
+        ],
+        &alice,
+    );
+
+    // The transfer fails, the fee is charged anyway
+    send(
+        "Pay bob 5 SOL (more than alice has)",
+        &[system_program::transfer(
+            &alice.pubkey(),
+            &bob.pubkey(),
+            5_000_000_000,
+        )],
+        &alice,
+    );
+
+    // Carol cannot even cover the fee, so nothing runs and nothing is
+    // charged
+    send(
+        "Carol pays bob 1,000 lamports",
+        &[system_program::transfer(
+            &carol.pubkey(),
+            &bob.pubkey(),
+            1_000,
+        )],
+        &carol,
+    );
+
+    println!("\nFees collected: {} lamports", bank.collected_fees());
+    Ok(())
+}
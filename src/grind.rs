@@ -0,0 +1,244 @@
+// ========================================================================
+// VANITY ADDRESSES (`simply_rust grind`)
+// ========================================================================
+//
+// complex_pattern_matching in main.rs looks for keys that start with "8".
+// Nobody picks a public key like that: it comes out of a one-way function
+// of the secret, so the only way to get one with a chosen prefix is to
+// make keypairs until one happens to match. Each base58 character in the
+// pattern multiplies the expected number of tries by 58:
+//
+//     1 character     58
+//     2 characters    3,364
+//     3 characters    195,112
+//     4 characters    11,316,496
+//
+// The search is split across std threads that share an attempt counter.
+// Each thread reads one 32-byte seed from the OS when it starts and makes
+// its candidate secrets as SHA-256(seed || counter), so the millions of
+// attempts do not each open /dev/urandom. A thread that finds a match
+// sends it over a channel, and the main thread writes it to <pubkey>.json
+// (the name solana-keygen grind uses) and reports progress while it waits.
+
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::hash::hashv;
+use crate::keypair::{os_random_bytes, Keypair};
+use crate::pubkey::is_base58_char;
+use crate::transaction::Signer;
+
+// A 32-byte key is 43 or 44 base58 characters
+const MAX_PATTERN_LEN: usize = 43;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    // Neither --starts-with nor --ends-with
+    Empty,
+    // 0, O, I and l are left out of base58 so they cannot be misread
+    NotBase58(char),
+    TooLong(usize),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatternError::Empty => write!(f, "give --starts-with, --ends-with or both"),
+            PatternError::NotBase58(c) => write!(
+                f,
+                "`{}` never appears in an address (base58 has no 0, O, I or l)",
+                c
+            ),
+            PatternError::TooLong(len) => write!(
+                f,
+                "a {}-character pattern is longer than an address can match",
+                len
+            ),
+        }
+    }
+}
+
+// ----------------------------------------
+// Patterns
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    starts_with: String,
+    ends_with: String,
+}
+
+impl Pattern {
+    pub fn new(starts_with: &str, ends_with: &str) -> Result<Self, PatternError> {
+        let len = starts_with.chars().count() + ends_with.chars().count();
+        if len == 0 {
+            return Err(PatternError::Empty);
+        }
+        if let Some(c) = starts_with
+            .chars()
+            .chain(ends_with.chars())
+            .find(|&c| !is_base58_char(c))
+        {
+            return Err(PatternError::NotBase58(c));
+        }
+        if len > MAX_PATTERN_LEN {
+            return Err(PatternError::TooLong(len));
+        }
+        Ok(Pattern {
+            starts_with: starts_with.to_string(),
+            ends_with: ends_with.to_string(),
+        })
+    }
+
+    pub fn matches(&self, address: &str) -> bool {
+        address.starts_with(&self.starts_with) && address.ends_with(&self.ends_with)
+    }
+
+    // 58 per character, on average
+    pub fn expected_attempts(&self) -> f64 {
+        58f64.powi((self.starts_with.len() + self.ends_with.len()) as i32)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}...{}", self.starts_with, self.ends_with)
+    }
+}
+
+// ----------------------------------------
+// Searching
+// ----------------------------------------
+
+// Runs `threads` workers until `count` matching keypairs have been passed
+// to `found`, calling `progress` with the attempts so far every couple of
+// seconds. Returns the total number of attempts, or the error from the
+// first worker that could not read its seed.
+pub fn grind(
+    pattern: &Pattern,
+    threads: usize,
+    count: usize,
+    mut found: impl FnMut(Keypair),
+    mut progress: impl FnMut(u64),
+) -> io::Result<u64> {
+    let attempts = AtomicU64::new(0);
+    let done = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let (attempts, done) = (&attempts, &done);
+            scope.spawn(move || {
+                let seed: [u8; 32] = match os_random_bytes() {
+                    Ok(seed) => seed,
+                    Err(err) => {
+                        let _ = sender.send(Err(err));
+                        return;
+                    }
+                };
+                let mut counter = 0u64;
+                while !done.load(Ordering::Relaxed) {
+                    let secret = hashv(&[&seed, &counter.to_le_bytes()]).to_bytes();
+                    counter += 1;
+                    let keypair = Keypair::from_seed(&secret);
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    if pattern.matches(&keypair.pubkey().to_string())
+                        && sender.send(Ok(keypair)).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+        // Only the workers hold senders now, so if they all die the
+        // receiver sees a disconnect instead of waiting forever
+        drop(sender);
+
+        let mut matches = 0;
+        let mut result = Ok(());
+        while matches < count {
+            match receiver.recv_timeout(PROGRESS_INTERVAL) {
+                Ok(Ok(keypair)) => {
+                    matches += 1;
+                    found(keypair);
+                }
+                Ok(Err(err)) => {
+                    result = Err(err);
+                    break;
+                }
+                Err(RecvTimeoutError::Timeout) => progress(attempts.load(Ordering::Relaxed)),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        done.store(true, Ordering::Relaxed);
+        result
+    })?;
+    Ok(attempts.into_inner())
+}
+
+// simply_rust grind --starts-with 8
+// simply_rust grind --starts-with so --ends-with L --threads 4 --count 2
+pub fn run(args: &[String]) -> Result<(), String> {
+    const USAGE: &str = "usage: simply_rust grind [--starts-with <prefix>] \
+                         [--ends-with <suffix>] [--threads N] [--count N]";
+    let mut starts_with = String::new();
+    let mut ends_with = String::new();
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut count = 1;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(USAGE)?;
+        let number = || match value.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("{} takes a number above 0, got `{}`", flag, value)),
+        };
+        match flag.as_str() {
+            "--starts-with" => starts_with = value.clone(),
+            "--ends-with" => ends_with = value.clone(),
+            "--threads" => threads = number()?,
+            "--count" => count = number()?,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let pattern = Pattern::new(&starts_with, &ends_with).map_err(|err| err.to_string())?;
+
+    println!(
+        "Searching for {} with {} thread{} (about {:.0} attempts per match)",
+        pattern,
+        threads,
+        if threads == 1 { "" } else { "s" },
+        pattern.expected_attempts()
+    );
+    let start = Instant::now();
+    let rate = |attempts: u64| attempts as f64 / start.elapsed().as_secs_f64();
+    let mut write_error = None;
+    let attempts = grind(
+        &pattern,
+        threads,
+        count,
+        |keypair| {
+            let path = format!("{}.json", keypair.pubkey());
+            match keypair.write_to_file(&path, false) {
+                Ok(()) => println!("Wrote keypair to {}", path),
+                Err(err) => {
+                    write_error.get_or_insert_with(|| format!("cannot write {}: {}", path, err));
+                }
+            }
+        },
+        |attempts| println!("  {} attempts, {:.0} per second", attempts, rate(attempts)),
+    )
+    .map_err(|err| format!("cannot read random bytes from the OS: {}", err))?;
+    println!(
+        "Searched {} keypairs in {:.1}s ({:.0} per second)",
+        attempts,
+        start.elapsed().as_secs_f64(),
+        rate(attempts)
+    );
+    write_error.map_or(Ok(()), Err)
+}
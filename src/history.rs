@@ -0,0 +1,241 @@
+// ========================================================================
+// TRANSACTION HISTORY
+// ========================================================================
+//
+// The bank records every transaction it executes, successful or not,
+// together with the accounts it touched and their balances before and
+// after. Three HashMap indexes answer the questions an auditor (or an
+// explorer) asks:
+//
+//     get_transaction(signature)             what happened in this one?
+//     signatures_for_address(address, ..)    which touched this address?
+//     balance_at_slot(address, slot)         what did it hold back then?
+//
+// Transactions rejected before execution (stale blockhash, bad signature,
+// already processed) never reach the ledger, so they are not recorded.
+
+use std::collections::HashMap;
+
+use crate::bank::{Bank, TransactionError, TransactionOutcome};
+use crate::pubkey::Pubkey;
+use crate::system_program;
+use crate::transaction::{DemoSigner, Signature, Signer, Transaction};
+
+// One entry of a signatures_for_address page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureInfo {
+    pub signature: Signature,
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionHistory {
+    // In processing order
+    records: Vec<TransactionOutcome>,
+    by_signature: HashMap<Signature, usize>,
+    // Record indexes per address, oldest first
+    by_address: HashMap<Pubkey, Vec<usize>>,
+    // (slot, balance at the end of that change) per address, oldest first
+    balances: HashMap<Pubkey, Vec<(u64, u64)>>,
+}
+
+impl TransactionHistory {
+    pub fn new() -> Self {
+        TransactionHistory::default()
+    }
+
+    pub fn record(&mut self, outcome: TransactionOutcome) {
+        let index = self.records.len();
+        self.by_signature.insert(outcome.signature, index);
+        for (i, key) in outcome.account_keys.iter().enumerate() {
+            self.by_address.entry(*key).or_default().push(index);
+
+            let timeline = self.balances.entry(*key).or_default();
+            // The first time an address shows up, remember what it held
+            // going in as well
+            if timeline.is_empty() {
+                timeline.push((outcome.slot, outcome.pre_balances[i]));
+            }
+            if timeline.last().map(|&(_, balance)| balance) != Some(outcome.post_balances[i]) {
+                timeline.push((outcome.slot, outcome.post_balances[i]));
+            }
+        }
+        self.records.push(outcome);
+    }
+
+    // Balance changes made outside transactions, such as airdrops
+    pub fn record_balance(&mut self, pubkey: &Pubkey, slot: u64, lamports: u64) {
+        self.balances
+            .entry(*pubkey)
+            .or_default()
+            .push((slot, lamports));
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    // ----------------------------------------
+    // Queries
+    // ----------------------------------------
+
+    pub fn get_transaction(&self, signature: &Signature) -> Option<&TransactionOutcome> {
+        self.by_signature
+            .get(signature)
+            .map(|&index| &self.records[index])
+    }
+
+    // Newest first, at most `limit` entries. Pass the last signature of a
+    // page as `before` to fetch the next (older) page.
+    pub fn signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<&Signature>,
+        limit: usize,
+    ) -> Vec<SignatureInfo> {
+        let indexes = match self.by_address.get(address) {
+            Some(indexes) => indexes,
+            None => return Vec::new(),
+        };
+        let end = match before.and_then(|signature| self.by_signature.get(signature)) {
+            Some(&before) => indexes.partition_point(|&index| index < before),
+            None if before.is_some() => return Vec::new(),
+            None => indexes.len(),
+        };
+
+        indexes[..end]
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|&index| {
+                let record = &self.records[index];
+                SignatureInfo {
+                    signature: record.signature,
+                    slot: record.slot,
+                    err: record.status.clone().err(),
+                }
+            })
+            .collect()
+    }
+
+    // The balance at the end of `slot`, or None if nothing is known about
+    // the address that early
+    pub fn balance_at_slot(&self, address: &Pubkey, slot: u64) -> Option<u64> {
+        let timeline = self.balances.get(address)?;
+        let known = timeline.partition_point(|&(changed, _)| changed <= slot);
+        if known == 0 {
+            return None;
+        }
+        Some(timeline[known - 1].1)
+    }
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo history`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let carol = DemoSigner::new("carol");
+    bank.airdrop(&alice.pubkey(), 10_000_000_000)
+        .map_err(|err| err.to_string())?;
+
+    // Alice makes a few payments, one slot apart; the last one is more
+    // than she has left and fails
+    let payments = [
+        (&bob, 1_000_000_000),
+        (&carol, 500_000_000),
+        (&bob, 2_000_000_000),
+        (&bob, 50_000_000_000),
+    ];
+    for (to, lamports) in payments {
+        bank.advance_slots(1);
+        let tx = Transaction::new_signed_with_payer(
+            &[system_program::transfer(
+                &alice.pubkey(),
+                &to.pubkey(),
+                lamports,
+            )],
+            Some(&alice.pubkey()),
+            &[&alice],
+            bank.last_blockhash(),
+        );
+        bank.execute_transaction(&tx);
+    }
+    bank.advance_slots(1);
+    let tx = Transaction::new_signed_with_payer(
+        &[system_program::transfer(
+            &bob.pubkey(),
+            &carol.pubkey(),
+            250_000_000,
+        )],
+        Some(&bob.pubkey()),
+        &[&bob],
+        bank.last_blockhash(),
+    );
+    bank.execute_transaction(&tx);
+
+    let history = bank.history();
+    println!("{} transactions recorded\n", history.len());
+
+    // Page through Bob's signatures two at a time, newest first
+    println!("Signatures for bob ({}):", bob.pubkey());
+    let mut before = None;
+    loop {
+        let page = history.signatures_for_address(&bob.pubkey(), before.as_ref(), 2);
+        let last = match page.last() {
+            Some(info) => info.signature,
+            None => break,
+        };
+        println!("  -- page --");
+        for info in &page {
+            let status = match &info.err {
+                Some(err) => format!("failed: {}", err),
+                None => "ok".to_string(),
+            };
+            println!(
+                "  slot {:>2}  {:.16}...  {}",
+                info.slot,
+                info.signature.to_string(),
+                status
+            );
+        }
+        before = Some(last);
+    }
+
+    // Full details of Bob's most recent transaction
+    let newest = history.signatures_for_address(&bob.pubkey(), None, 1);
+    let record = newest
+        .first()
+        .and_then(|info| history.get_transaction(&info.signature))
+        .ok_or("bob has no transactions")?;
+    println!(
+        "\nTransaction {:.16}... at slot {}",
+        record.signature.to_string(),
+        record.slot
+    );
+    println!("  fee {}", record.fee);
+    for (i, key) in record.account_keys.iter().enumerate() {
+        println!(
+            "  {:.12}...  {:>14} -> {:>14}",
+            key.to_string(),
+            record.pre_balances[i],
+            record.post_balances[i]
+        );
+    }
+    for line in &record.logs {
+        println!("  {}", line);
+    }
+
+    println!("\nBob's balance by slot:");
+    for slot in 0..=bank.slot() {
+        match history.balance_at_slot(&bob.pubkey(), slot) {
+            Some(lamports) => println!("  slot {}: {} lamports", slot, lamports),
+            None => println!("  slot {}: (no record)", slot),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,275 @@
+// ========================================================================
+// KEYPAIRS AND KEYPAIR FILES
+// ========================================================================
+//
+// A wallet address like "8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa" is
+// only the public half of a keypair. The `Keypair` holds the 32-byte
+// secret as well, so it can sign (see ed25519.rs).
+//
+// solana-keygen stores a keypair as a JSON array of 64 numbers, the
+// secret followed by the public key:
+//
+//     [174,47,154,16,...,202,241,8,63]
+//
+// and `simply_rust keygen` reads and writes the same format, so files
+// made by either tool work with the other.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::ed25519::{self, SECRET_KEY_BYTES};
+use crate::json::{self, Value};
+use crate::pubkey::Pubkey;
+use crate::transaction::{Signature, Signer};
+
+pub const KEYPAIR_BYTES: usize = 64;
+
+#[derive(Debug)]
+pub enum KeypairError {
+    Io(io::Error),
+    // Not a JSON array of byte values
+    InvalidFormat,
+    WrongLength(usize),
+    // The stored public key is not the one the secret gives
+    PubkeyMismatch,
+}
+
+impl fmt::Display for KeypairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeypairError::Io(err) => write!(f, "keypair file I/O error: {}", err),
+            KeypairError::InvalidFormat => {
+                write!(f, "a keypair file holds a JSON array of numbers 0-255")
+            }
+            KeypairError::WrongLength(len) => {
+                write!(f, "a keypair is {} bytes, got {}", KEYPAIR_BYTES, len)
+            }
+            KeypairError::PubkeyMismatch => {
+                write!(f, "the public key does not belong to the secret key")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for KeypairError {
+    fn from(err: io::Error) -> Self {
+        KeypairError::Io(err)
+    }
+}
+
+// Deliberately not Copy, and Debug shows only the public key, so the
+// secret is not duplicated or printed by accident
+#[derive(Clone)]
+pub struct Keypair {
+    secret: [u8; SECRET_KEY_BYTES],
+    pubkey: Pubkey,
+}
+
+impl Keypair {
+    // A fresh keypair from the operating system's random number generator.
+    // Panics if there is none, as solana-sdk does.
+    pub fn new() -> Self {
+        let secret = os_random_bytes().expect("cannot read random bytes from the OS");
+        Keypair::from_seed(&secret)
+    }
+
+    // The same seed always gives the same keypair
+    pub fn from_seed(seed: &[u8; SECRET_KEY_BYTES]) -> Self {
+        Keypair {
+            secret: *seed,
+            pubkey: Pubkey::new(ed25519::public_key(seed)),
+        }
+    }
+
+    // Secret then public key, checking that the two belong together
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KeypairError> {
+        if bytes.len() != KEYPAIR_BYTES {
+            return Err(KeypairError::WrongLength(bytes.len()));
+        }
+        let keypair = Keypair::from_seed(bytes[..SECRET_KEY_BYTES].try_into().unwrap());
+        if keypair.pubkey.as_ref() != &bytes[SECRET_KEY_BYTES..] {
+            return Err(KeypairError::PubkeyMismatch);
+        }
+        Ok(keypair)
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEYPAIR_BYTES] {
+        let mut bytes = [0u8; KEYPAIR_BYTES];
+        bytes[..SECRET_KEY_BYTES].copy_from_slice(&self.secret);
+        bytes[SECRET_KEY_BYTES..].copy_from_slice(self.pubkey.as_ref());
+        bytes
+    }
+
+    // ----------------------------------------
+    // Keypair Files
+    // ----------------------------------------
+
+    pub fn to_json(&self) -> String {
+        let bytes = self.to_bytes().iter().map(|&b| Value::from(b)).collect();
+        Value::Array(bytes).to_string()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, KeypairError> {
+        let value = json::parse(text).map_err(|_| KeypairError::InvalidFormat)?;
+        let bytes = value
+            .as_array()
+            .ok_or(KeypairError::InvalidFormat)?
+            .iter()
+            .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(KeypairError::InvalidFormat)?;
+        Keypair::from_bytes(&bytes)
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, KeypairError> {
+        Keypair::from_json(&fs::read_to_string(path)?)
+    }
+
+    // Readable by the owner only (on Unix), like solana-keygen's files.
+    // Without `overwrite` an existing file is an AlreadyExists error,
+    // decided in the same call that creates the file. `mode` only applies
+    // to new files, so an overwritten one has its permissions set before
+    // the secret goes in.
+    pub fn write_to_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        overwrite: bool,
+    ) -> Result<(), KeypairError> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true);
+        if overwrite {
+            options.create(true).truncate(true);
+        } else {
+            options.create_new(true);
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        #[cfg(unix)]
+        if overwrite {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        io::Write::write_all(&mut file, self.to_json().as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Default for Keypair {
+    fn default() -> Self {
+        Keypair::new()
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Keypair({})", self.pubkey)
+    }
+}
+
+impl Signer for Keypair {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Signature {
+        Signature(ed25519::sign(
+            &self.secret,
+            &self.pubkey.to_bytes(),
+            message,
+        ))
+    }
+}
+
+pub fn os_random_bytes<const N: usize>() -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+// ----------------------------------------
+// `simply_rust keygen`
+// ----------------------------------------
+
+// simply_rust keygen
+// simply_rust keygen --outfile wallet.json --force
+// simply_rust keygen pubkey wallet.json
+pub fn keygen(args: &[String]) -> Result<(), String> {
+    const USAGE: &str =
+        "usage: simply_rust keygen [--outfile <file>] [--force] | keygen pubkey <file>";
+    if let [command, path] = args {
+        if command == "pubkey" {
+            let keypair = Keypair::read_from_file(path).map_err(|err| err.to_string())?;
+            println!("{}", keypair.pubkey());
+            return Ok(());
+        }
+    }
+
+    let mut outfile = "keypair.json".to_string();
+    let mut force = false;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--outfile" | "-o" => outfile = args.next().ok_or(USAGE)?.clone(),
+            "--force" | "-f" => force = true,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let keypair = os_random_bytes()
+        .map(|seed| Keypair::from_seed(&seed))
+        .map_err(|err| format!("cannot read random bytes: {}", err))?;
+    match keypair.write_to_file(&outfile, force) {
+        Err(KeypairError::Io(err)) if err.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(format!(
+                "refusing to overwrite {} (use --force to allow)",
+                outfile
+            ));
+        }
+        result => result.map_err(|err| err.to_string())?,
+    }
+    println!("Wrote new keypair to {}", outfile);
+    println!("pubkey: {}", keypair.pubkey());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_created_once_unless_overwritten() {
+        let path =
+            std::env::temp_dir().join(format!("simply_rust_test_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let first = Keypair::from_seed(&[1; 32]);
+        let second = Keypair::from_seed(&[2; 32]);
+
+        first.write_to_file(&path, false).unwrap();
+        assert!(matches!(
+            second.write_to_file(&path, false),
+            Err(KeypairError::Io(err)) if err.kind() == io::ErrorKind::AlreadyExists
+        ));
+        assert_eq!(
+            Keypair::read_from_file(&path).unwrap().pubkey(),
+            first.pubkey()
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&path), 0o600);
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            second.write_to_file(&path, true).unwrap();
+            assert_eq!(mode(&path), 0o600);
+        }
+        second.write_to_file(&path, true).unwrap();
+        assert_eq!(
+            Keypair::read_from_file(&path).unwrap().pubkey(),
+            second.pubkey()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
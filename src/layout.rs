@@ -0,0 +1,499 @@
+// ========================================================================
+// TYPE LAYOUTS
+// ========================================================================
+//
+// How big is a `TokenAccount<'a>`, and where does each field sit? The
+// compiler knows, and `std::mem` will tell:
+//
+//     size_of::<T>()              bytes one value takes, padding included
+//     align_of::<T>()             addresses a value may start at
+//     offset_of!(T, field)        where a field starts inside T
+//
+// `simply_rust layout` lists every struct and enum the cheat sheet defines
+// (the few it declared inside functions now sit just above them) along
+// with the standard types whose layouts are worth knowing.
+// `simply_rust layout <Type>` prints one in detail:
+//
+//     simply_rust layout ProgramAccount
+//     simply_rust layout "Option<Box<T>>"
+//
+// Rust may reorder fields to save padding; only `#[repr(C)]` fixes the
+// declared order. Every type also shows what wrapping it in Option costs.
+// Nothing, when the type has a "niche": a bit pattern no valid value uses,
+// such as a null pointer in a Box or reference, or 2 in a bool. Option
+// stores None there instead of adding a tag.
+
+use std::any::type_name;
+use std::fmt;
+use std::mem::{align_of, offset_of, size_of};
+use std::num::NonZeroU64;
+
+use crate::amount::{Lamports, TokenAmount};
+use crate::multisig::Multisig;
+use crate::pubkey::Pubkey;
+use crate::{
+    AccountKey, Balance, ComplexError, Holder, NFTTransfer, ProgramAccount, ProgramError,
+    TokenAccount, TokenAccount2, TokenAcct, TokenError, TokenInstruction, TokenTransfer,
+    UserAccount,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub ty: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeLayout {
+    pub name: &'static str,
+    pub size: usize,
+    pub align: usize,
+    // The same type wrapped in Option
+    pub option_size: usize,
+    // By offset, which need not be the declared order
+    pub fields: Vec<Field>,
+    pub variants: &'static [&'static str],
+    pub note: &'static str,
+}
+
+// The field accessor is only there for its types: it makes the compiler
+// check that `name` is a field of T and tells us what type it has
+fn field<T, F>(name: &'static str, offset: usize, _access: fn(&T) -> &F) -> Field {
+    Field {
+        name,
+        ty: short_type_name(type_name::<F>()),
+        offset,
+        size: size_of::<F>(),
+    }
+}
+
+// `alloc::string::String` -> `String`, inside generics too
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut rest = name;
+    while let Some(pos) = rest.find("::") {
+        short.push_str(&rest[..pos]);
+        let kept = short.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_');
+        short.truncate(kept.len());
+        rest = &rest[pos + 2..];
+    }
+    short.push_str(rest);
+    short
+}
+
+// layout!("Name", Type { field, .. }, "note")   a struct's fields
+// layout!("Name", Type [Variant, ..], "note")   an enum's variants
+// layout!("Name", Type, "note")                 size and alignment only
+macro_rules! layout {
+    ($name:literal, $ty:ty { $($field:tt),* }, $note:literal) => {
+        TypeLayout {
+            fields: {
+                let mut fields = vec![
+                    $(field::<$ty, _>(stringify!($field), offset_of!($ty, $field), |v| &v.$field)),*
+                ];
+                fields.sort_by_key(|field| field.offset);
+                fields
+            },
+            ..layout!($name, $ty, $note)
+        }
+    };
+    ($name:literal, $ty:ty [ $($variant:ident),* ], $note:literal) => {
+        TypeLayout {
+            variants: &[$(stringify!($variant)),*],
+            ..layout!($name, $ty, $note)
+        }
+    };
+    ($name:literal, $ty:ty, $note:literal) => {
+        TypeLayout {
+            name: $name,
+            size: size_of::<$ty>(),
+            align: align_of::<$ty>(),
+            option_size: size_of::<Option<$ty>>(),
+            fields: Vec::new(),
+            variants: &[],
+            note: $note,
+        }
+    };
+}
+
+// ----------------------------------------
+// The Types
+// ----------------------------------------
+
+// Everything the cheat sheet itself defines, in the order it does
+pub fn cheat_sheet_types() -> Vec<TypeLayout> {
+    vec![
+        layout!(
+            "TokenInstruction",
+            TokenInstruction[Initialize, Transfer, Mint, Burn],
+            "Four variants without data need one byte for the tag, and \
+             Option takes one of the 252 values left over"
+        ),
+        layout!(
+            "TokenAccount<'a>",
+            TokenAccount<'static> { amount, owner },
+            "A &str is a pointer and a length; the lifetime costs nothing \
+             at runtime"
+        ),
+        layout!(
+            "Holder<u8>",
+            Holder<u8> { value },
+            "A generic struct is laid out separately for every T it holds"
+        ),
+        layout!(
+            "Holder<u64>",
+            Holder<u64> { value },
+            "A generic struct is laid out separately for every T it holds"
+        ),
+        layout!(
+            "Holder<String>",
+            Holder<String> { value },
+            "A generic struct is laid out separately for every T it holds"
+        ),
+        layout!(
+            "TokenAccount2<'a>",
+            TokenAccount2<'static> {
+                mint,
+                owner,
+                amount
+            },
+            "TokenAccount<'a> with one more &str: 16 bytes more, and \
+             still no padding"
+        ),
+        layout!(
+            "TokenTransfer",
+            TokenTransfer {
+                from,
+                to,
+                amount_lamports,
+                sig
+            },
+            "Three Strings are three pointer/capacity/length triples; the \
+             text itself is on the heap"
+        ),
+        layout!(
+            "NFTTransfer",
+            NFTTransfer {
+                collection,
+                token_id,
+                new_owner,
+                authority,
+                signed_by,
+                sig
+            },
+            "Declared second, token_id ends up last: the order in memory \
+             is the compiler's choice"
+        ),
+        layout!(
+            "UserAccount",
+            UserAccount { name, lamports },
+            "Eight-byte fields only, so no padding"
+        ),
+        layout!(
+            "ProgramAccount",
+            ProgramAccount { id, is_executable },
+            "A bool after a String leaves seven bytes of padding, so that \
+             the next value in an array is aligned again"
+        ),
+        layout!(
+            "Balance",
+            Balance[Sol, Spl],
+            "The tag hides in a niche of Spl's String, so the enum is no \
+             bigger than its largest variant"
+        ),
+        layout!(
+            "TokenAcct",
+            TokenAcct { owner, amount },
+            "Same fields as UserAccount in the other order, same layout"
+        ),
+        layout!(
+            "TokenError",
+            TokenError[
+                InsufficientBalance,
+                AccountNotFound,
+                UnauthorizedSigner,
+                InvalidAmount
+            ],
+            "Unit variants only: a single byte"
+        ),
+        layout!(
+            "ComplexError",
+            ComplexError[Token, Network, Serialization],
+            "Token and Serialization fit in the niche of Network's String"
+        ),
+        layout!(
+            "ProgramError",
+            ProgramError[
+                InvalidInstruction,
+                NotRentExempt,
+                InsufficientFunds,
+                AlreadyInitialized
+            ],
+            "Unit variants only: a single byte"
+        ),
+        layout!(
+            "AccountKey",
+            AccountKey { pubkey, is_signer },
+            "As a HashMap key the whole struct is hashed and stored, String \
+             and all"
+        ),
+        layout!(
+            "Lamports",
+            Lamports { 0 },
+            "A newtype costs nothing: same size and alignment as the u64 it \
+             wraps"
+        ),
+        layout!(
+            "TokenAmount",
+            TokenAmount { raw, decimals },
+            "A u64 and a u8 round up to 16 bytes. Padding is not a niche: \
+             its bytes may hold anything, so Option needs a tag"
+        ),
+        layout!(
+            "Pubkey",
+            Pubkey { 0 },
+            "32 bytes with alignment 1: it can start anywhere in account \
+             data"
+        ),
+        layout!(
+            "Multisig",
+            Multisig { m, signers },
+            "Declared m first, but the Vec goes first to avoid padding \
+             between them"
+        ),
+    ]
+}
+
+// Standard types, mostly to show which have niches
+pub fn std_types() -> Vec<TypeLayout> {
+    vec![
+        layout!(
+            "u64",
+            u64,
+            "Every bit pattern is a valid u64, so Option needs a separate \
+             tag, padded to eight bytes"
+        ),
+        layout!(
+            "NonZeroU64",
+            NonZeroU64,
+            "Zero is never valid, so Option<NonZeroU64> uses it for None"
+        ),
+        layout!(
+            "bool",
+            bool,
+            "Only 0 and 1 are valid; Option uses 2 for None"
+        ),
+        layout!(
+            "Option<bool>",
+            Option<bool>,
+            "Still one byte, and so is Option<Option<bool>>: 3 is free too"
+        ),
+        layout!("char", char, "Values above 0x10FFFF are never valid chars"),
+        layout!(
+            "&u64",
+            &u64,
+            "References are never null, so None is the null pointer"
+        ),
+        layout!(
+            "Box<u64>",
+            Box<u64>,
+            "Never null either: Option<Box<T>> is a nullable pointer for free"
+        ),
+        layout!(
+            "Box<[u8]>",
+            Box<[u8]>,
+            "A pointer to a slice carries its length: two words"
+        ),
+        layout!(
+            "&str",
+            &str,
+            "Pointer and length; the text stays where it is"
+        ),
+        layout!("&[u8]", &[u8], "Pointer and length, like &str"),
+        layout!(
+            "String",
+            String,
+            "Pointer, capacity and length; the capacity can never exceed \
+             isize::MAX, which leaves a niche"
+        ),
+        layout!(
+            "Vec<u8>",
+            Vec<u8>,
+            "Laid out like String, which is a Vec<u8> underneath"
+        ),
+        layout!(
+            "[u8; 32]",
+            [u8; 32],
+            "An array is its elements back to back, with no length stored"
+        ),
+        layout!(
+            "()",
+            (),
+            "Zero-sized: takes no space at all, though Option<()> needs its tag"
+        ),
+        layout!(
+            "Result<(), TokenError>",
+            Result<(), TokenError>,
+            "Ok goes in one of TokenError's unused tag values"
+        ),
+        layout!(
+            "Result<u64, TokenError>",
+            Result<u64, TokenError>,
+            "A u64 has no niche, so the tag gets its own eight-byte slot"
+        ),
+    ]
+}
+
+// ----------------------------------------
+// Display
+// ----------------------------------------
+
+impl fmt::Display for TypeLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {}, align {}",
+            self.name,
+            bytes(self.size),
+            self.align
+        )?;
+        if !self.fields.is_empty() {
+            writeln!(f, "  offset  size  field")?;
+            let mut end = 0;
+            for field in &self.fields {
+                if field.offset > end {
+                    writeln!(f, "  {:>6}  {:>4}  (padding)", end, field.offset - end)?;
+                }
+                writeln!(
+                    f,
+                    "  {:>6}  {:>4}  {}: {}",
+                    field.offset, field.size, field.name, field.ty
+                )?;
+                end = end.max(field.offset + field.size);
+            }
+            if self.size > end {
+                writeln!(f, "  {:>6}  {:>4}  (padding)", end, self.size - end)?;
+            }
+        }
+        if !self.variants.is_empty() {
+            writeln!(f, "  variants: {}", self.variants.join(", "))?;
+        }
+        if self.option_size == self.size {
+            writeln!(
+                f,
+                "  Option<{}> is also {}: None uses a niche",
+                self.name,
+                bytes(self.option_size)
+            )?;
+        } else {
+            writeln!(
+                f,
+                "  Option<{}> is {}: {} more for its tag and padding",
+                self.name,
+                bytes(self.option_size),
+                self.option_size - self.size
+            )?;
+        }
+        write!(f, "  {}", self.note)
+    }
+}
+
+fn bytes(n: usize) -> String {
+    format!("{} byte{}", n, if n == 1 { "" } else { "s" })
+}
+
+// ----------------------------------------
+// Command Line
+// ----------------------------------------
+
+// Ignoring spaces and case, so `option<box<u64>>` works
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Up to the first `<`: `Holder<T>` finds every Holder
+fn base_name(name: &str) -> &str {
+    name.split('<').next().unwrap_or(name)
+}
+
+// The layouts `query` names: an exact match if there is one, otherwise
+// every instance of a generic. `Option<X>` finds X, whose layout covers
+// its Option too, and `Option<Box<T>>` every Box.
+fn find(layouts: &[TypeLayout], query: &str) -> Vec<usize> {
+    let query = normalize(query);
+    let mut candidates = vec![query.as_str()];
+    let mut inner = query.as_str();
+    while let Some(unwrapped) = inner
+        .strip_prefix("option<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        candidates.push(unwrapped);
+        inner = unwrapped;
+    }
+
+    for candidate in &candidates {
+        let exact: Vec<usize> = (0..layouts.len())
+            .filter(|&i| normalize(layouts[i].name) == *candidate)
+            .collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+    }
+    for candidate in candidates.iter().rev() {
+        let generic: Vec<usize> = (0..layouts.len())
+            .filter(|&i| {
+                let name = normalize(layouts[i].name);
+                name.contains('<') && base_name(&name) == base_name(candidate)
+            })
+            .collect();
+        if !generic.is_empty() {
+            return generic;
+        }
+    }
+    Vec::new()
+}
+
+// simply_rust layout
+// simply_rust layout "TokenAccount<'a>"
+pub fn run(args: &[String]) -> Result<(), String> {
+    let cheat_sheet = cheat_sheet_types();
+    let std = std_types();
+    let query = match args {
+        [] => {
+            println!("Usage: simply_rust layout <Type>\n");
+            for (title, layouts) in [
+                ("Cheat sheet types", &cheat_sheet),
+                ("Standard types", &std),
+            ] {
+                println!("{:<26} {:>5} {:>6} {:>7}", title, "size", "align", "Option");
+                for layout in layouts {
+                    println!(
+                        "  {:<24} {:>5} {:>6} {:>7}",
+                        layout.name, layout.size, layout.align, layout.option_size
+                    );
+                }
+                println!();
+            }
+            return Ok(());
+        }
+        [query] => query,
+        _ => return Err("usage: simply_rust layout [Type]".to_string()),
+    };
+
+    let layouts: Vec<TypeLayout> = cheat_sheet.into_iter().chain(std).collect();
+    let found = find(&layouts, query);
+    if found.is_empty() {
+        return Err(format!(
+            "no layout for `{}` (run `simply_rust layout` to list them)",
+            query
+        ));
+    }
+    let details: Vec<String> = found.iter().map(|&i| layouts[i].to_string()).collect();
+    println!("{}", details.join("\n\n"));
+    Ok(())
+}
@@ -0,0 +1,370 @@
+// ========================================================================
+// ACCOUNT STORE AND LEDGER SNAPSHOTS
+// ========================================================================
+//
+// Every piece of on-chain state is an account: a lamport balance, a byte
+// buffer of data, the program that owns it, and whether it is executable.
+// `AccountStore` keeps them in a HashMap keyed by address, and can be
+// saved to (and restored from) a binary snapshot file so demos can start
+// from a prepared ledger instead of rebuilding it every run.
+//
+// Snapshot layout (all integers little-endian):
+//
+//     magic        8 bytes   "SRLEDGER"
+//     version      u32       SNAPSHOT_VERSION
+//     count        u64       number of accounts
+//     accounts     count x { pubkey [32], lamports u64, owner [32],
+//                            executable u8, data_len u64, data }
+//     checksum     32 bytes  SHA-256 of everything above
+//
+// Accounts are written in address order, so the same store always
+// produces the same file.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::amount::Lamports;
+use crate::hash::hash;
+use crate::pubkey::{Pubkey, PUBKEY_BYTES, SYSTEM_PROGRAM_ID};
+
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"SRLEDGER";
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+// ----------------------------------------
+// Accounts
+// ----------------------------------------
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl Account {
+    // A zero-filled data buffer of `space` bytes owned by `owner`
+    pub fn new(lamports: u64, space: usize, owner: &Pubkey) -> Self {
+        Account {
+            lamports,
+            data: vec![0; space],
+            owner: *owner,
+            executable: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountStore {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl AccountStore {
+    pub fn new() -> Self {
+        AccountStore::default()
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    pub fn get_mut(&mut self, pubkey: &Pubkey) -> Option<&mut Account> {
+        self.accounts.get_mut(pubkey)
+    }
+
+    // Insert or replace an account, returning the previous one
+    pub fn store(&mut self, pubkey: Pubkey, account: Account) -> Option<Account> {
+        self.accounts.insert(pubkey, account)
+    }
+
+    pub fn remove(&mut self, pubkey: &Pubkey) -> Option<Account> {
+        self.accounts.remove(pubkey)
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    // Lamports of an address, 0 if the account does not exist
+    pub fn lamports(&self, pubkey: &Pubkey) -> u64 {
+        self.get(pubkey).map_or(0, |account| account.lamports)
+    }
+
+    // None if the balances add up to more than u64::MAX, which any
+    // store loaded from a snapshot file can
+    pub fn total_lamports(&self) -> Option<Lamports> {
+        self.accounts
+            .values()
+            .try_fold(Lamports::ZERO, |total, account| {
+                total.checked_add(Lamports(account.lamports))
+            })
+    }
+
+    // Accounts sorted by address, for stable output and snapshots
+    pub fn sorted(&self) -> Vec<(&Pubkey, &Account)> {
+        let mut accounts: Vec<_> = self.accounts.iter().collect();
+        accounts.sort_by_key(|(pubkey, _)| **pubkey);
+        accounts
+    }
+}
+
+// ----------------------------------------
+// Snapshot Errors
+// ----------------------------------------
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    NotASnapshot,
+    UnsupportedVersion { found: u32, supported: u32 },
+    ChecksumMismatch,
+    Truncated,
+    TrailingBytes(usize),
+    // The same address stored twice
+    DuplicateAccount(Pubkey),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "snapshot I/O error: {}", err),
+            SnapshotError::NotASnapshot => write!(f, "not a ledger snapshot (bad magic bytes)"),
+            SnapshotError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "snapshot version {} is not supported (this build reads version {})",
+                found, supported
+            ),
+            SnapshotError::ChecksumMismatch => {
+                write!(f, "snapshot checksum mismatch: the file is corrupted")
+            }
+            SnapshotError::Truncated => write!(f, "snapshot ends unexpectedly"),
+            SnapshotError::TrailingBytes(n) => {
+                write!(f, "snapshot has {} unexpected trailing bytes", n)
+            }
+            SnapshotError::DuplicateAccount(pubkey) => {
+                write!(f, "snapshot holds account {} more than once", pubkey)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+// ----------------------------------------
+// Snapshot Encoding
+// ----------------------------------------
+
+impl AccountStore {
+    pub fn to_snapshot_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(SNAPSHOT_MAGIC);
+        out.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.len() as u64).to_le_bytes());
+
+        for (pubkey, account) in self.sorted() {
+            out.extend_from_slice(pubkey.as_ref());
+            out.extend_from_slice(&account.lamports.to_le_bytes());
+            out.extend_from_slice(account.owner.as_ref());
+            out.push(account.executable as u8);
+            out.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            out.extend_from_slice(&account.data);
+        }
+
+        let checksum = hash(&out);
+        out.extend_from_slice(checksum.as_ref());
+        out
+    }
+
+    pub fn from_snapshot_bytes(bytes: &[u8]) -> Result<AccountStore, SnapshotError> {
+        // Check magic and version before the checksum, so an older or newer
+        // file reports a version problem rather than "corrupted"
+        if bytes.len() < SNAPSHOT_MAGIC.len() || &bytes[..8] != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let mut reader = SnapshotReader {
+            bytes,
+            pos: SNAPSHOT_MAGIC.len(),
+        };
+        let version = reader.u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: version,
+                supported: SNAPSHOT_VERSION,
+            });
+        }
+
+        if bytes.len() < reader.pos + 32 {
+            return Err(SnapshotError::Truncated);
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - 32);
+        if hash(body).as_ref() != checksum {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+
+        let mut reader = SnapshotReader {
+            bytes: body,
+            pos: reader.pos,
+        };
+        let count = reader.u64()?;
+        let mut store = AccountStore::new();
+        for _ in 0..count {
+            let pubkey = reader.pubkey()?;
+            let lamports = reader.u64()?;
+            let owner = reader.pubkey()?;
+            let executable = reader.take(1)?[0] != 0;
+            let data_len = reader.u64()? as usize;
+            let data = reader.take(data_len)?.to_vec();
+            let account = Account {
+                lamports,
+                data,
+                owner,
+                executable,
+            };
+            if store.store(pubkey, account).is_some() {
+                return Err(SnapshotError::DuplicateAccount(pubkey));
+            }
+        }
+
+        let remaining = body.len() - reader.pos;
+        if remaining > 0 {
+            return Err(SnapshotError::TrailingBytes(remaining));
+        }
+        Ok(store)
+    }
+
+    // Write to a temporary file first so a crash never leaves a half
+    // written snapshot behind
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, self.to_snapshot_bytes())?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> Result<AccountStore, SnapshotError> {
+        let bytes = fs::read(path)?;
+        AccountStore::from_snapshot_bytes(&bytes)
+    }
+}
+
+struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if len > self.bytes.len() - self.pos {
+            return Err(SnapshotError::Truncated);
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, SnapshotError> {
+        Ok(Pubkey(self.take(PUBKEY_BYTES)?.try_into().unwrap()))
+    }
+}
+
+// ----------------------------------------
+// Prepared Demo State
+// ----------------------------------------
+
+// The wallets and data account used across the cheat sheet examples
+pub fn demo_store() -> AccountStore {
+    let mut store = AccountStore::new();
+    let wallets = [
+        (
+            "8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa",
+            42_500_000_000,
+        ),
+        ("4Qkev8aNZcqzmXLY6oKWwR8ziedQrXG47kJuNHSfsjNj", 1_000_000),
+    ];
+    for (address, lamports) in wallets {
+        let pubkey: Pubkey = address.parse().expect("valid example address");
+        store.store(pubkey, Account::new(lamports, 0, &SYSTEM_PROGRAM_ID));
+    }
+
+    // The "SOL/USDC" pair account decoded in the slices section
+    let token_program: Pubkey = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        .parse()
+        .expect("valid program id");
+    let pair: Pubkey = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+        .parse()
+        .expect("valid example address");
+    let mut pair_account = Account::new(2_039_280, 0, &token_program);
+    pair_account.data = vec![
+        1, 0, 0, 0, 255, 255, 255, 255, 8, 0, 0, 0, 83, 79, 76, 47, 85, 83, 68, 67,
+    ];
+    store.store(pair, pair_account);
+    store
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A snapshot of `entries` as written, duplicates and all, with a
+    // valid checksum
+    fn snapshot(entries: &[(Pubkey, u64)]) -> Vec<u8> {
+        let mut out = SNAPSHOT_MAGIC.to_vec();
+        out.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+        for (pubkey, lamports) in entries {
+            out.extend_from_slice(pubkey.as_ref());
+            out.extend_from_slice(&lamports.to_le_bytes());
+            out.extend_from_slice(SYSTEM_PROGRAM_ID.as_ref());
+            out.push(0);
+            out.extend_from_slice(&0u64.to_le_bytes());
+        }
+        let checksum = hash(&out);
+        out.extend_from_slice(checksum.as_ref());
+        out
+    }
+
+    #[test]
+    fn snapshots_round_trip() {
+        let store = demo_store();
+        let restored = AccountStore::from_snapshot_bytes(&store.to_snapshot_bytes()).unwrap();
+        assert_eq!(restored, store);
+    }
+
+    #[test]
+    fn totals_past_u64_max_are_none() {
+        let huge = (1 << 63) + 5;
+        let bytes = snapshot(&[(Pubkey::new_unique(), huge), (Pubkey::new_unique(), huge)]);
+        let store = AccountStore::from_snapshot_bytes(&bytes).unwrap();
+        assert_eq!(store.total_lamports(), None);
+
+        let bytes = snapshot(&[(Pubkey::new_unique(), 5), (Pubkey::new_unique(), 7)]);
+        let store = AccountStore::from_snapshot_bytes(&bytes).unwrap();
+        assert_eq!(store.total_lamports(), Some(Lamports(12)));
+    }
+
+    #[test]
+    fn duplicate_accounts_are_rejected() {
+        let pubkey = Pubkey::new_unique();
+        let bytes = snapshot(&[(pubkey, 1), (pubkey, 2)]);
+        assert!(matches!(
+            AccountStore::from_snapshot_bytes(&bytes),
+            Err(SnapshotError::DuplicateAccount(key)) if key == pubkey
+        ));
+    }
+}
@@ -7,6 +7,49 @@
 // concepts, with detailed explanations to help newcomers understand
 // the language features.
 
+mod account_view;
+mod address_lookup_table_program;
+mod alloc_counter;
+mod amm_program;
+mod amount;
+mod associated_token_program;
+mod bank;
+mod cli;
+mod clock;
+mod compile_fail;
+mod compute_budget;
+mod curve25519;
+mod ed25519;
+mod escrow_program;
+mod exercise;
+mod fee;
+mod grind;
+mod hash;
+mod history;
+mod json;
+mod keypair;
+mod layout;
+mod ledger;
+mod mnemonic;
+mod multisig;
+mod nft_program;
+mod program;
+mod property;
+mod pubkey;
+mod rpc;
+mod scheduler;
+mod schema;
+mod simulation;
+mod system_program;
+mod timelock;
+mod token_program;
+mod transaction;
+mod transaction_builder;
+
+use amount::{Lamports, TokenAmount};
+use multisig::Multisig;
+use pubkey::Pubkey;
+
 // ========================================================================
 // 1. BASIC CONCEPTS
 // ========================================================================
@@ -15,16 +58,18 @@
 // Printing and Basic Output
 // ----------------------------------------
 
+#[allow(clippy::print_with_newline)]
 fn printing_examples() {
     // Basic print
     println!("Hello, Solana!");
 
-    // Print with variables
-    let sol_balance = 42.5;
-    println!("Account balance: {} SOL", sol_balance);
+    // Print with variables (balances are exact integers, see src/amount.rs)
+    let sol_balance: Lamports = "42.5 SOL".parse().unwrap();
+    println!("Account balance: {}", sol_balance); // 42.5 SOL
 
     // Formatting options
-    println!("Balance in lamports: {:.0}", sol_balance * 1_000_000_000.0); // No decimal
+    println!("Balance in lamports: {}", sol_balance.0); // 42500000000, no float math
+    println!("Balance (2 places): {} SOL", sol_balance.to_sol_rounded(2)); // 42.50
     println!("Hex pubkey: {:#x}", 0x1234ABCD); // Hex format with 0x prefix
 
     // Debug printing (useful for complex data structures)
@@ -49,8 +94,14 @@ fn variables_and_mutability() {
     let wallet_address = "8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa";
     println!("Wallet address: {}", wallet_address);
 
+    // An address is only the public half of a keypair; signing needs the
+    // secret half too (`simply_rust keygen` makes one, see src/keypair.rs)
+    let keypair = keypair::Keypair::from_seed(&[7; 32]);
+    println!("Keypair from a fixed seed: {:?}", keypair);
+
     // This would cause an error:
     // wallet_address = "different_address"; // Cannot reassign immutable variable
+    // (E0384 - run `simply_rust compile-fail immutable_reassign` to see it)
 
     // For mutable variables, use 'mut'
     let mut token_balance = 100;
@@ -70,6 +121,7 @@ fn variables_and_mutability() {
 // Shadowing
 // ----------------------------------------
 
+#[allow(unused_variables)]
 fn shadowing_examples() {
     // Shadowing allows reusing variable names
     let balance = "50";
@@ -106,6 +158,7 @@ fn convert_bytes_to_instruction(data: &[u8]) -> String {
 // Basic Data Types
 // ----------------------------------------
 
+#[allow(unused_variables)]
 fn basic_data_types() {
     // Integers (commonly used in Solana for amounts, timestamps, etc.)
     let lamports: u64 = 5_000_000_000; // Unsigned 64-bit integer (no negative values)
@@ -115,7 +168,19 @@ fn basic_data_types() {
     // because negative balances don't make sense
 
     // Floating-point (rarely used in blockchain for precision reasons)
-    let sol_amount: f64 = 5.0; // Avoid for financial calculations!
+    let utilization: f64 = 0.75; // Fine for ratios and statistics...
+    println!("0.1 + 0.2 = {} (not 0.3!)", 0.1 + 0.2); // ...but avoid for money!
+
+    // For money, keep the integer amount and its decimals instead
+    let sol_amount = Lamports(lamports); // 5 SOL, exactly
+    let usdc_amount = TokenAmount::parse_ui("0.3", 6).unwrap(); // raw = 300_000
+    println!(
+        "SOL: {}, USDC: {} (raw {}, {} to 2 places)",
+        sol_amount,
+        usdc_amount,
+        usdc_amount.raw,
+        usdc_amount.to_ui_rounded(2)
+    );
 
     // Boolean
     let is_signer: bool = true;
@@ -148,11 +213,11 @@ fn basic_data_types() {
 
 fn control_flow_examples() {
     // If expressions (expressions return values)
-    let lamports = 1_500_000_000;
-    let sol_amount = if lamports >= 1_000_000_000 {
-        lamports as f64 / 1_000_000_000.0
+    let lamports = Lamports(1_500_000_000);
+    let sol_amount = if lamports >= Lamports::from_sol(1).unwrap() {
+        lamports.to_sol_string()
     } else {
-        0.0
+        "less than 1".to_string()
     };
     println!("SOL amount: {}", sol_amount);
 
@@ -211,6 +276,7 @@ fn control_flow_examples() {
 }
 
 // Enum for the control flow example
+#[allow(dead_code)]
 enum TokenInstruction {
     Initialize,
     Transfer,
@@ -277,6 +343,9 @@ fn complex_pattern_matching() {
 // 3. MEMORY MANAGEMENT: STACK vs HEAP
 // ========================================================================
 
+use std::hint::black_box;
+
+#[allow(unused_variables, clippy::useless_vec)]
 fn stack_vs_heap_examples() {
     // STACK: Fixed size, fast access, follows LIFO (Last In, First Out)
     // - Primitive types (i32, bool, etc.)
@@ -350,6 +419,41 @@ fn stack_vs_heap_examples() {
 
     // Visualizing the box
     println!("Box value: {}", *heap_box); // Dereference to get the value
+
+    // --------- MEASURING IT ---------
+
+    // Every heap allocation goes through the global allocator, so the
+    // claims above can be counted. Build with
+    // `cargo run --features count-allocations` to install a counting
+    // allocator (see src/alloc_counter.rs). black_box keeps the optimizer
+    // from removing values nobody reads.
+    if !alloc_counter::enabled() {
+        println!("(run with `--features count-allocations` to count heap allocations)");
+        return;
+    }
+    println!("Heap allocations per example:");
+    alloc_counter::print_allocations("let n: u64 = 123456789", || black_box(123456789u64));
+    alloc_counter::print_allocations("let id: [u8; 32] = [0; 32]", || black_box([0u8; 32]));
+    alloc_counter::print_allocations("let r = &s", || black_box(&s).len());
+    alloc_counter::print_allocations("String::from(\"Solana Public Key\")", || {
+        black_box(String::from("Solana Public Key"))
+    });
+    alloc_counter::print_allocations("vec![10, 20, 30, 40]", || black_box(vec![10, 20, 30, 40]));
+    alloc_counter::print_allocations("Box::new(50)", || black_box(Box::new(50)));
+    alloc_counter::print_allocations("Vec::<i32>::new()", || black_box(Vec::<i32>::new()));
+    alloc_counter::print_allocations("Vec::<u8>::with_capacity(5)", || {
+        black_box(Vec::<u8>::with_capacity(5))
+    });
+    // Growing past the capacity moves the elements to a bigger block
+    alloc_counter::print_allocations("push 100 u64s onto Vec::new()", || {
+        let mut amounts = Vec::new();
+        for amount in 0..100u64 {
+            amounts.push(black_box(amount));
+        }
+        amounts
+    });
+    alloc_counter::print_allocations("s.clone()", || black_box(s.clone()));
+    alloc_counter::print_allocations("Option<Box<u64>> = None", || black_box(None::<Box<u64>>));
 }
 
 // ========================================================================
@@ -360,6 +464,7 @@ fn stack_vs_heap_examples() {
 // Ownership Basics
 // ----------------------------------------
 
+#[allow(unused_variables)]
 fn ownership_basics() {
     // OWNERSHIP RULES:
     // 1. Each value has an owner
@@ -379,7 +484,7 @@ fn ownership_basics() {
     let token_name2 = token_name; // Ownership moves to token_name2
 
     // This would fail because token_name no longer owns the data:
-    // println!("Token: {}", token_name); // Error!
+    // println!("Token: {}", token_name); // Error! (E0382, see compile_fail/use_after_move.rs)
     println!("Token: {}", token_name2); // Works
 
     // For primitive types that implement Copy trait, values are copied instead of moved
@@ -393,7 +498,7 @@ fn ownership_basics() {
     // Function ownership
     let program_id = String::from("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
     takes_ownership(program_id);
-    // program_id is no longer valid here
+    // program_id is no longer valid here (E0382, see compile_fail/moved_into_function.rs)
 
     let new_id = gives_ownership();
     println!("New ID: {}", new_id);
@@ -411,6 +516,7 @@ fn takes_ownership(program_id: String) {
 }
 
 // Function that gives ownership
+#[allow(clippy::let_and_return)]
 fn gives_ownership() -> String {
     let id = String::from("MangoCzJ36AjZyKwVj3VnYU4GTonjfVEnJmvvWaxLac");
     id // Return and transfer ownership
@@ -426,6 +532,7 @@ fn takes_and_gives_back(program_id: String) -> String {
 // Referencing, Dereferencing, and Pointers
 // ----------------------------------------
 
+#[allow(clippy::needless_borrow)]
 fn references_and_borrowing() {
     // REFERENCE RULES:
     // 1. At any time, you can have EITHER:
@@ -479,6 +586,7 @@ fn references_and_borrowing() {
         // These would fail:
         // let supply_ref2 = &mut token_supply; // Error: cannot borrow as mutable more than once
         // let supply_ref3 = &token_supply;    // Error: cannot borrow as immutable while mutable borrow
+        // (E0499 and E0502, see compile_fail/double_mutable_borrow.rs and shared_while_mutable.rs)
 
         // Modify through mutable reference
         supply_ref.push_str(" SPL");
@@ -558,7 +666,7 @@ fn lifetime_examples() {
     // LIFETIME BASICS:
 
     // Problem: This function would fail without lifetime parameters
-    // fn longest(s1: &str, s2: &str) -> &str { ... }
+    // fn longest(s1: &str, s2: &str) -> &str { ... } // E0106, see compile_fail/missing_lifetime.rs
 
     // The compiler needs to know that the returned reference
     // will be valid for at least as long as the inputs
@@ -595,6 +703,9 @@ fn lifetime_examples() {
         };
 
         println!("Account owner: {}", account.owner);
+
+        // account cannot be used after owner_name is dropped
+        // (E0597, see compile_fail/struct_outlives_owner.rs)
     } // Both owner_name and account go out of scope here
 
     // ------ STATIC LIFETIME ------
@@ -625,6 +736,7 @@ struct TokenAccount<'a> {
 // 5. STRINGS AND SLICES
 // ========================================================================
 
+#[allow(unused_variables, clippy::redundant_slicing)]
 fn string_and_slice_examples() {
     // ---- TWO STRING TYPES IN RUST ----
 
@@ -684,6 +796,7 @@ fn string_and_slice_examples() {
     // Using + operator (note: it takes ownership of the first String)
     let message = greeting + &audience; // greeting is moved here and can't be used after
     println!("Message: {}", message);
+    // println!("{}", greeting); // E0382, see compile_fail/moved_by_concatenation.rs
 
     // Better way for multiple strings: format! macro
     let part1 = "Solana";
@@ -737,6 +850,7 @@ fn string_and_slice_examples() {
 // 6. SLICES (GENERAL CONCEPT)
 // ========================================================================
 
+#[allow(unused_variables, clippy::useless_vec)]
 fn slice_examples() {
     // Slices = References to contiguous sequence of elements
     // - Type: &[T]
@@ -792,22 +906,65 @@ fn slice_examples() {
 
     // In Solana, account data is often accessed via slices
     let account_data = vec![
-        1, 0, 0, 0, 255, 255, 255, 255, 5, 0, 0, 0, 83, 79, 76, 47, 85, 83, 68, 67,
+        1, 0, 0, 0, 255, 255, 255, 255, 8, 0, 0, 0, 83, 79, 76, 47, 85, 83, 68, 67,
     ];
 
     // Get the instruction type (first byte)
     let instruction_type = account_data[0];
 
-    // Get the token name (bytes 8-12 in this example)
-    let name_length = account_data[8] as usize; // 5 bytes
-    let name_slice = &account_data[9..9 + name_length];
+    // Get the token name (u32 length at bytes 8-12, then the name itself)
+    let name_length = u32::from_le_bytes([
+        account_data[8],
+        account_data[9],
+        account_data[10],
+        account_data[11],
+    ]) as usize; // 8 bytes
+    let name_slice = &account_data[12..12 + name_length];
 
     // Convert bytes to string (in real code, use proper error handling)
     let name = std::str::from_utf8(name_slice).unwrap_or("Invalid UTF-8");
     println!("Token pair name: {}", name); // "SOL/USDC"
 
+    // The pool trading this pair lives in src/amm_program.rs
+    // (`simply_rust demo amm`)
+
     // Slices help efficiently process parts of serialized data
     // without needing to copy the entire dataset
+
+    // ----- DECODING WITH A LAYOUT SCHEMA -----
+
+    // Hand-written offsets are easy to get wrong. A schema describes each
+    // field once and a generic decoder walks the slice for us
+    // (see src/schema.rs and schemas/token_pair.layout)
+    use schema::{DecodedField, Field, FieldTree, FieldType, LengthPrefix, Schema, Value};
+
+    let pair_schema = Schema::new(
+        "TokenPair",
+        vec![
+            Field::new("kind", FieldType::U32),
+            Field::at("flags", FieldType::U32, 4),
+            Field::new("name", FieldType::String(LengthPrefix::U32)),
+        ],
+    );
+
+    match pair_schema.decode(&account_data) {
+        Ok(fields) => {
+            print!("{}", FieldTree(&fields));
+            if let Some(DecodedField {
+                value: Value::String(name),
+                ..
+            }) = DecodedField::find(&fields, "name")
+            {
+                println!("Decoded pair name: {}", name);
+            }
+        }
+        Err(err) => println!("Decode failed: {}", err),
+    }
+
+    // A truncated buffer reports which field failed and where
+    if let Err(err) = pair_schema.decode(&account_data[..15]) {
+        println!("Truncated buffer: {}", err); // field `name` at byte 12: ...
+    }
 }
 
 // Helper function for sum calculation
@@ -908,15 +1065,49 @@ fn generic_examples() {
     }
 
     // In Solana, generics are often used for account serialization/deserialization
-    // Example: Simulating a token account with generic owner type
+    // Example: Simulating a token account with generic owner type. Which
+    // account holds a wallet's tokens of a mint is not made up by hand: the
+    // Associated Token Account program derives its address from the two
+    // (see src/associated_token_program.rs)
+    let wallet: Pubkey = "8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa"
+        .parse()
+        .unwrap();
+    let usdc: Pubkey = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+        .parse()
+        .unwrap();
+    let address = associated_token_program::get_associated_token_address(&wallet, &usdc);
+
+    // TokenAccount2 borrows its strings, so they need an owner that lives
+    // at least as long as the account does
+    let (mint, owner) = (usdc.to_string(), wallet.to_string());
     let token_account = TokenAccount2 {
-        mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
-        owner: "8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa",
+        mint: &mint,
+        owner: &owner,
         amount: 100,
     };
 
     // Process the account
-    process_token_account(&token_account);
+    process_token_account(&address, &token_account);
+
+    // On chain the account is 165 bytes, not three strings. A view borrows
+    // straight from those bytes the way TokenAccount2 borrows its &str
+    // fields, so reading the balance copies nothing (see src/account_view.rs)
+    let data = token_program::TokenAccount {
+        mint: usdc,
+        owner: wallet,
+        amount: token_account.amount,
+        state: token_program::AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack();
+    match account_view::TokenAccountView::new(&data) {
+        Ok(view) => println!(
+            "  Viewed from bytes: {} owns {}",
+            view.owner(),
+            view.amount()
+        ),
+        Err(err) => println!("  Cannot view: {}", err),
+    }
 }
 
 // Generic function that works with any collection type
@@ -935,9 +1126,9 @@ struct TokenAccount2<'a> {
     amount: u64,
 }
 
-// Process the generic token account
-fn process_token_account<'a>(account: &TokenAccount2<'a>) {
-    println!("Processing token account:");
+// Process the generic token account stored at `address`
+fn process_token_account<'a>(address: &Pubkey, account: &TokenAccount2<'a>) {
+    println!("Processing token account {}:", address);
     println!("  Mint: {}", account.mint);
     println!("  Owner: {}", account.owner);
     println!("  Amount: {}", account.amount);
@@ -957,6 +1148,11 @@ pub trait Transaction {
     fn is_valid(&self) -> bool {
         self.verify() && self.amount() > 0
     }
+
+    // Sending it costs the base fee for one signature (see src/fee.rs)
+    fn fee(&self) -> Lamports {
+        Lamports(fee::DEFAULT_LAMPORTS_PER_SIGNATURE)
+    }
 }
 
 // Implement the trait for a specific type
@@ -986,12 +1182,17 @@ impl Transaction for TokenTransfer {
     // We're using the default implementation for is_valid()
 }
 
-// Another type implementing the same trait
+// Another type implementing the same trait. The collection's authority is
+// an M-of-N multisig (src/multisig.rs), so a transfer is only valid once
+// enough of its members have signed. Real NFT data (creators, royalties,
+// collections, editions) lives in src/nft_program.rs; see
+// `simply_rust demo nft`.
 struct NFTTransfer {
     collection: String,
     token_id: u64,
     new_owner: String,
-    signed_by: String,
+    authority: Multisig,
+    signed_by: Vec<Pubkey>,
     sig: String,
 }
 
@@ -1005,7 +1206,7 @@ impl Transaction for NFTTransfer {
     }
 
     fn verify(&self) -> bool {
-        !self.sig.is_empty() && self.signed_by == "authority"
+        !self.sig.is_empty() && self.authority.check(&self.signed_by).is_ok()
     }
 
     // Override the default implementation
@@ -1024,14 +1225,27 @@ fn trait_examples() {
         sig: "0x123abc".to_string(),
     };
 
+    // A 2-of-3 collection authority; two members signed this transfer
+    let members = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let authority = Multisig::new(2, &members).unwrap();
     let nft_tx = NFTTransfer {
         collection: "Solana Monkeys".to_string(),
         token_id: 42,
         new_owner: "Charlie".to_string(),
-        signed_by: "authority".to_string(),
+        authority: authority.clone(),
+        signed_by: vec![members[0], members[2]],
         sig: "valid_sig".to_string(),
     };
 
+    // With a single signature the check says exactly who is missing
+    if let Err(err) = authority.check(&[members[0]]) {
+        println!("One signature is not enough: {}", err);
+    }
+
     // Use trait methods
     process_transaction(&token_tx);
     process_transaction(&nft_tx);
@@ -1047,6 +1261,7 @@ fn process_transaction(tx: &impl Transaction) {
     println!("Processing transaction:");
     println!("  Signature: {}", tx.signature());
     println!("  Amount: {}", tx.amount());
+    println!("  Fee: {}", tx.fee());
     println!("  Valid: {}", tx.is_valid());
 }
 
@@ -1142,6 +1357,19 @@ impl Account for ProgramAccount {
 // 8. ARRAYS AND VECTORS
 // ========================================================================
 
+// The mixed-type vector example in arrays_and_vectors below
+#[derive(Debug)]
+enum Balance {
+    Sol(Lamports),
+    Spl(TokenAmount, String), // amount, token address
+}
+
+#[allow(
+    unused_variables,
+    clippy::len_zero,
+    clippy::useless_vec,
+    clippy::vec_init_then_push
+)]
 fn arrays_and_vectors() {
     // ---- ARRAYS ----
 
@@ -1259,25 +1487,37 @@ fn arrays_and_vectors() {
         instructions.push(format!("Instruction {}", i));
     }
 
-    // Vector with different types using enums
-    #[derive(Debug)]
-    enum TokenAmount {
-        Sol(f64),
-        Spl(u64, String), // amount, token address
-    }
-
+    // Vector with different types using an enum (Balance, above)
     let mut balances = Vec::new();
-    balances.push(TokenAmount::Sol(1.5));
-    balances.push(TokenAmount::Spl(1000, "Token1".to_string()));
+    balances.push(Balance::Sol(Lamports(1_500_000_000)));
+    balances.push(Balance::Spl(
+        TokenAmount::new(1000, 0),
+        "Token1".to_string(),
+    ));
 
     // Process different types in a vector
     for balance in &balances {
         match balance {
-            TokenAmount::Sol(amount) => println!("SOL balance: {}", amount),
-            TokenAmount::Spl(amount, token) => println!("SPL token {}: {}", token, amount),
+            Balance::Sol(amount) => println!("SOL balance: {}", amount),
+            Balance::Spl(amount, token) => println!("SPL token {}: {}", token, amount),
         }
     }
 
+    // Amounts support checked math and operators (panicking on overflow)
+    let total: Lamports = balances
+        .iter()
+        .filter_map(|balance| match balance {
+            Balance::Sol(amount) => Some(*amount),
+            Balance::Spl(..) => None,
+        })
+        .sum();
+    let fee = Lamports(5_000) * 2; // two signatures
+    println!("Total SOL: {}, after fees: {}", total, total - fee);
+    println!(
+        "Overflow check: {:?}",
+        Lamports(u64::MAX).checked_add(Lamports(1))
+    ); // None
+
     // Sorting a vector
     let mut values = vec![5, 2, 8, 1, 9];
     values.sort();
@@ -1296,6 +1536,13 @@ fn arrays_and_vectors() {
 // 9. ITERATORS
 // ========================================================================
 
+// One of the token accounts iterated over in iterator_examples below
+struct TokenAcct {
+    owner: String,
+    amount: u64,
+}
+
+#[allow(unused_variables, clippy::manual_repeat_n, clippy::useless_vec)]
 fn iterator_examples() {
     // Iterators provide a way to process sequences of elements
 
@@ -1340,6 +1587,8 @@ fn iterator_examples() {
     println!("All balances: {:?}", all_balances);
 
     // zip - combine two iterators
+    // (as written, the commented code below fails with E0277 because
+    // accounts.iter() yields &&str; see compile_fail/zip_wrong_item_type.rs)
     let accounts = vec!["Alice", "Bob", "Charlie", "Dave"];
     // let account_info: Vec<(&str, &i32)> = accounts
     //     .iter()
@@ -1384,12 +1633,7 @@ fn iterator_examples() {
 
     // ---- ITERATOR EXAMPLES IN SOLANA CONTEXT ----
 
-    // Simulating processing multiple token accounts
-    struct TokenAcct {
-        owner: String,
-        amount: u64,
-    }
-
+    // Simulating processing multiple token accounts (TokenAcct, above)
     let token_accounts = vec![
         TokenAcct {
             owner: "Alice".to_string(),
@@ -1441,6 +1685,7 @@ fn iterator_examples() {
 // ========================================================================
 
 // Custom error type
+#[allow(dead_code)]
 #[derive(Debug)]
 enum TokenError {
     InsufficientBalance,
@@ -1558,6 +1803,7 @@ fn transfer_tokens(from: &str, to: &str, amount: u64) -> TokenResult<String> {
 }
 
 // Function that propagates errors manually
+#[allow(clippy::question_mark)]
 fn process_transaction_with_result(from: &str, to: &str, amount: u64) -> TokenResult<String> {
     // Check if accounts exist
     let from_account = match find_account(from) {
@@ -1602,6 +1848,7 @@ fn process_transaction_with_question_mark(
 }
 
 // More complex error example
+#[allow(dead_code)]
 #[derive(Debug)]
 enum ComplexError {
     Token(TokenError),
@@ -1616,6 +1863,8 @@ impl From<TokenError> for ComplexError {
     }
 }
 
+// Bank::simulate (src/simulation.rs) shows what a real transaction would
+// do, instruction by instruction, before anything is committed
 fn run_complex_operation() -> Result<String, ComplexError> {
     // ? operator works with From implementations for error conversion
     let tx_id = transfer_tokens("alice", "bob", 100).map_err(ComplexError::from)?;
@@ -1632,19 +1881,23 @@ fn run_complex_operation() -> Result<String, ComplexError> {
 // Error Handling in Solana Programs
 // ----------------------------------------
 
+// The program error type of solana_error_handling_examples below
+#[allow(dead_code)]
+#[derive(Debug)]
+enum ProgramError {
+    InvalidInstruction,
+    NotRentExempt,
+    InsufficientFunds,
+    AlreadyInitialized,
+    // etc.
+}
+
 // In Solana, error handling is crucial for secure program execution
+#[allow(unused_variables)]
 fn solana_error_handling_examples() {
     println!("In Solana programs, error handling typically follows these patterns:");
 
-    // 1. Define program errors as enums
-    #[derive(Debug)]
-    enum ProgramError {
-        InvalidInstruction,
-        NotRentExempt,
-        InsufficientFunds,
-        AlreadyInitialized,
-        // etc.
-    }
+    // 1. Define program errors as enums (ProgramError, above)
 
     // 2. Functions return Result types
     type ProgramResult<T> = Result<T, ProgramError>;
@@ -1685,6 +1938,14 @@ fn solana_error_handling_examples() {
 
 use std::collections::HashMap;
 
+// A custom HashMap key, used in hashmap_examples below
+#[derive(Debug, Eq, PartialEq, Hash)]
+struct AccountKey {
+    pubkey: String,
+    is_signer: bool,
+}
+
+#[allow(clippy::useless_vec)]
 fn hashmap_examples() {
     // HashMap<K, V> provides key-value storage with O(1) average lookup
 
@@ -1809,13 +2070,7 @@ fn hashmap_examples() {
         println!("  {}: {}", token, count);
     }
 
-    // Example: HashMap with custom key and error handling
-    #[derive(Debug, Eq, PartialEq, Hash)]
-    struct AccountKey {
-        pubkey: String,
-        is_signer: bool,
-    }
-
+    // Example: HashMap with custom key (AccountKey, above) and error handling
     let mut account_data: HashMap<AccountKey, Vec<u8>> = HashMap::new();
 
     // Insert data
@@ -1837,6 +2092,92 @@ fn hashmap_examples() {
         Some(data) => println!("Found account data: {:?}", data),
         None => println!("Account not found"),
     }
+
+    // ---- PERSISTING ACCOUNT STATE ----
+
+    // A HashMap only lives as long as the process. The ledger's
+    // AccountStore (a HashMap<Pubkey, Account> underneath) can be written
+    // to a versioned, checksummed snapshot file and loaded back later.
+    let mut store = ledger::demo_store();
+    for (account, change) in &account_updates {
+        // Apply the simulated updates as lamport balances
+        let pubkey = pubkey::Pubkey::new_unique();
+        let lamports = change.unsigned_abs() * 1_000_000;
+        store.store(
+            pubkey,
+            ledger::Account::new(lamports, 0, &pubkey::SYSTEM_PROGRAM_ID),
+        );
+        println!("  {} -> {} lamports", account, lamports);
+    }
+
+    let path = std::env::temp_dir().join("simply_rust_hashmap_demo.snapshot");
+    match store.save_snapshot(&path) {
+        Ok(()) => match ledger::AccountStore::load_snapshot(&path) {
+            Ok(restored) => {
+                let total = match restored.total_lamports() {
+                    Some(total) => total.to_string(),
+                    None => "over u64::MAX lamports".to_string(),
+                };
+                println!(
+                    "Restored {} accounts holding {} (identical: {})",
+                    restored.len(),
+                    total,
+                    restored == store
+                )
+            }
+            Err(err) => println!("Restore failed: {}", err),
+        },
+        Err(err) => println!("Save failed: {}", err),
+    }
+
+    // Flipping a single byte is caught by the checksum
+    let mut corrupted = store.to_snapshot_bytes();
+    corrupted[20] ^= 0xff;
+    if let Err(err) = ledger::AccountStore::from_snapshot_bytes(&corrupted) {
+        println!("Corrupted snapshot: {}", err);
+    }
+
+    // ---- RECORDING EVERY TRANSACTION ----
+
+    // account_updates above only keeps the net change of one batch. The
+    // bank's TransactionHistory keeps every transaction, indexed by
+    // signature and by address (HashMap<Signature, usize> and
+    // HashMap<Pubkey, Vec<usize>>), so past activity can be queried.
+    use transaction::{DemoSigner, Signer};
+
+    let mut bank = bank::Bank::new();
+    let payer = DemoSigner::new("Account1");
+    let recipient = DemoSigner::new("Account2");
+    bank.airdrop(&payer.pubkey(), 1_000_000_000)
+        .expect("a new account holds far less than u64::MAX");
+    for lamports in [100_000_000, 25_000_000] {
+        bank.advance_slots(1);
+        let tx = transaction::Transaction::new_signed_with_payer(
+            &[system_program::transfer(
+                &payer.pubkey(),
+                &recipient.pubkey(),
+                lamports,
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            bank.last_blockhash(),
+        );
+        bank.execute_transaction(&tx);
+    }
+
+    let history = bank.history();
+    for info in history.signatures_for_address(&recipient.pubkey(), None, 10) {
+        println!(
+            "  slot {}: {:.12}...",
+            info.slot,
+            info.signature.to_string()
+        );
+    }
+    println!(
+        "Account2 held {:?} lamports at slot 1 and {:?} at slot 2",
+        history.balance_at_slot(&recipient.pubkey(), 1),
+        history.balance_at_slot(&recipient.pubkey(), 2)
+    );
 }
 
 // ========================================================================
@@ -1910,6 +2251,7 @@ macro_rules! instruction_data {
     }};
 }
 
+#[allow(unused_variables, clippy::useless_vec)]
 fn macro_examples() {
     // Using the debug_account macro
     debug_account!("8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa");
@@ -1974,6 +2316,16 @@ fn macro_examples() {
 // ========================================================================
 
 fn main() {
+    // Any arguments select one of the command line tools (see src/cli.rs)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(err) = cli::run(&args) {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!("\n==============================");
     println!("RUST FOR SOLANA BLOCKCHAIN DEVELOPMENT CHEAT SHEET");
     println!("==============================\n");
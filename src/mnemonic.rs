@@ -0,0 +1,558 @@
+// ========================================================================
+// SEED PHRASES (BIP39) AND DERIVATION PATHS (SLIP-0010)
+// ========================================================================
+//
+// Wallets back up their keys as 12 or 24 words rather than a keypair
+// file. BIP39 turns random bytes into words:
+//
+//     entropy    16 to 32 random bytes
+//     checksum   the first (entropy bits / 32) bits of SHA-256(entropy)
+//     words      entropy || checksum cut into 11-bit numbers, each an
+//                index into a fixed list of 2048 English words
+//
+// The words are not the key. They are stretched into a 64-byte seed:
+//
+//     seed = PBKDF2-HMAC-SHA512(password: the phrase,
+//                               salt:     "mnemonic" + passphrase,
+//                               2048 rounds)
+//
+// and one seed gives any number of keypairs through SLIP-0010. The master
+// secret and chain code are HMAC-SHA512("ed25519 seed", seed), split in
+// half, and every step of a path like m/44'/501'/0'/0' (purpose 44,
+// Solana's coin type 501, account 0, change 0) is one more HMAC keyed by
+// the chain code. Ed25519 only has hardened steps, written with a '.
+//
+// Phantom, Solflare and `solana-keygen recover 'prompt://?key=0/0'` all
+// use m/44'/501'/<account>'/0', so the same words give the same
+// addresses here.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::bank::{print_outcome, Bank};
+use crate::ed25519::SECRET_KEY_BYTES;
+use crate::hash::{hash, hmac_sha512v, HmacSha512};
+use crate::keypair::{os_random_bytes, Keypair};
+use crate::system_program;
+use crate::transaction::{Signer, Transaction};
+
+// The official list: sorted, and no two words share their first 4 letters
+const ENGLISH: &str = include_str!("../wordlists/english.txt");
+
+pub const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+pub const SEED_BYTES: usize = 64;
+pub const PBKDF2_ROUNDS: u32 = 2048;
+pub const SOLANA_COIN_TYPE: u32 = 501;
+
+const BITS_PER_WORD: usize = 11;
+const HARDENED: u32 = 1 << 31;
+
+fn wordlist() -> Vec<&'static str> {
+    ENGLISH.lines().collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MnemonicError {
+    // 12, 15, 18, 21 or 24 words
+    InvalidWordCount(usize),
+    // 16, 20, 24, 28 or 32 bytes
+    InvalidEntropyLength(usize),
+    UnknownWord(String),
+    // Every word is in the list, but the last one does not match the rest
+    InvalidChecksum,
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MnemonicError::InvalidWordCount(count) => {
+                write!(
+                    f,
+                    "a seed phrase has 12, 15, 18, 21 or 24 words, got {}",
+                    count
+                )
+            }
+            MnemonicError::InvalidEntropyLength(len) => {
+                write!(f, "entropy is 16, 20, 24, 28 or 32 bytes, got {}", len)
+            }
+            MnemonicError::UnknownWord(word) => {
+                write!(f, "`{}` is not in the BIP39 English word list", word)
+            }
+            MnemonicError::InvalidChecksum => write!(f, "the seed phrase checksum does not match"),
+        }
+    }
+}
+
+// ----------------------------------------
+// Mnemonics
+// ----------------------------------------
+
+// Debug shows only the word count, for the same reason Keypair's shows
+// only the public key. Display prints the phrase itself.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    words: Vec<&'static str>,
+}
+
+impl Mnemonic {
+    // A fresh phrase from the operating system's random number generator.
+    // Panics if there is none, like Keypair::new.
+    pub fn generate(word_count: usize) -> Result<Self, MnemonicError> {
+        if !WORD_COUNTS.contains(&word_count) {
+            return Err(MnemonicError::InvalidWordCount(word_count));
+        }
+        let random: [u8; 32] = os_random_bytes().expect("cannot read random bytes from the OS");
+        Mnemonic::from_entropy(&random[..word_count * 4 / 3])
+    }
+
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, MnemonicError> {
+        if !(16..=32).contains(&entropy.len()) || !entropy.len().is_multiple_of(4) {
+            return Err(MnemonicError::InvalidEntropyLength(entropy.len()));
+        }
+        let mut bits = entropy.to_vec();
+        bits.push(hash(entropy).to_bytes()[0]);
+
+        let list = wordlist();
+        let word_count = entropy.len() * 3 / 4;
+        let words = (0..word_count)
+            .map(|word| {
+                let index = (0..BITS_PER_WORD).fold(0, |index, bit| {
+                    let position = word * BITS_PER_WORD + bit;
+                    index << 1 | (bits[position / 8] >> (7 - position % 8) & 1) as usize
+                });
+                list[index]
+            })
+            .collect();
+        Ok(Mnemonic { words })
+    }
+
+    // Checks every word and the checksum. Words may be separated by any
+    // whitespace but must be lowercase, as wallets print them.
+    pub fn parse(phrase: &str) -> Result<Self, MnemonicError> {
+        let list = wordlist();
+        let words = phrase
+            .split_whitespace()
+            .map(|word| match list.binary_search(&word) {
+                Ok(index) => Ok(list[index]),
+                Err(_) => Err(MnemonicError::UnknownWord(word.to_string())),
+            })
+            .collect::<Result<Vec<&'static str>, MnemonicError>>()?;
+        if !WORD_COUNTS.contains(&words.len()) {
+            return Err(MnemonicError::InvalidWordCount(words.len()));
+        }
+
+        let mnemonic = Mnemonic { words };
+        if Mnemonic::from_entropy(&mnemonic.entropy())? != mnemonic {
+            return Err(MnemonicError::InvalidChecksum);
+        }
+        Ok(mnemonic)
+    }
+
+    // The random bytes the phrase encodes, without the checksum bits
+    pub fn entropy(&self) -> Vec<u8> {
+        let list = wordlist();
+        let mut bits = vec![0u8; (self.words.len() * BITS_PER_WORD).div_ceil(8)];
+        for (word_index, word) in self.words.iter().enumerate() {
+            let index = list.binary_search(word).expect("words come from the list");
+            for bit in 0..BITS_PER_WORD {
+                if index >> (BITS_PER_WORD - 1 - bit) & 1 == 1 {
+                    let position = word_index * BITS_PER_WORD + bit;
+                    bits[position / 8] |= 0x80 >> (position % 8);
+                }
+            }
+        }
+        bits.truncate(self.words.len() * 4 / 3);
+        bits
+    }
+
+    pub fn words(&self) -> &[&'static str] {
+        &self.words
+    }
+
+    pub fn phrase(&self) -> String {
+        self.words.join(" ")
+    }
+
+    // The optional passphrase (the "25th word") gives a completely
+    // different seed, and every passphrase is valid. BIP39 asks for it to
+    // be NFKD-normalized first; that is left to the caller, and changes
+    // nothing for ASCII.
+    pub fn to_seed(&self, passphrase: &str) -> [u8; SEED_BYTES] {
+        pbkdf2_hmac_sha512(
+            self.phrase().as_bytes(),
+            format!("mnemonic{}", passphrase).as_bytes(),
+            PBKDF2_ROUNDS,
+        )
+    }
+
+    // The keypair a wallet shows for this phrase at `path`
+    pub fn to_keypair(&self, passphrase: &str, path: &DerivationPath) -> Keypair {
+        derive_keypair(&self.to_seed(passphrase), path)
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = MnemonicError;
+
+    fn from_str(phrase: &str) -> Result<Self, Self::Err> {
+        Mnemonic::parse(phrase)
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.phrase())
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mnemonic({} words)", self.words.len())
+    }
+}
+
+// RFC 8018, for a single 64-byte block: U1 = HMAC(password, salt || 1),
+// each further U the HMAC of the one before, and the result all of them
+// XORed together. The keyed state is computed once and cloned per round.
+fn pbkdf2_hmac_sha512(password: &[u8], salt: &[u8], rounds: u32) -> [u8; 64] {
+    let keyed = HmacSha512::new(password);
+    let mut mac = keyed.clone();
+    mac.update(salt);
+    mac.update(&1u32.to_be_bytes());
+    let mut u = mac.finalize();
+    let mut out = u;
+    for _ in 1..rounds {
+        let mut mac = keyed.clone();
+        mac.update(&u);
+        u = mac.finalize();
+        for (byte, next) in out.iter_mut().zip(u) {
+            *byte ^= next;
+        }
+    }
+    out
+}
+
+// ----------------------------------------
+// Derivation Paths
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerivationPathError {
+    // Paths start at the master key, "m"
+    MissingRoot,
+    InvalidIndex(String),
+    // Ed25519 cannot derive child public keys, so every step is hardened
+    NotHardened(String),
+}
+
+impl fmt::Display for DerivationPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DerivationPathError::MissingRoot => {
+                write!(
+                    f,
+                    "a derivation path starts with `m`, as in m/44'/501'/0'/0'"
+                )
+            }
+            DerivationPathError::InvalidIndex(step) => {
+                write!(f, "`{}` is not an index below 2^31", step)
+            }
+            DerivationPathError::NotHardened(step) => {
+                write!(f, "ed25519 only supports hardened steps: `{}'`", step)
+            }
+        }
+    }
+}
+
+// The steps below the master key, all hardened. Stored without the
+// hardened bit, so m/44'/501' holds [44, 501].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    // Panics on an index of 2^31 or more, which cannot be hardened
+    pub fn new(indexes: &[u32]) -> Self {
+        assert!(
+            indexes.iter().all(|&index| index < HARDENED),
+            "derivation path indexes are below 2^31"
+        );
+        DerivationPath(indexes.to_vec())
+    }
+
+    // m/44'/501'/<account>'/<change>'
+    pub fn solana(account: u32, change: u32) -> Self {
+        DerivationPath::new(&[44, SOLANA_COIN_TYPE, account, change])
+    }
+
+    pub fn indexes(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+// m/44'/501'/0'/0', a wallet's first address
+impl Default for DerivationPath {
+    fn default() -> Self {
+        DerivationPath::solana(0, 0)
+    }
+}
+
+// "m/44'/501'/0'/0'", also accepting h or H in place of '
+impl FromStr for DerivationPath {
+    type Err = DerivationPathError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut steps = text.trim().split('/');
+        if steps.next() != Some("m") {
+            return Err(DerivationPathError::MissingRoot);
+        }
+        let indexes = steps
+            .map(|step| {
+                let index = step
+                    .strip_suffix(['\'', 'h', 'H'])
+                    .ok_or_else(|| DerivationPathError::NotHardened(step.to_string()))?;
+                index
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&index| index < HARDENED)
+                    .ok_or_else(|| DerivationPathError::InvalidIndex(step.to_string()))
+            })
+            .collect::<Result<Vec<u32>, DerivationPathError>>()?;
+        Ok(DerivationPath(indexes))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}'", index)?;
+        }
+        Ok(())
+    }
+}
+
+// The ed25519 secret at `path` below a BIP39 seed (or any seed of 16 to 64
+// bytes), by SLIP-0010
+pub fn derive_secret(seed: &[u8], path: &DerivationPath) -> [u8; SECRET_KEY_BYTES] {
+    let master = hmac_sha512v(b"ed25519 seed", &[seed]);
+    let (mut secret, mut chain_code) = split_key(&master);
+    for index in path.indexes() {
+        let child = hmac_sha512v(
+            &chain_code,
+            &[&[0], &secret, &(index | HARDENED).to_be_bytes()],
+        );
+        (secret, chain_code) = split_key(&child);
+    }
+    secret
+}
+
+pub fn derive_keypair(seed: &[u8], path: &DerivationPath) -> Keypair {
+    Keypair::from_seed(&derive_secret(seed, path))
+}
+
+// Secret on the left, chain code on the right
+fn split_key(bytes: &[u8; 64]) -> ([u8; 32], [u8; 32]) {
+    (
+        bytes[..32].try_into().unwrap(),
+        bytes[32..].try_into().unwrap(),
+    )
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo mnemonic`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    // The all-zero entropy, a phrase every BIP39 library tests against
+    let mnemonic = Mnemonic::from_entropy(&[0; 16]).map_err(|err| err.to_string())?;
+    println!("Seed phrase: {}", mnemonic);
+    let seed = mnemonic.to_seed("");
+    println!("Seed:        {}...", to_hex(&seed[..16]));
+    println!(
+        "With passphrase \"TREZOR\": {}...",
+        to_hex(&mnemonic.to_seed("TREZOR")[..16])
+    );
+
+    println!("\nAccounts (m/44'/501'/<n>'/0'):");
+    let accounts: Vec<Keypair> = (0..3)
+        .map(|account| derive_keypair(&seed, &DerivationPath::solana(account, 0)))
+        .collect();
+    for (account, keypair) in accounts.iter().enumerate() {
+        println!("  {}  {}", account, keypair.pubkey());
+    }
+
+    // Derived keypairs are ordinary signers
+    let mut bank = Bank::new();
+    bank.airdrop(&accounts[0].pubkey(), 1_000_000_000)
+        .map_err(|err| err.to_string())?;
+    let tx = Transaction::new_signed_with_payer(
+        &[system_program::transfer(
+            &accounts[0].pubkey(),
+            &accounts[1].pubkey(),
+            250_000_000,
+        )],
+        Some(&accounts[0].pubkey()),
+        &[&accounts[0]],
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&tx);
+    print_outcome("Account 0 pays account 1 0.25 SOL", &outcome.status, &[]);
+    for (account, keypair) in accounts.iter().enumerate().take(2) {
+        println!(
+            "  account {}: {} lamports",
+            account,
+            bank.get_balance(&keypair.pubkey())
+        );
+    }
+
+    // Typing the phrase back in recovers the same wallet
+    let recovered: Mnemonic = mnemonic
+        .phrase()
+        .parse()
+        .map_err(|err: MnemonicError| err.to_string())?;
+    let path: DerivationPath = "m/44'/501'/1'/0'"
+        .parse()
+        .map_err(|err: DerivationPathError| err.to_string())?;
+    println!(
+        "\nRecovered {} from the phrase: {}",
+        path,
+        recovered.to_keypair("", &path).pubkey() == accounts[1].pubkey()
+    );
+
+    println!("\nMistakes the checks catch:");
+    let mut swapped = mnemonic.words().to_vec();
+    swapped.swap(0, 11);
+    let attempts = [
+        swapped.join(" "),
+        mnemonic.phrase().replace("about", "abuot"),
+        mnemonic.words()[..11].join(" "),
+    ];
+    for phrase in &attempts {
+        if let Err(err) = Mnemonic::parse(phrase) {
+            println!("  {}", err);
+        }
+    }
+    if let Err(err) = "m/44'/501'/0/0".parse::<DerivationPath>() {
+        println!("  {}", err);
+    }
+
+    let fresh = Mnemonic::generate(24).map_err(|err| err.to_string())?;
+    println!("\nA fresh 24-word phrase:\n  {}", fresh);
+    println!(
+        "  first address: {}",
+        fresh.to_keypair("", &DerivationPath::default()).pubkey()
+    );
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // From the BIP39 reference vectors, all with the passphrase "TREZOR":
+    // entropy, phrase, seed
+    const BIP39_VECTORS: [(&str, &str, &str); 5] = [
+        (
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon about",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e5349553\
+             1f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner \
+             thank yellow",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6f\
+             a457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+        ),
+        (
+            "80808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter \
+             advice cage above",
+            "d71de856f81a8acc65e6fc851a38d4d7ec216fd0796d0a6827a3ad6ed5511a30\
+             fa280f12eb2e47ed2ac03b5c462a0358d18d69fe4f985ec81778c1b370b652a8",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+            "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13\
+             332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon art",
+            "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd30971\
+             70af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+        ),
+    ];
+
+    #[test]
+    fn bip39_vectors() {
+        for (entropy, phrase, seed) in BIP39_VECTORS {
+            let mnemonic = Mnemonic::from_entropy(&unhex(entropy)).unwrap();
+            assert_eq!(mnemonic.phrase(), phrase);
+            assert_eq!(Mnemonic::parse(phrase).unwrap().entropy(), unhex(entropy));
+            assert_eq!(to_hex(&mnemonic.to_seed("TREZOR")), seed);
+        }
+    }
+
+    // SLIP-0010 test vector 1 for ed25519: path, private key, public key
+    // (the spec prints public keys with a leading 00 byte, dropped here)
+    const SLIP10_VECTORS: [(&str, &str, &str); 6] = [
+        (
+            "m",
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+            "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+        ),
+        (
+            "m/0'",
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+            "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+        ),
+        (
+            "m/0'/1'",
+            "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+            "1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187",
+        ),
+        (
+            "m/0'/1'/2'",
+            "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9",
+            "ae98736566d30ed0e9d2f4486a64bc95740d89c7db33f52121f8ea8f76ff0fc1",
+        ),
+        (
+            "m/0'/1'/2'/2'",
+            "30d1dc7e5fc04c31219ab25a27ae00b50f6fd66622f6e9c913253d6511d1e662",
+            "8abae2d66361c879b900d204ad2cc4984fa2aa344dd7ddc46007329ac76c429c",
+        ),
+        (
+            "m/0'/1'/2'/2'/1000000000'",
+            "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+            "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+        ),
+    ];
+
+    #[test]
+    fn slip10_vectors() {
+        let seed = unhex("000102030405060708090a0b0c0d0e0f");
+        for (path, secret, public) in SLIP10_VECTORS {
+            let path: DerivationPath = path.parse().unwrap();
+            assert_eq!(to_hex(&derive_secret(&seed, &path)), secret);
+            let pubkey = derive_keypair(&seed, &path).pubkey();
+            assert_eq!(to_hex(pubkey.as_ref()), public);
+        }
+    }
+}
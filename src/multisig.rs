@@ -0,0 +1,340 @@
+// ========================================================================
+// MULTISIG AUTHORITIES
+// ========================================================================
+//
+// An M-of-N multisig is an account listing N member keys and a threshold
+// M. Wherever a program expects an authority (the mint authority of a
+// token, the owner of a token account, the update authority of an NFT)
+// the multisig's address can stand in for a single key: the instruction
+// then carries the members who signed as extra accounts, and at least M
+// of them must have signed.
+//
+// The account layout is the one SPL Token uses (355 bytes):
+//
+//     m               u8
+//     n               u8
+//     is_initialized  u8
+//     signers         [[u8; 32]; 11]   first n entries used
+
+use std::fmt;
+
+use crate::bank::{print_outcome, Bank};
+use crate::program::{InstructionError, InvokeContext};
+use crate::pubkey::{Pubkey, PUBKEY_BYTES};
+use crate::system_program;
+use crate::token_program::{self, TokenAccount, TokenProgram};
+use crate::transaction::{DemoSigner, Signer, Transaction};
+
+pub const MAX_SIGNERS: usize = 11;
+pub const MULTISIG_LEN: usize = 3 + MAX_SIGNERS * PUBKEY_BYTES;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multisig {
+    pub m: u8,
+    pub signers: Vec<Pubkey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultisigError {
+    InvalidThreshold {
+        m: u8,
+        n: usize,
+    },
+    TooManySigners(usize),
+    DuplicateSigner(Pubkey),
+    // Fewer than m members signed; `missing` lists the members who did not
+    MissingSigners {
+        required: u8,
+        signed: u8,
+        missing: Vec<Pubkey>,
+    },
+}
+
+impl fmt::Display for MultisigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MultisigError::InvalidThreshold { m, n } => {
+                write!(f, "threshold {} is not between 1 and {} signers", m, n)
+            }
+            MultisigError::TooManySigners(n) => {
+                write!(
+                    f,
+                    "{} signers given, a multisig holds at most {}",
+                    n, MAX_SIGNERS
+                )
+            }
+            MultisigError::DuplicateSigner(pubkey) => {
+                write!(f, "{} is listed more than once", pubkey)
+            }
+            MultisigError::MissingSigners {
+                required,
+                signed,
+                missing,
+            } => {
+                let names: Vec<String> = missing.iter().map(|key| key.to_string()).collect();
+                write!(
+                    f,
+                    "{} of {} required signatures present; {} more needed from: {}",
+                    signed,
+                    required,
+                    required - signed,
+                    names.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl Multisig {
+    pub fn new(m: u8, signers: &[Pubkey]) -> Result<Self, MultisigError> {
+        if signers.len() > MAX_SIGNERS {
+            return Err(MultisigError::TooManySigners(signers.len()));
+        }
+        if m == 0 || m as usize > signers.len() {
+            return Err(MultisigError::InvalidThreshold {
+                m,
+                n: signers.len(),
+            });
+        }
+        for (i, signer) in signers.iter().enumerate() {
+            if signers[..i].contains(signer) {
+                return Err(MultisigError::DuplicateSigner(*signer));
+            }
+        }
+        Ok(Multisig {
+            m,
+            signers: signers.to_vec(),
+        })
+    }
+
+    // Succeeds if at least m distinct members are among `signed`
+    pub fn check(&self, signed: &[Pubkey]) -> Result<(), MultisigError> {
+        let (present, missing): (Vec<Pubkey>, Vec<Pubkey>) = self
+            .signers
+            .iter()
+            .partition(|member| signed.contains(member));
+        if present.len() >= self.m as usize {
+            return Ok(());
+        }
+        Err(MultisigError::MissingSigners {
+            required: self.m,
+            signed: present.len() as u8,
+            missing,
+        })
+    }
+
+    pub fn pack(&self) -> [u8; MULTISIG_LEN] {
+        let mut data = [0u8; MULTISIG_LEN];
+        data[0] = self.m;
+        data[1] = self.signers.len() as u8;
+        data[2] = 1;
+        for (chunk, signer) in data[3..].chunks_exact_mut(PUBKEY_BYTES).zip(&self.signers) {
+            chunk.copy_from_slice(signer.as_ref());
+        }
+        data
+    }
+
+    // None unless `data` is an initialized multisig
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() != MULTISIG_LEN || data[2] != 1 {
+            return None;
+        }
+        let n = data[1] as usize;
+        if n > MAX_SIGNERS {
+            return None;
+        }
+        let signers = data[3..]
+            .chunks_exact(PUBKEY_BYTES)
+            .take(n)
+            .map(|chunk| Pubkey::new(chunk.try_into().unwrap()))
+            .collect();
+        Some(Multisig {
+            m: data[0],
+            signers,
+        })
+    }
+}
+
+// ----------------------------------------
+// Checking an Authority Inside a Program
+// ----------------------------------------
+
+// Check that the account at `authority` is `expected` and has approved
+// the instruction. If it is a multisig owned by `multisig_owner`, the
+// signing members must be passed as the accounts from `signers_start`
+// on; otherwise the authority itself must have signed.
+pub fn check_authority(
+    ctx: &InvokeContext,
+    expected: &Pubkey,
+    authority: usize,
+    signers_start: usize,
+    multisig_owner: &Pubkey,
+) -> Result<(), InstructionError> {
+    if ctx.key(authority)? != expected {
+        return Err(InstructionError::IncorrectAuthority);
+    }
+
+    let account = ctx.account(authority)?;
+    let multisig = if account.owner == *multisig_owner {
+        Multisig::unpack(&account.data)
+    } else {
+        None
+    };
+    let multisig = match multisig {
+        Some(multisig) => multisig,
+        None => {
+            ctx.signer(authority)?;
+            return Ok(());
+        }
+    };
+
+    let mut signed = Vec::new();
+    for i in signers_start..ctx.num_accounts() {
+        if ctx.is_signer(i)? {
+            signed.push(*ctx.key(i)?);
+        }
+    }
+    multisig.check(&signed)?;
+    Ok(())
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo multisig`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(TokenProgram));
+
+    let payer = DemoSigner::new("payer");
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let carol = DemoSigner::new("carol");
+    let dave = DemoSigner::new("dave");
+    let council = DemoSigner::new("council multisig");
+    let mint = DemoSigner::new("council mint");
+    let treasury = DemoSigner::new("council treasury");
+    let dave_tokens = DemoSigner::new("dave tokens");
+    bank.airdrop(&payer.pubkey(), 10_000_000_000)
+        .map_err(|err| err.to_string())?;
+
+    for (name, signer) in [("alice", &alice), ("bob", &bob), ("carol", &carol)] {
+        println!("{:<6} {}", name, signer.pubkey());
+    }
+
+    // A 2-of-3 council becomes the mint authority and owns the treasury
+    let create = |account: &DemoSigner, space: usize| {
+        system_program::create_account(
+            &payer.pubkey(),
+            &account.pubkey(),
+            1_000_000,
+            space as u64,
+            &token_program::ID,
+        )
+    };
+    let setup = Transaction::new_signed_with_payer(
+        &[
+            create(&council, MULTISIG_LEN),
+            token_program::initialize_multisig(
+                &council.pubkey(),
+                &[&alice.pubkey(), &bob.pubkey(), &carol.pubkey()],
+                2,
+            ),
+            create(&mint, token_program::MINT_LEN),
+            token_program::initialize_mint(&mint.pubkey(), &council.pubkey(), None, 0),
+            create(&treasury, token_program::ACCOUNT_LEN),
+            token_program::initialize_account(
+                &treasury.pubkey(),
+                &mint.pubkey(),
+                &council.pubkey(),
+            ),
+            create(&dave_tokens, token_program::ACCOUNT_LEN),
+            token_program::initialize_account(
+                &dave_tokens.pubkey(),
+                &mint.pubkey(),
+                &dave.pubkey(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &council, &mint, &treasury, &dave_tokens],
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&setup);
+    print_outcome(
+        "Create a 2-of-3 council, its mint and treasury",
+        &outcome.status,
+        &[],
+    );
+
+    // Alice alone cannot mint: the error names who still has to sign
+    let alone = Transaction::new_signed_with_payer(
+        &[token_program::mint_to(
+            &mint.pubkey(),
+            &treasury.pubkey(),
+            &council.pubkey(),
+            &[&alice.pubkey()],
+            1_000,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer, &alice],
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&alone);
+    print_outcome(
+        "Mint 1000 with alice's signature",
+        &outcome.status,
+        &outcome.logs,
+    );
+
+    let together = Transaction::new_signed_with_payer(
+        &[token_program::mint_to(
+            &mint.pubkey(),
+            &treasury.pubkey(),
+            &council.pubkey(),
+            &[&alice.pubkey(), &carol.pubkey()],
+            1_000,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer, &alice, &carol],
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&together);
+    print_outcome(
+        "Mint 1000 with alice and carol",
+        &outcome.status,
+        &outcome.logs,
+    );
+
+    // The treasury is owned by the council, so paying from it needs two
+    // members as well
+    let pay = Transaction::new_signed_with_payer(
+        &[token_program::transfer(
+            &treasury.pubkey(),
+            &dave_tokens.pubkey(),
+            &council.pubkey(),
+            &[&bob.pubkey(), &carol.pubkey()],
+            250,
+        )],
+        Some(&payer.pubkey()),
+        &[&payer, &bob, &carol],
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&pay);
+    print_outcome(
+        "Pay dave 250 from the treasury with bob and carol",
+        &outcome.status,
+        &outcome.logs,
+    );
+
+    let balance = |account: &DemoSigner| -> Result<u64, String> {
+        let data = &bank
+            .get_account(&account.pubkey())
+            .ok_or("token account missing")?
+            .data;
+        let state = TokenAccount::unpack(data).map_err(|err| err.to_string())?;
+        Ok(state.amount)
+    };
+    println!("\nTreasury holds {} tokens", balance(&treasury)?);
+    println!("Dave holds {} tokens", balance(&dave_tokens)?);
+    Ok(())
+}
@@ -0,0 +1,1277 @@
+// ========================================================================
+// THE NFT METADATA PROGRAM
+// ========================================================================
+//
+// An NFT is a token mint with a supply of exactly one, zero decimals and
+// no mint authority left. Everything that makes it more than a balance
+// lives in accounts owned by this program, modelled on Metaplex Token
+// Metadata:
+//
+//     Metadata        name, symbol, URI, creators and their shares, the
+//                     seller fee (royalty) in basis points, and the
+//                     collection the NFT claims to belong to
+//     MasterEdition   marks an NFT whose copies can be printed, and how
+//                     many prints are allowed
+//     Edition         one numbered print of a master edition
+//
+// Claiming a collection proves nothing on its own: the collection's update
+// authority has to verify the membership. A creator entry is only marked
+// verified if that creator signed.
+//
+// Royalties are computed from the metadata. The first (primary) sale pays
+// the whole price to the creators by share; later sales pay them
+// seller_fee_basis_points of the price and the rest to the seller. They
+// are advisory, as they are for ordinary Metaplex NFTs: `sell` builds the
+// payments an honest marketplace makes, but nothing on chain checks them,
+// and a plain token transfer moves the NFT without paying anyone.
+//
+// Each mint has at most one metadata and one edition account, at program
+// addresses derived from the mint
+//
+//     metadata   find_program_address(["metadata", program id, mint])
+//     edition    find_program_address(["metadata", program id, mint, "edition"])
+//
+// The program creates them itself, paid for by a payer account, and
+// refuses any other address. Update authorities may be token program
+// multisigs (see multisig.rs).
+
+use std::fmt;
+
+use crate::bank::{print_outcome, Bank};
+use crate::multisig;
+use crate::program::{InstructionError, InvokeContext, Program};
+use crate::pubkey::{Pubkey, PUBKEY_BYTES, SYSTEM_PROGRAM_ID};
+use crate::system_program;
+use crate::token_program::{self, AuthorityType, Mint, TokenAccount, TokenProgram};
+use crate::transaction::{AccountMeta, DemoSigner, Instruction, Signer, Transaction};
+
+pub const ID: Pubkey = Pubkey::from_str_const("NFTMetadata11111111111111111111111111111111");
+
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+pub const MAX_URI_LENGTH: usize = 200;
+pub const MAX_CREATOR_LIMIT: usize = 5;
+pub const MAX_BASIS_POINTS: u16 = 10_000;
+
+const METADATA_SEED: &[u8] = b"metadata";
+const EDITION_SEED: &[u8] = b"edition";
+
+const CREATOR_LEN: usize = PUBKEY_BYTES + 2;
+
+// Metadata is stored at its largest possible size so it can be updated in
+// place; the unused tail stays zero
+pub const METADATA_LEN: usize = 1
+    + PUBKEY_BYTES
+    + PUBKEY_BYTES
+    + (4 + MAX_NAME_LENGTH)
+    + (4 + MAX_SYMBOL_LENGTH)
+    + (4 + MAX_URI_LENGTH)
+    + 2
+    + (4 + MAX_CREATOR_LIMIT * CREATOR_LEN)
+    + (1 + PUBKEY_BYTES + 1)
+    + 1;
+pub const MASTER_EDITION_LEN: usize = 1 + PUBKEY_BYTES + 8 + 9;
+pub const EDITION_LEN: usize = 1 + PUBKEY_BYTES + PUBKEY_BYTES + 8;
+
+// The first byte of every account says what it holds; 0 is a new account
+const KEY_METADATA: u8 = 1;
+const KEY_MASTER_EDITION: u8 = 2;
+const KEY_EDITION: u8 = 3;
+
+fn metadata_seeds(mint: &Pubkey) -> [&[u8]; 3] {
+    [METADATA_SEED, ID.as_ref(), mint.as_ref()]
+}
+
+fn edition_seeds(mint: &Pubkey) -> [&[u8]; 4] {
+    [METADATA_SEED, ID.as_ref(), mint.as_ref(), EDITION_SEED]
+}
+
+pub fn get_metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&metadata_seeds(mint), &ID).0
+}
+
+// Master edition or print, whichever the mint is
+pub fn get_edition_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&edition_seeds(mint), &ID).0
+}
+
+// ----------------------------------------
+// Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NftError {
+    NameTooLong,
+    SymbolTooLong,
+    UriTooLong,
+    InvalidBasisPoints(u16),
+    TooManyCreators,
+    DuplicateCreator(Pubkey),
+    // Creator shares must add up to 100
+    InvalidShareTotal(u32),
+    // A creator was marked verified without signing
+    CreatorNotSigner(Pubkey),
+    MintMismatch,
+    // An NFT mint holds exactly one token with zero decimals
+    NotSingleToken,
+    // Editions need a mint nobody can mint more of
+    MintAuthorityNotRevoked,
+    CollectionMismatch,
+    MaxSupplyReached(u64),
+    NotTokenOwner,
+}
+
+impl fmt::Display for NftError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NftError::NameTooLong => {
+                write!(f, "name is longer than {} bytes", MAX_NAME_LENGTH)
+            }
+            NftError::SymbolTooLong => {
+                write!(f, "symbol is longer than {} bytes", MAX_SYMBOL_LENGTH)
+            }
+            NftError::UriTooLong => write!(f, "URI is longer than {} bytes", MAX_URI_LENGTH),
+            NftError::InvalidBasisPoints(bps) => {
+                write!(
+                    f,
+                    "seller fee of {} basis points is over {}",
+                    bps, MAX_BASIS_POINTS
+                )
+            }
+            NftError::TooManyCreators => {
+                write!(f, "more than {} creators", MAX_CREATOR_LIMIT)
+            }
+            NftError::DuplicateCreator(pubkey) => {
+                write!(f, "creator {} is listed more than once", pubkey)
+            }
+            NftError::InvalidShareTotal(total) => {
+                write!(f, "creator shares add up to {}, not 100", total)
+            }
+            NftError::CreatorNotSigner(pubkey) => {
+                write!(f, "creator {} is marked verified but did not sign", pubkey)
+            }
+            NftError::MintMismatch => write!(f, "account belongs to a different mint"),
+            NftError::NotSingleToken => {
+                write!(f, "mint must have a supply of 1 and 0 decimals")
+            }
+            NftError::MintAuthorityNotRevoked => {
+                write!(f, "mint authority must be revoked first")
+            }
+            NftError::CollectionMismatch => {
+                write!(f, "metadata does not claim this collection")
+            }
+            NftError::MaxSupplyReached(max_supply) => {
+                write!(f, "all {} prints have been made", max_supply)
+            }
+            NftError::NotTokenOwner => write!(f, "signer does not hold this NFT"),
+        }
+    }
+}
+
+// ----------------------------------------
+// Account State
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    // Percentage of the royalties, all shares add up to 100
+    pub share: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collection {
+    // Mint of the collection NFT
+    pub key: Pubkey,
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<Creator>,
+    pub collection: Option<Collection>,
+    pub primary_sale_happened: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasterEdition {
+    pub mint: Pubkey,
+    // Prints made so far
+    pub supply: u64,
+    // None for unlimited prints
+    pub max_supply: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edition {
+    pub mint: Pubkey,
+    // The master edition account this was printed from
+    pub parent: Pubkey,
+    pub edition: u64,
+}
+
+// Who gets paid what when an NFT sells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaleProceeds {
+    pub royalties: Vec<(Pubkey, u64)>,
+    pub seller: u64,
+}
+
+impl Metadata {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(METADATA_LEN);
+        data.push(KEY_METADATA);
+        data.extend_from_slice(self.update_authority.as_ref());
+        data.extend_from_slice(self.mint.as_ref());
+        push_string(&mut data, &self.name);
+        push_string(&mut data, &self.symbol);
+        push_string(&mut data, &self.uri);
+        data.extend_from_slice(&self.seller_fee_basis_points.to_le_bytes());
+        push_creators(&mut data, &self.creators);
+        match &self.collection {
+            Some(collection) => {
+                data.push(1);
+                data.extend_from_slice(collection.key.as_ref());
+                data.push(collection.verified as u8);
+            }
+            None => data.push(0),
+        }
+        data.push(self.primary_sale_happened as u8);
+        data.resize(METADATA_LEN, 0);
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let mut reader = Reader::new(data, InstructionError::InvalidAccountData);
+        if data.len() != METADATA_LEN || reader.u8()? != KEY_METADATA {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        Ok(Metadata {
+            update_authority: reader.pubkey()?,
+            mint: reader.pubkey()?,
+            name: reader.string()?,
+            symbol: reader.string()?,
+            uri: reader.string()?,
+            seller_fee_basis_points: reader.u16()?,
+            creators: reader.creators()?,
+            collection: match reader.bool()? {
+                true => Some(Collection {
+                    key: reader.pubkey()?,
+                    verified: reader.bool()?,
+                }),
+                false => None,
+            },
+            primary_sale_happened: reader.bool()?,
+        })
+    }
+
+    // Lengths, fee and creator shares within the limits
+    pub fn validate(&self) -> Result<(), NftError> {
+        if self.name.len() > MAX_NAME_LENGTH {
+            return Err(NftError::NameTooLong);
+        }
+        if self.symbol.len() > MAX_SYMBOL_LENGTH {
+            return Err(NftError::SymbolTooLong);
+        }
+        if self.uri.len() > MAX_URI_LENGTH {
+            return Err(NftError::UriTooLong);
+        }
+        if self.seller_fee_basis_points > MAX_BASIS_POINTS {
+            return Err(NftError::InvalidBasisPoints(self.seller_fee_basis_points));
+        }
+        if self.creators.len() > MAX_CREATOR_LIMIT {
+            return Err(NftError::TooManyCreators);
+        }
+        for (i, creator) in self.creators.iter().enumerate() {
+            if self.creators[..i]
+                .iter()
+                .any(|other| other.address == creator.address)
+            {
+                return Err(NftError::DuplicateCreator(creator.address));
+            }
+        }
+        let total: u32 = self.creators.iter().map(|c| c.share as u32).sum();
+        if !self.creators.is_empty() && total != 100 {
+            return Err(NftError::InvalidShareTotal(total));
+        }
+        Ok(())
+    }
+
+    // Split a sale price between the creators and the seller. Rounding
+    // leftovers go to the seller. Nothing makes a sale pay this; see the
+    // note on royalties at the top.
+    pub fn sale_proceeds(&self, price: u64) -> SaleProceeds {
+        let pool = if self.primary_sale_happened {
+            (price as u128 * self.seller_fee_basis_points as u128 / MAX_BASIS_POINTS as u128) as u64
+        } else {
+            price
+        };
+        let royalties: Vec<(Pubkey, u64)> = self
+            .creators
+            .iter()
+            .map(|creator| {
+                let amount = (pool as u128 * creator.share as u128 / 100) as u64;
+                (creator.address, amount)
+            })
+            .collect();
+        let paid: u64 = royalties.iter().map(|(_, amount)| amount).sum();
+        SaleProceeds {
+            royalties,
+            seller: price - paid,
+        }
+    }
+}
+
+impl MasterEdition {
+    pub fn pack(&self) -> [u8; MASTER_EDITION_LEN] {
+        let mut data = [0u8; MASTER_EDITION_LEN];
+        data[0] = KEY_MASTER_EDITION;
+        data[1..33].copy_from_slice(self.mint.as_ref());
+        data[33..41].copy_from_slice(&self.supply.to_le_bytes());
+        if let Some(max_supply) = self.max_supply {
+            data[41] = 1;
+            data[42..50].copy_from_slice(&max_supply.to_le_bytes());
+        }
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let mut reader = Reader::new(data, InstructionError::InvalidAccountData);
+        if data.len() != MASTER_EDITION_LEN || reader.u8()? != KEY_MASTER_EDITION {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        Ok(MasterEdition {
+            mint: reader.pubkey()?,
+            supply: reader.u64()?,
+            max_supply: match reader.bool()? {
+                true => Some(reader.u64()?),
+                false => None,
+            },
+        })
+    }
+}
+
+impl Edition {
+    pub fn pack(&self) -> [u8; EDITION_LEN] {
+        let mut data = [0u8; EDITION_LEN];
+        data[0] = KEY_EDITION;
+        data[1..33].copy_from_slice(self.mint.as_ref());
+        data[33..65].copy_from_slice(self.parent.as_ref());
+        data[65..73].copy_from_slice(&self.edition.to_le_bytes());
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let mut reader = Reader::new(data, InstructionError::InvalidAccountData);
+        if data.len() != EDITION_LEN || reader.u8()? != KEY_EDITION {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        Ok(Edition {
+            mint: reader.pubkey()?,
+            parent: reader.pubkey()?,
+            edition: reader.u64()?,
+        })
+    }
+}
+
+// ----------------------------------------
+// Encoding
+// ----------------------------------------
+
+// Strings and vectors carry a u32 length, options a one-byte tag (the
+// Borsh conventions Metaplex uses)
+fn push_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+fn push_creators(data: &mut Vec<u8>, creators: &[Creator]) {
+    data.extend_from_slice(&(creators.len() as u32).to_le_bytes());
+    for creator in creators {
+        data.extend_from_slice(creator.address.as_ref());
+        data.push(creator.verified as u8);
+        data.push(creator.share);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    // Returned for anything malformed
+    err: InstructionError,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], err: InstructionError) -> Self {
+        Reader { bytes, pos: 0, err }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], InstructionError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or_else(|| self.err.clone())?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, InstructionError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, InstructionError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(self.err.clone()),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16, InstructionError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, InstructionError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, InstructionError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, InstructionError> {
+        Ok(Pubkey::new(self.take(PUBKEY_BYTES)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, InstructionError> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.err.clone())
+    }
+
+    fn creators(&mut self) -> Result<Vec<Creator>, InstructionError> {
+        let count = self.u32()? as usize;
+        if count > MAX_CREATOR_LIMIT {
+            return Err(self.err.clone());
+        }
+        (0..count)
+            .map(|_| {
+                Ok(Creator {
+                    address: self.pubkey()?,
+                    verified: self.bool()?,
+                    share: self.u8()?,
+                })
+            })
+            .collect()
+    }
+
+    fn finish(&self) -> Result<(), InstructionError> {
+        match self.pos == self.bytes.len() {
+            true => Ok(()),
+            false => Err(self.err.clone()),
+        }
+    }
+}
+
+// ----------------------------------------
+// Instructions
+// ----------------------------------------
+
+// Everything in a new Metadata account except the keys, which come from
+// the instruction's accounts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<Creator>,
+    // Mint of the collection this NFT claims to belong to
+    pub collection: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NftInstruction {
+    // [writable] metadata, mint, mint authority, update authority,
+    // [signer, writable] payer, system program, signing creators...
+    CreateMetadata(MetadataArgs),
+    // [writable] metadata, collection update authority, collection metadata
+    VerifyCollection,
+    // [writable] master edition, mint, metadata, update authority,
+    // [signer, writable] payer, system program
+    CreateMasterEdition { max_supply: Option<u64> },
+    // [writable] edition, [writable] new metadata, new mint,
+    // [writable] master edition, master metadata, update authority,
+    // [signer, writable] payer, system program
+    PrintEdition,
+    // [writable] metadata, [signer] owner, owner's token account
+    UpdatePrimarySaleHappened,
+}
+
+impl NftInstruction {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            NftInstruction::CreateMetadata(args) => {
+                data.push(0);
+                push_string(&mut data, &args.name);
+                push_string(&mut data, &args.symbol);
+                push_string(&mut data, &args.uri);
+                data.extend_from_slice(&args.seller_fee_basis_points.to_le_bytes());
+                push_creators(&mut data, &args.creators);
+                match &args.collection {
+                    Some(key) => {
+                        data.push(1);
+                        data.extend_from_slice(key.as_ref());
+                    }
+                    None => data.push(0),
+                }
+            }
+            NftInstruction::VerifyCollection => data.push(1),
+            NftInstruction::CreateMasterEdition { max_supply } => {
+                data.push(2);
+                match max_supply {
+                    Some(max_supply) => {
+                        data.push(1);
+                        data.extend_from_slice(&max_supply.to_le_bytes());
+                    }
+                    None => data.push(0),
+                }
+            }
+            NftInstruction::PrintEdition => data.push(3),
+            NftInstruction::UpdatePrimarySaleHappened => data.push(4),
+        }
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let mut reader = Reader::new(data, InstructionError::InvalidInstructionData);
+        let instruction = match reader.u8()? {
+            0 => NftInstruction::CreateMetadata(MetadataArgs {
+                name: reader.string()?,
+                symbol: reader.string()?,
+                uri: reader.string()?,
+                seller_fee_basis_points: reader.u16()?,
+                creators: reader.creators()?,
+                collection: match reader.bool()? {
+                    true => Some(reader.pubkey()?),
+                    false => None,
+                },
+            }),
+            1 => NftInstruction::VerifyCollection,
+            2 => NftInstruction::CreateMasterEdition {
+                max_supply: match reader.bool()? {
+                    true => Some(reader.u64()?),
+                    false => None,
+                },
+            },
+            3 => NftInstruction::PrintEdition,
+            4 => NftInstruction::UpdatePrimarySaleHappened,
+            _ => return Err(InstructionError::InvalidInstructionData),
+        };
+        reader.finish()?;
+        Ok(instruction)
+    }
+}
+
+// ----------------------------------------
+// Instruction Constructors
+// ----------------------------------------
+
+// Same convention as the token program: `authority` signs itself unless
+// `signers` (members of a multisig authority) are given
+fn authority_metas(authority: &Pubkey, signers: &[&Pubkey]) -> Vec<AccountMeta> {
+    let mut metas = vec![AccountMeta::new_readonly(*authority, signers.is_empty())];
+    metas.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(**signer, true)),
+    );
+    metas
+}
+
+// Payer and system program, for the accounts the program creates
+fn payer_metas(payer: &Pubkey) -> [AccountMeta; 2] {
+    [
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ]
+}
+
+// Creators marked verified in `args` must sign this instruction too
+pub fn create_metadata(
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    update_authority: &Pubkey,
+    payer: &Pubkey,
+    args: MetadataArgs,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(get_metadata_address(mint), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*mint_authority, true),
+        AccountMeta::new_readonly(*update_authority, false),
+    ];
+    accounts.extend(payer_metas(payer));
+    accounts.extend(
+        args.creators
+            .iter()
+            .filter(|creator| creator.verified)
+            .map(|creator| AccountMeta::new_readonly(creator.address, true)),
+    );
+    Instruction::new(ID, &NftInstruction::CreateMetadata(args).pack(), accounts)
+}
+
+pub fn verify_collection(
+    mint: &Pubkey,
+    collection_authority: &Pubkey,
+    signers: &[&Pubkey],
+    collection_mint: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(get_metadata_address(mint), false)];
+    let mut authority = authority_metas(collection_authority, signers);
+    let members = authority.split_off(1);
+    accounts.extend(authority);
+    accounts.push(AccountMeta::new_readonly(
+        get_metadata_address(collection_mint),
+        false,
+    ));
+    accounts.extend(members);
+    Instruction::new(ID, &NftInstruction::VerifyCollection.pack(), accounts)
+}
+
+pub fn create_master_edition(
+    mint: &Pubkey,
+    update_authority: &Pubkey,
+    signers: &[&Pubkey],
+    payer: &Pubkey,
+    max_supply: Option<u64>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(get_edition_address(mint), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(get_metadata_address(mint), false),
+    ];
+    let mut authority = authority_metas(update_authority, signers);
+    let members = authority.split_off(1);
+    accounts.extend(authority);
+    accounts.extend(payer_metas(payer));
+    accounts.extend(members);
+    Instruction::new(
+        ID,
+        &NftInstruction::CreateMasterEdition { max_supply }.pack(),
+        accounts,
+    )
+}
+
+// A new print of `master_mint`'s master edition under `new_mint`
+pub fn print_edition(
+    new_mint: &Pubkey,
+    master_mint: &Pubkey,
+    update_authority: &Pubkey,
+    signers: &[&Pubkey],
+    payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(get_edition_address(new_mint), false),
+        AccountMeta::new(get_metadata_address(new_mint), false),
+        AccountMeta::new_readonly(*new_mint, false),
+        AccountMeta::new(get_edition_address(master_mint), false),
+        AccountMeta::new_readonly(get_metadata_address(master_mint), false),
+    ];
+    let mut authority = authority_metas(update_authority, signers);
+    let members = authority.split_off(1);
+    accounts.extend(authority);
+    accounts.extend(payer_metas(payer));
+    accounts.extend(members);
+    Instruction::new(ID, &NftInstruction::PrintEdition.pack(), accounts)
+}
+
+pub fn update_primary_sale_happened(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    token_account: &Pubkey,
+) -> Instruction {
+    Instruction::new(
+        ID,
+        &NftInstruction::UpdatePrimarySaleHappened.pack(),
+        vec![
+            AccountMeta::new(get_metadata_address(mint), false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*token_account, false),
+        ],
+    )
+}
+
+// Everything a sale of `metadata`'s NFT at `price` lamports needs: the
+// token moves to the buyer, the buyer pays creators and seller, and the
+// first sale is recorded. Seller and buyer both sign. The royalty
+// transfers are only as binding as the client that sends them.
+pub fn sell(
+    metadata: &Metadata,
+    seller: &Pubkey,
+    seller_token: &Pubkey,
+    buyer: &Pubkey,
+    buyer_token: &Pubkey,
+    price: u64,
+) -> Vec<Instruction> {
+    let proceeds = metadata.sale_proceeds(price);
+    let mut instructions = vec![token_program::transfer(
+        seller_token,
+        buyer_token,
+        seller,
+        &[],
+        1,
+    )];
+    for (creator, amount) in &proceeds.royalties {
+        if *amount > 0 {
+            instructions.push(system_program::transfer(buyer, creator, *amount));
+        }
+    }
+    if proceeds.seller > 0 {
+        instructions.push(system_program::transfer(buyer, seller, proceeds.seller));
+    }
+    if !metadata.primary_sale_happened {
+        instructions.push(update_primary_sale_happened(
+            &metadata.mint,
+            buyer,
+            buyer_token,
+        ));
+    }
+    instructions
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct NftProgram;
+
+impl Program for NftProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "nft_metadata"
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        match NftInstruction::unpack(data)? {
+            NftInstruction::CreateMetadata(args) => {
+                ctx.log("Instruction: CreateMetadata");
+                process_create_metadata(ctx, args)
+            }
+            NftInstruction::VerifyCollection => {
+                ctx.log("Instruction: VerifyCollection");
+                let mut metadata = Metadata::unpack(owned_data(ctx, 0)?)?;
+                let collection_metadata = Metadata::unpack(owned_data(ctx, 2)?)?;
+                let collection = metadata
+                    .collection
+                    .as_mut()
+                    .filter(|collection| collection.key == collection_metadata.mint)
+                    .ok_or(NftError::CollectionMismatch)?;
+                multisig::check_authority(
+                    ctx,
+                    &collection_metadata.update_authority,
+                    1,
+                    3,
+                    &token_program::ID,
+                )?;
+                collection.verified = true;
+                ctx.account_mut(0)?.data = metadata.pack();
+                Ok(())
+            }
+            NftInstruction::CreateMasterEdition { max_supply } => {
+                ctx.log("Instruction: CreateMasterEdition");
+                let metadata = Metadata::unpack(owned_data(ctx, 2)?)?;
+                if metadata.mint != *ctx.key(1)? {
+                    return Err(NftError::MintMismatch.into());
+                }
+                check_single_token(ctx, 1)?;
+                multisig::check_authority(
+                    ctx,
+                    &metadata.update_authority,
+                    3,
+                    6,
+                    &token_program::ID,
+                )?;
+                create_account(
+                    ctx,
+                    0,
+                    4,
+                    &edition_seeds(&metadata.mint),
+                    MASTER_EDITION_LEN,
+                )?;
+                let edition = MasterEdition {
+                    mint: metadata.mint,
+                    supply: 0,
+                    max_supply,
+                };
+                ctx.account_mut(0)?.data = edition.pack().to_vec();
+                Ok(())
+            }
+            NftInstruction::PrintEdition => {
+                ctx.log("Instruction: PrintEdition");
+                process_print_edition(ctx)
+            }
+            NftInstruction::UpdatePrimarySaleHappened => {
+                ctx.log("Instruction: UpdatePrimarySaleHappened");
+                let mut metadata = Metadata::unpack(owned_data(ctx, 0)?)?;
+                let owner = *ctx.signer(1)?;
+                let token = ctx.account(2)?;
+                if token.owner != token_program::ID {
+                    return Err(InstructionError::IncorrectProgramId);
+                }
+                let token = TokenAccount::unpack(&token.data)?;
+                if token.mint != metadata.mint {
+                    return Err(NftError::MintMismatch.into());
+                }
+                if token.owner != owner || token.amount != 1 {
+                    return Err(NftError::NotTokenOwner.into());
+                }
+                metadata.primary_sale_happened = true;
+                ctx.account_mut(0)?.data = metadata.pack();
+                Ok(())
+            }
+        }
+    }
+}
+
+fn owned_data<'c>(ctx: &'c InvokeContext, i: usize) -> Result<&'c [u8], InstructionError> {
+    let account = ctx.account(i)?;
+    if account.owner != ID {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+    Ok(&account.data)
+}
+
+// Create account `i` at the address `seeds` derive, with `space` zeroed
+// bytes, paid for by the signer at `payer`
+fn create_account(
+    ctx: &mut InvokeContext,
+    i: usize,
+    payer: usize,
+    seeds: &[&[u8]],
+    space: usize,
+) -> Result<(), InstructionError> {
+    let address = *ctx.key(i)?;
+    let (expected, bump) = Pubkey::find_program_address(seeds, &ID);
+    if address != expected {
+        ctx.log("Error: Account address does not match seed derivation");
+        return Err(InstructionError::InvalidSeeds);
+    }
+    if ctx.account(i)?.owner == ID {
+        return Err(InstructionError::AccountAlreadyInitialized);
+    }
+    let payer = *ctx.signer(payer)?;
+    let bump_seed = [bump];
+    let mut signer_seeds = seeds.to_vec();
+    signer_seeds.push(&bump_seed);
+    system_program::create_pda_account(ctx, i, &payer, space, &ID, &signer_seeds)
+}
+
+// An edition's mint must hold one token forever
+fn check_single_token(ctx: &InvokeContext, i: usize) -> Result<(), InstructionError> {
+    let account = ctx.account(i)?;
+    if account.owner != token_program::ID {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+    let mint = Mint::unpack(&account.data)?;
+    if mint.supply != 1 || mint.decimals != 0 {
+        return Err(NftError::NotSingleToken.into());
+    }
+    if mint.mint_authority.is_some() {
+        return Err(NftError::MintAuthorityNotRevoked.into());
+    }
+    Ok(())
+}
+
+fn process_create_metadata(
+    ctx: &mut InvokeContext,
+    args: MetadataArgs,
+) -> Result<(), InstructionError> {
+    let mint_account = ctx.account(1)?;
+    if mint_account.owner != token_program::ID {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+    let mint = Mint::unpack(&mint_account.data)?;
+    let mint_authority = mint
+        .mint_authority
+        .ok_or(token_program::TokenError::FixedSupply)?;
+    multisig::check_authority(ctx, &mint_authority, 2, 6, &token_program::ID)?;
+
+    for creator in args.creators.iter().filter(|creator| creator.verified) {
+        if !signed_by(ctx, &creator.address)? {
+            return Err(NftError::CreatorNotSigner(creator.address).into());
+        }
+    }
+
+    let metadata = Metadata {
+        update_authority: *ctx.key(3)?,
+        mint: *ctx.key(1)?,
+        name: args.name,
+        symbol: args.symbol,
+        uri: args.uri,
+        seller_fee_basis_points: args.seller_fee_basis_points,
+        creators: args.creators,
+        collection: args.collection.map(|key| Collection {
+            key,
+            verified: false,
+        }),
+        primary_sale_happened: false,
+    };
+    metadata.validate()?;
+    create_account(ctx, 0, 4, &metadata_seeds(&metadata.mint), METADATA_LEN)?;
+    ctx.account_mut(0)?.data = metadata.pack();
+    Ok(())
+}
+
+fn process_print_edition(ctx: &mut InvokeContext) -> Result<(), InstructionError> {
+    check_single_token(ctx, 2)?;
+    let mut master = MasterEdition::unpack(owned_data(ctx, 3)?)?;
+    let master_metadata = Metadata::unpack(owned_data(ctx, 4)?)?;
+    if master.mint != master_metadata.mint {
+        return Err(NftError::MintMismatch.into());
+    }
+    multisig::check_authority(
+        ctx,
+        &master_metadata.update_authority,
+        5,
+        8,
+        &token_program::ID,
+    )?;
+
+    // A mint already printed, or already a master, has its edition
+    // account taken
+    let new_mint = *ctx.key(2)?;
+    create_account(ctx, 0, 6, &edition_seeds(&new_mint), EDITION_LEN)?;
+    create_account(ctx, 1, 6, &metadata_seeds(&new_mint), METADATA_LEN)?;
+
+    if let Some(max_supply) = master.max_supply {
+        if master.supply >= max_supply {
+            return Err(NftError::MaxSupplyReached(max_supply).into());
+        }
+    }
+    master.supply += 1;
+
+    let edition = Edition {
+        mint: new_mint,
+        parent: *ctx.key(3)?,
+        edition: master.supply,
+    };
+    // A print carries the master's metadata under its own mint
+    let metadata = Metadata {
+        mint: new_mint,
+        ..master_metadata
+    };
+    ctx.account_mut(0)?.data = edition.pack().to_vec();
+    ctx.account_mut(1)?.data = metadata.pack();
+    ctx.account_mut(3)?.data = master.pack().to_vec();
+    ctx.log(&format!("Printed edition {}", edition.edition));
+    Ok(())
+}
+
+fn signed_by(ctx: &InvokeContext, key: &Pubkey) -> Result<bool, InstructionError> {
+    for i in 0..ctx.num_accounts() {
+        if ctx.key(i)? == key && ctx.is_signer(i)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo nft`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(TokenProgram));
+    bank.add_program(Box::new(NftProgram));
+
+    let payer = DemoSigner::new("payer");
+    let artist = DemoSigner::new("artist");
+    let writer = DemoSigner::new("writer");
+    let buyer = DemoSigner::new("buyer");
+    let collector = DemoSigner::new("collector");
+    bank.airdrop(&payer.pubkey(), 10_000_000_000)
+        .map_err(|err| err.to_string())?;
+    bank.airdrop(&artist.pubkey(), 1_000_000)
+        .map_err(|err| err.to_string())?;
+    bank.airdrop(&buyer.pubkey(), 5_000_000_000)
+        .map_err(|err| err.to_string())?;
+    bank.airdrop(&collector.pubkey(), 5_000_000_000)
+        .map_err(|err| err.to_string())?;
+
+    // Create `account` owned by `owner` with room for `space` bytes
+    let create = |account: &DemoSigner, space: usize, owner: &Pubkey| {
+        system_program::create_account(
+            &payer.pubkey(),
+            &account.pubkey(),
+            1_000_000,
+            space as u64,
+            owner,
+        )
+    };
+    // An NFT mint: one token in `holder`'s token account, then no more
+    let mint_nft = |mint: &DemoSigner, token: &DemoSigner, holder: &DemoSigner| {
+        vec![
+            create(mint, token_program::MINT_LEN, &token_program::ID),
+            token_program::initialize_mint(&mint.pubkey(), &artist.pubkey(), None, 0),
+            create(token, token_program::ACCOUNT_LEN, &token_program::ID),
+            token_program::initialize_account(&token.pubkey(), &mint.pubkey(), &holder.pubkey()),
+            token_program::mint_to(&mint.pubkey(), &token.pubkey(), &artist.pubkey(), &[], 1),
+        ]
+    };
+    let revoke = |mint: &DemoSigner| {
+        token_program::set_authority(
+            &mint.pubkey(),
+            None,
+            AuthorityType::MintTokens,
+            &artist.pubkey(),
+            &[],
+        )
+    };
+    let metadata_args = |name: &str, collection: Option<Pubkey>| MetadataArgs {
+        name: name.to_string(),
+        symbol: "MONK".to_string(),
+        uri: format!("https://example.com/{}.json", name.replace(' ', "-")),
+        seller_fee_basis_points: 500,
+        creators: vec![
+            Creator {
+                address: artist.pubkey(),
+                verified: true,
+                share: 70,
+            },
+            Creator {
+                address: writer.pubkey(),
+                verified: false,
+                share: 30,
+            },
+        ],
+        collection,
+    };
+    // The collection is an NFT of its own
+    let collection_mint = DemoSigner::new("collection mint");
+    let collection_token = DemoSigner::new("collection token");
+    let mut instructions = mint_nft(&collection_mint, &collection_token, &artist);
+    instructions.push(create_metadata(
+        &collection_mint.pubkey(),
+        &artist.pubkey(),
+        &artist.pubkey(),
+        &payer.pubkey(),
+        metadata_args("Solana Monkeys", None),
+    ));
+    send(
+        &mut bank,
+        &payer,
+        "Create the Solana Monkeys collection",
+        &instructions,
+        &[&payer, &artist, &collection_mint, &collection_token],
+    );
+
+    // Monkey #1 claims the collection; the collection authority verifies it
+    let mint = DemoSigner::new("monkey mint");
+    let artist_token = DemoSigner::new("artist monkey token");
+    // Metadata needs the mint authority's signature, editions need it gone
+    let mut instructions = mint_nft(&mint, &artist_token, &artist);
+    instructions.push(create_metadata(
+        &mint.pubkey(),
+        &artist.pubkey(),
+        &artist.pubkey(),
+        &payer.pubkey(),
+        metadata_args("Monkey 1", Some(collection_mint.pubkey())),
+    ));
+    instructions.push(verify_collection(
+        &mint.pubkey(),
+        &artist.pubkey(),
+        &[],
+        &collection_mint.pubkey(),
+    ));
+    instructions.push(revoke(&mint));
+    instructions.push(create_master_edition(
+        &mint.pubkey(),
+        &artist.pubkey(),
+        &[],
+        &payer.pubkey(),
+        Some(2),
+    ));
+    send(
+        &mut bank,
+        &payer,
+        "Mint Monkey 1 as a master edition with 2 prints",
+        &instructions,
+        &[&payer, &artist, &mint, &artist_token],
+    );
+
+    // Two prints are allowed, the third is refused
+    let mut print_mints = Vec::new();
+    for n in 1..=3 {
+        let print_mint = DemoSigner::new(&format!("print mint {}", n));
+        let print_token = DemoSigner::new(&format!("print token {}", n));
+        let mut instructions = mint_nft(&print_mint, &print_token, &artist);
+        instructions.push(revoke(&print_mint));
+        instructions.push(print_edition(
+            &print_mint.pubkey(),
+            &mint.pubkey(),
+            &artist.pubkey(),
+            &[],
+            &payer.pubkey(),
+        ));
+        send(
+            &mut bank,
+            &payer,
+            &format!("Print edition {}", n),
+            &instructions,
+            &[&payer, &artist, &print_mint, &print_token],
+        );
+        print_mints.push(print_mint);
+    }
+
+    // Each mint has one edition account, so a mint prints only once
+    send(
+        &mut bank,
+        &payer,
+        "Print edition 1 again",
+        &[print_edition(
+            &print_mints[0].pubkey(),
+            &mint.pubkey(),
+            &artist.pubkey(),
+            &[],
+            &payer.pubkey(),
+        )],
+        &[&payer, &artist],
+    );
+    println!();
+    for print_mint in &print_mints {
+        let Some(account) = bank.get_account(&get_edition_address(&print_mint.pubkey())) else {
+            continue;
+        };
+        let edition = Edition::unpack(&account.data).map_err(|err| err.to_string())?;
+        println!(
+            "{:.8}... is edition {} of {:.8}...",
+            edition.mint.to_string(),
+            edition.edition,
+            edition.parent.to_string()
+        );
+    }
+
+    let read_metadata = |bank: &Bank| -> Result<Metadata, String> {
+        let account = bank
+            .get_account(&get_metadata_address(&mint.pubkey()))
+            .ok_or("metadata missing")?;
+        Metadata::unpack(&account.data).map_err(|err| err.to_string())
+    };
+    let info = read_metadata(&bank)?;
+    println!(
+        "\n{} ({}) {}\n  royalty {} bps, collection verified: {}",
+        info.name,
+        info.symbol,
+        info.uri,
+        info.seller_fee_basis_points,
+        info.collection.as_ref().is_some_and(|c| c.verified)
+    );
+
+    // Primary sale: the whole price goes to the creators. Secondary sale:
+    // 5% to the creators, the rest to the seller.
+    let buyer_token = DemoSigner::new("buyer monkey token");
+    let collector_token = DemoSigner::new("collector monkey token");
+    let sales = [
+        (&artist, &artist_token, &buyer, &buyer_token, 2_000_000_000),
+        (
+            &buyer,
+            &buyer_token,
+            &collector,
+            &collector_token,
+            3_000_000_000,
+        ),
+    ];
+    for (seller, seller_token, purchaser, purchaser_token, price) in sales {
+        let info = read_metadata(&bank)?;
+        let proceeds = info.sale_proceeds(price);
+        println!("\nSale for {} lamports:", price);
+        for (creator, amount) in &proceeds.royalties {
+            println!("  creator {:.12}...  {:>13}", creator.to_string(), amount);
+        }
+        println!("  seller              {:>13}", proceeds.seller);
+
+        let before: Vec<u64> = [&artist, &writer, &buyer]
+            .iter()
+            .map(|signer| bank.get_balance(&signer.pubkey()))
+            .collect();
+        let mut instructions = vec![
+            create(
+                purchaser_token,
+                token_program::ACCOUNT_LEN,
+                &token_program::ID,
+            ),
+            token_program::initialize_account(
+                &purchaser_token.pubkey(),
+                &mint.pubkey(),
+                &purchaser.pubkey(),
+            ),
+        ];
+        instructions.extend(sell(
+            &info,
+            &seller.pubkey(),
+            &seller_token.pubkey(),
+            &purchaser.pubkey(),
+            &purchaser_token.pubkey(),
+            price,
+        ));
+        send(
+            &mut bank,
+            &payer,
+            &format!("Sell Monkey 1 to {:.8}...", purchaser.pubkey().to_string()),
+            &instructions,
+            &[&payer, seller, purchaser, purchaser_token],
+        );
+        for (name, (signer, before)) in ["artist", "writer", "buyer"]
+            .iter()
+            .zip([&artist, &writer, &buyer].iter().zip(before))
+        {
+            let after = bank.get_balance(&signer.pubkey());
+            println!("  {:<7} {:+}", name, after as i128 - before as i128);
+        }
+    }
+    Ok(())
+}
+
+// Run one demo step and show only the program's own log lines
+fn send(
+    bank: &mut Bank,
+    payer: &DemoSigner,
+    title: &str,
+    instructions: &[Instruction],
+    signers: &[&dyn Signer],
+) {
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        signers,
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&tx);
+    let logs: Vec<String> = outcome
+        .logs
+        .iter()
+        .filter(|line| line.starts_with("Program log:"))
+        .cloned()
+        .collect();
+    print_outcome(title, &outcome.status, &logs);
+    bank.advance_slots(1);
+}
@@ -0,0 +1,672 @@
+// ========================================================================
+// PROGRAMS AND THE INVOKE CONTEXT
+// ========================================================================
+//
+// On-chain programs are stateless: everything they read or change arrives
+// as accounts in the instruction. Here a program is any type implementing
+// `Program`. The bank looks it up by program id and hands it an
+// `InvokeContext` holding the instruction's accounts, the Clock sysvar and
+// the transaction's log.
+//
+// After every instruction the runtime enforces the same account rules as
+// Solana:
+//   - only the owning program may change an account's data or debit its
+//     lamports, but any program may credit a writable account
+//   - read-only accounts may not change at all
+//   - only the owner may reassign an account, and only with zeroed data
+//   - the instruction's accounts hold the same total lamports afterwards
+//
+// A program can call another with `ctx.invoke` / `ctx.invoke_signed`,
+// passing on accounts it was given. It can sign for program derived
+// addresses of its own (see Pubkey::create_program_address), which is how
+// a program controls accounts nobody holds a private key for. Calls nest
+// at most MAX_INVOKE_DEPTH deep, counting the transaction's instruction,
+// and a program may call itself but may not be re-entered through
+// another program (A -> B -> A), so it never runs on top of its own
+// half-finished state.
+//
+// Every invocation, nested ones included, costs the program's
+// `compute_units` from the transaction's compute budget (see
+// compute_budget.rs). Real programs are metered per BPF instruction; a
+// flat cost per call is close enough to show where a budget goes.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::amm_program::AmmError;
+use crate::clock::Clock;
+use crate::escrow_program::EscrowError;
+use crate::ledger::Account;
+use crate::multisig::MultisigError;
+use crate::nft_program::NftError;
+use crate::pubkey::Pubkey;
+use crate::system_program::SystemError;
+use crate::timelock::TimelockError;
+use crate::token_program::TokenError;
+use crate::transaction::Instruction;
+
+// The most programs running at once: the one the transaction called and
+// up to four nested calls below it
+pub const MAX_INVOKE_DEPTH: usize = 5;
+
+// What a call costs for programs that do not say otherwise
+pub const DEFAULT_PROGRAM_COMPUTE_UNITS: u64 = 1_000;
+
+pub trait Program: Send + Sync {
+    fn id(&self) -> Pubkey;
+
+    fn name(&self) -> &'static str;
+
+    // Compute units charged for every call
+    fn compute_units(&self) -> u64 {
+        DEFAULT_PROGRAM_COMPUTE_UNITS
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError>;
+}
+
+// ----------------------------------------
+// Instruction Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstructionError {
+    // Reported by programs
+    InvalidArgument,
+    InvalidInstructionData,
+    InvalidAccountData,
+    AccountDataTooSmall,
+    InsufficientFunds,
+    IncorrectProgramId,
+    MissingRequiredSignature,
+    // The authority account is not the one the state names
+    IncorrectAuthority,
+    AccountAlreadyInitialized,
+    UninitializedAccount,
+    NotEnoughAccountKeys,
+    AccountNotWritable,
+    ArithmeticOverflow,
+    // The account can no longer be changed, such as a frozen lookup table
+    Immutable,
+    InvalidAccountOwner,
+
+    // Account rules enforced by the runtime
+    UnsupportedProgramId,
+    ModifiedProgramId,
+    ExternalAccountLamportSpend,
+    ExternalAccountDataModified,
+    ReadonlyLamportChange,
+    ReadonlyDataModified,
+    ExecutableModified,
+    UnbalancedInstruction,
+
+    // Cross-program invocation
+    InvalidSeeds,
+    MissingAccount,
+    PrivilegeEscalation,
+    CallDepth,
+    ReentrancyNotAllowed,
+    // The transaction used up its compute budget
+    ComputationalBudgetExceeded,
+
+    // Program specific errors
+    System(SystemError),
+    Timelock(TimelockError),
+    Multisig(MultisigError),
+    Token(TokenError),
+    Nft(NftError),
+    Amm(AmmError),
+    Escrow(EscrowError),
+}
+
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InstructionError::InvalidArgument => write!(f, "invalid program argument"),
+            InstructionError::InvalidInstructionData => write!(f, "invalid instruction data"),
+            InstructionError::InvalidAccountData => {
+                write!(f, "invalid account data for instruction")
+            }
+            InstructionError::AccountDataTooSmall => {
+                write!(f, "account data too small for instruction")
+            }
+            InstructionError::InsufficientFunds => {
+                write!(f, "insufficient funds for instruction")
+            }
+            InstructionError::IncorrectProgramId => {
+                write!(f, "incorrect program id for instruction")
+            }
+            InstructionError::MissingRequiredSignature => {
+                write!(f, "missing required signature for instruction")
+            }
+            InstructionError::IncorrectAuthority => {
+                write!(f, "incorrect authority provided")
+            }
+            InstructionError::AccountAlreadyInitialized => {
+                write!(f, "instruction requires an uninitialized account")
+            }
+            InstructionError::UninitializedAccount => {
+                write!(f, "instruction requires an initialized account")
+            }
+            InstructionError::NotEnoughAccountKeys => {
+                write!(f, "insufficient account keys for instruction")
+            }
+            InstructionError::AccountNotWritable => {
+                write!(f, "instruction needs a writable account that is read-only")
+            }
+            InstructionError::ArithmeticOverflow => write!(f, "arithmetic overflowed"),
+            InstructionError::Immutable => write!(f, "account is immutable"),
+            InstructionError::InvalidAccountOwner => write!(f, "invalid account owner"),
+            InstructionError::UnsupportedProgramId => write!(f, "unsupported program id"),
+            InstructionError::ModifiedProgramId => {
+                write!(
+                    f,
+                    "instruction illegally modified the program id of an account"
+                )
+            }
+            InstructionError::ExternalAccountLamportSpend => {
+                write!(
+                    f,
+                    "instruction spent from the balance of an account it does not own"
+                )
+            }
+            InstructionError::ExternalAccountDataModified => {
+                write!(f, "instruction modified data of an account it does not own")
+            }
+            InstructionError::ReadonlyLamportChange => {
+                write!(f, "instruction changed the balance of a read-only account")
+            }
+            InstructionError::ReadonlyDataModified => {
+                write!(f, "instruction modified data of a read-only account")
+            }
+            InstructionError::ExecutableModified => {
+                write!(f, "instruction changed the executable flag of an account")
+            }
+            InstructionError::UnbalancedInstruction => {
+                write!(
+                    f,
+                    "sum of account balances before and after instruction do not match"
+                )
+            }
+            InstructionError::InvalidSeeds => {
+                write!(f, "seeds do not give a valid program address")
+            }
+            InstructionError::MissingAccount => {
+                write!(f, "an account required by the instruction is missing")
+            }
+            InstructionError::PrivilegeEscalation => {
+                write!(
+                    f,
+                    "cross-program invocation with unauthorized signer or writable account"
+                )
+            }
+            InstructionError::CallDepth => {
+                write!(f, "cross-program invocation call depth too deep")
+            }
+            InstructionError::ReentrancyNotAllowed => {
+                write!(f, "cross-program invocation reentrancy not allowed")
+            }
+            InstructionError::ComputationalBudgetExceeded => {
+                write!(f, "computational budget exceeded")
+            }
+            InstructionError::System(err) => write!(f, "{}", err),
+            InstructionError::Timelock(err) => write!(f, "{}", err),
+            InstructionError::Multisig(err) => write!(f, "{}", err),
+            InstructionError::Token(err) => write!(f, "{}", err),
+            InstructionError::Nft(err) => write!(f, "{}", err),
+            InstructionError::Amm(err) => write!(f, "{}", err),
+            InstructionError::Escrow(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl InstructionError {
+    // The number a program error is reported as to clients, as in
+    // `{"Custom": 1}`. System and token errors use their mainnet numbers;
+    // the others count up in declaration order.
+    pub fn custom_code(&self) -> Option<u32> {
+        let code = match self {
+            InstructionError::System(err) => match err {
+                SystemError::AccountAlreadyInUse => 0,
+                SystemError::ResultWithNegativeLamports => 1,
+                SystemError::InvalidAccountDataLength => 3,
+                SystemError::TransferFromAccountWithData => 9,
+            },
+            InstructionError::Timelock(err) => match err {
+                TimelockError::StillLocked { .. } => 0,
+                TimelockError::WrongBeneficiary => 1,
+            },
+            InstructionError::Multisig(err) => match err {
+                MultisigError::InvalidThreshold { .. } => 0,
+                MultisigError::TooManySigners(_) => 1,
+                MultisigError::DuplicateSigner(_) => 2,
+                MultisigError::MissingSigners { .. } => 3,
+            },
+            InstructionError::Token(err) => match err {
+                TokenError::InsufficientBalance => 1,
+                TokenError::InvalidMint => 2,
+                TokenError::MintMismatch => 3,
+                TokenError::FixedSupply => 5,
+                TokenError::AlreadyInUse => 6,
+                TokenError::UninitializedState => 9,
+                TokenError::NonNativeHasBalance => 11,
+                TokenError::Overflow => 14,
+                TokenError::AuthorityTypeNotSupported => 15,
+                TokenError::AccountFrozen => 17,
+            },
+            InstructionError::Nft(err) => match err {
+                NftError::NameTooLong => 0,
+                NftError::SymbolTooLong => 1,
+                NftError::UriTooLong => 2,
+                NftError::InvalidBasisPoints(_) => 3,
+                NftError::TooManyCreators => 4,
+                NftError::DuplicateCreator(_) => 5,
+                NftError::InvalidShareTotal(_) => 6,
+                NftError::CreatorNotSigner(_) => 7,
+                NftError::MintMismatch => 8,
+                NftError::NotSingleToken => 9,
+                NftError::MintAuthorityNotRevoked => 10,
+                NftError::CollectionMismatch => 11,
+                NftError::MaxSupplyReached(_) => 12,
+                NftError::NotTokenOwner => 13,
+            },
+            InstructionError::Amm(err) => match err {
+                AmmError::InvalidFee(_) => 0,
+                AmmError::IdenticalMints => 1,
+                AmmError::InvalidVault => 2,
+                AmmError::InvalidLpMint => 3,
+                AmmError::PoolMismatch => 4,
+                AmmError::MintMismatch => 5,
+                AmmError::ZeroAmount => 6,
+                AmmError::InsufficientLiquidity => 7,
+                AmmError::ZeroOutput => 8,
+                AmmError::SlippageExceeded => 9,
+                AmmError::InvariantViolated => 10,
+            },
+            InstructionError::Escrow(err) => match err {
+                EscrowError::ZeroAmount => 0,
+                EscrowError::MintMismatch => 1,
+                EscrowError::EscrowMismatch => 2,
+            },
+            _ => return None,
+        };
+        Some(code)
+    }
+}
+
+impl From<SystemError> for InstructionError {
+    fn from(err: SystemError) -> Self {
+        InstructionError::System(err)
+    }
+}
+
+impl From<TimelockError> for InstructionError {
+    fn from(err: TimelockError) -> Self {
+        InstructionError::Timelock(err)
+    }
+}
+
+impl From<MultisigError> for InstructionError {
+    fn from(err: MultisigError) -> Self {
+        InstructionError::Multisig(err)
+    }
+}
+
+impl From<TokenError> for InstructionError {
+    fn from(err: TokenError) -> Self {
+        InstructionError::Token(err)
+    }
+}
+
+impl From<NftError> for InstructionError {
+    fn from(err: NftError) -> Self {
+        InstructionError::Nft(err)
+    }
+}
+
+impl From<AmmError> for InstructionError {
+    fn from(err: AmmError) -> Self {
+        InstructionError::Amm(err)
+    }
+}
+
+impl From<EscrowError> for InstructionError {
+    fn from(err: EscrowError) -> Self {
+        InstructionError::Escrow(err)
+    }
+}
+
+// ----------------------------------------
+// Invoke Context
+// ----------------------------------------
+
+// One account as the current instruction sees it: an index into the
+// transaction's accounts plus the privileges granted for this call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionAccount {
+    pub index: usize,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+// Everything the instructions of one transaction share
+pub struct TransactionContext<'a> {
+    pub keys: &'a [Pubkey],
+    pub accounts: &'a mut [Account],
+    pub programs: &'a HashMap<Pubkey, Box<dyn Program>>,
+    pub clock: &'a Clock,
+    pub logs: &'a mut Vec<String>,
+    // Units used so far, out of the transaction's compute_unit_limit
+    pub compute_units_consumed: &'a mut u64,
+    pub compute_unit_limit: u64,
+}
+
+impl TransactionContext<'_> {
+    // The same transaction, lent to a nested invocation
+    fn reborrow(&mut self) -> TransactionContext<'_> {
+        TransactionContext {
+            keys: self.keys,
+            accounts: self.accounts,
+            programs: self.programs,
+            clock: self.clock,
+            logs: self.logs,
+            compute_units_consumed: self.compute_units_consumed,
+            compute_unit_limit: self.compute_unit_limit,
+        }
+    }
+}
+
+// Accounts are shared with the whole transaction, so an account listed
+// twice in one instruction is the same account both times
+pub struct InvokeContext<'a> {
+    program_id: Pubkey,
+    tx: TransactionContext<'a>,
+    instruction_accounts: Vec<InstructionAccount>,
+    // Each distinct account once, with the strongest privileges it was
+    // passed with, and its state when this program last took control
+    touched: Vec<InstructionAccount>,
+    pre_accounts: Vec<Account>,
+    // The programs running, from the one the transaction called down to
+    // this one
+    stack: Vec<Pubkey>,
+}
+
+impl<'a> InvokeContext<'a> {
+    pub fn new(
+        program_id: Pubkey,
+        tx: TransactionContext<'a>,
+        instruction_accounts: Vec<InstructionAccount>,
+        stack: Vec<Pubkey>,
+    ) -> Self {
+        let mut touched: Vec<InstructionAccount> = Vec::new();
+        for account in &instruction_accounts {
+            match touched.iter_mut().find(|t| t.index == account.index) {
+                Some(existing) => {
+                    existing.is_signer |= account.is_signer;
+                    existing.is_writable |= account.is_writable;
+                }
+                None => touched.push(*account),
+            }
+        }
+        let pre_accounts = touched
+            .iter()
+            .map(|t| tx.accounts[t.index].clone())
+            .collect();
+        InvokeContext {
+            program_id,
+            tx,
+            instruction_accounts,
+            touched,
+            pre_accounts,
+            stack,
+        }
+    }
+
+    // 1 for the transaction's own instructions, one more per nested call
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    // The Clock sysvar at the time the transaction runs
+    pub fn clock(&self) -> &Clock {
+        self.tx.clock
+    }
+
+    // Appears in the transaction's logs as "Program log: ..."
+    pub fn log(&mut self, message: &str) {
+        self.tx.logs.push(format!("Program log: {}", message));
+    }
+
+    pub fn num_accounts(&self) -> usize {
+        self.instruction_accounts.len()
+    }
+
+    fn instruction_account(&self, i: usize) -> Result<InstructionAccount, InstructionError> {
+        self.instruction_accounts
+            .get(i)
+            .copied()
+            .ok_or(InstructionError::NotEnoughAccountKeys)
+    }
+
+    pub fn key(&self, i: usize) -> Result<&Pubkey, InstructionError> {
+        Ok(&self.tx.keys[self.instruction_account(i)?.index])
+    }
+
+    pub fn is_signer(&self, i: usize) -> Result<bool, InstructionError> {
+        Ok(self.instruction_account(i)?.is_signer)
+    }
+
+    // The key of account `i`, which must have signed
+    pub fn signer(&self, i: usize) -> Result<&Pubkey, InstructionError> {
+        if !self.is_signer(i)? {
+            return Err(InstructionError::MissingRequiredSignature);
+        }
+        self.key(i)
+    }
+
+    pub fn account(&self, i: usize) -> Result<&Account, InstructionError> {
+        Ok(&self.tx.accounts[self.instruction_account(i)?.index])
+    }
+
+    pub fn account_mut(&mut self, i: usize) -> Result<&mut Account, InstructionError> {
+        let account = self.instruction_account(i)?;
+        if !account.is_writable {
+            return Err(InstructionError::AccountNotWritable);
+        }
+        Ok(&mut self.tx.accounts[account.index])
+    }
+
+    // Move lamports from an account this program owns (or, for the system
+    // program, a system account) to any writable account
+    pub fn transfer_lamports(
+        &mut self,
+        from: usize,
+        to: usize,
+        lamports: u64,
+    ) -> Result<(), InstructionError> {
+        let from = self.account_mut(from)?;
+        from.lamports = from
+            .lamports
+            .checked_sub(lamports)
+            .ok_or(InstructionError::InsufficientFunds)?;
+        let to = self.account_mut(to)?;
+        to.lamports = to
+            .lamports
+            .checked_add(lamports)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+// ----------------------------------------
+// Cross-Program Invocation
+// ----------------------------------------
+
+impl InvokeContext<'_> {
+    // Call another program from inside this one. The callee only gets
+    // accounts this instruction already has, with no more privileges than
+    // it has here, except that this program may sign for addresses derived
+    // from its own id with `signers_seeds`.
+    pub fn invoke_signed(
+        &mut self,
+        instruction: &Instruction,
+        signers_seeds: &[&[&[u8]]],
+    ) -> Result<(), InstructionError> {
+        if self.depth() >= MAX_INVOKE_DEPTH {
+            return Err(InstructionError::CallDepth);
+        }
+        if instruction.program_id != self.program_id && self.stack.contains(&instruction.program_id)
+        {
+            return Err(InstructionError::ReentrancyNotAllowed);
+        }
+
+        let pda_signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &self.program_id))
+            .collect::<Result<Vec<Pubkey>, _>>()
+            .map_err(|_| InstructionError::InvalidSeeds)?;
+
+        let mut callee_accounts = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let caller = self
+                .touched
+                .iter()
+                .find(|account| self.tx.keys[account.index] == meta.pubkey)
+                .ok_or(InstructionError::MissingAccount)?;
+            let can_sign = caller.is_signer || pda_signers.contains(&meta.pubkey);
+            if (meta.is_writable && !caller.is_writable) || (meta.is_signer && !can_sign) {
+                return Err(InstructionError::PrivilegeEscalation);
+            }
+            callee_accounts.push(InstructionAccount {
+                index: caller.index,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            });
+        }
+        let programs = self.tx.programs;
+        let program = programs
+            .get(&instruction.program_id)
+            .ok_or(InstructionError::UnsupportedProgramId)?;
+
+        // What this program changed so far has to follow the rules on its
+        // own; the callee's changes are checked when it returns and then
+        // become the new starting point
+        self.verify()?;
+        invoke_program(
+            program.as_ref(),
+            self.tx.reborrow(),
+            callee_accounts,
+            &instruction.data,
+            &self.stack,
+        )?;
+        for (t, pre) in self.touched.iter().zip(&mut self.pre_accounts) {
+            *pre = self.tx.accounts[t.index].clone();
+        }
+        Ok(())
+    }
+
+    pub fn invoke(&mut self, instruction: &Instruction) -> Result<(), InstructionError> {
+        self.invoke_signed(instruction, &[])
+    }
+
+    // Check every change since this program took control
+    fn verify(&self) -> Result<(), InstructionError> {
+        for (account, pre) in self.touched.iter().zip(&self.pre_accounts) {
+            verify_account(
+                &self.program_id,
+                account,
+                pre,
+                &self.tx.accounts[account.index],
+            )?;
+        }
+        let pre_total: u128 = self.pre_accounts.iter().map(|a| a.lamports as u128).sum();
+        let post_total: u128 = self
+            .touched
+            .iter()
+            .map(|t| self.tx.accounts[t.index].lamports as u128)
+            .sum();
+        if pre_total != post_total {
+            return Err(InstructionError::UnbalancedInstruction);
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------
+// Running One Instruction
+// ----------------------------------------
+
+// Invoke `program` over the transaction's accounts and check the account
+// rules afterwards. `callers` are the programs already running, none for
+// the transaction's own instructions. Changes stay in `accounts` even on
+// error; the caller throws the whole working copy away if any instruction
+// fails.
+pub fn invoke_program(
+    program: &dyn Program,
+    tx: TransactionContext,
+    instruction_accounts: Vec<InstructionAccount>,
+    data: &[u8],
+    callers: &[Pubkey],
+) -> Result<(), InstructionError> {
+    let program_id = program.id();
+    let mut stack = callers.to_vec();
+    stack.push(program_id);
+    tx.logs
+        .push(format!("Program {} invoke [{}]", program_id, stack.len()));
+
+    let consumed = *tx.compute_units_consumed + program.compute_units();
+    let limit = tx.compute_unit_limit;
+    *tx.compute_units_consumed = consumed.min(limit);
+
+    let mut ctx = InvokeContext::new(program_id, tx, instruction_accounts, stack);
+    let result = if consumed > limit {
+        Err(InstructionError::ComputationalBudgetExceeded)
+    } else {
+        program
+            .process_instruction(&mut ctx, data)
+            .and_then(|()| ctx.verify())
+    };
+
+    let line = match &result {
+        Ok(()) => format!("Program {} success", program_id),
+        Err(err) => format!("Program {} failed: {}", program_id, err),
+    };
+    ctx.tx.logs.push(line);
+    result
+}
+
+fn verify_account(
+    program_id: &Pubkey,
+    account: &InstructionAccount,
+    pre: &Account,
+    post: &Account,
+) -> Result<(), InstructionError> {
+    if !account.is_writable {
+        if post.lamports != pre.lamports {
+            return Err(InstructionError::ReadonlyLamportChange);
+        }
+        if post != pre {
+            return Err(InstructionError::ReadonlyDataModified);
+        }
+        return Ok(());
+    }
+
+    let owned = pre.owner == *program_id;
+    if post.owner != pre.owner && (!owned || post.data.iter().any(|&b| b != 0)) {
+        return Err(InstructionError::ModifiedProgramId);
+    }
+    if post.lamports < pre.lamports && !owned {
+        return Err(InstructionError::ExternalAccountLamportSpend);
+    }
+    if post.data != pre.data && !owned {
+        return Err(InstructionError::ExternalAccountDataModified);
+    }
+    if post.executable != pre.executable {
+        return Err(InstructionError::ExecutableModified);
+    }
+    Ok(())
+}
@@ -0,0 +1,1283 @@
+// ========================================================================
+// PROPERTY CHECKS
+// ========================================================================
+//
+// A property is a claim about every input, such as "a token transfer
+// fails with InsufficientBalance exactly when the source holds less than
+// the amount". `simply_rust check` tries each one on a hundred random
+// inputs; the first input that breaks it is shrunk to a minimal one and
+// printed along with the seed that found it:
+//
+//     simply_rust check                          every property
+//     simply_rust check token-supply --seed 42   one property, reproducibly
+//
+// Generators do not build values directly. They draw numbers from a `Gen`,
+// which records every choice it hands out:
+//
+//     let balance = g.range(0, 1_000);      // one choice
+//     let memo = g.option(|g| g.u64());     // a flag, then maybe a value
+//
+// A failing input is therefore just a list of numbers, and shrinking works
+// on that list without knowing what it means: delete runs of choices, set
+// them to zero, make them smaller, and keep any change after which the
+// property still fails. Replaying a shorter list reads zeros past its end,
+// and every generator is written so that zero is its simplest answer (the
+// smallest number, the first variant, `None`, stop adding elements), so
+// shrunk inputs come out small and readable.
+//
+// Properties report what they did through `g.note(..)`; only the notes of
+// the final, minimal run are printed. A panic counts as a failure.
+
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::address_lookup_table_program::LookupTableInstruction;
+use crate::amm_program::{self, AmmInstruction, PoolBalances, MINIMUM_LIQUIDITY};
+use crate::associated_token_program::{AssociatedTokenInstruction, TOKEN_ACCOUNT_LAMPORTS};
+use crate::bank::{Bank, TransactionError};
+use crate::compute_budget::ComputeBudgetInstruction;
+use crate::escrow_program::EscrowInstruction;
+use crate::hash::hashv;
+use crate::keypair;
+use crate::ledger::Account;
+use crate::nft_program::{self, Creator, MetadataArgs, NftInstruction};
+use crate::program::InstructionError;
+use crate::pubkey::Pubkey;
+use crate::system_program::{self, SystemInstruction};
+use crate::timelock::TimelockInstruction;
+use crate::token_program::{
+    self, AccountState, AuthorityType, Mint, TokenAccount, TokenError, TokenInstruction,
+};
+use crate::transaction::{DemoSigner, Instruction, Signer, Transaction};
+
+pub const DEFAULT_CASES: usize = 100;
+
+// Choices a single run may draw before it is abandoned
+const MAX_CHOICES: usize = 10_000;
+
+// Property runs spent on shrinking one failure
+const MAX_SHRINK_RUNS: usize = 2_000;
+
+// ----------------------------------------
+// Choices
+// ----------------------------------------
+
+// SplitMix64: tiny, fast and good enough to pick test inputs
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+// The source of every value a property sees: random while searching,
+// a recorded list of choices while shrinking
+pub struct Gen {
+    // None when replaying `prefix`
+    rng: Option<SplitMix64>,
+    prefix: Vec<u64>,
+    // What this run drew, after clamping to each draw's bound
+    choices: Vec<u64>,
+    notes: Vec<String>,
+}
+
+impl Gen {
+    fn random(seed: u64) -> Self {
+        Gen {
+            rng: Some(SplitMix64(seed)),
+            prefix: Vec::new(),
+            choices: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    fn replay(prefix: Vec<u64>) -> Self {
+        Gen {
+            rng: None,
+            prefix,
+            choices: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    // The primitive every generator is built on: a number in 0..=max
+    fn draw(&mut self, max: u64) -> u64 {
+        if self.choices.len() >= MAX_CHOICES {
+            panic!("drew more than {} choices", MAX_CHOICES);
+        }
+        let value = match &mut self.rng {
+            Some(rng) if max == u64::MAX => rng.next(),
+            Some(rng) => rng.next() % (max + 1),
+            None => self
+                .prefix
+                .get(self.choices.len())
+                .map_or(0, |&value| value.min(max)),
+        };
+        self.choices.push(value);
+        value
+    }
+
+    // Record a line for the failure report
+    pub fn note(&mut self, line: impl Into<String>) {
+        self.notes.push(line.into());
+    }
+
+    // A number in min..=max, shrinking towards `min`
+    pub fn range(&mut self, min: u64, max: u64) -> u64 {
+        assert!(min <= max, "empty range {}..={}", min, max);
+        min + self.draw(max - min)
+    }
+
+    // An index into a collection of `len` items
+    pub fn index(&mut self, len: usize) -> usize {
+        assert!(len > 0, "cannot pick from nothing");
+        self.draw(len as u64 - 1) as usize
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    // True with probability `p` while searching, shrinking to false
+    pub fn weighted(&mut self, p: f64) -> bool {
+        if self.choices.len() >= MAX_CHOICES {
+            panic!("drew more than {} choices", MAX_CHOICES);
+        }
+        let value = match &mut self.rng {
+            Some(rng) => ((rng.next() as f64) < p * u64::MAX as f64) as u64,
+            None => self
+                .prefix
+                .get(self.choices.len())
+                .map_or(0, |&value| value.min(1)),
+        };
+        self.choices.push(value);
+        value == 1
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.weighted(0.5)
+    }
+
+    pub fn u8(&mut self) -> u8 {
+        self.draw(u8::MAX as u64) as u8
+    }
+
+    pub fn u16(&mut self) -> u16 {
+        self.draw(u16::MAX as u64) as u16
+    }
+
+    pub fn u32(&mut self) -> u32 {
+        self.u64_below(u32::MAX as u64) as u32
+    }
+
+    // Any u64, with small numbers and values next to the maximum much
+    // likelier than a uniform draw would make them
+    pub fn u64(&mut self) -> u64 {
+        self.u64_below(u64::MAX)
+    }
+
+    fn u64_below(&mut self, max: u64) -> u64 {
+        match self.draw(3) {
+            0 => self.draw(16.min(max)),
+            1 => self.draw(1_000_000.min(max)),
+            2 => max - self.draw(16.min(max)),
+            _ => self.draw(max),
+        }
+    }
+
+    pub fn i64(&mut self) -> i64 {
+        let magnitude = self.u64_below(i64::MAX as u64) as i64;
+        if self.bool() {
+            -magnitude - 1
+        } else {
+            magnitude
+        }
+    }
+
+    // Either the default key or one from 32 random bytes
+    pub fn pubkey(&mut self) -> Pubkey {
+        if !self.weighted(0.9) {
+            return Pubkey::default();
+        }
+        let mut bytes = [0u8; 32];
+        for chunk in bytes.chunks_exact_mut(8) {
+            chunk.copy_from_slice(&self.draw(u64::MAX).to_le_bytes());
+        }
+        Pubkey::new(bytes)
+    }
+
+    pub fn option<T>(&mut self, value: impl FnOnce(&mut Gen) -> T) -> Option<T> {
+        self.bool().then(|| value(self))
+    }
+
+    // Up to `max_len` elements, each preceded by a "one more" flag so
+    // that deleting choices deletes elements
+    pub fn vec<T>(&mut self, max_len: usize, mut element: impl FnMut(&mut Gen) -> T) -> Vec<T> {
+        let mut items = Vec::new();
+        while items.len() < max_len && self.weighted(0.8) {
+            items.push(element(self));
+        }
+        items
+    }
+
+    // Mostly ASCII, with some multi-byte characters to keep length and
+    // byte count apart
+    pub fn string(&mut self, max_len: usize) -> String {
+        const CHARS: &[char] = &['a', 'b', 'z', 'A', '0', ' ', '/', 'é', '€', '🦀'];
+        self.vec(max_len, |g| *g.pick(CHARS)).into_iter().collect()
+    }
+}
+
+// ----------------------------------------
+// Running Properties
+// ----------------------------------------
+
+pub struct Property {
+    pub name: &'static str,
+    pub description: &'static str,
+    check: fn(&mut Gen) -> Result<(), String>,
+}
+
+// The smallest failing input found, as the property saw it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub choices: Vec<u64>,
+    pub error: String,
+    pub notes: Vec<String>,
+    pub shrink_runs: usize,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "minimal input ({} choices, {} shrinking runs):",
+            self.choices.len(),
+            self.shrink_runs
+        )?;
+        for note in &self.notes {
+            writeln!(f, "  {}", note)?;
+        }
+        write!(f, "error: {}", self.error)
+    }
+}
+
+impl Property {
+    fn run(&self, gen: &mut Gen) -> Result<(), String> {
+        panic::catch_unwind(AssertUnwindSafe(|| (self.check)(gen)))
+            .unwrap_or_else(|payload| Err(format!("panicked: {}", panic_message(&*payload))))
+    }
+
+    // Try `cases` random inputs; on the first failure, return it shrunk
+    // along with the number of the case that found it
+    pub fn check(&self, seed: u64, cases: usize) -> Result<(), (usize, Counterexample)> {
+        let seed = hashv(&[b"property", self.name.as_bytes(), &seed.to_le_bytes()]).to_bytes();
+        let mut rng = SplitMix64(u64::from_le_bytes(seed[..8].try_into().unwrap()));
+
+        // The default hook would print every panic the search provokes
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let mut result = Ok(());
+        for case in 1..=cases {
+            let mut gen = Gen::random(rng.next());
+            if let Err(error) = self.run(&mut gen) {
+                result = Err((case, self.shrink(gen, error)));
+                break;
+            }
+        }
+        panic::set_hook(hook);
+        result
+    }
+
+    fn shrink(&self, gen: Gen, error: String) -> Counterexample {
+        let mut shrinker = Shrinker {
+            property: self,
+            best: Counterexample {
+                choices: gen.choices,
+                error,
+                notes: gen.notes,
+                shrink_runs: 0,
+            },
+        };
+        while shrinker.pass() {}
+        shrinker.best
+    }
+}
+
+struct Shrinker<'a> {
+    property: &'a Property,
+    best: Counterexample,
+}
+
+impl Shrinker<'_> {
+    // Replay `candidate` and keep it if it still fails and what it drew
+    // is simpler than the best so far: fewer choices, or as many but
+    // smaller ones
+    fn consider(&mut self, candidate: Vec<u64>) -> bool {
+        if self.best.shrink_runs >= MAX_SHRINK_RUNS {
+            return false;
+        }
+        self.best.shrink_runs += 1;
+        let mut gen = Gen::replay(candidate);
+        let Err(error) = self.property.run(&mut gen) else {
+            return false;
+        };
+        let simpler =
+            (gen.choices.len(), &gen.choices) < (self.best.choices.len(), &self.best.choices);
+        if simpler {
+            self.best.choices = gen.choices;
+            self.best.notes = gen.notes;
+            self.best.error = error;
+        }
+        simpler
+    }
+
+    // One round of every transformation; true if any of them helped
+    fn pass(&mut self) -> bool {
+        let mut improved = false;
+
+        // Delete runs of choices, longest first, from the back
+        for len in (1..=8).rev() {
+            let mut start = self.best.choices.len().saturating_sub(len);
+            loop {
+                let choices = &self.best.choices;
+                if start + len <= choices.len() {
+                    let candidate = [&choices[..start], &choices[start + len..]].concat();
+                    improved |= self.consider(candidate);
+                }
+                if start == 0 {
+                    break;
+                }
+                start -= 1;
+            }
+        }
+
+        // Make each choice as small as it can be: zero, else the lowest
+        // value a binary search finds
+        let mut i = 0;
+        while i < self.best.choices.len() {
+            let value = self.best.choices[i];
+            if value > 0 && !self.try_value(i, 0) {
+                let (mut passes, mut fails) = (0, value);
+                while passes + 1 < fails {
+                    let mid = passes + (fails - passes) / 2;
+                    if self.try_value(i, mid) {
+                        fails = mid;
+                    } else {
+                        passes = mid;
+                    }
+                }
+            }
+            improved |= self.best.choices.get(i) != Some(&value);
+            i += 1;
+        }
+        improved && self.best.shrink_runs < MAX_SHRINK_RUNS
+    }
+
+    fn try_value(&mut self, i: usize, value: u64) -> bool {
+        let mut candidate = self.best.choices.clone();
+        candidate[i] = value;
+        self.consider(candidate)
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+// ----------------------------------------
+// Properties
+// ----------------------------------------
+
+const PROPERTIES: &[Property] = &[
+    Property {
+        name: "token-supply",
+        description: "Transfers, mints and burns keep token balances adding up to the supply",
+        check: token_supply,
+    },
+    Property {
+        name: "insufficient-balance",
+        description: "A token transfer fails with InsufficientBalance iff balance < amount",
+        check: insufficient_balance,
+    },
+    Property {
+        name: "lamports",
+        description: "System transfers move lamports without creating, losing or wrapping any",
+        check: lamports_conserved,
+    },
+    Property {
+        name: "amm-invariant",
+        description: "Swaps never lower k, and no deposit or withdrawal lowers an LP token's share",
+        check: amm_invariant,
+    },
+    Property {
+        name: "roundtrip-system",
+        description: "SystemInstruction unpacks to what was packed",
+        check: |g| {
+            roundtrip(
+                system_instruction(g),
+                |i| i.pack(),
+                SystemInstruction::unpack,
+            )
+        },
+    },
+    Property {
+        name: "roundtrip-token",
+        description: "TokenInstruction unpacks to what was packed",
+        check: |g| roundtrip(token_instruction(g), |i| i.pack(), TokenInstruction::unpack),
+    },
+    Property {
+        name: "roundtrip-ata",
+        description: "AssociatedTokenInstruction unpacks to what was packed",
+        check: |g| {
+            let instruction = *g.pick(&[
+                AssociatedTokenInstruction::Create,
+                AssociatedTokenInstruction::CreateIdempotent,
+            ]);
+            roundtrip(
+                instruction,
+                |i| i.pack(),
+                AssociatedTokenInstruction::unpack,
+            )
+        },
+    },
+    Property {
+        name: "roundtrip-compute-budget",
+        description: "ComputeBudgetInstruction unpacks to what was packed",
+        check: |g| {
+            let instruction = match g.draw(1) {
+                0 => ComputeBudgetInstruction::SetComputeUnitLimit(g.u32()),
+                _ => ComputeBudgetInstruction::SetComputeUnitPrice(g.u64()),
+            };
+            roundtrip(instruction, |i| i.pack(), ComputeBudgetInstruction::unpack)
+        },
+    },
+    Property {
+        name: "roundtrip-lookup-table",
+        description: "LookupTableInstruction unpacks to what was packed",
+        check: |g| {
+            let instruction = match g.draw(4) {
+                0 => LookupTableInstruction::Create {
+                    recent_slot: g.u64(),
+                    bump_seed: g.u8(),
+                },
+                1 => LookupTableInstruction::Freeze,
+                2 => LookupTableInstruction::Extend {
+                    new_addresses: g.vec(8, Gen::pubkey),
+                },
+                3 => LookupTableInstruction::Deactivate,
+                _ => LookupTableInstruction::Close,
+            };
+            roundtrip(instruction, |i| i.pack(), LookupTableInstruction::unpack)
+        },
+    },
+    Property {
+        name: "roundtrip-amm",
+        description: "AmmInstruction unpacks to what was packed",
+        check: |g| {
+            let instruction = match g.draw(3) {
+                0 => AmmInstruction::InitializePool {
+                    fee_bps: g.u16(),
+                    amount_a: g.u64(),
+                    amount_b: g.u64(),
+                },
+                1 => AmmInstruction::Deposit {
+                    lp_amount: g.u64(),
+                    max_amount_a: g.u64(),
+                    max_amount_b: g.u64(),
+                },
+                2 => AmmInstruction::Withdraw {
+                    lp_amount: g.u64(),
+                    min_amount_a: g.u64(),
+                    min_amount_b: g.u64(),
+                },
+                _ => AmmInstruction::Swap {
+                    amount_in: g.u64(),
+                    min_amount_out: g.u64(),
+                },
+            };
+            roundtrip(instruction, |i| i.pack(), AmmInstruction::unpack)
+        },
+    },
+    Property {
+        name: "roundtrip-escrow",
+        description: "EscrowInstruction unpacks to what was packed",
+        check: |g| {
+            let instruction = match g.draw(2) {
+                0 => EscrowInstruction::Make {
+                    seed: g.u64(),
+                    offer_amount: g.u64(),
+                    want_amount: g.u64(),
+                },
+                1 => EscrowInstruction::Take,
+                _ => EscrowInstruction::Cancel,
+            };
+            roundtrip(instruction, |i| i.pack(), EscrowInstruction::unpack)
+        },
+    },
+    Property {
+        name: "roundtrip-nft",
+        description: "NftInstruction unpacks to what was packed",
+        check: |g| roundtrip(nft_instruction(g), |i| i.pack(), NftInstruction::unpack),
+    },
+    Property {
+        name: "roundtrip-timelock",
+        description: "TimelockInstruction unpacks to what was packed",
+        check: |g| {
+            let instruction = match g.draw(1) {
+                0 => TimelockInstruction::Lock {
+                    beneficiary: g.pubkey(),
+                    unlock_timestamp: g.i64(),
+                },
+                _ => TimelockInstruction::Release,
+            };
+            roundtrip(instruction, |i| i.pack(), TimelockInstruction::unpack)
+        },
+    },
+];
+
+// ----------------------------------------
+// Token and Bank Properties
+// ----------------------------------------
+
+// Transactions sent in one case at most
+const MAX_STEPS: usize = 12;
+
+// Three token owners, each able to pay fees
+fn owners(bank: &mut Bank) -> Result<Vec<DemoSigner>, String> {
+    let owners: Vec<DemoSigner> = ["alice", "bob", "carol"]
+        .into_iter()
+        .map(DemoSigner::new)
+        .collect();
+    for owner in &owners {
+        bank.airdrop(&owner.pubkey(), 1_000_000_000)
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(owners)
+}
+
+// A token balance: small, large, or near the most four accounts of one
+// mint can hold between them
+fn balance(g: &mut Gen) -> u64 {
+    match g.draw(2) {
+        0 => g.range(0, 1_000),
+        1 => g.range(0, 1_000_000_000_000),
+        _ => u64::MAX / 4 - g.range(0, 16),
+    }
+}
+
+// An amount to take out of `balance`: within it, just around it, or
+// anything at all
+fn amount_for(g: &mut Gen, balance: u64) -> u64 {
+    match g.draw(2) {
+        0 => g.range(0, balance),
+        1 => balance.saturating_sub(1).saturating_add(g.range(0, 2)),
+        _ => g.u64(),
+    }
+}
+
+// A mint whose supply is exactly what `balances` hold, and one token
+// account per balance, stored straight into the bank
+fn token_accounts(
+    bank: &mut Bank,
+    mint_authority: &Pubkey,
+    balances: &[(usize, u64)],
+    owners: &[DemoSigner],
+) -> (Pubkey, Vec<Pubkey>) {
+    let mint = Pubkey::new_unique();
+    let supply = balances.iter().map(|(_, amount)| amount).sum();
+    let mint_state = Mint {
+        mint_authority: Some(*mint_authority),
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: None,
+    };
+    bank.store_account(mint, token_account_data(&mint_state.pack()));
+
+    let accounts = balances
+        .iter()
+        .map(|&(owner, amount)| {
+            let key = Pubkey::new_unique();
+            let account = TokenAccount {
+                mint,
+                owner: owners[owner].pubkey(),
+                amount,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            };
+            bank.store_account(key, token_account_data(&account.pack()));
+            key
+        })
+        .collect();
+    (mint, accounts)
+}
+
+fn token_account_data(data: &[u8]) -> Account {
+    let mut account = Account::new(TOKEN_ACCOUNT_LAMPORTS, 0, &token_program::ID);
+    account.data = data.to_vec();
+    account
+}
+
+fn token_amount(bank: &Bank, key: &Pubkey) -> u64 {
+    bank.get_account(key)
+        .and_then(|account| TokenAccount::unpack(&account.data).ok())
+        .map_or(0, |account| account.amount)
+}
+
+fn supply(bank: &Bank, mint: &Pubkey) -> u64 {
+    bank.get_account(mint)
+        .and_then(|account| Mint::unpack(&account.data).ok())
+        .map_or(0, |mint| mint.supply)
+}
+
+// Send one instruction paid for and signed by `signer`, in a slot of its
+// own so that repeating it is not a duplicate
+fn send(
+    bank: &mut Bank,
+    instruction: Instruction,
+    signer: &DemoSigner,
+) -> Result<(), TransactionError> {
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&signer.pubkey()),
+        &[signer],
+        bank.last_blockhash(),
+    );
+    let status = bank.execute_transaction(&tx).status;
+    bank.advance_slots(1);
+    status
+}
+
+fn status_text(status: &Result<(), TransactionError>) -> String {
+    match status {
+        Ok(()) => "ok".to_string(),
+        Err(err) => err.to_string(),
+    }
+}
+
+fn token_supply(g: &mut Gen) -> Result<(), String> {
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(token_program::TokenProgram));
+    let owners = owners(&mut bank)?;
+    let names = ["alice", "bob", "carol"];
+    let count = g.range(1, 4) as usize;
+    let balances: Vec<(usize, u64)> = (0..count)
+        .map(|_| (g.index(owners.len()), balance(g)))
+        .collect();
+    let (mint, accounts) = token_accounts(&mut bank, &owners[0].pubkey(), &balances, &owners);
+    for (i, (owner, amount)) in balances.iter().enumerate() {
+        g.note(format!("account #{}: {} owns {}", i, names[*owner], amount));
+    }
+
+    for _ in 0..MAX_STEPS {
+        if !g.weighted(0.9) {
+            break;
+        }
+        let step = g.draw(2);
+        let amounts: Vec<u64> = accounts
+            .iter()
+            .map(|key| token_amount(&bank, key))
+            .collect();
+        let before_supply = supply(&bank, &mint);
+        let from = g.index(accounts.len());
+        let amount = amount_for(g, amounts[from]);
+        // Mostly the right authority, sometimes someone else
+        let signer = if g.weighted(0.2) {
+            g.index(owners.len())
+        } else if step == 1 {
+            0
+        } else {
+            balances[from].0
+        };
+        let (instruction, action) = match step {
+            0 => {
+                let to = g.index(accounts.len());
+                (
+                    token_program::transfer(
+                        &accounts[from],
+                        &accounts[to],
+                        &owners[signer].pubkey(),
+                        &[],
+                        amount,
+                    ),
+                    format!("transfer {} from #{} to #{}", amount, from, to),
+                )
+            }
+            1 => (
+                token_program::mint_to(
+                    &mint,
+                    &accounts[from],
+                    &owners[signer].pubkey(),
+                    &[],
+                    amount,
+                ),
+                format!("mint {} to #{}", amount, from),
+            ),
+            _ => (
+                token_program::burn(
+                    &accounts[from],
+                    &mint,
+                    &owners[signer].pubkey(),
+                    &[],
+                    amount,
+                ),
+                format!("burn {} from #{}", amount, from),
+            ),
+        };
+        let status = send(&mut bank, instruction, &owners[signer]);
+        g.note(format!(
+            "{} signed by {}: {}",
+            action,
+            names[signer],
+            status_text(&status)
+        ));
+
+        let after: Vec<u64> = accounts
+            .iter()
+            .map(|key| token_amount(&bank, key))
+            .collect();
+        let total: u128 = after.iter().map(|&amount| amount as u128).sum();
+        let supply = supply(&bank, &mint);
+        if total != supply as u128 {
+            return Err(format!(
+                "balances add up to {} but the supply is {}",
+                total, supply
+            ));
+        }
+        if status.is_err() && (after != amounts || supply != before_supply) {
+            return Err(format!(
+                "failed, yet balances went from {:?} to {:?}",
+                amounts, after
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn insufficient_balance(g: &mut Gen) -> Result<(), String> {
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(token_program::TokenProgram));
+    let owners = owners(&mut bank)?;
+    let source_balance = balance(g);
+    let destination_balance = balance(g);
+    let (_, accounts) = token_accounts(
+        &mut bank,
+        &owners[0].pubkey(),
+        &[(0, source_balance), (1, destination_balance)],
+        &owners,
+    );
+    // Occasionally to itself, which moves nothing but is still checked
+    let destination = if g.weighted(0.1) {
+        accounts[0]
+    } else {
+        accounts[1]
+    };
+    let amount = amount_for(g, source_balance);
+    g.note(format!(
+        "source holds {}, destination {}",
+        source_balance,
+        if destination == accounts[0] {
+            "is the source".to_string()
+        } else {
+            destination_balance.to_string()
+        }
+    ));
+
+    let instruction =
+        token_program::transfer(&accounts[0], &destination, &owners[0].pubkey(), &[], amount);
+    let status = send(&mut bank, instruction, &owners[0]);
+    g.note(format!("transfer {}: {}", amount, status_text(&status)));
+
+    let insufficient = matches!(
+        status,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Token(TokenError::InsufficientBalance)
+        ))
+    );
+    if insufficient != (source_balance < amount) {
+        return Err(format!(
+            "balance {} and amount {}, but InsufficientBalance was {}",
+            source_balance,
+            amount,
+            if insufficient {
+                "returned"
+            } else {
+                "not returned"
+            }
+        ));
+    }
+    if status.is_ok() && destination != accounts[0] {
+        let (source, destination) = (
+            token_amount(&bank, &accounts[0]),
+            token_amount(&bank, &destination),
+        );
+        if source != source_balance - amount || destination != destination_balance + amount {
+            return Err(format!(
+                "source now holds {} and destination {}",
+                source, destination
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn lamports_conserved(g: &mut Gen) -> Result<(), String> {
+    let mut bank = Bank::new();
+    let wallets: Vec<DemoSigner> = ["alice", "bob", "carol"]
+        .into_iter()
+        .map(DemoSigner::new)
+        .collect();
+    let names = ["alice", "bob", "carol", "nobody"];
+    for (wallet, name) in wallets.iter().zip(names) {
+        let lamports = match g.draw(2) {
+            0 => g.range(0, 10_000),
+            1 => g.range(0, 10_000_000_000),
+            _ => u64::MAX / 4 - g.range(0, 16),
+        };
+        if lamports > 0 {
+            bank.airdrop(&wallet.pubkey(), lamports)
+                .map_err(|err| err.to_string())?;
+        }
+        g.note(format!("{} has {}", name, lamports));
+    }
+    // Receives but never signs; starts out not existing at all
+    let mut keys: Vec<Pubkey> = wallets.iter().map(|wallet| wallet.pubkey()).collect();
+    keys.push(DemoSigner::new("nobody").pubkey());
+    let total = |bank: &Bank| -> u128 {
+        keys.iter()
+            .map(|key| bank.get_balance(key) as u128)
+            .sum::<u128>()
+            + bank.collected_fees() as u128
+    };
+    let initial = total(&bank);
+
+    for _ in 0..MAX_STEPS {
+        if !g.weighted(0.9) {
+            break;
+        }
+        let (from, to) = (g.index(wallets.len()), g.index(keys.len()));
+        let balance = bank.get_balance(&keys[from]);
+        let lamports = amount_for(g, balance);
+        let fees = bank.collected_fees();
+        let instruction = system_program::transfer(&keys[from], &keys[to], lamports);
+        let status = send(&mut bank, instruction, &wallets[from]);
+        let fee = bank.collected_fees() - fees;
+        g.note(format!(
+            "{} sends {} to {}: {}",
+            names[from],
+            lamports,
+            names[to],
+            status_text(&status)
+        ));
+
+        if total(&bank) != initial {
+            return Err(format!(
+                "lamports and fees add up to {}, not {}",
+                total(&bank),
+                initial
+            ));
+        }
+        let after = bank.get_balance(&keys[from]);
+        if status.is_err() && after != balance - fee {
+            return Err(format!(
+                "failed, yet {} went from {} to {} with a fee of {}",
+                names[from], balance, after, fee
+            ));
+        }
+        if let Some(key) = keys
+            .iter()
+            .find(|key| bank.get_balance(key) as u128 > initial)
+        {
+            return Err(format!(
+                "{} holds {}, more than exists",
+                key,
+                bank.get_balance(key)
+            ));
+        }
+    }
+    Ok(())
+}
+
+// ----------------------------------------
+// AMM Properties
+// ----------------------------------------
+
+// The pool math alone, as the processor uses it: a pool from its first
+// deposit, then swaps, deposits and withdrawals of any size. Amounts
+// that would not fit a reserve are skipped, as the token program would
+// refuse them.
+fn amm_invariant(g: &mut Gen) -> Result<(), String> {
+    let reserve = |g: &mut Gen| match g.draw(2) {
+        0 => g.range(MINIMUM_LIQUIDITY + 1, 1_000_000),
+        1 => g.range(MINIMUM_LIQUIDITY + 1, 1_000_000_000_000_000),
+        _ => u64::MAX / 4 - g.range(0, 16),
+    };
+    let (amount_a, amount_b) = (reserve(g), reserve(g));
+    let fee_bps = g.range(0, amm_program::MAX_FEE_BPS as u64) as u16;
+    let lp_amount = amm_program::initial_liquidity(amount_a, amount_b)
+        .ok_or("first deposit mints no LP tokens")?;
+    let mut state = PoolBalances {
+        reserve_a: amount_a,
+        reserve_b: amount_b,
+        supply: lp_amount + MINIMUM_LIQUIDITY,
+    };
+    g.note(format!(
+        "pool of {} A and {} B, fee {} bps",
+        amount_a, amount_b, fee_bps
+    ));
+
+    for _ in 0..MAX_STEPS {
+        if !g.weighted(0.9) {
+            break;
+        }
+        let mut next = state;
+        let reserves = (state.reserve_a, state.reserve_b);
+        match g.draw(3) {
+            step @ (0 | 1) => {
+                let (reserve_in, reserve_out) = match step {
+                    0 => (&mut next.reserve_a, &mut next.reserve_b),
+                    _ => (&mut next.reserve_b, &mut next.reserve_a),
+                };
+                let amount_in = amount_for(g, *reserve_in);
+                let out = amm_program::swap_output(amount_in, *reserve_in, *reserve_out, fee_bps);
+                let Some(sum) = reserve_in.checked_add(amount_in) else {
+                    continue;
+                };
+                *reserve_in = sum;
+                *reserve_out -= out;
+                g.note(format!(
+                    "swap {} {} for {}",
+                    amount_in,
+                    ["A", "B"][step as usize],
+                    out
+                ));
+                if next.k() < state.k() {
+                    return Err(format!("k fell from {} to {}", state.k(), next.k()));
+                }
+            }
+            2 => {
+                let lp_amount = amount_for(g, state.supply);
+                let Some((a, b)) = amm_program::deposit_amounts(lp_amount, reserves, state.supply)
+                else {
+                    continue;
+                };
+                let (Some(reserve_a), Some(reserve_b), Some(supply)) = (
+                    state.reserve_a.checked_add(a),
+                    state.reserve_b.checked_add(b),
+                    state.supply.checked_add(lp_amount),
+                ) else {
+                    continue;
+                };
+                next = PoolBalances {
+                    reserve_a,
+                    reserve_b,
+                    supply,
+                };
+                g.note(format!("deposit {} A and {} B for {} LP", a, b, lp_amount));
+                share_kept(&state, &next)?;
+            }
+            _ => {
+                let lp_amount = g.range(0, state.supply - MINIMUM_LIQUIDITY);
+                let (a, b) = amm_program::withdraw_amounts(lp_amount, reserves, state.supply);
+                next.reserve_a -= a;
+                next.reserve_b -= b;
+                next.supply -= lp_amount;
+                g.note(format!("withdraw {} A and {} B for {} LP", a, b, lp_amount));
+                share_kept(&state, &next)?;
+            }
+        }
+        state = next;
+    }
+    Ok(())
+}
+
+// Neither reserve per LP token may shrink: reserve' / supply' >= reserve /
+// supply, compared without dividing
+fn share_kept(before: &PoolBalances, after: &PoolBalances) -> Result<(), String> {
+    let kept = |reserve: u64, reserve_after: u64| {
+        reserve_after as u128 * before.supply as u128 >= reserve as u128 * after.supply as u128
+    };
+    if kept(before.reserve_a, after.reserve_a) && kept(before.reserve_b, after.reserve_b) {
+        return Ok(());
+    }
+    Err(format!(
+        "an LP token's share went from {:?} to {:?}",
+        before, after
+    ))
+}
+
+// ----------------------------------------
+// Encoding Properties
+// ----------------------------------------
+
+fn roundtrip<T: fmt::Debug + PartialEq>(
+    instruction: T,
+    pack: impl Fn(&T) -> Vec<u8>,
+    unpack: fn(&[u8]) -> Result<T, InstructionError>,
+) -> Result<(), String> {
+    let data = pack(&instruction);
+    match unpack(&data) {
+        Ok(unpacked) if unpacked == instruction => Ok(()),
+        Ok(unpacked) => Err(format!(
+            "{:?} packed to {:02x?} and came back as {:?}",
+            instruction, data, unpacked
+        )),
+        Err(err) => Err(format!(
+            "{:?} packed to {:02x?}, which does not unpack: {}",
+            instruction, data, err
+        )),
+    }
+}
+
+fn system_instruction(g: &mut Gen) -> SystemInstruction {
+    match g.draw(3) {
+        0 => SystemInstruction::Transfer { lamports: g.u64() },
+        1 => SystemInstruction::CreateAccount {
+            lamports: g.u64(),
+            space: g.u64(),
+            owner: g.pubkey(),
+        },
+        2 => SystemInstruction::Assign { owner: g.pubkey() },
+        _ => SystemInstruction::Allocate { space: g.u64() },
+    }
+}
+
+fn token_instruction(g: &mut Gen) -> TokenInstruction {
+    match g.draw(7) {
+        0 => TokenInstruction::Transfer { amount: g.u64() },
+        1 => TokenInstruction::InitializeMint {
+            decimals: g.u8(),
+            mint_authority: g.pubkey(),
+            freeze_authority: g.option(Gen::pubkey),
+        },
+        2 => TokenInstruction::InitializeAccount,
+        3 => TokenInstruction::InitializeMultisig { m: g.u8() },
+        4 => TokenInstruction::SetAuthority {
+            authority_type: *g.pick(&[
+                AuthorityType::MintTokens,
+                AuthorityType::FreezeAccount,
+                AuthorityType::AccountOwner,
+                AuthorityType::CloseAccount,
+            ]),
+            new_authority: g.option(Gen::pubkey),
+        },
+        5 => TokenInstruction::MintTo { amount: g.u64() },
+        6 => TokenInstruction::Burn { amount: g.u64() },
+        _ => TokenInstruction::CloseAccount,
+    }
+}
+
+fn nft_instruction(g: &mut Gen) -> NftInstruction {
+    match g.draw(4) {
+        0 => NftInstruction::CreateMetadata(MetadataArgs {
+            name: g.string(nft_program::MAX_NAME_LENGTH),
+            symbol: g.string(nft_program::MAX_SYMBOL_LENGTH),
+            uri: g.string(nft_program::MAX_URI_LENGTH),
+            seller_fee_basis_points: g.u16(),
+            creators: g.vec(nft_program::MAX_CREATOR_LIMIT, |g| Creator {
+                address: g.pubkey(),
+                verified: g.bool(),
+                share: g.u8(),
+            }),
+            collection: g.option(Gen::pubkey),
+        }),
+        1 => NftInstruction::VerifyCollection,
+        2 => NftInstruction::CreateMasterEdition {
+            max_supply: g.option(Gen::u64),
+        },
+        3 => NftInstruction::PrintEdition,
+        _ => NftInstruction::UpdatePrimarySaleHappened,
+    }
+}
+
+// ----------------------------------------
+// Command Line
+// ----------------------------------------
+
+// simply_rust check
+// simply_rust check token-supply --seed 42 --cases 1000
+pub fn run(args: &[String]) -> Result<(), String> {
+    const USAGE: &str = "usage: simply_rust check [property] [--seed N] [--cases N]";
+    let mut name = None;
+    let mut seed = None;
+    let mut cases = DEFAULT_CASES;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            if name.replace(arg).is_some() {
+                return Err(USAGE.to_string());
+            }
+            continue;
+        }
+        let value = args.next().ok_or(USAGE)?;
+        match arg.as_str() {
+            "--seed" => {
+                seed = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| format!("--seed takes a number, got `{}`", value))?,
+                )
+            }
+            "--cases" => {
+                cases = match value.parse::<usize>() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("--cases takes a number above 0, got `{}`", value)),
+                }
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let properties: Vec<&Property> = match name {
+        Some(name) => {
+            let property = PROPERTIES
+                .iter()
+                .find(|property| property.name == name)
+                .ok_or_else(|| {
+                    let names: Vec<&str> =
+                        PROPERTIES.iter().map(|property| property.name).collect();
+                    format!(
+                        "unknown property `{}`; available: {}",
+                        name,
+                        names.join(", ")
+                    )
+                })?;
+            vec![property]
+        }
+        None => PROPERTIES.iter().collect(),
+    };
+    let seed = match seed {
+        Some(seed) => seed,
+        None => u64::from_le_bytes(
+            keypair::os_random_bytes().map_err(|err| format!("cannot pick a seed: {}", err))?,
+        ),
+    };
+
+    println!("Checking {} cases per property with seed {}\n", cases, seed);
+    let mut failures = 0;
+    for property in &properties {
+        match property.check(seed, cases) {
+            Ok(()) => println!("ok       {:<26} {}", property.name, property.description),
+            Err((case, counterexample)) => {
+                failures += 1;
+                println!(
+                    "FAILED   {:<26} {}\n         case {} of {} fails; {}",
+                    property.name,
+                    property.description,
+                    case,
+                    cases,
+                    counterexample.to_string().replace('\n', "\n         ")
+                );
+                println!(
+                    "         rerun with: simply_rust check {} --seed {}",
+                    property.name, seed
+                );
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(format!(
+            "{} of {} properties failed",
+            failures,
+            properties.len()
+        ));
+    }
+    println!("\nAll {} properties hold", properties.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // `Property::check` swaps the process-wide panic hook, so checks
+    // running on parallel test threads could leave the silent one behind
+    static CHECKING: Mutex<()> = Mutex::new(());
+
+    // Deliberately false, so the search finds a failure and shrinks it
+    fn failing(check: fn(&mut Gen) -> Result<(), String>) -> Counterexample {
+        let property = Property {
+            name: "false",
+            description: "does not hold",
+            check,
+        };
+        let _checking = CHECKING.lock().unwrap_or_else(|err| err.into_inner());
+        let (_, counterexample) = property
+            .check(0, DEFAULT_CASES)
+            .expect_err("the property is false");
+        counterexample
+    }
+
+    #[test]
+    fn shrinks_a_number_to_the_boundary() {
+        let counterexample = failing(|g| {
+            let n = g.range(0, 1_000_000);
+            g.note(format!("n = {}", n));
+            if n < 100 {
+                Ok(())
+            } else {
+                Err(format!("{} is not below 100", n))
+            }
+        });
+        assert_eq!(counterexample.choices, [100]);
+        assert_eq!(counterexample.notes, ["n = 100"]);
+        assert_eq!(counterexample.error, "100 is not below 100");
+    }
+
+    #[test]
+    fn shrinks_a_vec_to_one_element() {
+        let counterexample = failing(|g| {
+            let items = g.vec(20, |g| g.range(0, 1_000));
+            g.note(format!("{:?}", items));
+            match items.iter().find(|&&item| item >= 50) {
+                Some(item) => Err(format!("{} is not below 50", item)),
+                None => Ok(()),
+            }
+        });
+        // One more element, 50, then stop
+        assert_eq!(counterexample.choices, [1, 50, 0]);
+        assert_eq!(counterexample.notes, ["[50]"]);
+        assert_eq!(counterexample.error, "50 is not below 50");
+    }
+
+    #[test]
+    fn counts_a_panic_as_a_failure() {
+        let counterexample = failing(|g| {
+            let divisor = g.range(0, 10);
+            assert!(100 / divisor > 0);
+            Ok(())
+        });
+        assert_eq!(counterexample.choices, [0]);
+        assert_eq!(counterexample.error, "panicked: attempt to divide by zero");
+    }
+
+    #[test]
+    fn every_property_holds() {
+        let _checking = CHECKING.lock().unwrap_or_else(|err| err.into_inner());
+        for property in PROPERTIES {
+            if let Err((case, counterexample)) = property.check(42, 20) {
+                panic!(
+                    "{} failed on case {}; {}",
+                    property.name, case, counterexample
+                );
+            }
+        }
+    }
+}
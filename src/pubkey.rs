@@ -0,0 +1,292 @@
+// ========================================================================
+// PUBLIC KEYS AND BASE58
+// ========================================================================
+//
+// A Solana address is 32 raw bytes. The strings used throughout the cheat
+// sheet ("8ZgMzTUqx7Uq7LUwHEfUvVi3FKEG5BEWdv25TpNB2mKa") are those bytes
+// written in base58, an alphabet without the look-alike characters
+// 0, O, I and l.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::curve25519;
+use crate::hash::hashv;
+
+pub const PUBKEY_BYTES: usize = 32;
+
+// Limits on program derived address seeds (the bump counts as a seed)
+pub const MAX_SEEDS: usize = 16;
+pub const MAX_SEED_LEN: usize = 32;
+const PDA_MARKER: &[u8] = b"ProgramDerivedAddress";
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// Transparent, so a Pubkey has exactly the layout of its 32 bytes and
+// can be viewed in place in account data (see account_view.rs)
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct Pubkey(pub [u8; PUBKEY_BYTES]);
+
+// The System Program's address is 32 zero bytes ("1111...1111")
+pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey([0; PUBKEY_BYTES]);
+
+impl Pubkey {
+    pub const fn new(bytes: [u8; PUBKEY_BYTES]) -> Self {
+        Pubkey(bytes)
+    }
+
+    // Decode a base58 address at compile time, so well-known program ids
+    // can be constants:
+    //
+    //     pub const ID: Pubkey = Pubkey::from_str_const("Sysvar...");
+    //
+    // Panics (a compile error in a const) if the string is not a valid
+    // 32-byte base58 address.
+    pub const fn from_str_const(text: &str) -> Pubkey {
+        let text = text.as_bytes();
+        let mut bytes = [0u8; PUBKEY_BYTES];
+        let mut i = 0;
+        while i < text.len() {
+            let mut value = 0;
+            while value < 58 && BASE58_ALPHABET[value] != text[i] {
+                value += 1;
+            }
+            assert!(value < 58, "invalid base58 character");
+
+            // bytes = bytes * 58 + value, as a big-endian number
+            let mut carry = value as u32;
+            let mut j = PUBKEY_BYTES;
+            while j > 0 {
+                j -= 1;
+                carry += bytes[j] as u32 * 58;
+                bytes[j] = (carry & 0xff) as u8;
+                carry >>= 8;
+            }
+            assert!(carry == 0, "base58 address is longer than 32 bytes");
+            i += 1;
+        }
+
+        // Each leading '1' stands for a leading zero byte; the number
+        // itself must fill the rest exactly
+        let mut zeros = 0;
+        while zeros < text.len() && text[zeros] == b'1' {
+            zeros += 1;
+        }
+        let mut significant = 0;
+        while significant < PUBKEY_BYTES && bytes[significant] == 0 {
+            significant += 1;
+        }
+        assert!(
+            significant == zeros || (zeros == PUBKEY_BYTES && significant == PUBKEY_BYTES),
+            "base58 address does not decode to 32 bytes"
+        );
+        Pubkey(bytes)
+    }
+
+    // A fresh, distinct key for examples (like solana_sdk's new_unique)
+    pub fn new_unique() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(1);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mut bytes = [0u8; PUBKEY_BYTES];
+        bytes[24..].copy_from_slice(&n.to_be_bytes());
+        // Keep it distinguishable from the System Program's all-zero key
+        bytes[0] = 0xff;
+        Pubkey(bytes)
+    }
+
+    pub fn to_bytes(self) -> [u8; PUBKEY_BYTES] {
+        self.0
+    }
+
+    // Whether these bytes are a valid ed25519 public key
+    pub fn is_on_curve(&self) -> bool {
+        curve25519::is_on_curve(&self.0)
+    }
+
+    // ----------------------------------------
+    // Program Derived Addresses
+    // ----------------------------------------
+
+    // sha256(seeds.. || program_id || "ProgramDerivedAddress"), rejected if
+    // the result happens to be a valid public key: a PDA has no private
+    // key, only the program it is derived from can sign for it
+    pub fn create_program_address(
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        if seeds.len() > MAX_SEEDS {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+        if seeds.iter().any(|seed| seed.len() > MAX_SEED_LEN) {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+        let mut parts = seeds.to_vec();
+        parts.push(program_id.as_ref());
+        parts.push(PDA_MARKER);
+        let address = Pubkey(hashv(&parts).to_bytes());
+        if address.is_on_curve() {
+            return Err(PubkeyError::InvalidSeeds);
+        }
+        Ok(address)
+    }
+
+    // The first bump seed counting down from 255 that gives a valid PDA,
+    // which makes the address (and its bump) canonical for these seeds
+    pub fn try_find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Option<(Pubkey, u8)> {
+        (0..=u8::MAX).rev().find_map(|bump| {
+            let bump_seed = [bump];
+            let mut seeds_with_bump = seeds.to_vec();
+            seeds_with_bump.push(&bump_seed);
+            Pubkey::create_program_address(&seeds_with_bump, program_id)
+                .ok()
+                .map(|address| (address, bump))
+        })
+    }
+
+    // Panics if no bump works, which for 256 tries is practically
+    // impossible
+    pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::try_find_program_address(seeds, program_id)
+            .expect("unable to find a viable program address bump seed")
+    }
+}
+
+impl AsRef<[u8]> for Pubkey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", encode_base58(&self.0))
+    }
+}
+
+impl fmt::Debug for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PubkeyError {
+    // Too many seeds, or a seed longer than MAX_SEED_LEN
+    MaxSeedLengthExceeded,
+    // The seeds hash to a point on the curve
+    InvalidSeeds,
+}
+
+impl fmt::Display for PubkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PubkeyError::MaxSeedLengthExceeded => write!(
+                f,
+                "at most {} seeds of at most {} bytes each",
+                MAX_SEEDS, MAX_SEED_LEN
+            ),
+            PubkeyError::InvalidSeeds => {
+                write!(f, "seeds give an address on the ed25519 curve")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsePubkeyError {
+    InvalidCharacter(char),
+    WrongLength(usize),
+}
+
+impl fmt::Display for ParsePubkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsePubkeyError::InvalidCharacter(c) => {
+                write!(f, "`{}` is not a base58 character", c)
+            }
+            ParsePubkeyError::WrongLength(len) => {
+                write!(f, "public keys are 32 bytes, this decodes to {}", len)
+            }
+        }
+    }
+}
+
+impl FromStr for Pubkey {
+    type Err = ParsePubkeyError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_base58(text)?;
+        let bytes: [u8; PUBKEY_BYTES] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| ParsePubkeyError::WrongLength(bytes.len()))?;
+        Ok(Pubkey(bytes))
+    }
+}
+
+// ----------------------------------------
+// Base58
+// ----------------------------------------
+
+pub fn is_base58_char(c: char) -> bool {
+    c.is_ascii() && BASE58_ALPHABET.contains(&(c as u8))
+}
+
+// Treat the bytes as one big-endian number and repeatedly divide by 58.
+// Each leading zero byte becomes a leading '1'.
+pub fn encode_base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+
+    // Base58 digits, least significant first
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut out = String::with_capacity(zeros + digits.len());
+    out.extend(std::iter::repeat_n('1', zeros));
+    out.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| BASE58_ALPHABET[digit as usize] as char),
+    );
+    out
+}
+
+pub fn decode_base58(text: &str) -> Result<Vec<u8>, ParsePubkeyError> {
+    let zeros = text.chars().take_while(|&c| c == '1').count();
+
+    // Bytes, least significant first
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len());
+    for c in text.chars().skip(zeros) {
+        let value = BASE58_ALPHABET
+            .iter()
+            .position(|&a| c.is_ascii() && a == c as u8)
+            .ok_or(ParsePubkeyError::InvalidCharacter(c))?;
+        let mut carry = value as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.iter().rev());
+    Ok(out)
+}
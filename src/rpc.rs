@@ -0,0 +1,795 @@
+// ========================================================================
+// A LOCAL JSON-RPC SERVER
+// ========================================================================
+//
+// `simply_rust serve` answers a handful of Solana's JSON-RPC methods over
+// HTTP on localhost, backed by an in-process Bank, so client code has
+// something to talk to:
+//
+//     getBalance            getAccountInfo        getLatestBlockhash
+//     sendTransaction       simulateTransaction   getSignatureStatuses
+//     requestAirdrop
+//
+// Requests and responses have the same JSON shapes and error codes as a
+// real validator. Each transaction (airdrops included) lands in a slot of
+// its own, and everything is final straight away; there is only one node.
+//
+// The HTTP side is the bare minimum: one connection at a time, a POST
+// body in, a JSON body out, then the connection is closed.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use crate::amount::LAMPORTS_PER_SOL;
+use crate::bank::{Bank, TransactionError, TransactionOutcome};
+use crate::json::{self, Value};
+use crate::program::InstructionError;
+use crate::pubkey::{decode_base58, encode_base58, Pubkey};
+use crate::system_program;
+use crate::transaction::{
+    DemoSigner, Signature, Signer, Transaction, VersionedTransaction, PACKET_DATA_SIZE,
+};
+
+pub const DEFAULT_PORT: u16 = 8899;
+
+// The faucet behind requestAirdrop starts with 500 million SOL, like
+// solana-test-validator's
+const FAUCET_LAMPORTS: u64 = 500_000_000 * LAMPORTS_PER_SOL;
+
+const MAX_BODY_BYTES: usize = 64 * 1024;
+const MAX_HEADER_LINE_BYTES: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+// A slot this many slots old can no longer be rolled back
+const SLOTS_TO_FINALITY: u64 = 32;
+
+// ----------------------------------------
+// Error Codes
+// ----------------------------------------
+
+// JSON-RPC 2.0
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+// Solana's own
+pub const SEND_TRANSACTION_PREFLIGHT_FAILURE: i64 = -32002;
+pub const TRANSACTION_SIGNATURE_VERIFICATION_FAILURE: i64 = -32003;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        RpcError::new(INVALID_PARAMS, message)
+    }
+
+    fn to_json(&self) -> Value {
+        let mut fields = vec![
+            ("code", Value::Int(self.code as i128)),
+            ("message", Value::from(self.message.as_str())),
+        ];
+        if let Some(data) = &self.data {
+            fields.push(("data", data.clone()));
+        }
+        Value::object(fields)
+    }
+}
+
+// ----------------------------------------
+// Results as JSON
+// ----------------------------------------
+
+// Unit variants go by name and program errors as {"Custom": code}, the
+// way a validator reports them
+fn instruction_error_json(err: &InstructionError) -> Value {
+    match err.custom_code() {
+        Some(code) => Value::object([("Custom", Value::from(code as u64))]),
+        None => Value::from(format!("{:?}", err)),
+    }
+}
+
+pub fn transaction_error_json(err: &TransactionError) -> Value {
+    match err {
+        TransactionError::DuplicateInstruction(index) => {
+            Value::object([("DuplicateInstruction", Value::from(*index))])
+        }
+        TransactionError::InstructionError(index, err) => Value::object([(
+            "InstructionError",
+            Value::Array(vec![Value::from(*index), instruction_error_json(err)]),
+        )]),
+        other => Value::from(format!("{:?}", other)),
+    }
+}
+
+fn status_json(status: &Result<(), TransactionError>) -> Value {
+    match status {
+        Ok(()) => Value::object([("Ok", Value::Null)]),
+        Err(err) => Value::object([("Err", transaction_error_json(err))]),
+    }
+}
+
+fn err_json(status: &Result<(), TransactionError>) -> Value {
+    match status {
+        Ok(()) => Value::Null,
+        Err(err) => transaction_error_json(err),
+    }
+}
+
+// ----------------------------------------
+// Base64
+// ----------------------------------------
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Padding is required, as in the standard encoding
+pub fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return Err("length is not a multiple of 4".to_string());
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for (index, chunk) in text.chunks(4).enumerate() {
+        let last = index == text.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return Err("misplaced padding".to_string());
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .ok_or_else(|| format!("invalid character {:?}", c as char))?;
+            n = n << 6 | value as u32;
+        }
+        n <<= 6 * padding as u32;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Ok(out)
+}
+
+// ----------------------------------------
+// Parameters
+// ----------------------------------------
+
+// Positional parameter `index`, treating null as absent
+fn param(params: &[Value], index: usize) -> Option<&Value> {
+    params.get(index).filter(|value| !value.is_null())
+}
+
+fn string_param(params: &[Value], index: usize) -> Result<&str, RpcError> {
+    match param(params, index) {
+        Some(value) => value.as_str().ok_or_else(|| {
+            RpcError::invalid_params(format!("Invalid param {}: expected a string", index))
+        }),
+        None => Err(RpcError::invalid_params(format!(
+            "Invalid params: missing param {}",
+            index
+        ))),
+    }
+}
+
+fn pubkey_param(params: &[Value], index: usize) -> Result<Pubkey, RpcError> {
+    string_param(params, index)?
+        .parse()
+        .map_err(|err| RpcError::invalid_params(format!("Invalid param: {}", err)))
+}
+
+// The optional config object at `index`
+fn config_param(params: &[Value], index: usize) -> Result<Option<&Value>, RpcError> {
+    match param(params, index) {
+        Some(config @ Value::Object(_)) => Ok(Some(config)),
+        Some(_) => Err(RpcError::invalid_params(format!(
+            "Invalid param {}: expected a config object",
+            index
+        ))),
+        None => Ok(None),
+    }
+}
+
+fn config_bool(config: Option<&Value>, key: &str) -> Result<bool, RpcError> {
+    match config.and_then(|config| config.get(key)) {
+        None | Some(Value::Null) => Ok(false),
+        Some(value) => value.as_bool().ok_or_else(|| {
+            RpcError::invalid_params(format!("Invalid param: {} must be a boolean", key))
+        }),
+    }
+}
+
+fn config_str<'a>(config: Option<&'a Value>, key: &str) -> Result<Option<&'a str>, RpcError> {
+    match config.and_then(|config| config.get(key)) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_str().map(Some).ok_or_else(|| {
+            RpcError::invalid_params(format!("Invalid param: {} must be a string", key))
+        }),
+    }
+}
+
+// The first parameter of sendTransaction and simulateTransaction: a wire
+// format transaction, base58 (the default) or base64 encoded
+// Legacy or v0, no larger than a packet
+fn transaction_param(params: &[Value]) -> Result<VersionedTransaction, RpcError> {
+    let encoded = string_param(params, 0)?;
+    let config = config_param(params, 1)?;
+    let bytes = match config_str(config, "encoding")?.unwrap_or("base58") {
+        "base58" => decode_base58(encoded)
+            .map_err(|err| RpcError::invalid_params(format!("invalid base58 encoding: {}", err)))?,
+        "base64" => decode_base64(encoded)
+            .map_err(|err| RpcError::invalid_params(format!("invalid base64 encoding: {}", err)))?,
+        other => {
+            return Err(RpcError::invalid_params(format!(
+                "unsupported encoding: {}. Supported encodings: base58, base64",
+                other
+            )))
+        }
+    };
+    if bytes.len() > PACKET_DATA_SIZE {
+        return Err(RpcError::invalid_params(format!(
+            "transaction too large: {} bytes (max: {} bytes)",
+            bytes.len(),
+            PACKET_DATA_SIZE
+        )));
+    }
+    VersionedTransaction::deserialize(&bytes).map_err(|err| {
+        RpcError::invalid_params(format!("failed to deserialize transaction: {}", err))
+    })
+}
+
+// ----------------------------------------
+// The Server
+// ----------------------------------------
+
+pub struct RpcServer {
+    bank: Bank,
+    faucet: DemoSigner,
+}
+
+impl RpcServer {
+    // Fails if a loaded snapshot leaves no room to fund the faucet
+    pub fn new(mut bank: Bank) -> Result<Self, InstructionError> {
+        let faucet = DemoSigner::new("faucet");
+        bank.airdrop(&faucet.pubkey(), FAUCET_LAMPORTS)?;
+        Ok(RpcServer { bank, faucet })
+    }
+
+    pub fn faucet(&self) -> Pubkey {
+        self.faucet.pubkey()
+    }
+
+    // A request body in, a response body out. A batch (an array of
+    // requests) gets an array of responses. Notifications, requests
+    // without an id, get no response, so the result may be empty.
+    pub fn handle_body(&mut self, body: &str) -> String {
+        let request = match json::parse(body) {
+            Ok(request) => request,
+            Err(err) => {
+                let error = RpcError::new(PARSE_ERROR, format!("Parse error: {}", err));
+                return response(Value::Null, Err(error)).to_string();
+            }
+        };
+        match request {
+            Value::Array(requests) if !requests.is_empty() => {
+                let responses: Vec<Value> = requests
+                    .iter()
+                    .filter_map(|request| self.handle_request(request))
+                    .collect();
+                if responses.is_empty() {
+                    String::new()
+                } else {
+                    Value::Array(responses).to_string()
+                }
+            }
+            request => self
+                .handle_request(&request)
+                .map(|response| response.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn handle_request(&mut self, request: &Value) -> Option<Value> {
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str);
+        let params = request.get("params");
+
+        let result = match (request.get("jsonrpc").and_then(Value::as_str), method) {
+            (Some("2.0"), Some(method)) => match params {
+                None | Some(Value::Null) => self.call(method, &[]),
+                Some(Value::Array(params)) => self.call(method, params),
+                Some(_) => Err(RpcError::invalid_params(
+                    "Invalid params: expected an array",
+                )),
+            },
+            _ => Err(RpcError::new(INVALID_REQUEST, "Invalid request")),
+        };
+        match id {
+            Some(id) => Some(response(id, result)),
+            // A malformed request is answered even without an id
+            None if result.is_err() && method.is_none() => Some(response(Value::Null, result)),
+            None => None,
+        }
+    }
+
+    pub fn call(&mut self, method: &str, params: &[Value]) -> Result<Value, RpcError> {
+        match method {
+            "getBalance" => self.get_balance(params),
+            "getAccountInfo" => self.get_account_info(params),
+            "getLatestBlockhash" => Ok(self.get_latest_blockhash()),
+            "sendTransaction" => self.send_transaction(params),
+            "simulateTransaction" => self.simulate_transaction(params),
+            "getSignatureStatuses" => self.get_signature_statuses(params),
+            "requestAirdrop" => self.request_airdrop(params),
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, "Method not found")),
+        }
+    }
+
+    // Results that describe state come wrapped with the slot they are from
+    fn with_context(&self, value: Value) -> Value {
+        Value::object([
+            (
+                "context",
+                Value::object([("slot", Value::from(self.bank.slot()))]),
+            ),
+            ("value", value),
+        ])
+    }
+
+    // ["<pubkey>", {config}]
+    fn get_balance(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let pubkey = pubkey_param(params, 0)?;
+        config_param(params, 1)?;
+        Ok(self.with_context(Value::from(self.bank.get_balance(&pubkey))))
+    }
+
+    // ["<pubkey>", {"encoding": "base58" | "base64"}]
+    fn get_account_info(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let pubkey = pubkey_param(params, 0)?;
+        let config = config_param(params, 1)?;
+        let encoding = config_str(config, "encoding")?;
+        let account = match self.bank.get_account(&pubkey) {
+            Some(account) => account,
+            None => return Ok(self.with_context(Value::Null)),
+        };
+
+        let data = match encoding.unwrap_or("binary") {
+            // The legacy default: a bare base58 string
+            "binary" => Value::from(encode_base58(&account.data)),
+            "base58" => Value::Array(vec![
+                Value::from(encode_base58(&account.data)),
+                Value::from("base58"),
+            ]),
+            // There are no parsers here, so jsonParsed falls back to
+            // base64 as it does on a validator for unknown programs
+            "base64" | "jsonParsed" => Value::Array(vec![
+                Value::from(encode_base64(&account.data)),
+                Value::from("base64"),
+            ]),
+            other => {
+                return Err(RpcError::invalid_params(format!(
+                    "unsupported encoding: {}",
+                    other
+                )))
+            }
+        };
+        Ok(self.with_context(Value::object([
+            ("data", data),
+            ("executable", Value::from(account.executable)),
+            ("lamports", Value::from(account.lamports)),
+            ("owner", Value::from(account.owner.to_string())),
+            // Rent is not modelled; this is what rent-exempt accounts show
+            ("rentEpoch", Value::from(u64::MAX)),
+            ("space", Value::from(account.data.len())),
+        ])))
+    }
+
+    fn get_latest_blockhash(&self) -> Value {
+        self.with_context(self.latest_blockhash_json())
+    }
+
+    // One blockhash per slot, so a blockhash stays valid until the block
+    // height (here, the slot) has moved on by the queue's maximum age
+    fn latest_blockhash_json(&self) -> Value {
+        let last_valid = self.bank.slot() + self.bank.blockhash_queue().max_age() as u64;
+        Value::object([
+            (
+                "blockhash",
+                Value::from(self.bank.last_blockhash().to_string()),
+            ),
+            ("lastValidBlockHeight", Value::from(last_valid)),
+        ])
+    }
+
+    // ["<encoded transaction>", {"encoding", "skipPreflight"}]
+    //
+    // Unless preflight is skipped, the transaction is simulated first and
+    // refused if that fails. Once sent, the signature is returned whether
+    // or not it succeeds; getSignatureStatuses tells which.
+    fn send_transaction(&mut self, params: &[Value]) -> Result<Value, RpcError> {
+        let tx = transaction_param(params)?;
+        let config = config_param(params, 1)?;
+        if !tx.verify() {
+            return Err(RpcError::new(
+                TRANSACTION_SIGNATURE_VERIFICATION_FAILURE,
+                "Transaction signature verification failure",
+            ));
+        }
+        if !config_bool(config, "skipPreflight")? {
+            let simulation = self.bank.simulate_versioned_transaction(&tx, false);
+            if let Err(err) = &simulation.status {
+                return Err(RpcError {
+                    code: SEND_TRANSACTION_PREFLIGHT_FAILURE,
+                    message: format!("Transaction simulation failed: {}", err),
+                    data: Some(simulation_json(&simulation, None)),
+                });
+            }
+        }
+        self.bank.execute_versioned_transaction(&tx);
+        self.bank.advance_slots(1);
+        Ok(Value::from(tx.signature().to_string()))
+    }
+
+    // ["<encoded transaction>", {"encoding", "sigVerify", "replaceRecentBlockhash"}]
+    fn simulate_transaction(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let mut tx = transaction_param(params)?;
+        let config = config_param(params, 1)?;
+        let verify_signatures = config_bool(config, "sigVerify")?;
+        let replace_blockhash = config_bool(config, "replaceRecentBlockhash")?;
+        if verify_signatures && replace_blockhash {
+            return Err(RpcError::invalid_params(
+                "sigVerify may not be used with replaceRecentBlockhash",
+            ));
+        }
+
+        let replacement = if replace_blockhash {
+            tx.message.set_recent_blockhash(self.bank.last_blockhash());
+            Some(self.latest_blockhash_json())
+        } else {
+            None
+        };
+        let simulation = self
+            .bank
+            .simulate_versioned_transaction(&tx, verify_signatures);
+        Ok(self.with_context(simulation_json(&simulation, replacement)))
+    }
+
+    // [["<signature>", ...], {"searchTransactionHistory"}]
+    //
+    // Every transaction the bank ran is in its history, so the search
+    // flag changes nothing. Signatures never processed (or rejected
+    // before running) come back as null.
+    fn get_signature_statuses(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let signatures = param(params, 0).and_then(Value::as_array).ok_or_else(|| {
+            RpcError::invalid_params("Invalid params: expected an array of signatures")
+        })?;
+        config_param(params, 1)?;
+        if signatures.len() > MAX_SIGNATURE_STATUSES {
+            return Err(RpcError::invalid_params(format!(
+                "Too many inputs provided; max {}",
+                MAX_SIGNATURE_STATUSES
+            )));
+        }
+
+        let statuses = signatures
+            .iter()
+            .map(|signature| {
+                let signature: Signature = signature
+                    .as_str()
+                    .ok_or("expected a string")
+                    .and_then(|text| text.parse().map_err(|_| "Invalid signature"))
+                    .map_err(|err| RpcError::invalid_params(format!("Invalid param: {}", err)))?;
+                let record = match self.bank.history().get_transaction(&signature) {
+                    Some(record) => record,
+                    None => return Ok(Value::Null),
+                };
+
+                let confirmations = self.bank.slot() - record.slot;
+                let (confirmations, level) = match confirmations {
+                    n if n >= SLOTS_TO_FINALITY => (Value::Null, "finalized"),
+                    0 => (Value::from(0u64), "processed"),
+                    n => (Value::from(n), "confirmed"),
+                };
+                Ok(Value::object([
+                    ("slot", Value::from(record.slot)),
+                    ("confirmations", confirmations),
+                    ("err", err_json(&record.status)),
+                    ("status", status_json(&record.status)),
+                    ("confirmationStatus", Value::from(level)),
+                ]))
+            })
+            .collect::<Result<Vec<Value>, RpcError>>()?;
+        Ok(self.with_context(Value::Array(statuses)))
+    }
+
+    // ["<pubkey>", lamports]
+    //
+    // A real transfer from the faucet, so the airdrop has a signature that
+    // getSignatureStatuses knows about
+    fn request_airdrop(&mut self, params: &[Value]) -> Result<Value, RpcError> {
+        let pubkey = pubkey_param(params, 0)?;
+        let lamports = param(params, 1)
+            .and_then(Value::as_u64)
+            .ok_or_else(|| RpcError::invalid_params("Invalid param: lamports must be a u64"))?;
+
+        let tx = Transaction::new_signed_with_payer(
+            &[system_program::transfer(
+                &self.faucet.pubkey(),
+                &pubkey,
+                lamports,
+            )],
+            Some(&self.faucet.pubkey()),
+            &[&self.faucet],
+            self.bank.last_blockhash(),
+        );
+        let outcome = self.bank.execute_transaction(&tx);
+        self.bank.advance_slots(1);
+        match outcome.status {
+            Ok(()) => Ok(Value::from(tx.signature().to_string())),
+            Err(err) => Err(RpcError::new(
+                INTERNAL_ERROR,
+                format!("airdrop request failed: {}", err),
+            )),
+        }
+    }
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    let (key, value) = match result {
+        Ok(value) => ("result", value),
+        Err(err) => ("error", err.to_json()),
+    };
+    Value::object([("jsonrpc", Value::from("2.0")), (key, value), ("id", id)])
+}
+
+// What simulateTransaction returns, and what a failed preflight carries
+// as its error data
+fn simulation_json(simulation: &TransactionOutcome, replacement: Option<Value>) -> Value {
+    let mut fields = vec![
+        ("err", err_json(&simulation.status)),
+        (
+            "logs",
+            Value::Array(
+                simulation
+                    .logs
+                    .iter()
+                    .map(|line| Value::from(line.as_str()))
+                    .collect(),
+            ),
+        ),
+        ("accounts", Value::Null),
+        (
+            "unitsConsumed",
+            Value::from(simulation.compute_units_consumed),
+        ),
+        ("returnData", Value::Null),
+    ];
+    if let Some(replacement) = replacement {
+        fields.push(("replacementBlockhash", replacement));
+    }
+    Value::object(fields)
+}
+
+// ----------------------------------------
+// HTTP
+// ----------------------------------------
+
+// Serve requests until the process is stopped
+pub fn serve(server: &mut RpcServer, port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("JSON-RPC listening on http://{}", listener.local_addr()?);
+    println!("Faucet {}", server.faucet());
+    for stream in listener.incoming() {
+        // A broken connection only loses that one request
+        if let Err(err) = stream.and_then(|stream| handle_connection(server, stream)) {
+            eprintln!("connection error: {}", err);
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(server: &mut RpcServer, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+
+    let Some((request_line, content_length)) = read_head(&mut reader)? else {
+        return respond(&stream, "431 Request Header Fields Too Large", "");
+    };
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/health")) => ("200 OK", "ok".to_string()),
+        (Some("POST"), _) => match content_length {
+            Some(length) if length <= MAX_BODY_BYTES => {
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body)?;
+                (
+                    "200 OK",
+                    server.handle_body(&String::from_utf8_lossy(&body)),
+                )
+            }
+            Some(_) => ("413 Payload Too Large", String::new()),
+            None => ("411 Length Required", String::new()),
+        },
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+    respond(&stream, status, &body)
+}
+
+// The request line and Content-Length. None if a line is longer than
+// MAX_HEADER_LINE_BYTES or there are more than MAX_HEADERS headers, so a
+// client cannot make the server buffer an endless head.
+fn read_head(reader: &mut impl BufRead) -> io::Result<Option<(String, Option<usize>)>> {
+    // False if the line was cut off at the limit
+    let mut read_line = |line: &mut String| -> io::Result<bool> {
+        let limit = MAX_HEADER_LINE_BYTES as u64;
+        let read = reader.by_ref().take(limit).read_line(line)?;
+        Ok(read < MAX_HEADER_LINE_BYTES || line.ends_with('\n'))
+    };
+
+    let mut request_line = String::new();
+    if !read_line(&mut request_line)? {
+        return Ok(None);
+    }
+    let mut content_length = None;
+    for _ in 0..=MAX_HEADERS {
+        let mut line = String::new();
+        if !read_line(&mut line)? {
+            return Ok(None);
+        }
+        if line.trim_end().is_empty() {
+            return Ok(Some((request_line, content_length)));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn respond(mut stream: &TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo rpc`
+// ----------------------------------------
+
+// The requests a client would send, handled in-process with no socket
+pub fn demo() -> Result<(), String> {
+    let mut server = RpcServer::new(Bank::new()).map_err(|err| err.to_string())?;
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+
+    let mut request = |method: &str, params: Value| -> Value {
+        let body = Value::object([
+            ("jsonrpc", Value::from("2.0")),
+            ("id", Value::from(1u64)),
+            ("method", Value::from(method)),
+            ("params", params),
+        ])
+        .to_string();
+        println!("\n--> {}", body);
+        let response = server.handle_body(&body);
+        println!("<-- {}", response);
+        json::parse(&response).unwrap_or(Value::Null)
+    };
+    let result = |response: &Value, path: &[&str]| -> Result<String, String> {
+        let mut value = response.get("result");
+        for key in path {
+            value = value.and_then(|value| value.get(key));
+        }
+        value
+            .and_then(Value::as_str)
+            .map(String::from)
+            .ok_or_else(|| format!("unexpected response: {}", response))
+    };
+
+    request(
+        "requestAirdrop",
+        Value::Array(vec![
+            Value::from(alice.pubkey().to_string()),
+            Value::from(2 * LAMPORTS_PER_SOL),
+        ]),
+    );
+    let response = request("getLatestBlockhash", Value::Array(Vec::new()));
+    let blockhash = result(&response, &["value", "blockhash"])?
+        .parse()
+        .map_err(|err| format!("bad blockhash: {}", err))?;
+
+    // Alice signs a payment to bob and sends it base64 encoded
+    let payment = |lamports: u64| {
+        let tx = Transaction::new_signed_with_payer(
+            &[system_program::transfer(
+                &alice.pubkey(),
+                &bob.pubkey(),
+                lamports,
+            )],
+            Some(&alice.pubkey()),
+            &[&alice],
+            blockhash,
+        );
+        Value::from(encode_base64(&tx.serialize()))
+    };
+    let base64 = Value::object([("encoding", Value::from("base64"))]);
+    let response = request(
+        "sendTransaction",
+        Value::Array(vec![payment(LAMPORTS_PER_SOL), base64.clone()]),
+    );
+    let signature = result(&response, &[])?;
+    request(
+        "getSignatureStatuses",
+        Value::Array(vec![Value::Array(vec![Value::from(signature)])]),
+    );
+    // The same transaction again: preflight knows it already ran
+    request(
+        "sendTransaction",
+        Value::Array(vec![payment(LAMPORTS_PER_SOL), base64.clone()]),
+    );
+    request(
+        "getBalance",
+        Value::Array(vec![Value::from(bob.pubkey().to_string())]),
+    );
+    request(
+        "getAccountInfo",
+        Value::Array(vec![
+            Value::from(system_program::ID.to_string()),
+            base64.clone(),
+        ]),
+    );
+
+    // Too much: simulation shows the failure, and preflight refuses it
+    request(
+        "simulateTransaction",
+        Value::Array(vec![payment(5 * LAMPORTS_PER_SOL), base64.clone()]),
+    );
+    request(
+        "sendTransaction",
+        Value::Array(vec![payment(5 * LAMPORTS_PER_SOL), base64]),
+    );
+    request("getBlock", Value::Array(Vec::new()));
+    Ok(())
+}
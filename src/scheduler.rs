@@ -0,0 +1,473 @@
+// ========================================================================
+// PARALLEL TRANSACTION SCHEDULING
+// ========================================================================
+//
+// validate_transactions in main.rs walks its slice one transaction at a
+// time, and so does Bank::execute_versioned_transaction. Most transactions
+// in a block have nothing to do with each other, though, and a message
+// says up front which accounts it writes and which it only reads (the
+// header's writable/readonly split). That is enough to tell, without
+// running anything, whether two transactions can run side by side:
+//
+//     A writes X, B reads X      conflict: B must see what A wrote
+//     A reads X,  B writes X     conflict: A must not see what B wrote
+//     A reads X,  B reads X      no conflict
+//
+// `schedule` puts each transaction of a batch in the first wave after
+// every earlier transaction it conflicts with. Nothing within a wave
+// conflicts, so Bank::execute_batch runs a whole wave on a pool of
+// threads against the bank as it stood when the wave started, then
+// commits the results in batch order before starting the next wave.
+// Conflicting transactions still happen in batch order, so the bank ends
+// up exactly as if the batch had run one transaction at a time.
+//
+// `simply_rust demo scheduler` walks through a small batch, and
+// `simply_rust bench scheduler` measures the speedup on a large one.
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::amount::{Lamports, LAMPORTS_PER_SOL};
+use crate::bank::{self, Bank, TransactionOutcome};
+use crate::pubkey::{Pubkey, SYSTEM_PROGRAM_ID};
+use crate::system_program;
+use crate::transaction::{DemoSigner, LoadedMessage, Signer, Transaction, VersionedTransaction};
+
+// ----------------------------------------
+// Account Locks
+// ----------------------------------------
+
+// The accounts a transaction writes and the ones it only reads. Address
+// lookup tables count as read, since the addresses a v0 message loads
+// depend on them.
+//
+// A batch's locks are all worked out before any of it runs. That holds
+// up because of the lookup table warmup rule: addresses a table gains
+// only load from the next slot on, and a batch runs within one slot, so
+// an earlier transaction cannot change what a later one's lookups load.
+// Closing a table only makes later lookups fail. Bank::execute_batch
+// asserts that every transaction ran with the locks it was scheduled by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionLocks {
+    pub writable: Vec<Pubkey>,
+    pub readonly: Vec<Pubkey>,
+}
+
+impl TransactionLocks {
+    // None if the accounts cannot be worked out before the transaction
+    // runs (a malformed message or a bad lookup). The bank will reject it,
+    // but it still has to be rejected at the right point in the batch.
+    pub fn new(bank: &Bank, tx: &VersionedTransaction) -> Option<Self> {
+        bank::sanitize(tx).ok()?;
+        let message = bank.resolve_message(&tx.message).ok()?;
+        Some(TransactionLocks::for_message(tx, &message))
+    }
+
+    // The locks of `tx` with its lookups resolved to `message`
+    pub fn for_message(tx: &VersionedTransaction, message: &LoadedMessage) -> Self {
+        let mut locks = TransactionLocks {
+            writable: Vec::new(),
+            readonly: Vec::new(),
+        };
+        for (i, key) in message.account_keys().iter().enumerate() {
+            if message.is_writable(i) {
+                locks.writable.push(*key);
+            } else {
+                locks.readonly.push(*key);
+            }
+        }
+        let tables = tx.message.address_table_lookups().iter();
+        locks
+            .readonly
+            .extend(tables.map(|lookup| lookup.account_key));
+        locks
+    }
+}
+
+// ----------------------------------------
+// Scheduling
+// ----------------------------------------
+
+// Split a batch into waves of transaction indexes, each in batch order.
+// Transactions without locks get a wave to themselves, after everything
+// before them and before everything after them.
+pub fn schedule(locks: &[Option<TransactionLocks>]) -> Vec<Vec<usize>> {
+    let mut waves: Vec<Vec<usize>> = Vec::new();
+    // The last wave that wrote and that read each account
+    let mut last_write: HashMap<Pubkey, usize> = HashMap::new();
+    let mut last_read: HashMap<Pubkey, usize> = HashMap::new();
+    // No transaction may go before the last one that ran alone
+    let mut floor = 0;
+
+    for (i, locks) in locks.iter().enumerate() {
+        let wave = match locks {
+            Some(locks) => {
+                let after =
+                    |waves: &HashMap<Pubkey, usize>, key| waves.get(key).map_or(0, |w| w + 1);
+                let reads = locks.readonly.iter().map(|key| after(&last_write, key));
+                let writes = locks
+                    .writable
+                    .iter()
+                    .map(|key| after(&last_write, key).max(after(&last_read, key)));
+                let wave = reads.chain(writes).fold(floor, usize::max);
+
+                for key in &locks.writable {
+                    last_write.insert(*key, wave);
+                }
+                for key in &locks.readonly {
+                    let last = last_read.entry(*key).or_insert(wave);
+                    *last = (*last).max(wave);
+                }
+                wave
+            }
+            None => {
+                floor = waves.len() + 1;
+                waves.len()
+            }
+        };
+        if wave == waves.len() {
+            waves.push(Vec::new());
+        }
+        waves[wave].push(i);
+    }
+    waves
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo scheduler`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let names = ["alice", "bob", "carol", "dave", "erin", "frank", "grace"];
+    let signers: Vec<DemoSigner> = names.iter().map(|name| DemoSigner::new(name)).collect();
+    let signer = |name: &str| &signers[names.iter().position(|n| *n == name).unwrap()];
+    let name_of = |key: &Pubkey| {
+        if *key == SYSTEM_PROGRAM_ID {
+            return "system program";
+        }
+        names
+            .iter()
+            .zip(&signers)
+            .find(|(_, signer)| signer.pubkey() == *key)
+            .map_or("?", |(name, _)| name)
+    };
+    let setup = || -> Result<Bank, String> {
+        let mut bank = Bank::new();
+        for name in ["alice", "carol", "frank"] {
+            bank.airdrop(&signer(name).pubkey(), 10 * LAMPORTS_PER_SOL)
+                .map_err(|err| err.to_string())?;
+        }
+        bank.airdrop(&signer("erin").pubkey(), LAMPORTS_PER_SOL / 100)
+            .map_err(|err| err.to_string())?;
+        Ok(bank)
+    };
+
+    let mut bank = setup()?;
+    let payments = [
+        ("alice", "bob", "2"),
+        ("carol", "dave", "1"),
+        // bob and dave only have something to pay with once the first
+        // two have been committed
+        ("bob", "erin", "1"),
+        ("dave", "alice", "0.5"),
+        ("frank", "grace", "3"),
+        // More than erin will have: fails, but the fee is still charged
+        ("erin", "frank", "5"),
+    ];
+    let txs = payments
+        .iter()
+        .map(|&(from, to, sol)| {
+            let from = signer(from);
+            let lamports = sol.parse::<Lamports>().map_err(|err| err.to_string())?;
+            Ok(VersionedTransaction::from(
+                Transaction::new_signed_with_payer(
+                    &[system_program::transfer(
+                        &from.pubkey(),
+                        &signer(to).pubkey(),
+                        lamports.0,
+                    )],
+                    Some(&from.pubkey()),
+                    &[from],
+                    bank.last_blockhash(),
+                ),
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    println!(
+        "A batch of {} transfers and the accounts they lock:",
+        txs.len()
+    );
+    let locks: Vec<_> = txs
+        .iter()
+        .map(|tx| TransactionLocks::new(&bank, tx))
+        .collect();
+    for (i, ((from, to, sol), locks)) in payments.iter().zip(&locks).enumerate() {
+        let locks = locks.as_ref().ok_or("every transfer has locks")?;
+        let list = |keys: &[Pubkey]| keys.iter().map(name_of).collect::<Vec<_>>().join(", ");
+        println!(
+            "  #{} {:<5} -> {:<5} {:>3} SOL   writes {}; reads {}",
+            i,
+            from,
+            to,
+            sol,
+            list(&locks.writable),
+            list(&locks.readonly)
+        );
+    }
+
+    // Everything reads the system program, which conflicts with nothing
+    println!("\nWaves (nothing within a wave writes what another one uses):");
+    for (n, wave) in schedule(&locks).iter().enumerate() {
+        let members: Vec<_> = wave.iter().map(|i| format!("#{}", i)).collect();
+        println!("  {}: {}", n + 1, members.join(" "));
+    }
+
+    let outcomes = bank.execute_batch(&txs, 4);
+    println!("\nRun with 4 threads:");
+    for (i, outcome) in outcomes.iter().enumerate() {
+        match &outcome.status {
+            Ok(()) => println!("  #{} ok", i),
+            Err(err) => println!("  #{} {}", i, err),
+        }
+    }
+
+    let mut sequential = setup()?;
+    let expected: Vec<_> = txs
+        .iter()
+        .map(|tx| sequential.execute_versioned_transaction(tx))
+        .collect();
+    println!(
+        "\nSame outcomes, accounts and history as one at a time: {}",
+        same_result(&bank, &outcomes, &sequential, &expected)
+    );
+    println!("Balances:");
+    for name in names {
+        println!(
+            "  {:<5} {:>12} lamports",
+            name,
+            bank.get_balance(&signer(name).pubkey())
+        );
+    }
+    Ok(())
+}
+
+fn same_result(
+    bank: &Bank,
+    outcomes: &[TransactionOutcome],
+    expected_bank: &Bank,
+    expected: &[TransactionOutcome],
+) -> bool {
+    outcomes == expected
+        && bank.accounts() == expected_bank.accounts()
+        && bank.history() == expected_bank.history()
+        && bank.collected_fees() == expected_bank.collected_fees()
+}
+
+// ----------------------------------------
+// Benchmark: `simply_rust bench scheduler`
+// ----------------------------------------
+
+const BENCH_PAYERS: usize = 64;
+const BENCH_TRANSACTIONS: usize = 1024;
+
+pub fn bench() -> Result<(), String> {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    println!(
+        "{} transfers from {} payers, {} core{} available",
+        BENCH_TRANSACTIONS,
+        BENCH_PAYERS,
+        cores,
+        if cores == 1 { "" } else { "s" }
+    );
+    if cfg!(debug_assertions) {
+        println!("(a debug build: run with `cargo run --release` for real numbers)");
+    }
+
+    let payers: Vec<DemoSigner> = (0..BENCH_PAYERS)
+        .map(|i| DemoSigner::new(&format!("payer {}", i)))
+        .collect();
+    let setup = || -> Result<Bank, String> {
+        let mut bank = Bank::new();
+        for payer in &payers {
+            bank.airdrop(&payer.pubkey(), 100 * LAMPORTS_PER_SOL)
+                .map_err(|err| err.to_string())?;
+        }
+        Ok(bank)
+    };
+    let blockhash = setup()?.last_blockhash();
+    let treasury = Pubkey::new_unique();
+    // Every `shared`th transfer goes to the same treasury account
+    let workload = |shared: usize| -> Vec<VersionedTransaction> {
+        (0..BENCH_TRANSACTIONS)
+            .map(|i| {
+                let payer = &payers[i % BENCH_PAYERS];
+                let to = if i % shared == 0 {
+                    treasury
+                } else {
+                    Pubkey::new_unique()
+                };
+                VersionedTransaction::from(Transaction::new_signed_with_payer(
+                    &[system_program::transfer(
+                        &payer.pubkey(),
+                        &to,
+                        1_000_000 + i as u64,
+                    )],
+                    Some(&payer.pubkey()),
+                    &[payer],
+                    blockhash,
+                ))
+            })
+            .collect()
+    };
+
+    let mut threads = vec![1];
+    while *threads.last().unwrap() < cores.max(4) {
+        threads.push(threads.last().unwrap() * 2);
+    }
+    for (title, shared) in [
+        ("Independent transfers", usize::MAX),
+        ("Every 8th to one treasury", 8),
+    ] {
+        let txs = workload(shared);
+        let bank = setup()?;
+        let locks: Vec<_> = txs
+            .iter()
+            .map(|tx| TransactionLocks::new(&bank, tx))
+            .collect();
+        let waves = schedule(&locks);
+        println!(
+            "\n{}: {} waves, the largest {} transactions",
+            title,
+            waves.len(),
+            waves.iter().map(Vec::len).max().unwrap_or(0)
+        );
+
+        let mut sequential = setup()?;
+        let start = Instant::now();
+        let expected: Vec<_> = txs
+            .iter()
+            .map(|tx| sequential.execute_versioned_transaction(tx))
+            .collect();
+        let baseline = start.elapsed();
+        print_timing("one at a time", baseline, baseline);
+
+        for &count in &threads {
+            let mut bank = setup()?;
+            let start = Instant::now();
+            let outcomes = bank.execute_batch(&txs, count);
+            let elapsed = start.elapsed();
+            if !same_result(&bank, &outcomes, &sequential, &expected) {
+                return Err(format!(
+                    "{} threads left the bank different from sequential execution",
+                    count
+                ));
+            }
+            let label = format!("{} thread{}", count, if count == 1 { "" } else { "s" });
+            print_timing(&label, elapsed, baseline);
+        }
+    }
+    Ok(())
+}
+
+fn print_timing(label: &str, elapsed: Duration, baseline: Duration) {
+    println!(
+        "  {:<14} {:>8.1} ms  {:>7.0} tx/s  {:.2}x",
+        label,
+        elapsed.as_secs_f64() * 1000.0,
+        BENCH_TRANSACTIONS as f64 / elapsed.as_secs_f64(),
+        baseline.as_secs_f64() / elapsed.as_secs_f64()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn funded_bank() -> Bank {
+        let mut bank = Bank::new();
+        for name in ["alice", "carol", "frank"] {
+            bank.airdrop(&DemoSigner::new(name).pubkey(), 10 * LAMPORTS_PER_SOL)
+                .unwrap();
+        }
+        bank
+    }
+
+    fn transfers(bank: &Bank, payments: &[(&str, &str, u64)]) -> Vec<VersionedTransaction> {
+        payments
+            .iter()
+            .map(|&(from, to, lamports)| {
+                let from = DemoSigner::new(from);
+                VersionedTransaction::from(Transaction::new_signed_with_payer(
+                    &[system_program::transfer(
+                        &from.pubkey(),
+                        &DemoSigner::new(to).pubkey(),
+                        lamports,
+                    )],
+                    Some(&from.pubkey()),
+                    &[&from],
+                    bank.last_blockhash(),
+                ))
+            })
+            .collect()
+    }
+
+    // Runs the batch in waves and one at a time from the same start, and
+    // checks both end with the same statuses and accounts
+    fn check_matches_sequential(payments: &[(&str, &str, u64)], waves: usize) {
+        let mut parallel = funded_bank();
+        let mut sequential = funded_bank();
+        let txs = transfers(&parallel, payments);
+        let locks: Vec<_> = txs
+            .iter()
+            .map(|tx| TransactionLocks::new(&parallel, tx))
+            .collect();
+        assert_eq!(schedule(&locks).len(), waves);
+
+        let outcomes = parallel.execute_batch(&txs, 4);
+        let expected: Vec<_> = txs
+            .iter()
+            .map(|tx| sequential.execute_versioned_transaction(tx))
+            .collect();
+        let statuses = |outcomes: &[TransactionOutcome]| {
+            outcomes
+                .iter()
+                .map(|outcome| outcome.status.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(statuses(&outcomes), statuses(&expected));
+        assert_eq!(parallel.accounts(), sequential.accounts());
+        assert!(same_result(&parallel, &outcomes, &sequential, &expected));
+    }
+
+    #[test]
+    fn independent_transfers_share_a_wave() {
+        check_matches_sequential(
+            &[
+                ("alice", "bob", 2 * LAMPORTS_PER_SOL),
+                ("carol", "dave", LAMPORTS_PER_SOL),
+                ("frank", "grace", 3 * LAMPORTS_PER_SOL),
+            ],
+            1,
+        );
+    }
+
+    #[test]
+    fn conflicting_transfers_keep_batch_order() {
+        check_matches_sequential(
+            &[
+                ("alice", "bob", 2 * LAMPORTS_PER_SOL),
+                ("carol", "dave", LAMPORTS_PER_SOL),
+                // Only has funds once the first wave is committed
+                ("bob", "erin", LAMPORTS_PER_SOL),
+                ("dave", "alice", LAMPORTS_PER_SOL / 2),
+                // More than erin will have: fails after paying the fee
+                ("erin", "frank", 5 * LAMPORTS_PER_SOL),
+                // Never funded at all
+                ("grace", "alice", 1),
+            ],
+            3,
+        );
+    }
+}
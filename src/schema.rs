@@ -0,0 +1,802 @@
+// ========================================================================
+// ACCOUNT LAYOUT SCHEMAS
+// ========================================================================
+//
+// Instead of decoding account data with hand-written offsets like
+// `account_data[8]`, a schema describes each field once (name, type and
+// optionally a fixed offset) and a generic decoder turns any byte buffer
+// into a tree of named values.
+//
+// Schemas can also be written in a small text format, so unknown program
+// accounts can be inspected without writing any Rust:
+//
+//     # SOL/USDC pair account
+//     schema TokenPair
+//     kind: u32
+//     flags: u32 @ 4
+//     name: string<u32>
+//     fees: struct {
+//         trade_bps: u16
+//         owner_bps: u16
+//     }
+//
+// Supported types:
+//   u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 bool
+//   bytes[N]          fixed number of raw bytes
+//   bytes<u32>        raw bytes prefixed by a u8/u16/u32/u64 length
+//   string<u32>       UTF-8 string prefixed by a u8/u16/u32/u64 length
+//   array<T, N>       N values of type T
+//   vec<T, u32>       values of type T prefixed by a length
+//   struct { ... }    nested fields (one per line, closed by `}`)
+//
+// `@ N` places a field at byte N of its enclosing struct; fields without
+// an offset follow the previous field. All integers are little-endian.
+
+use std::cell::Cell;
+use std::fmt;
+
+// ----------------------------------------
+// Schema Description
+// ----------------------------------------
+
+// Width of the length prefix in front of strings, byte blobs and vectors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthPrefix {
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl LengthPrefix {
+    fn size(self) -> usize {
+        match self {
+            LengthPrefix::U8 => 1,
+            LengthPrefix::U16 => 2,
+            LengthPrefix::U32 => 4,
+            LengthPrefix::U64 => 8,
+        }
+    }
+
+    fn parse(name: &str) -> Option<LengthPrefix> {
+        match name {
+            "u8" => Some(LengthPrefix::U8),
+            "u16" => Some(LengthPrefix::U16),
+            "u32" => Some(LengthPrefix::U32),
+            "u64" => Some(LengthPrefix::U64),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Bool,
+    FixedBytes(usize),
+    Bytes(LengthPrefix),
+    String(LengthPrefix),
+    Array(Box<FieldType>, usize),
+    Vec(Box<FieldType>, LengthPrefix),
+    Struct(Vec<Field>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub ty: FieldType,
+    // Byte offset relative to the enclosing struct; None means "right
+    // after the previous field"
+    pub offset: Option<usize>,
+}
+
+impl Field {
+    pub fn new(name: &str, ty: FieldType) -> Self {
+        Field {
+            name: name.to_string(),
+            ty,
+            offset: None,
+        }
+    }
+
+    pub fn at(name: &str, ty: FieldType, offset: usize) -> Self {
+        Field {
+            name: name.to_string(),
+            ty,
+            offset: Some(offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+impl Schema {
+    pub fn new(name: &str, fields: Vec<Field>) -> Self {
+        Schema {
+            name: name.to_string(),
+            fields,
+        }
+    }
+
+    // Decode a buffer into a tree of fields
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<DecodedField>, DecodeError> {
+        let item_budget = data.len() + field_count(&self.fields);
+        let decoder = Decoder {
+            data,
+            item_budget,
+            items_left: Cell::new(item_budget),
+        };
+        let (fields, _) = decoder.decode_struct(&self.fields, 0, "")?;
+        Ok(fields)
+    }
+
+    // Load a schema from the text format described at the top of this file
+    pub fn parse(text: &str) -> Result<Schema, SchemaError> {
+        Parser::new(text).parse()
+    }
+
+    pub fn from_file(path: &str) -> Result<Schema, SchemaError> {
+        let text = std::fs::read_to_string(path).map_err(|err| SchemaError {
+            line: 0,
+            message: format!("cannot read {}: {}", path, err),
+        })?;
+        Schema::parse(&text)
+    }
+}
+
+// Every field, counting those of nested structs and list items once
+fn field_count(fields: &[Field]) -> usize {
+    fn count(ty: &FieldType) -> usize {
+        match ty {
+            FieldType::Array(item, _) | FieldType::Vec(item, _) => count(item),
+            FieldType::Struct(fields) => field_count(fields),
+            _ => 0,
+        }
+    }
+    fields.iter().map(|field| 1 + count(&field.ty)).sum()
+}
+
+// ----------------------------------------
+// Decoded Values
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unsigned(u128),
+    Signed(i128),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    String(String),
+    List(Vec<DecodedField>),
+    Struct(Vec<DecodedField>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedField {
+    pub name: String,
+    // Absolute byte offset into the decoded buffer
+    pub offset: usize,
+    pub len: usize,
+    pub value: Value,
+}
+
+impl DecodedField {
+    // Look up a nested field by dotted path, e.g. "fees.trade_bps"
+    pub fn find<'a>(fields: &'a [DecodedField], path: &str) -> Option<&'a DecodedField> {
+        let (head, rest) = match path.split_once('.') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (path, None),
+        };
+        let field = fields.iter().find(|field| field.name == head)?;
+        match (rest, &field.value) {
+            (None, _) => Some(field),
+            (Some(rest), Value::Struct(children)) | (Some(rest), Value::List(children)) => {
+                DecodedField::find(children, rest)
+            }
+            (Some(_), _) => None,
+        }
+    }
+
+    fn write_tree(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        let location = format!("[{}..{}]", self.offset, self.offset + self.len);
+        match &self.value {
+            Value::Struct(children) | Value::List(children) => {
+                writeln!(f, "{}{} {}", indent, self.name, location)?;
+                for child in children {
+                    child.write_tree(f, depth + 1)?;
+                }
+                Ok(())
+            }
+            value => writeln!(f, "{}{} {} = {}", indent, self.name, location, value),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Unsigned(n) => write!(f, "{}", n),
+            Value::Signed(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Bytes(bytes) => {
+                write!(f, "0x")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+            Value::String(s) => write!(f, "{:?}", s),
+            Value::List(items) | Value::Struct(items) => write!(f, "<{} fields>", items.len()),
+        }
+    }
+}
+
+// Prints a decoded field tree with byte ranges, one field per line
+pub struct FieldTree<'a>(pub &'a [DecodedField]);
+
+impl fmt::Display for FieldTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for field in self.0 {
+            field.write_tree(f, 0)?;
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------
+// Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    UnexpectedEnd { needed: usize, available: usize },
+    InvalidBool(u8),
+    InvalidUtf8,
+    LengthTooLarge(u64),
+    // Lists holding more items in total than the decoder's budget
+    TooManyItems(usize),
+    // An offset past usize::MAX
+    OffsetOverflow,
+}
+
+// Names the field that failed (as a dotted path) and where it started
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub field: String,
+    pub offset: usize,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "field `{}` at byte {}: ", self.field, self.offset)?;
+        match &self.kind {
+            DecodeErrorKind::UnexpectedEnd { needed, available } => {
+                write!(f, "needs {} bytes but only {} available", needed, available)
+            }
+            DecodeErrorKind::InvalidBool(byte) => write!(f, "invalid bool byte {}", byte),
+            DecodeErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            DecodeErrorKind::LengthTooLarge(len) => write!(f, "length prefix {} is too large", len),
+            DecodeErrorKind::TooManyItems(budget) => {
+                write!(f, "lists hold more than {} items in total", budget)
+            }
+            DecodeErrorKind::OffsetOverflow => write!(f, "offset overflows"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+// ----------------------------------------
+// Decoder
+// ----------------------------------------
+
+// Items that take up no bytes never run out of buffer, and lists nested
+// in lists multiply their counts, so every list item decoded anywhere is
+// paid for out of one budget: the buffer's length plus the schema's field
+// count. Without it a few kilobytes could take minutes to decode.
+struct Decoder<'a> {
+    data: &'a [u8],
+    item_budget: usize,
+    items_left: Cell<usize>,
+}
+
+impl<'a> Decoder<'a> {
+    // Borrow `len` bytes at `offset`, or explain why they are not there
+    fn take(&self, path: &str, offset: usize, len: usize) -> Result<&'a [u8], DecodeError> {
+        // Even zero bytes cannot be taken from past the end
+        let available = self.data.len().saturating_sub(offset);
+        if len > available || offset > self.data.len() {
+            return Err(DecodeError {
+                field: path.to_string(),
+                offset,
+                kind: DecodeErrorKind::UnexpectedEnd {
+                    needed: len,
+                    available,
+                },
+            });
+        }
+        Ok(&self.data[offset..offset + len])
+    }
+
+    fn unsigned(&self, path: &str, offset: usize, size: usize) -> Result<u128, DecodeError> {
+        let bytes = self.take(path, offset, size)?;
+        let mut buf = [0u8; 16];
+        buf[..size].copy_from_slice(bytes);
+        Ok(u128::from_le_bytes(buf))
+    }
+
+    fn signed(&self, path: &str, offset: usize, size: usize) -> Result<i128, DecodeError> {
+        let raw = self.unsigned(path, offset, size)?;
+        // Sign-extend from `size` bytes to 128 bits
+        let shift = 128 - size * 8;
+        Ok(((raw << shift) as i128) >> shift)
+    }
+
+    fn length(
+        &self,
+        path: &str,
+        offset: usize,
+        prefix: LengthPrefix,
+    ) -> Result<usize, DecodeError> {
+        let len = self.unsigned(path, offset, prefix.size())? as u64;
+        // A length longer than the whole buffer can never be satisfied
+        if len > self.data.len() as u64 {
+            return Err(DecodeError {
+                field: path.to_string(),
+                offset,
+                kind: DecodeErrorKind::LengthTooLarge(len),
+            });
+        }
+        Ok(len as usize)
+    }
+
+    fn add(&self, path: &str, offset: usize, len: usize) -> Result<usize, DecodeError> {
+        offset.checked_add(len).ok_or_else(|| DecodeError {
+            field: path.to_string(),
+            offset,
+            kind: DecodeErrorKind::OffsetOverflow,
+        })
+    }
+
+    // Take `count` items out of the budget, before decoding any of them
+    fn spend_items(&self, path: &str, offset: usize, count: usize) -> Result<(), DecodeError> {
+        let left = self.items_left.get();
+        if count > left {
+            return Err(DecodeError {
+                field: path.to_string(),
+                offset,
+                kind: DecodeErrorKind::TooManyItems(self.item_budget),
+            });
+        }
+        self.items_left.set(left - count);
+        Ok(())
+    }
+
+    // Returns the decoded fields and the end offset of the struct
+    fn decode_struct(
+        &self,
+        fields: &[Field],
+        base: usize,
+        parent: &str,
+    ) -> Result<(Vec<DecodedField>, usize), DecodeError> {
+        let mut decoded = Vec::with_capacity(fields.len());
+        let mut cursor = base;
+        let mut end = base;
+
+        for field in fields {
+            let path = if parent.is_empty() {
+                field.name.clone()
+            } else {
+                format!("{}.{}", parent, field.name)
+            };
+            let offset = match field.offset {
+                Some(offset) => self.add(&path, base, offset)?,
+                None => cursor,
+            };
+            let (value, len) = self.decode_value(&field.ty, offset, &path)?;
+            cursor = self.add(&path, offset, len)?;
+            end = end.max(cursor);
+            decoded.push(DecodedField {
+                name: field.name.clone(),
+                offset,
+                len,
+                value,
+            });
+        }
+
+        Ok((decoded, end))
+    }
+
+    fn decode_list(
+        &self,
+        item: &FieldType,
+        count: usize,
+        offset: usize,
+        path: &str,
+    ) -> Result<(Vec<DecodedField>, usize), DecodeError> {
+        self.spend_items(path, offset, count)?;
+        let mut items = Vec::with_capacity(count);
+        let mut cursor = offset;
+        for i in 0..count {
+            let item_path = format!("{}[{}]", path, i);
+            let (value, len) = self.decode_value(item, cursor, &item_path)?;
+            items.push(DecodedField {
+                name: format!("[{}]", i),
+                offset: cursor,
+                len,
+                value,
+            });
+            cursor = self.add(&item_path, cursor, len)?;
+        }
+        Ok((items, cursor - offset))
+    }
+
+    // Returns the value and how many bytes it occupied
+    fn decode_value(
+        &self,
+        ty: &FieldType,
+        offset: usize,
+        path: &str,
+    ) -> Result<(Value, usize), DecodeError> {
+        let int = |size: usize| -> Result<(Value, usize), DecodeError> {
+            Ok((Value::Unsigned(self.unsigned(path, offset, size)?), size))
+        };
+        let sint = |size: usize| -> Result<(Value, usize), DecodeError> {
+            Ok((Value::Signed(self.signed(path, offset, size)?), size))
+        };
+
+        match ty {
+            FieldType::U8 => int(1),
+            FieldType::U16 => int(2),
+            FieldType::U32 => int(4),
+            FieldType::U64 => int(8),
+            FieldType::U128 => int(16),
+            FieldType::I8 => sint(1),
+            FieldType::I16 => sint(2),
+            FieldType::I32 => sint(4),
+            FieldType::I64 => sint(8),
+            FieldType::I128 => sint(16),
+            FieldType::Bool => match self.take(path, offset, 1)?[0] {
+                0 => Ok((Value::Bool(false), 1)),
+                1 => Ok((Value::Bool(true), 1)),
+                other => Err(DecodeError {
+                    field: path.to_string(),
+                    offset,
+                    kind: DecodeErrorKind::InvalidBool(other),
+                }),
+            },
+            FieldType::FixedBytes(len) => {
+                let bytes = self.take(path, offset, *len)?;
+                Ok((Value::Bytes(bytes.to_vec()), *len))
+            }
+            FieldType::Bytes(prefix) => {
+                let len = self.length(path, offset, *prefix)?;
+                let bytes = self.take(path, offset + prefix.size(), len)?;
+                Ok((Value::Bytes(bytes.to_vec()), prefix.size() + len))
+            }
+            FieldType::String(prefix) => {
+                let len = self.length(path, offset, *prefix)?;
+                let bytes = self.take(path, offset + prefix.size(), len)?;
+                let text = std::str::from_utf8(bytes).map_err(|_| DecodeError {
+                    field: path.to_string(),
+                    offset,
+                    kind: DecodeErrorKind::InvalidUtf8,
+                })?;
+                Ok((Value::String(text.to_string()), prefix.size() + len))
+            }
+            FieldType::Array(item, count) => {
+                let (items, len) = self.decode_list(item, *count, offset, path)?;
+                Ok((Value::List(items), len))
+            }
+            FieldType::Vec(item, prefix) => {
+                let count = self.length(path, offset, *prefix)?;
+                let (items, len) = self.decode_list(item, count, offset + prefix.size(), path)?;
+                Ok((Value::List(items), prefix.size() + len))
+            }
+            FieldType::Struct(fields) => {
+                let (children, end) = self.decode_struct(fields, offset, path)?;
+                Ok((Value::Struct(children), end - offset))
+            }
+        }
+    }
+}
+
+// ----------------------------------------
+// Text Format Parser
+// ----------------------------------------
+
+struct Parser<'a> {
+    // (line number, trimmed content) for every non-empty, non-comment line
+    lines: Vec<(usize, &'a str)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        let lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let line = match line.find('#') {
+                    Some(idx) => &line[..idx],
+                    None => line,
+                };
+                (i + 1, line.trim())
+            })
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+        Parser { lines, pos: 0 }
+    }
+
+    fn parse(mut self) -> Result<Schema, SchemaError> {
+        let name = match self.lines.first() {
+            Some((_, line)) if line.starts_with("schema ") => {
+                self.pos = 1;
+                line["schema ".len()..].trim().to_string()
+            }
+            _ => "unnamed".to_string(),
+        };
+
+        let fields = self.parse_fields(None)?;
+        if fields.is_empty() {
+            return Err(SchemaError {
+                line: 0,
+                message: "schema has no fields".to_string(),
+            });
+        }
+        Ok(Schema { name, fields })
+    }
+
+    // Parse field lines until the end of input, or until `}` when inside a
+    // struct opened on `opened_at`
+    fn parse_fields(&mut self, opened_at: Option<usize>) -> Result<Vec<Field>, SchemaError> {
+        let mut fields = Vec::new();
+
+        while self.pos < self.lines.len() {
+            let (line_no, line) = self.lines[self.pos];
+            self.pos += 1;
+
+            if line == "}" {
+                return match opened_at {
+                    Some(_) => Ok(fields),
+                    None => Err(SchemaError {
+                        line: line_no,
+                        message: "unmatched `}`".to_string(),
+                    }),
+                };
+            }
+
+            fields.push(self.parse_field(line_no, line)?);
+        }
+
+        match opened_at {
+            Some(line) => Err(SchemaError {
+                line,
+                message: "struct is never closed with `}`".to_string(),
+            }),
+            None => Ok(fields),
+        }
+    }
+
+    // name: type [@ offset]
+    fn parse_field(&mut self, line_no: usize, line: &str) -> Result<Field, SchemaError> {
+        let error = |message: String| SchemaError {
+            line: line_no,
+            message,
+        };
+
+        let (name, rest) = line
+            .split_once(':')
+            .ok_or_else(|| error(format!("expected `name: type`, found `{}`", line)))?;
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(error(format!("invalid field name `{}`", name)));
+        }
+
+        let (type_text, offset) = match rest.split_once('@') {
+            Some((ty, offset)) => {
+                let offset = offset
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| error(format!("invalid offset `{}`", offset.trim())))?;
+                (ty.trim(), Some(offset))
+            }
+            None => (rest.trim(), None),
+        };
+
+        let ty = if type_text == "struct {" || type_text == "struct{" {
+            FieldType::Struct(self.parse_fields(Some(line_no))?)
+        } else {
+            parse_type(type_text).map_err(error)?
+        };
+
+        Ok(Field {
+            name: name.to_string(),
+            ty,
+            offset,
+        })
+    }
+}
+
+// Parse a single type expression such as `vec<array<u8, 4>, u32>`
+fn parse_type(text: &str) -> Result<FieldType, String> {
+    let text = text.trim();
+
+    let simple = match text {
+        "u8" => Some(FieldType::U8),
+        "u16" => Some(FieldType::U16),
+        "u32" => Some(FieldType::U32),
+        "u64" => Some(FieldType::U64),
+        "u128" => Some(FieldType::U128),
+        "i8" => Some(FieldType::I8),
+        "i16" => Some(FieldType::I16),
+        "i32" => Some(FieldType::I32),
+        "i64" => Some(FieldType::I64),
+        "i128" => Some(FieldType::I128),
+        "bool" => Some(FieldType::Bool),
+        _ => None,
+    };
+    if let Some(ty) = simple {
+        return Ok(ty);
+    }
+
+    if let Some(len) = text
+        .strip_prefix("bytes[")
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        let len = len
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid byte count in `{}`", text))?;
+        return Ok(FieldType::FixedBytes(len));
+    }
+
+    // Generic forms: name<args>
+    let (head, args) = match text.split_once('<') {
+        Some((head, rest)) => match rest.strip_suffix('>') {
+            Some(args) => (head.trim(), args),
+            None => return Err(format!("missing `>` in `{}`", text)),
+        },
+        None => return Err(format!("unknown type `{}`", text)),
+    };
+    let args = split_type_args(args);
+    let prefix = |arg: &str| {
+        LengthPrefix::parse(arg.trim())
+            .ok_or_else(|| format!("length prefix must be u8, u16, u32 or u64, found `{}`", arg))
+    };
+
+    match (head, args.as_slice()) {
+        ("string", [len]) => Ok(FieldType::String(prefix(len)?)),
+        ("bytes", [len]) => Ok(FieldType::Bytes(prefix(len)?)),
+        ("array", [item, count]) => {
+            let count = count
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid array length `{}`", count.trim()))?;
+            Ok(FieldType::Array(Box::new(parse_type(item)?), count))
+        }
+        ("vec", [item, len]) => Ok(FieldType::Vec(Box::new(parse_type(item)?), prefix(len)?)),
+        _ => Err(format!("unknown type `{}`", text)),
+    }
+}
+
+// Split "array<u8, 4>, u32" on top-level commas only
+fn split_type_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&args[start..]);
+    parts
+}
+
+// ----------------------------------------
+// Hex Helpers (for the command line)
+// ----------------------------------------
+
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: String = text
+        .trim()
+        .trim_start_matches("0x")
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '_')
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("hex data must have an even number of digits".to_string());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex byte `{}`", &digits[i..i + 2]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(schema: &str, data: &[u8]) -> Result<Vec<DecodedField>, DecodeError> {
+        Schema::parse(schema).unwrap().decode(data)
+    }
+
+    #[test]
+    fn decodes_the_token_pair() {
+        let schema = Schema::from_file("schemas/token_pair.layout").unwrap();
+        let data = parse_hex("01000000ffffffff08000000534f4c2f55534443").unwrap();
+        let fields = schema.decode(&data).unwrap();
+        let name = DecodedField::find(&fields, "name").unwrap();
+        assert_eq!(name.value, Value::String("SOL/USDC".to_string()));
+        assert_eq!((name.offset, name.len), (8, 12));
+    }
+
+    #[test]
+    fn nested_lists_share_one_item_budget() {
+        // 999 inner lists each claiming 3996 empty items: four million
+        // items from four kilobytes without the budget
+        let mut data = 999u32.to_le_bytes().to_vec();
+        for _ in 0..999 {
+            data.extend_from_slice(&3996u32.to_le_bytes());
+        }
+        data.resize(4000, 0);
+        let err = decode("items: vec<vec<bytes[0], u32>, u32>", &data).unwrap_err();
+        assert_eq!(err.field, "items[0]");
+        assert_eq!(err.kind, DecodeErrorKind::TooManyItems(4001));
+
+        // Lists that fit in the buffer still decode
+        let data = [2, 0, 0, 0, 1, 0, 0, 0, 7, 0, 0, 0, 0];
+        let fields = decode("items: vec<vec<u8, u32>, u32>", &data).unwrap();
+        assert_eq!(fields[0].len, data.len());
+    }
+
+    #[test]
+    fn offsets_past_usize_max_are_errors() {
+        let schema = format!(
+            "outer: struct {{\n    empty: array<u8, 0> @ {}\n    inner: struct {{\n        x: bytes[0] @ 1\n    }}\n}}",
+            usize::MAX
+        );
+        let err = decode(&schema, &[]).unwrap_err();
+        assert_eq!(err.field, "outer.inner.x");
+        assert_eq!(err.kind, DecodeErrorKind::OffsetOverflow);
+    }
+}
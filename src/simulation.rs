@@ -0,0 +1,392 @@
+// ========================================================================
+// TRANSACTION SIMULATION REPORTS
+// ========================================================================
+//
+// run_complex_operation and process_transaction_with_question_mark in
+// main.rs either work or return an error, with nothing to show what they
+// would have done. Bank::simulate answers that for a real transaction
+// before it is sent: it runs the transaction exactly as the bank would,
+// against a working copy of the accounts the message names (everything
+// else is only read), and throws the copy away. Nothing is committed: no
+// fee is charged, no account changes and the history does not grow.
+//
+// The report holds what a wallet shows before asking for a signature:
+//
+//     status           ok, or the error and which instruction failed
+//     fee, units       what it costs and how much of the budget it uses
+//     balances         lamports of every account before and after
+//     token balances   the same for every token account among them, like
+//                      preTokenBalances / postTokenBalances on mainnet
+//     changed          each account it would create, close or modify
+//     logs             everything the programs logged
+//
+// A transaction that fails still pays its fee, so the payer's balance
+// changes either way.
+
+use std::fmt;
+
+use crate::amount;
+use crate::associated_token_program::{self, AssociatedTokenProgram};
+use crate::bank::{Bank, TransactionError, TransactionOutcome};
+use crate::compute_budget;
+use crate::ledger::Account;
+use crate::program::InstructionError;
+use crate::pubkey::Pubkey;
+use crate::system_program;
+use crate::token_program::{self, Mint, TokenAccount, TokenProgram};
+use crate::transaction::{DemoSigner, Instruction, Signer, Transaction, VersionedTransaction};
+
+// ----------------------------------------
+// The Report
+// ----------------------------------------
+
+// A token account among the transaction's accounts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub account_index: usize,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+// An account the transaction would change. One that does not exist yet,
+// or would be closed, is the default account on that side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountChange {
+    pub account_index: usize,
+    pub pubkey: Pubkey,
+    pub pre: Account,
+    pub post: Account,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation {
+    pub outcome: TransactionOutcome,
+    pub pre_token_balances: Vec<TokenBalance>,
+    pub post_token_balances: Vec<TokenBalance>,
+    pub changed_accounts: Vec<AccountChange>,
+}
+
+impl Simulation {
+    // `post_accounts` line up with the outcome's account keys, empty if
+    // the transaction was rejected before it ran
+    pub fn new(bank: &Bank, outcome: TransactionOutcome, post_accounts: Vec<Account>) -> Self {
+        let pre_accounts: Vec<Account> = outcome
+            .account_keys
+            .iter()
+            .map(|key| bank.get_account(key).cloned().unwrap_or_default())
+            .collect();
+        let keys = &outcome.account_keys;
+        let changed_accounts = keys
+            .iter()
+            .zip(pre_accounts.iter().zip(&post_accounts))
+            .enumerate()
+            .filter(|(_, (_, (pre, post)))| pre != post)
+            .map(|(account_index, (pubkey, (pre, post)))| AccountChange {
+                account_index,
+                pubkey: *pubkey,
+                pre: pre.clone(),
+                post: post.clone(),
+            })
+            .collect();
+        Simulation {
+            pre_token_balances: token_balances(bank, keys, &pre_accounts),
+            post_token_balances: token_balances(bank, keys, &post_accounts),
+            changed_accounts,
+            outcome,
+        }
+    }
+
+    // The index of the instruction that failed, and why
+    pub fn failed_instruction(&self) -> Option<(u8, &InstructionError)> {
+        match &self.outcome.status {
+            Err(TransactionError::InstructionError(index, err)) => Some((*index, err)),
+            _ => None,
+        }
+    }
+}
+
+fn token_balances(bank: &Bank, keys: &[Pubkey], accounts: &[Account]) -> Vec<TokenBalance> {
+    // The mint may be created by the transaction itself
+    let decimals = |mint: &Pubkey| {
+        let in_transaction = keys.iter().position(|key| key == mint);
+        let account = in_transaction.map_or_else(|| bank.get_account(mint), |i| accounts.get(i));
+        account
+            .filter(|account| account.owner == token_program::ID)
+            .and_then(|account| Mint::unpack_initialized(&account.data).ok())
+            .map_or(0, |mint| mint.decimals)
+    };
+    accounts
+        .iter()
+        .enumerate()
+        .filter(|(_, account)| account.owner == token_program::ID)
+        .filter_map(|(account_index, account)| {
+            let token = TokenAccount::unpack_initialized(&account.data).ok()?;
+            Some(TokenBalance {
+                account_index,
+                mint: token.mint,
+                owner: token.owner,
+                amount: token.amount,
+                decimals: decimals(&token.mint),
+            })
+        })
+        .collect()
+}
+
+// What happened to one account, in a few words
+fn describe_change(change: &AccountChange) -> String {
+    let (pre, post) = (&change.pre, &change.post);
+    if *pre == Account::default() {
+        return format!(
+            "created: {} lamports, {} bytes, owned by {}",
+            post.lamports,
+            post.data.len(),
+            post.owner
+        );
+    }
+    if *post == Account::default() {
+        return format!("closed: {} lamports returned", pre.lamports);
+    }
+    let mut parts = Vec::new();
+    if pre.lamports != post.lamports {
+        parts.push(format!(
+            "lamports {:+}",
+            post.lamports as i128 - pre.lamports as i128
+        ));
+    }
+    if pre.data != post.data {
+        let differ = pre
+            .data
+            .iter()
+            .zip(&post.data)
+            .filter(|(a, b)| a != b)
+            .count()
+            + pre.data.len().abs_diff(post.data.len());
+        parts.push(format!(
+            "data {} of {} bytes changed",
+            differ,
+            post.data.len()
+        ));
+    }
+    if pre.owner != post.owner {
+        parts.push(format!("owner {} -> {}", pre.owner, post.owner));
+    }
+    parts.join(", ")
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let outcome = &self.outcome;
+        match &outcome.status {
+            Ok(()) => writeln!(f, "status:  ok")?,
+            Err(err) => writeln!(f, "status:  {}", err)?,
+        }
+        if !outcome.was_executed() {
+            return Ok(());
+        }
+        writeln!(f, "fee:     {}", outcome.fee)?;
+        writeln!(f, "compute: {} units", outcome.compute_units_consumed)?;
+
+        writeln!(f, "balances (lamports):")?;
+        let balances = outcome.pre_balances.iter().zip(&outcome.post_balances);
+        for (i, (key, (pre, post))) in outcome.account_keys.iter().zip(balances).enumerate() {
+            let key = key.to_string();
+            write!(f, "  #{:<2} {:<44} {:>14} -> {:>14}", i, key, pre, post)?;
+            if pre != post {
+                write!(f, "  {:+}", *post as i128 - *pre as i128)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.pre_token_balances.is_empty() || !self.post_token_balances.is_empty() {
+            writeln!(f, "token balances:")?;
+            let indexes = self
+                .pre_token_balances
+                .iter()
+                .chain(&self.post_token_balances)
+                .map(|balance| balance.account_index);
+            let mut indexes: Vec<usize> = indexes.collect();
+            indexes.sort();
+            indexes.dedup();
+            for i in indexes {
+                let find = |balances: &[TokenBalance]| {
+                    balances
+                        .iter()
+                        .find(|balance| balance.account_index == i)
+                        .cloned()
+                };
+                let (pre, post) = (
+                    find(&self.pre_token_balances),
+                    find(&self.post_token_balances),
+                );
+                let Some(token) = post.as_ref().or(pre.as_ref()) else {
+                    continue;
+                };
+                let show = |balance: &Option<TokenBalance>| {
+                    balance.as_ref().map_or("-".to_string(), |balance| {
+                        amount::format_decimal(balance.amount, balance.decimals)
+                    })
+                };
+                writeln!(
+                    f,
+                    "  #{:<2} owner {} mint {}: {} -> {}",
+                    i,
+                    token.owner,
+                    token.mint,
+                    show(&pre),
+                    show(&post)
+                )?;
+            }
+        }
+
+        writeln!(f, "changed accounts:")?;
+        if self.changed_accounts.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        for change in &self.changed_accounts {
+            writeln!(
+                f,
+                "  #{:<2} {} {}",
+                change.account_index,
+                change.pubkey,
+                describe_change(change)
+            )?;
+        }
+
+        writeln!(f, "logs:")?;
+        for line in &outcome.logs {
+            writeln!(f, "  {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo simulate`
+// ----------------------------------------
+
+const USDC: u64 = 1_000_000;
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(TokenProgram));
+    bank.add_program(Box::new(AssociatedTokenProgram));
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let usdc_mint = DemoSigner::new("usdc mint");
+    bank.airdrop(&alice.pubkey(), 1_000_000_000)
+        .map_err(|err| err.to_string())?;
+    let ata = |wallet: &DemoSigner| {
+        associated_token_program::get_associated_token_address(
+            &wallet.pubkey(),
+            &usdc_mint.pubkey(),
+        )
+    };
+
+    let tx = |bank: &Bank, instructions: &[Instruction], signers: &[&dyn Signer]| {
+        VersionedTransaction::from(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&alice.pubkey()),
+            signers,
+            bank.last_blockhash(),
+        ))
+    };
+    let setup = tx(
+        &bank,
+        &[
+            system_program::create_account(
+                &alice.pubkey(),
+                &usdc_mint.pubkey(),
+                system_program::rent_exempt_lamports(token_program::MINT_LEN),
+                token_program::MINT_LEN as u64,
+                &token_program::ID,
+            ),
+            token_program::initialize_mint(&usdc_mint.pubkey(), &alice.pubkey(), None, 6),
+            associated_token_program::create_associated_token_account(
+                &alice.pubkey(),
+                &alice.pubkey(),
+                &usdc_mint.pubkey(),
+            ),
+            token_program::mint_to(
+                &usdc_mint.pubkey(),
+                &ata(&alice),
+                &alice.pubkey(),
+                &[],
+                100 * USDC,
+            ),
+        ],
+        &[&alice, &usdc_mint],
+    );
+    bank.execute_versioned_transaction(&setup)
+        .status
+        .map_err(|err| err.to_string())?;
+    bank.advance_slots(1);
+    println!("alice     {}", alice.pubkey());
+    println!("bob       {}", bob.pubkey());
+    println!("USDC mint {}", usdc_mint.pubkey());
+
+    // Open bob's USDC account, pay him 25 USDC and send some SOL along
+    let payment = |usdc: u64| {
+        [
+            associated_token_program::create_associated_token_account_idempotent(
+                &alice.pubkey(),
+                &bob.pubkey(),
+                &usdc_mint.pubkey(),
+            ),
+            token_program::transfer(&ata(&alice), &ata(&bob), &alice.pubkey(), &[], usdc),
+            system_program::transfer(&alice.pubkey(), &bob.pubkey(), 10_000_000),
+        ]
+    };
+    let pay = tx(&bank, &payment(25 * USDC), &[&alice]);
+    let simulation = bank.simulate(&pay, true);
+    println!("\nSimulate paying bob 25 USDC and 0.01 SOL\n{}", simulation);
+    println!(
+        "Nothing committed: alice still has {} lamports, bob's USDC account exists: {}, \
+         history has the transaction: {}",
+        bank.get_balance(&alice.pubkey()),
+        bank.get_account(&ata(&bob)).is_some(),
+        bank.history().get_transaction(&pay.signature()).is_some()
+    );
+
+    let outcome = bank.execute_versioned_transaction(&pay);
+    let executed = outcome.status.is_ok()
+        && outcome.post_balances == simulation.outcome.post_balances
+        && outcome.compute_units_consumed == simulation.outcome.compute_units_consumed;
+    println!(
+        "Sent for real: the same balances and compute units as simulated: {}",
+        executed
+    );
+    bank.advance_slots(1);
+
+    // More than alice has left: the second instruction fails, so only the
+    // fee would change hands
+    let overdraw = tx(&bank, &payment(500 * USDC), &[&alice]);
+    let simulation = bank.simulate(&overdraw, true);
+    println!("\nSimulate paying bob 500 USDC\n{}", simulation);
+    if let Some((index, err)) = simulation.failed_instruction() {
+        println!("Instruction {} would fail: {}", index, err);
+    }
+
+    // A compute budget too small for opening an account: the associated
+    // token program runs out partway through the calls it makes
+    let carol = DemoSigner::new("carol");
+    let tight = tx(
+        &bank,
+        &[
+            compute_budget::set_compute_unit_limit(16_000),
+            associated_token_program::create_associated_token_account(
+                &alice.pubkey(),
+                &carol.pubkey(),
+                &usdc_mint.pubkey(),
+            ),
+        ],
+        &[&alice],
+    );
+    let simulation = bank.simulate(&tight, true);
+    println!(
+        "\nSimulate opening carol's USDC account with a 16,000 unit budget\n{}",
+        simulation
+    );
+    Ok(())
+}
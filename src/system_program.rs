@@ -0,0 +1,339 @@
+// ========================================================================
+// THE SYSTEM PROGRAM
+// ========================================================================
+//
+// The System Program owns every plain wallet. It is the only way to move
+// lamports out of a wallet and the only way to create new accounts:
+// CreateAccount funds a fresh address, gives it a data buffer and hands
+// ownership to another program.
+//
+// Instruction data uses the same encoding as Solana (a little-endian u32
+// tag followed by the fields), so a transfer of 100 lamports is the
+// familiar
+//
+//     [2, 0, 0, 0, 100, 0, 0, 0, 0, 0, 0, 0]
+
+use std::fmt;
+
+use crate::program::{InstructionError, InvokeContext, Program};
+use crate::pubkey::{Pubkey, SYSTEM_PROGRAM_ID};
+use crate::transaction::{AccountMeta, Instruction};
+
+pub const ID: Pubkey = SYSTEM_PROGRAM_ID;
+
+// Largest data buffer an account may have (10 MiB)
+pub const MAX_PERMITTED_DATA_LENGTH: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SystemInstruction {
+    // Accounts: [funding (signer, writable), new account (signer, writable)]
+    CreateAccount {
+        lamports: u64,
+        space: u64,
+        owner: Pubkey,
+    },
+    // Accounts: [account (signer, writable)]
+    Assign {
+        owner: Pubkey,
+    },
+    // Accounts: [from (signer, writable), to (writable)]
+    Transfer {
+        lamports: u64,
+    },
+    // Accounts: [account (signer, writable)]
+    Allocate {
+        space: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SystemError {
+    AccountAlreadyInUse,
+    ResultWithNegativeLamports,
+    InvalidAccountDataLength,
+    // Lamports can only leave accounts without data
+    TransferFromAccountWithData,
+}
+
+impl fmt::Display for SystemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SystemError::AccountAlreadyInUse => {
+                write!(f, "an account with the same address already exists")
+            }
+            SystemError::ResultWithNegativeLamports => {
+                write!(
+                    f,
+                    "account does not have enough SOL to perform the operation"
+                )
+            }
+            SystemError::InvalidAccountDataLength => {
+                write!(f, "cannot allocate account data of this length")
+            }
+            SystemError::TransferFromAccountWithData => {
+                write!(f, "`from` must not carry data")
+            }
+        }
+    }
+}
+
+// ----------------------------------------
+// Encoding
+// ----------------------------------------
+
+impl SystemInstruction {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => {
+                data.extend_from_slice(&0u32.to_le_bytes());
+                data.extend_from_slice(&lamports.to_le_bytes());
+                data.extend_from_slice(&space.to_le_bytes());
+                data.extend_from_slice(owner.as_ref());
+            }
+            SystemInstruction::Assign { owner } => {
+                data.extend_from_slice(&1u32.to_le_bytes());
+                data.extend_from_slice(owner.as_ref());
+            }
+            SystemInstruction::Transfer { lamports } => {
+                data.extend_from_slice(&2u32.to_le_bytes());
+                data.extend_from_slice(&lamports.to_le_bytes());
+            }
+            SystemInstruction::Allocate { space } => {
+                data.extend_from_slice(&8u32.to_le_bytes());
+                data.extend_from_slice(&space.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let u64_at = |offset: usize| -> Result<u64, InstructionError> {
+            data.get(offset..offset + 8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or(InstructionError::InvalidInstructionData)
+        };
+        let pubkey_at = |offset: usize| -> Result<Pubkey, InstructionError> {
+            data.get(offset..offset + 32)
+                .map(|bytes| Pubkey::new(bytes.try_into().unwrap()))
+                .ok_or(InstructionError::InvalidInstructionData)
+        };
+
+        let tag = data
+            .get(..4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or(InstructionError::InvalidInstructionData)?;
+        let (instruction, len) = match tag {
+            0 => (
+                SystemInstruction::CreateAccount {
+                    lamports: u64_at(4)?,
+                    space: u64_at(12)?,
+                    owner: pubkey_at(20)?,
+                },
+                52,
+            ),
+            1 => (
+                SystemInstruction::Assign {
+                    owner: pubkey_at(4)?,
+                },
+                36,
+            ),
+            2 => (
+                SystemInstruction::Transfer {
+                    lamports: u64_at(4)?,
+                },
+                12,
+            ),
+            8 => (SystemInstruction::Allocate { space: u64_at(4)? }, 12),
+            _ => return Err(InstructionError::InvalidInstructionData),
+        };
+        if data.len() != len {
+            return Err(InstructionError::InvalidInstructionData);
+        }
+        Ok(instruction)
+    }
+}
+
+// ----------------------------------------
+// Instruction Constructors
+// ----------------------------------------
+
+pub fn create_account(
+    from: &Pubkey,
+    to: &Pubkey,
+    lamports: u64,
+    space: u64,
+    owner: &Pubkey,
+) -> Instruction {
+    let data = SystemInstruction::CreateAccount {
+        lamports,
+        space,
+        owner: *owner,
+    }
+    .pack();
+    Instruction::new(
+        ID,
+        &data,
+        vec![AccountMeta::new(*from, true), AccountMeta::new(*to, true)],
+    )
+}
+
+pub fn assign(pubkey: &Pubkey, owner: &Pubkey) -> Instruction {
+    let data = SystemInstruction::Assign { owner: *owner }.pack();
+    Instruction::new(ID, &data, vec![AccountMeta::new(*pubkey, true)])
+}
+
+pub fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let data = SystemInstruction::Transfer { lamports }.pack();
+    Instruction::new(
+        ID,
+        &data,
+        vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+    )
+}
+
+pub fn allocate(pubkey: &Pubkey, space: u64) -> Instruction {
+    let data = SystemInstruction::Allocate { space }.pack();
+    Instruction::new(ID, &data, vec![AccountMeta::new(*pubkey, true)])
+}
+
+// ----------------------------------------
+// Creating Program Accounts
+// ----------------------------------------
+
+// Rent is not modelled, but new accounts still get what mainnet requires
+// to make `len` bytes rent exempt: 128 bytes of overhead plus the data,
+// at 6,960 lamports a byte
+pub const fn rent_exempt_lamports(len: usize) -> u64 {
+    (128 + len as u64) * 6_960
+}
+
+// Called from another program's processor: make account `i`, a program
+// derived address that `seeds` sign for, a rent-exempt account of `space`
+// zeroed bytes owned by `owner`, paid for by `payer`. Top up, then
+// allocate and assign, so lamports someone already sent to the address
+// do not get in the way (CreateAccount would refuse the address).
+pub fn create_pda_account(
+    ctx: &mut InvokeContext,
+    i: usize,
+    payer: &Pubkey,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<(), InstructionError> {
+    let address = *ctx.key(i)?;
+    let required = rent_exempt_lamports(space);
+    let funded = ctx.account(i)?.lamports;
+    if funded < required {
+        ctx.invoke(&transfer(payer, &address, required - funded))?;
+    }
+    ctx.invoke_signed(&allocate(&address, space as u64), &[seeds])?;
+    ctx.invoke_signed(&assign(&address, owner), &[seeds])
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct SystemProgram;
+
+impl Program for SystemProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "system"
+    }
+
+    // Builtin programs cost the same on every call, as on mainnet
+    fn compute_units(&self) -> u64 {
+        150
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        match SystemInstruction::unpack(data)? {
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => {
+                ctx.signer(0)?;
+                ctx.signer(1)?;
+                let new_account = ctx.account(1)?;
+                if new_account.lamports > 0
+                    || !new_account.data.is_empty()
+                    || new_account.owner != ID
+                {
+                    ctx.log(&format!("Create Account: {} already in use", ctx.key(1)?));
+                    return Err(SystemError::AccountAlreadyInUse.into());
+                }
+                allocate_and_assign(ctx, 1, space, &owner)?;
+                transfer_from_wallet(ctx, 0, 1, lamports)
+            }
+            SystemInstruction::Assign { owner } => {
+                ctx.signer(0)?;
+                if ctx.account(0)?.owner != owner {
+                    ctx.account_mut(0)?.owner = owner;
+                }
+                Ok(())
+            }
+            SystemInstruction::Transfer { lamports } => {
+                ctx.signer(0)?;
+                transfer_from_wallet(ctx, 0, 1, lamports)
+            }
+            SystemInstruction::Allocate { space } => {
+                ctx.signer(0)?;
+                allocate_and_assign(ctx, 0, space, &ID)
+            }
+        }
+    }
+}
+
+fn allocate_and_assign(
+    ctx: &mut InvokeContext,
+    index: usize,
+    space: u64,
+    owner: &Pubkey,
+) -> Result<(), InstructionError> {
+    let account = ctx.account(index)?;
+    if !account.data.is_empty() || account.owner != ID {
+        return Err(SystemError::AccountAlreadyInUse.into());
+    }
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        return Err(SystemError::InvalidAccountDataLength.into());
+    }
+    let account = ctx.account_mut(index)?;
+    account.data = vec![0; space as usize];
+    account.owner = *owner;
+    Ok(())
+}
+
+fn transfer_from_wallet(
+    ctx: &mut InvokeContext,
+    from: usize,
+    to: usize,
+    lamports: u64,
+) -> Result<(), InstructionError> {
+    let source = ctx.account(from)?;
+    if !source.data.is_empty() {
+        return Err(SystemError::TransferFromAccountWithData.into());
+    }
+    let balance = source.lamports;
+    if balance < lamports {
+        ctx.log(&format!(
+            "Transfer: insufficient lamports {}, need {}",
+            balance, lamports
+        ));
+        return Err(SystemError::ResultWithNegativeLamports.into());
+    }
+    ctx.transfer_lamports(from, to, lamports)
+}
@@ -0,0 +1,300 @@
+// ========================================================================
+// TIMELOCK PROGRAM
+// ========================================================================
+//
+// A small program that reads the Clock sysvar: a vault holds lamports for
+// a beneficiary and only releases them once the cluster's unix timestamp
+// has passed the unlock time. Release takes the sysvar as an account, the
+// way programs read sysvars before they could ask the runtime directly
+// (InvokeContext::clock).
+//
+// Vault layout (41 bytes):
+//
+//     state            u8      0 = uninitialized, 1 = locked
+//     beneficiary      [u8; 32]
+//     unlock_timestamp i64     little-endian unix seconds
+
+use std::fmt;
+
+use crate::bank::{print_outcome, Bank};
+use crate::clock::{Clock, CLOCK_SYSVAR_ID};
+use crate::program::{InstructionError, InvokeContext, Program};
+use crate::pubkey::Pubkey;
+use crate::system_program;
+use crate::transaction::{AccountMeta, DemoSigner, Instruction, Signer, Transaction};
+
+pub const ID: Pubkey = Pubkey::from_str_const("TimeLock11111111111111111111111111111111111");
+
+pub const VAULT_SIZE: usize = 41;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelockInstruction {
+    // Accounts: [vault (writable, owned by this program)]
+    Lock {
+        beneficiary: Pubkey,
+        unlock_timestamp: i64,
+    },
+    // Accounts: [vault (writable), beneficiary (signer, writable),
+    //            Clock sysvar]
+    Release,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelockError {
+    StillLocked { unlock_timestamp: i64, now: i64 },
+    WrongBeneficiary,
+}
+
+impl fmt::Display for TimelockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimelockError::StillLocked {
+                unlock_timestamp,
+                now,
+            } => write!(
+                f,
+                "vault is locked for another {}s (unlocks at {}, now {})",
+                unlock_timestamp - now,
+                unlock_timestamp,
+                now
+            ),
+            TimelockError::WrongBeneficiary => {
+                write!(f, "signer is not the vault's beneficiary")
+            }
+        }
+    }
+}
+
+impl TimelockInstruction {
+    pub fn pack(&self) -> Vec<u8> {
+        match self {
+            TimelockInstruction::Lock {
+                beneficiary,
+                unlock_timestamp,
+            } => {
+                let mut data = vec![0];
+                data.extend_from_slice(beneficiary.as_ref());
+                data.extend_from_slice(&unlock_timestamp.to_le_bytes());
+                data
+            }
+            TimelockInstruction::Release => vec![1],
+        }
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        match data {
+            [0, rest @ ..] if rest.len() == 40 => Ok(TimelockInstruction::Lock {
+                beneficiary: Pubkey::new(rest[..32].try_into().unwrap()),
+                unlock_timestamp: i64::from_le_bytes(rest[32..].try_into().unwrap()),
+            }),
+            [1] => Ok(TimelockInstruction::Release),
+            _ => Err(InstructionError::InvalidInstructionData),
+        }
+    }
+}
+
+// Create the vault account, fund it and lock it in one transaction
+pub fn lock(
+    payer: &Pubkey,
+    vault: &Pubkey,
+    beneficiary: &Pubkey,
+    lamports: u64,
+    unlock_timestamp: i64,
+) -> Vec<Instruction> {
+    let data = TimelockInstruction::Lock {
+        beneficiary: *beneficiary,
+        unlock_timestamp,
+    }
+    .pack();
+    vec![
+        system_program::create_account(payer, vault, lamports, VAULT_SIZE as u64, &ID),
+        Instruction::new(ID, &data, vec![AccountMeta::new(*vault, false)]),
+    ]
+}
+
+pub fn release(vault: &Pubkey, beneficiary: &Pubkey) -> Instruction {
+    Instruction::new(
+        ID,
+        &TimelockInstruction::Release.pack(),
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*beneficiary, true),
+            AccountMeta::new_readonly(CLOCK_SYSVAR_ID, false),
+        ],
+    )
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct TimelockProgram;
+
+impl Program for TimelockProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "timelock"
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        let vault = ctx.account(0)?;
+        if vault.owner != ID {
+            return Err(InstructionError::IncorrectProgramId);
+        }
+        if vault.data.len() != VAULT_SIZE {
+            return Err(InstructionError::InvalidAccountData);
+        }
+
+        match TimelockInstruction::unpack(data)? {
+            TimelockInstruction::Lock {
+                beneficiary,
+                unlock_timestamp,
+            } => {
+                if vault.data[0] != 0 {
+                    return Err(InstructionError::AccountAlreadyInitialized);
+                }
+                let data = &mut ctx.account_mut(0)?.data;
+                data[0] = 1;
+                data[1..33].copy_from_slice(beneficiary.as_ref());
+                data[33..41].copy_from_slice(&unlock_timestamp.to_le_bytes());
+                ctx.log(&format!("locked until {}", unlock_timestamp));
+                Ok(())
+            }
+            TimelockInstruction::Release => {
+                if vault.data[0] != 1 {
+                    return Err(InstructionError::UninitializedAccount);
+                }
+                let beneficiary = Pubkey::new(vault.data[1..33].try_into().unwrap());
+                let unlock_timestamp = i64::from_le_bytes(vault.data[33..41].try_into().unwrap());
+                if *ctx.signer(1)? != beneficiary {
+                    return Err(TimelockError::WrongBeneficiary.into());
+                }
+
+                if *ctx.key(2)? != CLOCK_SYSVAR_ID {
+                    return Err(InstructionError::InvalidArgument);
+                }
+                let now = Clock::from_account_data(&ctx.account(2)?.data)
+                    .ok_or(InstructionError::AccountDataTooSmall)?
+                    .unix_timestamp;
+                if now < unlock_timestamp {
+                    return Err(TimelockError::StillLocked {
+                        unlock_timestamp,
+                        now,
+                    }
+                    .into());
+                }
+
+                // Close the vault: every lamport goes to the beneficiary
+                let lamports = ctx.account(0)?.lamports;
+                ctx.transfer_lamports(0, 1, lamports)?;
+                ctx.account_mut(0)?.data.fill(0);
+                ctx.log(&format!("released {} lamports", lamports));
+                Ok(())
+            }
+        }
+    }
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo timelock`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    bank.add_program(Box::new(TimelockProgram));
+
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let vault = DemoSigner::new("vault");
+    bank.airdrop(&alice.pubkey(), 5_000_000_000)
+        .map_err(|err| err.to_string())?;
+    bank.airdrop(&bob.pubkey(), 1_000_000)
+        .map_err(|err| err.to_string())?;
+
+    let clock = bank.clock();
+    println!(
+        "Slot {} (epoch {}), unix time {}",
+        clock.slot, clock.epoch, clock.unix_timestamp
+    );
+
+    // Lock 1 SOL for Bob for one minute of slots
+    let unlock_timestamp = clock.unix_timestamp + 60;
+    let lock_tx = Transaction::new_signed_with_payer(
+        &lock(
+            &alice.pubkey(),
+            &vault.pubkey(),
+            &bob.pubkey(),
+            1_000_000_000,
+            unlock_timestamp,
+        ),
+        Some(&alice.pubkey()),
+        &[&alice, &vault],
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&lock_tx);
+    print_outcome("Lock 1 SOL for bob", &outcome.status, &outcome.logs);
+
+    // Replaying the exact same transaction is rejected
+    let replay = bank.process_transaction(&lock_tx);
+    print_outcome("Replay the lock transaction", &replay, &[]);
+
+    // Too early: the Clock sysvar says the vault is still locked
+    bank.advance_slots(10);
+    let release_tx = Transaction::new_signed_with_payer(
+        &[release(&vault.pubkey(), &bob.pubkey())],
+        Some(&bob.pubkey()),
+        &[&bob],
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&release_tx);
+    print_outcome(
+        &format!("Release at unix time {}", bank.clock().unix_timestamp),
+        &outcome.status,
+        &outcome.logs,
+    );
+
+    // 160 slots (64 seconds) later the lock has expired, and so has the
+    // blockhash the early release was signed with
+    bank.advance_slots(160);
+    println!(
+        "\nAdvanced to slot {} (epoch {}), unix time {}",
+        bank.slot(),
+        bank.epoch(),
+        bank.clock().unix_timestamp
+    );
+    print_outcome(
+        "Resend the early release",
+        &bank.process_transaction(&release_tx),
+        &[],
+    );
+
+    let fresh = Transaction::new_signed_with_payer(
+        &[release(&vault.pubkey(), &bob.pubkey())],
+        Some(&bob.pubkey()),
+        &[&bob],
+        bank.last_blockhash(),
+    );
+    let outcome = bank.execute_transaction(&fresh);
+    print_outcome(
+        "Release with a recent blockhash",
+        &outcome.status,
+        &outcome.logs,
+    );
+
+    println!(
+        "\nBob's balance: {} lamports",
+        bank.get_balance(&bob.pubkey())
+    );
+    println!(
+        "Vault account exists: {}",
+        bank.get_account(&vault.pubkey()).is_some()
+    );
+    Ok(())
+}
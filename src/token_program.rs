@@ -0,0 +1,750 @@
+// ========================================================================
+// THE TOKEN PROGRAM
+// ========================================================================
+//
+// SPL Token in miniature. A mint describes a token (supply, decimals, who
+// may mint more); a token account holds a balance of one mint for one
+// owner. Both are accounts owned by this program and use SPL Token's byte
+// layouts:
+//
+//     Mint (82 bytes)                     Token account (165 bytes)
+//     mint_authority    COption<Pubkey>   mint              Pubkey
+//     supply            u64               owner             Pubkey
+//     decimals          u8                amount            u64
+//     is_initialized    u8                delegate          COption<Pubkey>
+//     freeze_authority  COption<Pubkey>   state             u8
+//                                         is_native         COption<u64>
+//                                         delegated_amount  u64
+//                                         close_authority   COption<Pubkey>
+//
+// A COption is a little-endian u32 tag (0 = None, 1 = Some) followed by
+// the value's bytes, which are zero when absent.
+//
+// Instruction tags match SPL Token as well. Rent is not modelled, so the
+// rent sysvar account SPL expects in InitializeMint/InitializeAccount is
+// not passed. Every authority may be a multisig (see multisig.rs): pass
+// the multisig as the authority and its signing members after it.
+
+use std::fmt;
+
+use crate::multisig::{self, Multisig, MULTISIG_LEN};
+use crate::program::{InstructionError, InvokeContext, Program};
+use crate::pubkey::{Pubkey, PUBKEY_BYTES};
+use crate::transaction::{AccountMeta, Instruction};
+
+pub const ID: Pubkey = Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+pub const MINT_LEN: usize = 82;
+pub const ACCOUNT_LEN: usize = 165;
+
+// ----------------------------------------
+// Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    InsufficientBalance,
+    InvalidMint,
+    MintMismatch,
+    // The mint has no mint authority left
+    FixedSupply,
+    AlreadyInUse,
+    UninitializedState,
+    Overflow,
+    AccountFrozen,
+    // Only empty token accounts can be closed
+    NonNativeHasBalance,
+    AuthorityTypeNotSupported,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenError::InsufficientBalance => write!(f, "insufficient token balance"),
+            TokenError::InvalidMint => write!(f, "invalid mint"),
+            TokenError::MintMismatch => write!(f, "account not associated with this mint"),
+            TokenError::FixedSupply => write!(f, "fixed supply: the mint has no mint authority"),
+            TokenError::AlreadyInUse => write!(f, "already in use"),
+            TokenError::UninitializedState => write!(f, "state is uninitialized"),
+            TokenError::Overflow => write!(f, "operation overflowed"),
+            TokenError::AccountFrozen => write!(f, "account is frozen"),
+            TokenError::NonNativeHasBalance => {
+                write!(
+                    f,
+                    "non-native account can only be closed if its balance is zero"
+                )
+            }
+            TokenError::AuthorityTypeNotSupported => {
+                write!(f, "account does not support the specified authority type")
+            }
+        }
+    }
+}
+
+// ----------------------------------------
+// Account State
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Mint {
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AccountState {
+    #[default]
+    Uninitialized,
+    Initialized,
+    Frozen,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub delegate: Option<Pubkey>,
+    pub state: AccountState,
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Pubkey>,
+}
+
+impl Mint {
+    pub fn pack(&self) -> [u8; MINT_LEN] {
+        let mut data = [0u8; MINT_LEN];
+        pack_pubkey_option(&mut data[0..36], &self.mint_authority);
+        data[36..44].copy_from_slice(&self.supply.to_le_bytes());
+        data[44] = self.decimals;
+        data[45] = self.is_initialized as u8;
+        pack_pubkey_option(&mut data[46..82], &self.freeze_authority);
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        if data.len() != MINT_LEN {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        Ok(Mint {
+            mint_authority: unpack_pubkey_option(&data[0..36])?,
+            supply: u64::from_le_bytes(data[36..44].try_into().unwrap()),
+            decimals: data[44],
+            is_initialized: unpack_bool(data[45])?,
+            freeze_authority: unpack_pubkey_option(&data[46..82])?,
+        })
+    }
+
+    pub fn unpack_initialized(data: &[u8]) -> Result<Self, InstructionError> {
+        let mint = Mint::unpack(data).map_err(|_| TokenError::InvalidMint)?;
+        if !mint.is_initialized {
+            return Err(TokenError::UninitializedState.into());
+        }
+        Ok(mint)
+    }
+}
+
+impl TokenAccount {
+    pub fn pack(&self) -> [u8; ACCOUNT_LEN] {
+        let mut data = [0u8; ACCOUNT_LEN];
+        data[0..32].copy_from_slice(self.mint.as_ref());
+        data[32..64].copy_from_slice(self.owner.as_ref());
+        data[64..72].copy_from_slice(&self.amount.to_le_bytes());
+        pack_pubkey_option(&mut data[72..108], &self.delegate);
+        data[108] = self.state as u8;
+        if let Some(reserve) = self.is_native {
+            data[109..113].copy_from_slice(&1u32.to_le_bytes());
+            data[113..121].copy_from_slice(&reserve.to_le_bytes());
+        }
+        data[121..129].copy_from_slice(&self.delegated_amount.to_le_bytes());
+        pack_pubkey_option(&mut data[129..165], &self.close_authority);
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        if data.len() != ACCOUNT_LEN {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        let state = match data[108] {
+            0 => AccountState::Uninitialized,
+            1 => AccountState::Initialized,
+            2 => AccountState::Frozen,
+            _ => return Err(InstructionError::InvalidAccountData),
+        };
+        let is_native = match u32::from_le_bytes(data[109..113].try_into().unwrap()) {
+            0 => None,
+            1 => Some(u64::from_le_bytes(data[113..121].try_into().unwrap())),
+            _ => return Err(InstructionError::InvalidAccountData),
+        };
+        Ok(TokenAccount {
+            mint: Pubkey::new(data[0..32].try_into().unwrap()),
+            owner: Pubkey::new(data[32..64].try_into().unwrap()),
+            amount: u64::from_le_bytes(data[64..72].try_into().unwrap()),
+            delegate: unpack_pubkey_option(&data[72..108])?,
+            state,
+            is_native,
+            delegated_amount: u64::from_le_bytes(data[121..129].try_into().unwrap()),
+            close_authority: unpack_pubkey_option(&data[129..165])?,
+        })
+    }
+
+    pub fn unpack_initialized(data: &[u8]) -> Result<Self, InstructionError> {
+        let account = TokenAccount::unpack(data)?;
+        match account.state {
+            AccountState::Uninitialized => Err(TokenError::UninitializedState.into()),
+            AccountState::Frozen => Err(TokenError::AccountFrozen.into()),
+            AccountState::Initialized => Ok(account),
+        }
+    }
+}
+
+fn pack_pubkey_option(dst: &mut [u8], value: &Option<Pubkey>) {
+    if let Some(pubkey) = value {
+        dst[..4].copy_from_slice(&1u32.to_le_bytes());
+        dst[4..36].copy_from_slice(pubkey.as_ref());
+    }
+}
+
+fn unpack_pubkey_option(src: &[u8]) -> Result<Option<Pubkey>, InstructionError> {
+    match u32::from_le_bytes(src[..4].try_into().unwrap()) {
+        0 => Ok(None),
+        1 => Ok(Some(Pubkey::new(src[4..36].try_into().unwrap()))),
+        _ => Err(InstructionError::InvalidAccountData),
+    }
+}
+
+fn unpack_bool(byte: u8) -> Result<bool, InstructionError> {
+    match byte {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(InstructionError::InvalidAccountData),
+    }
+}
+
+// ----------------------------------------
+// Instructions
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorityType {
+    MintTokens,
+    FreezeAccount,
+    AccountOwner,
+    CloseAccount,
+}
+
+// Accounts listed as [writable] or [signer]; "authority" is either a
+// signing key or a multisig followed by its signing members
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenInstruction {
+    // [writable] mint
+    InitializeMint {
+        decimals: u8,
+        mint_authority: Pubkey,
+        freeze_authority: Option<Pubkey>,
+    },
+    // [writable] account, mint, owner
+    InitializeAccount,
+    // [writable] multisig, member keys...
+    InitializeMultisig {
+        m: u8,
+    },
+    // [writable] source, [writable] destination, authority
+    Transfer {
+        amount: u64,
+    },
+    // [writable] mint or account, current authority
+    SetAuthority {
+        authority_type: AuthorityType,
+        new_authority: Option<Pubkey>,
+    },
+    // [writable] mint, [writable] destination, mint authority
+    MintTo {
+        amount: u64,
+    },
+    // [writable] account, [writable] mint, authority
+    Burn {
+        amount: u64,
+    },
+    // [writable] account, [writable] destination, authority
+    CloseAccount,
+}
+
+impl TokenInstruction {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            TokenInstruction::InitializeMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+            } => {
+                data.push(0);
+                data.push(*decimals);
+                data.extend_from_slice(mint_authority.as_ref());
+                push_pubkey_option(&mut data, freeze_authority);
+            }
+            TokenInstruction::InitializeAccount => data.push(1),
+            TokenInstruction::InitializeMultisig { m } => data.extend_from_slice(&[2, *m]),
+            TokenInstruction::Transfer { amount } => {
+                data.push(3);
+                data.extend_from_slice(&amount.to_le_bytes());
+            }
+            TokenInstruction::SetAuthority {
+                authority_type,
+                new_authority,
+            } => {
+                data.push(6);
+                data.push(*authority_type as u8);
+                push_pubkey_option(&mut data, new_authority);
+            }
+            TokenInstruction::MintTo { amount } => {
+                data.push(7);
+                data.extend_from_slice(&amount.to_le_bytes());
+            }
+            TokenInstruction::Burn { amount } => {
+                data.push(8);
+                data.extend_from_slice(&amount.to_le_bytes());
+            }
+            TokenInstruction::CloseAccount => data.push(9),
+        }
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, InstructionError> {
+        let (&tag, rest) = data
+            .split_first()
+            .ok_or(InstructionError::InvalidInstructionData)?;
+        let amount = || -> Result<u64, InstructionError> {
+            let bytes: [u8; 8] = rest
+                .try_into()
+                .map_err(|_| InstructionError::InvalidInstructionData)?;
+            Ok(u64::from_le_bytes(bytes))
+        };
+
+        let instruction = match tag {
+            0 => {
+                let (&decimals, rest) = rest
+                    .split_first()
+                    .ok_or(InstructionError::InvalidInstructionData)?;
+                let mint_authority = rest
+                    .get(..PUBKEY_BYTES)
+                    .ok_or(InstructionError::InvalidInstructionData)?;
+                TokenInstruction::InitializeMint {
+                    decimals,
+                    mint_authority: Pubkey::new(mint_authority.try_into().unwrap()),
+                    freeze_authority: read_pubkey_option(&rest[PUBKEY_BYTES..])?,
+                }
+            }
+            1 if rest.is_empty() => TokenInstruction::InitializeAccount,
+            2 => match rest {
+                [m] => TokenInstruction::InitializeMultisig { m: *m },
+                _ => return Err(InstructionError::InvalidInstructionData),
+            },
+            3 => TokenInstruction::Transfer { amount: amount()? },
+            6 => {
+                let (&authority_type, rest) = rest
+                    .split_first()
+                    .ok_or(InstructionError::InvalidInstructionData)?;
+                let authority_type = match authority_type {
+                    0 => AuthorityType::MintTokens,
+                    1 => AuthorityType::FreezeAccount,
+                    2 => AuthorityType::AccountOwner,
+                    3 => AuthorityType::CloseAccount,
+                    _ => return Err(InstructionError::InvalidInstructionData),
+                };
+                TokenInstruction::SetAuthority {
+                    authority_type,
+                    new_authority: read_pubkey_option(rest)?,
+                }
+            }
+            7 => TokenInstruction::MintTo { amount: amount()? },
+            8 => TokenInstruction::Burn { amount: amount()? },
+            9 if rest.is_empty() => TokenInstruction::CloseAccount,
+            _ => return Err(InstructionError::InvalidInstructionData),
+        };
+        Ok(instruction)
+    }
+}
+
+// In instruction data an optional key is a one-byte tag, then the key
+// only if present
+fn push_pubkey_option(data: &mut Vec<u8>, value: &Option<Pubkey>) {
+    match value {
+        Some(pubkey) => {
+            data.push(1);
+            data.extend_from_slice(pubkey.as_ref());
+        }
+        None => data.push(0),
+    }
+}
+
+fn read_pubkey_option(data: &[u8]) -> Result<Option<Pubkey>, InstructionError> {
+    match data {
+        [0] => Ok(None),
+        [1, key @ ..] if key.len() == PUBKEY_BYTES => {
+            Ok(Some(Pubkey::new(key.try_into().unwrap())))
+        }
+        _ => Err(InstructionError::InvalidInstructionData),
+    }
+}
+
+// ----------------------------------------
+// Instruction Constructors
+// ----------------------------------------
+
+// `authority` signs itself when `signers` is empty; otherwise it is a
+// multisig and `signers` are the members approving the instruction
+fn authority_metas(authority: &Pubkey, signers: &[&Pubkey]) -> Vec<AccountMeta> {
+    let mut metas = vec![AccountMeta::new_readonly(*authority, signers.is_empty())];
+    metas.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(**signer, true)),
+    );
+    metas
+}
+
+pub fn initialize_mint(
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+    decimals: u8,
+) -> Instruction {
+    let data = TokenInstruction::InitializeMint {
+        decimals,
+        mint_authority: *mint_authority,
+        freeze_authority: freeze_authority.copied(),
+    }
+    .pack();
+    Instruction::new(ID, &data, vec![AccountMeta::new(*mint, false)])
+}
+
+pub fn initialize_account(account: &Pubkey, mint: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction::new(
+        ID,
+        &TokenInstruction::InitializeAccount.pack(),
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*owner, false),
+        ],
+    )
+}
+
+pub fn initialize_multisig(multisig: &Pubkey, members: &[&Pubkey], m: u8) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*multisig, false)];
+    accounts.extend(
+        members
+            .iter()
+            .map(|member| AccountMeta::new_readonly(**member, false)),
+    );
+    Instruction::new(
+        ID,
+        &TokenInstruction::InitializeMultisig { m }.pack(),
+        accounts,
+    )
+}
+
+pub fn transfer(
+    source: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    signers: &[&Pubkey],
+    amount: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+    ];
+    accounts.extend(authority_metas(authority, signers));
+    Instruction::new(ID, &TokenInstruction::Transfer { amount }.pack(), accounts)
+}
+
+pub fn set_authority(
+    target: &Pubkey,
+    new_authority: Option<&Pubkey>,
+    authority_type: AuthorityType,
+    authority: &Pubkey,
+    signers: &[&Pubkey],
+) -> Instruction {
+    let data = TokenInstruction::SetAuthority {
+        authority_type,
+        new_authority: new_authority.copied(),
+    }
+    .pack();
+    let mut accounts = vec![AccountMeta::new(*target, false)];
+    accounts.extend(authority_metas(authority, signers));
+    Instruction::new(ID, &data, accounts)
+}
+
+pub fn mint_to(
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    signers: &[&Pubkey],
+    amount: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*mint, false),
+        AccountMeta::new(*destination, false),
+    ];
+    accounts.extend(authority_metas(authority, signers));
+    Instruction::new(ID, &TokenInstruction::MintTo { amount }.pack(), accounts)
+}
+
+pub fn burn(
+    account: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    signers: &[&Pubkey],
+    amount: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*account, false),
+        AccountMeta::new(*mint, false),
+    ];
+    accounts.extend(authority_metas(authority, signers));
+    Instruction::new(ID, &TokenInstruction::Burn { amount }.pack(), accounts)
+}
+
+pub fn close_account(
+    account: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    signers: &[&Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*account, false),
+        AccountMeta::new(*destination, false),
+    ];
+    accounts.extend(authority_metas(authority, signers));
+    Instruction::new(ID, &TokenInstruction::CloseAccount.pack(), accounts)
+}
+
+// ----------------------------------------
+// Processor
+// ----------------------------------------
+
+pub struct TokenProgram;
+
+impl Program for TokenProgram {
+    fn id(&self) -> Pubkey {
+        ID
+    }
+
+    fn name(&self) -> &'static str {
+        "spl_token"
+    }
+
+    // About what a transfer costs the on-chain program
+    fn compute_units(&self) -> u64 {
+        4_500
+    }
+
+    fn process_instruction(
+        &self,
+        ctx: &mut InvokeContext,
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        match TokenInstruction::unpack(data)? {
+            TokenInstruction::InitializeMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+            } => {
+                ctx.log("Instruction: InitializeMint");
+                let mut mint = Mint::unpack(owned_data(ctx, 0)?)?;
+                if mint.is_initialized {
+                    return Err(TokenError::AlreadyInUse.into());
+                }
+                mint.mint_authority = Some(mint_authority);
+                mint.decimals = decimals;
+                mint.is_initialized = true;
+                mint.freeze_authority = freeze_authority;
+                ctx.account_mut(0)?.data = mint.pack().to_vec();
+                Ok(())
+            }
+            TokenInstruction::InitializeAccount => {
+                ctx.log("Instruction: InitializeAccount");
+                let mut account = TokenAccount::unpack(owned_data(ctx, 0)?)?;
+                if account.state != AccountState::Uninitialized {
+                    return Err(TokenError::AlreadyInUse.into());
+                }
+                Mint::unpack_initialized(owned_data(ctx, 1)?)?;
+                account.mint = *ctx.key(1)?;
+                account.owner = *ctx.key(2)?;
+                account.state = AccountState::Initialized;
+                ctx.account_mut(0)?.data = account.pack().to_vec();
+                Ok(())
+            }
+            TokenInstruction::InitializeMultisig { m } => {
+                ctx.log("Instruction: InitializeMultisig");
+                let data = owned_data(ctx, 0)?;
+                if data.len() != MULTISIG_LEN {
+                    return Err(InstructionError::InvalidAccountData);
+                }
+                if Multisig::unpack(data).is_some() {
+                    return Err(TokenError::AlreadyInUse.into());
+                }
+                let members = (1..ctx.num_accounts())
+                    .map(|i| ctx.key(i).copied())
+                    .collect::<Result<Vec<Pubkey>, InstructionError>>()?;
+                let multisig = Multisig::new(m, &members)?;
+                ctx.account_mut(0)?.data = multisig.pack().to_vec();
+                Ok(())
+            }
+            TokenInstruction::Transfer { amount } => {
+                ctx.log("Instruction: Transfer");
+                process_transfer(ctx, amount)
+            }
+            TokenInstruction::SetAuthority {
+                authority_type,
+                new_authority,
+            } => {
+                ctx.log("Instruction: SetAuthority");
+                process_set_authority(ctx, authority_type, new_authority)
+            }
+            TokenInstruction::MintTo { amount } => {
+                ctx.log("Instruction: MintTo");
+                let mut mint = Mint::unpack_initialized(owned_data(ctx, 0)?)?;
+                let mut destination = TokenAccount::unpack_initialized(owned_data(ctx, 1)?)?;
+                if destination.mint != *ctx.key(0)? {
+                    return Err(TokenError::MintMismatch.into());
+                }
+                let authority = mint.mint_authority.ok_or(TokenError::FixedSupply)?;
+                multisig::check_authority(ctx, &authority, 2, 3, &ID)?;
+
+                mint.supply = mint
+                    .supply
+                    .checked_add(amount)
+                    .ok_or(TokenError::Overflow)?;
+                destination.amount = destination
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(TokenError::Overflow)?;
+                ctx.account_mut(0)?.data = mint.pack().to_vec();
+                ctx.account_mut(1)?.data = destination.pack().to_vec();
+                Ok(())
+            }
+            TokenInstruction::Burn { amount } => {
+                ctx.log("Instruction: Burn");
+                let mut account = TokenAccount::unpack_initialized(owned_data(ctx, 0)?)?;
+                let mut mint = Mint::unpack_initialized(owned_data(ctx, 1)?)?;
+                if account.mint != *ctx.key(1)? {
+                    return Err(TokenError::MintMismatch.into());
+                }
+                multisig::check_authority(ctx, &account.owner, 2, 3, &ID)?;
+                account.amount = account
+                    .amount
+                    .checked_sub(amount)
+                    .ok_or(TokenError::InsufficientBalance)?;
+                mint.supply = mint
+                    .supply
+                    .checked_sub(amount)
+                    .ok_or(TokenError::Overflow)?;
+                ctx.account_mut(0)?.data = account.pack().to_vec();
+                ctx.account_mut(1)?.data = mint.pack().to_vec();
+                Ok(())
+            }
+            TokenInstruction::CloseAccount => {
+                ctx.log("Instruction: CloseAccount");
+                let account = TokenAccount::unpack_initialized(owned_data(ctx, 0)?)?;
+                if account.amount != 0 {
+                    return Err(TokenError::NonNativeHasBalance.into());
+                }
+                let authority = account.close_authority.unwrap_or(account.owner);
+                multisig::check_authority(ctx, &authority, 2, 3, &ID)?;
+
+                let lamports = ctx.account(0)?.lamports;
+                ctx.transfer_lamports(0, 1, lamports)?;
+                ctx.account_mut(0)?.data.fill(0);
+                Ok(())
+            }
+        }
+    }
+}
+
+// Data of account `i`, which must belong to this program
+fn owned_data<'c>(ctx: &'c InvokeContext, i: usize) -> Result<&'c [u8], InstructionError> {
+    let account = ctx.account(i)?;
+    if account.owner != ID {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+    Ok(&account.data)
+}
+
+// For programs that are passed token accounts and mints: the state of
+// account `i`, checking that this program owns it
+pub fn token_account(ctx: &InvokeContext, i: usize) -> Result<TokenAccount, InstructionError> {
+    TokenAccount::unpack_initialized(owned_data(ctx, i)?)
+}
+
+pub fn mint(ctx: &InvokeContext, i: usize) -> Result<Mint, InstructionError> {
+    Mint::unpack_initialized(owned_data(ctx, i)?)
+}
+
+fn process_transfer(ctx: &mut InvokeContext, amount: u64) -> Result<(), InstructionError> {
+    let mut source = TokenAccount::unpack_initialized(owned_data(ctx, 0)?)?;
+    let mut destination = TokenAccount::unpack_initialized(owned_data(ctx, 1)?)?;
+    if source.mint != destination.mint {
+        return Err(TokenError::MintMismatch.into());
+    }
+    multisig::check_authority(ctx, &source.owner, 2, 3, &ID)?;
+    if source.amount < amount {
+        return Err(TokenError::InsufficientBalance.into());
+    }
+    // A transfer to the same account changes nothing
+    if ctx.key(0)? == ctx.key(1)? {
+        return Ok(());
+    }
+
+    source.amount -= amount;
+    destination.amount = destination
+        .amount
+        .checked_add(amount)
+        .ok_or(TokenError::Overflow)?;
+    ctx.account_mut(0)?.data = source.pack().to_vec();
+    ctx.account_mut(1)?.data = destination.pack().to_vec();
+    Ok(())
+}
+
+fn process_set_authority(
+    ctx: &mut InvokeContext,
+    authority_type: AuthorityType,
+    new_authority: Option<Pubkey>,
+) -> Result<(), InstructionError> {
+    let data = owned_data(ctx, 0)?;
+    if data.len() == MINT_LEN {
+        let mut mint = Mint::unpack_initialized(data)?;
+        let slot = match authority_type {
+            AuthorityType::MintTokens => &mut mint.mint_authority,
+            AuthorityType::FreezeAccount => &mut mint.freeze_authority,
+            _ => return Err(TokenError::AuthorityTypeNotSupported.into()),
+        };
+        // Once removed, an authority can never be set again
+        let current = slot.ok_or(TokenError::FixedSupply)?;
+        multisig::check_authority(ctx, &current, 1, 2, &ID)?;
+        *slot = new_authority;
+        ctx.account_mut(0)?.data = mint.pack().to_vec();
+    } else {
+        let mut account = TokenAccount::unpack_initialized(data)?;
+        match authority_type {
+            AuthorityType::AccountOwner => {
+                multisig::check_authority(ctx, &account.owner, 1, 2, &ID)?;
+                account.owner = new_authority.ok_or(InstructionError::InvalidArgument)?;
+            }
+            AuthorityType::CloseAccount => {
+                let current = account.close_authority.unwrap_or(account.owner);
+                multisig::check_authority(ctx, &current, 1, 2, &ID)?;
+                account.close_authority = new_authority;
+            }
+            _ => return Err(TokenError::AuthorityTypeNotSupported.into()),
+        }
+        ctx.account_mut(0)?.data = account.pack().to_vec();
+    }
+    Ok(())
+}
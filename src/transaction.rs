@@ -0,0 +1,939 @@
+// ========================================================================
+// INSTRUCTIONS, MESSAGES AND TRANSACTIONS
+// ========================================================================
+//
+// An `Instruction` names a program, the accounts it touches and some
+// opaque data. A `Message` compiles one or more instructions into the
+// compact form that is signed and sent over the wire: every account key
+// appears once, ordered so that three small counts in the header say
+// which keys sign and which are writable:
+//
+//     [ writable signers | readonly signers | writable | readonly ]
+//       ^ fee payer
+//
+// A `Transaction` is that message plus one signature per required signer.
+// (Not to be confused with the `Transaction` trait in section 7, which
+// only validates string fields.)
+//
+// A v0 `VersionedMessage` adds address table lookups: accounts named by
+// a one-byte index into an on-chain lookup table instead of a full
+// 32-byte key, so a transaction can touch far more accounts before it
+// outgrows a 1232-byte packet.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ed25519;
+use crate::hash::{hashv, Hash};
+use crate::keypair::Keypair;
+use crate::pubkey::{decode_base58, encode_base58, Pubkey};
+
+pub const SIGNATURE_BYTES: usize = 64;
+
+// ----------------------------------------
+// Instructions
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AccountMeta {
+    pub fn new(pubkey: Pubkey, is_signer: bool) -> Self {
+        AccountMeta {
+            pubkey,
+            is_signer,
+            is_writable: true,
+        }
+    }
+
+    pub fn new_readonly(pubkey: Pubkey, is_signer: bool) -> Self {
+        AccountMeta {
+            pubkey,
+            is_signer,
+            is_writable: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl Instruction {
+    pub fn new(program_id: Pubkey, data: &[u8], accounts: Vec<AccountMeta>) -> Self {
+        Instruction {
+            program_id,
+            accounts,
+            data: data.to_vec(),
+        }
+    }
+}
+
+// ----------------------------------------
+// Messages
+// ----------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageHeader {
+    pub num_required_signatures: u8,
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+}
+
+// An instruction with its program and accounts replaced by indexes into
+// the message's account_keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Message {
+    pub header: MessageHeader,
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Hash,
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+impl Message {
+    // Compile instructions with a zero blockhash; set the real one when
+    // signing
+    pub fn new(instructions: &[Instruction], payer: Option<&Pubkey>) -> Self {
+        Message::new_with_blockhash(instructions, payer, &Hash::default())
+    }
+
+    pub fn new_with_blockhash(
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        blockhash: &Hash,
+    ) -> Self {
+        let keys = compile_keys(instructions, payer);
+        assert!(
+            keys.len() <= 256,
+            "a message can reference at most 256 accounts"
+        );
+        let account_keys: Vec<Pubkey> = keys.iter().map(|k| k.pubkey).collect();
+        Message {
+            header: compile_header(&keys),
+            instructions: compile_instructions(instructions, &account_keys),
+            account_keys,
+            recent_blockhash: *blockhash,
+        }
+    }
+
+    pub fn fee_payer(&self) -> Option<&Pubkey> {
+        self.account_keys.first()
+    }
+
+    pub fn signer_keys(&self) -> &[Pubkey] {
+        let signers = (self.header.num_required_signatures as usize).min(self.account_keys.len());
+        &self.account_keys[..signers]
+    }
+
+    pub fn is_signer(&self, index: usize) -> bool {
+        index < self.header.num_required_signatures as usize
+    }
+
+    pub fn is_writable(&self, index: usize) -> bool {
+        let signers = self.header.num_required_signatures as usize;
+        if index < signers {
+            index < signers - self.header.num_readonly_signed_accounts as usize
+        } else {
+            let readonly = self.header.num_readonly_unsigned_accounts as usize;
+            index < self.account_keys.len().saturating_sub(readonly)
+        }
+    }
+
+    pub fn program_id(&self, instruction: &CompiledInstruction) -> Option<&Pubkey> {
+        self.account_keys.get(instruction.program_id_index as usize)
+    }
+
+    // The wire format: header, keys, blockhash, then the instructions,
+    // with every length written as a compact-u16
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = vec![
+            self.header.num_required_signatures,
+            self.header.num_readonly_signed_accounts,
+            self.header.num_readonly_unsigned_accounts,
+        ];
+        write_compact_u16(&mut out, self.account_keys.len());
+        for key in &self.account_keys {
+            out.extend_from_slice(key.as_ref());
+        }
+        out.extend_from_slice(self.recent_blockhash.as_ref());
+        write_compact_u16(&mut out, self.instructions.len());
+        for instruction in &self.instructions {
+            out.push(instruction.program_id_index);
+            write_compact_u16(&mut out, instruction.accounts.len());
+            out.extend_from_slice(&instruction.accounts);
+            write_compact_u16(&mut out, instruction.data.len());
+            out.extend_from_slice(&instruction.data);
+        }
+        out
+    }
+}
+
+// Every key once, in first-seen order with its flags merged, then
+// stably partitioned into the four groups; the payer stays first
+fn compile_keys(instructions: &[Instruction], payer: Option<&Pubkey>) -> Vec<AccountMeta> {
+    let mut keys: Vec<AccountMeta> = Vec::new();
+    let mut add = |meta: AccountMeta| match keys.iter_mut().find(|k| k.pubkey == meta.pubkey) {
+        Some(existing) => {
+            existing.is_signer |= meta.is_signer;
+            existing.is_writable |= meta.is_writable;
+        }
+        None => keys.push(meta),
+    };
+    if let Some(payer) = payer {
+        add(AccountMeta::new(*payer, true));
+    }
+    for instruction in instructions {
+        for meta in &instruction.accounts {
+            add(meta.clone());
+        }
+        add(AccountMeta::new_readonly(instruction.program_id, false));
+    }
+
+    let group = |meta: &AccountMeta| match (meta.is_signer, meta.is_writable) {
+        (true, true) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (false, false) => 3,
+    };
+    keys.sort_by_key(group);
+    keys
+}
+
+// The counts for keys already in group order
+fn compile_header(keys: &[AccountMeta]) -> MessageHeader {
+    let count = |signer: bool, writable: bool| {
+        keys.iter()
+            .filter(|k| k.is_signer == signer && k.is_writable == writable)
+            .count() as u8
+    };
+    MessageHeader {
+        num_required_signatures: keys.iter().filter(|k| k.is_signer).count() as u8,
+        num_readonly_signed_accounts: count(true, false),
+        num_readonly_unsigned_accounts: count(false, false),
+    }
+}
+
+// Replace keys with their positions in `account_keys`, which must hold
+// every key the instructions use
+fn compile_instructions(
+    instructions: &[Instruction],
+    account_keys: &[Pubkey],
+) -> Vec<CompiledInstruction> {
+    let index_of = |pubkey: &Pubkey| account_keys.iter().position(|k| k == pubkey).unwrap() as u8;
+    instructions
+        .iter()
+        .map(|instruction| CompiledInstruction {
+            program_id_index: index_of(&instruction.program_id),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| index_of(&meta.pubkey))
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect()
+}
+
+// Seven bits per byte, high bit set while more bytes follow: lengths
+// under 128 cost a single byte
+pub fn write_compact_u16(out: &mut Vec<u8>, value: usize) {
+    assert!(value <= u16::MAX as usize, "compact-u16 value too large");
+    let mut value = value as u16;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// ----------------------------------------
+// Decoding the Wire Format
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    UnexpectedEnd,
+    // Overlong, or more than 16 bits
+    InvalidCompactU16,
+    TrailingBytes(usize),
+    // A versioned message other than v0
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            DecodeError::InvalidCompactU16 => write!(f, "invalid compact-u16 length"),
+            DecodeError::TrailingBytes(count) => {
+                write!(f, "{} unexpected bytes after the end", count)
+            }
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported message version {}", version)
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        let bytes = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    // At most three bytes, each one needed (no trailing zero groups)
+    fn compact_u16(&mut self) -> Result<usize, DecodeError> {
+        let mut value = 0usize;
+        for i in 0..3 {
+            let byte = self.u8()?;
+            if i > 0 && byte == 0 {
+                return Err(DecodeError::InvalidCompactU16);
+            }
+            value |= ((byte & 0x7f) as usize) << (i * 7);
+            if byte & 0x80 == 0 {
+                return u16::try_from(value)
+                    .map(|value| value as usize)
+                    .map_err(|_| DecodeError::InvalidCompactU16);
+            }
+        }
+        Err(DecodeError::InvalidCompactU16)
+    }
+
+    fn message(&mut self) -> Result<Message, DecodeError> {
+        let header = MessageHeader {
+            num_required_signatures: self.u8()?,
+            num_readonly_signed_accounts: self.u8()?,
+            num_readonly_unsigned_accounts: self.u8()?,
+        };
+        let account_keys = (0..self.compact_u16()?)
+            .map(|_| self.array().map(Pubkey::new))
+            .collect::<Result<_, _>>()?;
+        let recent_blockhash = Hash::new(self.array()?);
+        let instructions = (0..self.compact_u16()?)
+            .map(|_| {
+                let program_id_index = self.u8()?;
+                let len = self.compact_u16()?;
+                let accounts = self.take(len)?.to_vec();
+                let len = self.compact_u16()?;
+                let data = self.take(len)?.to_vec();
+                Ok(CompiledInstruction {
+                    program_id_index,
+                    accounts,
+                    data,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Message {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+        })
+    }
+
+    // A legacy message, or a version prefix and the message it announces
+    fn versioned_message(&mut self) -> Result<VersionedMessage, DecodeError> {
+        let prefix = match self.bytes.get(self.pos) {
+            Some(&byte) if byte & MESSAGE_VERSION_PREFIX != 0 => byte,
+            _ => return self.message().map(VersionedMessage::Legacy),
+        };
+        self.pos += 1;
+        let version = prefix & !MESSAGE_VERSION_PREFIX;
+        if version != 0 {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let message = self.message()?;
+        let address_table_lookups = (0..self.compact_u16()?)
+            .map(|_| {
+                let account_key = Pubkey::new(self.array()?);
+                let len = self.compact_u16()?;
+                let writable_indexes = self.take(len)?.to_vec();
+                let len = self.compact_u16()?;
+                let readonly_indexes = self.take(len)?.to_vec();
+                Ok(MessageAddressTableLookup {
+                    account_key,
+                    writable_indexes,
+                    readonly_indexes,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(VersionedMessage::V0(MessageV0 {
+            header: message.header,
+            account_keys: message.account_keys,
+            recent_blockhash: message.recent_blockhash,
+            instructions: message.instructions,
+            address_table_lookups,
+        }))
+    }
+
+    fn finish(&self) -> Result<(), DecodeError> {
+        match self.bytes.len() - self.pos {
+            0 => Ok(()),
+            extra => Err(DecodeError::TrailingBytes(extra)),
+        }
+    }
+}
+
+// ----------------------------------------
+// Signatures and Signers
+// ----------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Signature(pub [u8; SIGNATURE_BYTES]);
+
+impl Default for Signature {
+    fn default() -> Self {
+        Signature([0; SIGNATURE_BYTES])
+    }
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", encode_base58(&self.0))
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Signature({})", self)
+    }
+}
+
+impl FromStr for Signature {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_base58(text).map_err(|err| err.to_string())?;
+        let bytes: [u8; SIGNATURE_BYTES] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| format!("signature must be 64 bytes, got {}", bytes.len()))?;
+        Ok(Signature(bytes))
+    }
+}
+
+impl Signature {
+    pub fn verify(&self, pubkey: &Pubkey, message: &[u8]) -> bool {
+        ed25519::verify(&pubkey.to_bytes(), message, &self.0)
+    }
+}
+
+pub trait Signer {
+    fn pubkey(&self) -> Pubkey;
+    fn sign_message(&self, message: &[u8]) -> Signature;
+}
+
+// A keypair whose secret is derived from a name, so demos can say
+// DemoSigner::new("alice") and get the same key every run. The signatures
+// are real ed25519 ones, but anyone who knows the name can make them.
+#[derive(Debug, Clone)]
+pub struct DemoSigner {
+    keypair: Keypair,
+}
+
+impl DemoSigner {
+    pub fn new(name: &str) -> Self {
+        DemoSigner {
+            keypair: Keypair::from_seed(&hashv(&[b"demo signer", name.as_bytes()]).to_bytes()),
+        }
+    }
+}
+
+impl Signer for DemoSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Signature {
+        self.keypair.sign_message(message)
+    }
+}
+
+// Where each required signer is in `signers`, which must hold exactly the
+// required ones
+fn signer_positions(
+    required: &[Pubkey],
+    signers: &[&dyn Signer],
+) -> Result<Vec<usize>, SignerError> {
+    for signer in signers {
+        if !required.contains(&signer.pubkey()) {
+            return Err(SignerError::UnexpectedSigner(signer.pubkey()));
+        }
+    }
+    required
+        .iter()
+        .map(|key| {
+            signers
+                .iter()
+                .position(|signer| signer.pubkey() == *key)
+                .ok_or(SignerError::MissingSigner(*key))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerError {
+    // A required signer was not among the keys supplied
+    MissingSigner(Pubkey),
+    // A key was supplied that the message does not need
+    UnexpectedSigner(Pubkey),
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignerError::MissingSigner(pubkey) => write!(f, "missing signature for {}", pubkey),
+            SignerError::UnexpectedSigner(pubkey) => {
+                write!(f, "{} is not a required signer of this message", pubkey)
+            }
+        }
+    }
+}
+
+// ----------------------------------------
+// Transactions
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub signatures: Vec<Signature>,
+    pub message: Message,
+}
+
+impl Transaction {
+    // Every signature slot starts zeroed
+    pub fn new_unsigned(message: Message) -> Self {
+        Transaction {
+            signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+            message,
+        }
+    }
+
+    // Panics if `signers` does not match the message's required signers;
+    // use try_sign to handle that as an error
+    pub fn new_signed_with_payer(
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&dyn Signer],
+        recent_blockhash: Hash,
+    ) -> Self {
+        let mut transaction = Transaction::new_unsigned(Message::new(instructions, payer));
+        if let Err(err) = transaction.try_sign(signers, recent_blockhash) {
+            panic!("cannot sign transaction: {}", err);
+        }
+        transaction
+    }
+
+    pub fn try_sign(
+        &mut self,
+        signers: &[&dyn Signer],
+        recent_blockhash: Hash,
+    ) -> Result<(), SignerError> {
+        let positions = signer_positions(self.message.signer_keys(), signers)?;
+        self.message.recent_blockhash = recent_blockhash;
+        let message = self.message.serialize();
+        self.signatures = positions
+            .into_iter()
+            .map(|i| signers[i].sign_message(&message))
+            .collect();
+        Ok(())
+    }
+
+    // The first signature identifies the transaction
+    pub fn signature(&self) -> Signature {
+        self.signatures.first().copied().unwrap_or_default()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_compact_u16(&mut out, self.signatures.len());
+        for signature in &self.signatures {
+            out.extend_from_slice(signature.as_ref());
+        }
+        out.extend_from_slice(&self.message.serialize());
+        out
+    }
+}
+
+// ----------------------------------------
+// Versioned Messages
+// ----------------------------------------
+//
+// A legacy message spells out every account as a 32-byte key, so a
+// transaction fits only a few dozen accounts into one 1232-byte packet.
+// A v0 message can load accounts from address lookup tables instead (see
+// address_lookup_table_program.rs), at one byte each:
+//
+//     0x80 | version     a legacy message never starts this high, since
+//                        that first byte counts its signers
+//     header, static keys, blockhash, instructions    as before
+//     lookups            per table: its address, then the indexes of the
+//                        writable and of the readonly accounts to load
+//
+// Instructions index into the static keys, followed by the writable
+// addresses of every lookup, then the readonly ones. Signers and program
+// ids are always static keys.
+
+pub const PACKET_DATA_SIZE: usize = 1232;
+pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageAddressTableLookup {
+    pub account_key: Pubkey,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+// A table's address and contents as a client saw them when compiling a
+// message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressLookupTableAccount {
+    pub key: Pubkey,
+    pub addresses: Vec<Pubkey>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageV0 {
+    pub header: MessageHeader,
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Hash,
+    pub instructions: Vec<CompiledInstruction>,
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+impl MessageV0 {
+    // Like Message::new_with_blockhash, except that a key found in one of
+    // `lookup_tables` is loaded from it, unless it signs or is a program
+    // id. Earlier tables are searched first.
+    pub fn compile(
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        lookup_tables: &[AddressLookupTableAccount],
+        blockhash: &Hash,
+    ) -> Self {
+        let mut keys = compile_keys(instructions, Some(payer));
+        let is_program = |key: &Pubkey| instructions.iter().any(|ix| ix.program_id == *key);
+        let mut loaded = LoadedAddresses::default();
+        let mut address_table_lookups = Vec::new();
+        for table in lookup_tables {
+            let mut lookup = MessageAddressTableLookup {
+                account_key: table.key,
+                writable_indexes: Vec::new(),
+                readonly_indexes: Vec::new(),
+            };
+            keys.retain(|meta| {
+                let position = table.addresses.iter().position(|a| *a == meta.pubkey);
+                let index = match position.and_then(|i| u8::try_from(i).ok()) {
+                    Some(index) if !meta.is_signer && !is_program(&meta.pubkey) => index,
+                    _ => return true,
+                };
+                if meta.is_writable {
+                    lookup.writable_indexes.push(index);
+                    loaded.writable.push(meta.pubkey);
+                } else {
+                    lookup.readonly_indexes.push(index);
+                    loaded.readonly.push(meta.pubkey);
+                }
+                false
+            });
+            if !lookup.writable_indexes.is_empty() || !lookup.readonly_indexes.is_empty() {
+                address_table_lookups.push(lookup);
+            }
+        }
+
+        let account_keys: Vec<Pubkey> = keys.iter().map(|k| k.pubkey).collect();
+        let all_keys = [&account_keys[..], &loaded.writable, &loaded.readonly].concat();
+        assert!(
+            all_keys.len() <= 256,
+            "a message can reference at most 256 accounts"
+        );
+        MessageV0 {
+            header: compile_header(&keys),
+            instructions: compile_instructions(instructions, &all_keys),
+            account_keys,
+            recent_blockhash: *blockhash,
+            address_table_lookups,
+        }
+    }
+
+    // Everything but the lookups, laid out as a legacy message
+    pub fn static_message(&self) -> Message {
+        Message {
+            header: self.header,
+            account_keys: self.account_keys.clone(),
+            recent_blockhash: self.recent_blockhash,
+            instructions: self.instructions.clone(),
+        }
+    }
+
+    // The version prefix, the legacy layout, then the lookups
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = vec![MESSAGE_VERSION_PREFIX];
+        out.extend_from_slice(&self.static_message().serialize());
+        write_compact_u16(&mut out, self.address_table_lookups.len());
+        for lookup in &self.address_table_lookups {
+            out.extend_from_slice(lookup.account_key.as_ref());
+            write_compact_u16(&mut out, lookup.writable_indexes.len());
+            out.extend_from_slice(&lookup.writable_indexes);
+            write_compact_u16(&mut out, lookup.readonly_indexes.len());
+            out.extend_from_slice(&lookup.readonly_indexes);
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionedMessage {
+    Legacy(Message),
+    V0(MessageV0),
+}
+
+impl VersionedMessage {
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            VersionedMessage::Legacy(message) => &message.header,
+            VersionedMessage::V0(message) => &message.header,
+        }
+    }
+
+    // The keys written out in the message, without any loaded ones
+    pub fn static_account_keys(&self) -> &[Pubkey] {
+        match self {
+            VersionedMessage::Legacy(message) => &message.account_keys,
+            VersionedMessage::V0(message) => &message.account_keys,
+        }
+    }
+
+    pub fn recent_blockhash(&self) -> &Hash {
+        match self {
+            VersionedMessage::Legacy(message) => &message.recent_blockhash,
+            VersionedMessage::V0(message) => &message.recent_blockhash,
+        }
+    }
+
+    pub fn set_recent_blockhash(&mut self, blockhash: Hash) {
+        match self {
+            VersionedMessage::Legacy(message) => message.recent_blockhash = blockhash,
+            VersionedMessage::V0(message) => message.recent_blockhash = blockhash,
+        }
+    }
+
+    pub fn instructions(&self) -> &[CompiledInstruction] {
+        match self {
+            VersionedMessage::Legacy(message) => &message.instructions,
+            VersionedMessage::V0(message) => &message.instructions,
+        }
+    }
+
+    // Always empty for a legacy message
+    pub fn address_table_lookups(&self) -> &[MessageAddressTableLookup] {
+        match self {
+            VersionedMessage::Legacy(_) => &[],
+            VersionedMessage::V0(message) => &message.address_table_lookups,
+        }
+    }
+
+    pub fn signer_keys(&self) -> &[Pubkey] {
+        let keys = self.static_account_keys();
+        &keys[..(self.header().num_required_signatures as usize).min(keys.len())]
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        match self {
+            VersionedMessage::Legacy(message) => message.serialize(),
+            VersionedMessage::V0(message) => message.serialize(),
+        }
+    }
+}
+
+// The addresses a v0 message's lookups resolved to, in lookup order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadedAddresses {
+    pub writable: Vec<Pubkey>,
+    pub readonly: Vec<Pubkey>,
+}
+
+// A message with every account it uses spelled out: the static keys,
+// then the loaded writable and loaded readonly addresses. This is the
+// form the bank runs, whichever version the message was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedMessage {
+    message: Message,
+    loaded_addresses: LoadedAddresses,
+    account_keys: Vec<Pubkey>,
+}
+
+impl LoadedMessage {
+    pub fn new(message: &VersionedMessage, loaded_addresses: LoadedAddresses) -> Self {
+        let message = match message {
+            VersionedMessage::Legacy(message) => message.clone(),
+            VersionedMessage::V0(message) => message.static_message(),
+        };
+        let account_keys = [
+            &message.account_keys[..],
+            &loaded_addresses.writable,
+            &loaded_addresses.readonly,
+        ]
+        .concat();
+        LoadedMessage {
+            message,
+            loaded_addresses,
+            account_keys,
+        }
+    }
+
+    // The header, static keys and instructions, as a legacy message. Fees
+    // and compute budgets are read from this part alone.
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn loaded_addresses(&self) -> &LoadedAddresses {
+        &self.loaded_addresses
+    }
+
+    pub fn account_keys(&self) -> &[Pubkey] {
+        &self.account_keys
+    }
+
+    pub fn is_signer(&self, index: usize) -> bool {
+        self.message.is_signer(index)
+    }
+
+    // Loaded accounts are writable if their lookup said so
+    pub fn is_writable(&self, index: usize) -> bool {
+        let static_keys = self.message.account_keys.len();
+        if index < static_keys {
+            self.message.is_writable(index)
+        } else {
+            index - static_keys < self.loaded_addresses.writable.len()
+        }
+    }
+
+    pub fn has_duplicate_keys(&self) -> bool {
+        let keys = &self.account_keys;
+        keys.iter()
+            .enumerate()
+            .any(|(i, key)| keys[i + 1..].contains(key))
+    }
+}
+
+// A transaction over either kind of message. Legacy transactions convert
+// into this losslessly, so the bank runs both the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionedTransaction {
+    pub signatures: Vec<Signature>,
+    pub message: VersionedMessage,
+}
+
+impl VersionedTransaction {
+    // Sign `message` as it is, recent blockhash included
+    pub fn try_new(
+        message: VersionedMessage,
+        signers: &[&dyn Signer],
+    ) -> Result<Self, SignerError> {
+        let positions = signer_positions(message.signer_keys(), signers)?;
+        let bytes = message.serialize();
+        let signatures = positions
+            .into_iter()
+            .map(|i| signers[i].sign_message(&bytes))
+            .collect();
+        Ok(VersionedTransaction {
+            signatures,
+            message,
+        })
+    }
+
+    pub fn signature(&self) -> Signature {
+        self.signatures.first().copied().unwrap_or_default()
+    }
+
+    pub fn verify(&self) -> bool {
+        let message = self.message.serialize();
+        self.signatures.len() == self.message.signer_keys().len()
+            && self
+                .signatures
+                .iter()
+                .zip(self.message.signer_keys())
+                .all(|(signature, pubkey)| signature.verify(pubkey, &message))
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_compact_u16(&mut out, self.signatures.len());
+        for signature in &self.signatures {
+            out.extend_from_slice(signature.as_ref());
+        }
+        out.extend_from_slice(&self.message.serialize());
+        out
+    }
+
+    // A transaction as sent over the wire, such as the payload of an RPC
+    // sendTransaction. Checking the signatures is left to the bank.
+    pub fn deserialize(bytes: &[u8]) -> Result<VersionedTransaction, DecodeError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let signatures = (0..reader.compact_u16()?)
+            .map(|_| reader.array().map(Signature))
+            .collect::<Result<_, _>>()?;
+        let message = reader.versioned_message()?;
+        reader.finish()?;
+        Ok(VersionedTransaction {
+            signatures,
+            message,
+        })
+    }
+}
+
+impl From<Transaction> for VersionedTransaction {
+    fn from(tx: Transaction) -> Self {
+        VersionedTransaction {
+            signatures: tx.signatures,
+            message: VersionedMessage::Legacy(tx.message),
+        }
+    }
+}
@@ -0,0 +1,328 @@
+// ========================================================================
+// BUILDING TRANSACTIONS
+// ========================================================================
+//
+// Instruction bytes in main.rs come from `instruction_data!(transfer,
+// 100)` and literals like `vec![2, 0, 0, 0, 100, ...]`, and it is up to
+// the caller to get every byte right. The instruction constructors in the
+// program modules (system_program::transfer, token_program::transfer, ...)
+// already produce a whole `Instruction`, data and account metas
+// included. `TransactionBuilder` takes it from there:
+//
+//     let tx = TransactionBuilder::new(&payer)
+//         .add(token_program::transfer(&src, &dst, &owner, &[], amount))
+//         .compute_unit_price(10_000)
+//         .recent_blockhash(blockhash)
+//         .sign(&[&payer, &owner])?;
+//
+// Compiling the message merges accounts that appear more than once, with
+// the strongest privileges any instruction asks for, and orders them the
+// way the header needs (see transaction.rs). Everything that would make
+// the transaction unusable is an error from `sign` rather than a panic or
+// a rejection from the cluster: no instructions or blockhash, more than
+// 256 accounts, a required signer missing or an extra one supplied, or a
+// result bigger than a packet. With lookup tables the builder produces a
+// v0 message, otherwise a legacy one.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::bank::Bank;
+use crate::compute_budget;
+use crate::hash::Hash;
+use crate::pubkey::Pubkey;
+use crate::system_program;
+use crate::transaction::{
+    AddressLookupTableAccount, DemoSigner, Instruction, Message, MessageV0, Signer, SignerError,
+    VersionedMessage, VersionedTransaction, PACKET_DATA_SIZE,
+};
+
+pub const MAX_ACCOUNTS: usize = 256;
+
+// ----------------------------------------
+// Errors
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    NoInstructions,
+    MissingBlockhash,
+    TooManyAccounts(usize),
+    Signer(SignerError),
+    // Serialized size in bytes
+    TooLarge(usize),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::NoInstructions => write!(f, "transaction has no instructions"),
+            BuildError::MissingBlockhash => write!(f, "no recent blockhash set"),
+            BuildError::TooManyAccounts(count) => write!(
+                f,
+                "transaction uses {} accounts (max: {})",
+                count, MAX_ACCOUNTS
+            ),
+            BuildError::Signer(err) => write!(f, "{}", err),
+            BuildError::TooLarge(size) => write!(
+                f,
+                "transaction too large: {} bytes (max: {} bytes)",
+                size, PACKET_DATA_SIZE
+            ),
+        }
+    }
+}
+
+impl From<SignerError> for BuildError {
+    fn from(err: SignerError) -> Self {
+        BuildError::Signer(err)
+    }
+}
+
+// ----------------------------------------
+// Builder
+// ----------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionBuilder {
+    payer: Pubkey,
+    instructions: Vec<Instruction>,
+    recent_blockhash: Option<Hash>,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+impl TransactionBuilder {
+    pub fn new(payer: &Pubkey) -> Self {
+        TransactionBuilder {
+            payer: *payer,
+            instructions: Vec::new(),
+            recent_blockhash: None,
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            lookup_tables: Vec::new(),
+        }
+    }
+
+    pub fn add(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    pub fn add_all(mut self, instructions: impl IntoIterator<Item = Instruction>) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    pub fn recent_blockhash(mut self, blockhash: Hash) -> Self {
+        self.recent_blockhash = Some(blockhash);
+        self
+    }
+
+    // Both compute budget settings become instructions in front of the
+    // others, however often they are set
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+
+    // Micro-lamports per compute unit
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    // Load accounts from this table where possible, making a v0 message
+    pub fn lookup_table(mut self, table: AddressLookupTableAccount) -> Self {
+        self.lookup_tables.push(table);
+        self
+    }
+
+    // Every instruction the message will hold, in order
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let budget = [
+            self.compute_unit_limit
+                .map(compute_budget::set_compute_unit_limit),
+            self.compute_unit_price
+                .map(compute_budget::set_compute_unit_price),
+        ];
+        budget
+            .into_iter()
+            .flatten()
+            .chain(self.instructions.iter().cloned())
+            .collect()
+    }
+
+    // The message to sign, checked for everything but its signers
+    pub fn message(&self) -> Result<VersionedMessage, BuildError> {
+        if self.instructions.is_empty() {
+            return Err(BuildError::NoInstructions);
+        }
+        let blockhash = self.recent_blockhash.ok_or(BuildError::MissingBlockhash)?;
+        let instructions = self.instructions();
+
+        let mut keys = HashSet::from([self.payer]);
+        for instruction in &instructions {
+            keys.insert(instruction.program_id);
+            keys.extend(instruction.accounts.iter().map(|meta| meta.pubkey));
+        }
+        if keys.len() > MAX_ACCOUNTS {
+            return Err(BuildError::TooManyAccounts(keys.len()));
+        }
+
+        Ok(if self.lookup_tables.is_empty() {
+            VersionedMessage::Legacy(Message::new_with_blockhash(
+                &instructions,
+                Some(&self.payer),
+                &blockhash,
+            ))
+        } else {
+            VersionedMessage::V0(MessageV0::compile(
+                &self.payer,
+                &instructions,
+                &self.lookup_tables,
+                &blockhash,
+            ))
+        })
+    }
+
+    // `signers` must be exactly the message's required signers, in any
+    // order
+    pub fn sign(&self, signers: &[&dyn Signer]) -> Result<VersionedTransaction, BuildError> {
+        let tx = VersionedTransaction::try_new(self.message()?, signers)?;
+        let size = tx.serialize().len();
+        if size > PACKET_DATA_SIZE {
+            return Err(BuildError::TooLarge(size));
+        }
+        Ok(tx)
+    }
+}
+
+// ----------------------------------------
+// Demo: `simply_rust demo builder`
+// ----------------------------------------
+
+pub fn demo() -> Result<(), String> {
+    let mut bank = Bank::new();
+    let alice = DemoSigner::new("alice");
+    let bob = DemoSigner::new("bob");
+    let carol = DemoSigner::new("carol");
+    bank.airdrop(&alice.pubkey(), 1_000_000_000)
+        .map_err(|err| err.to_string())?;
+    bank.airdrop(&carol.pubkey(), 1_000_000_000)
+        .map_err(|err| err.to_string())?;
+    let name_of = |key: &Pubkey| {
+        [(&alice, "alice"), (&bob, "bob"), (&carol, "carol")]
+            .iter()
+            .find(|(signer, _)| signer.pubkey() == *key)
+            .map_or_else(|| key.to_string(), |(_, name)| name.to_string())
+    };
+
+    // alice pays bob, and carol pays bob and alice, with alice paying
+    // the fee plus a priority fee
+    let builder = TransactionBuilder::new(&alice.pubkey())
+        .add(system_program::transfer(
+            &alice.pubkey(),
+            &bob.pubkey(),
+            10_000_000,
+        ))
+        .add_all([
+            system_program::transfer(&carol.pubkey(), &bob.pubkey(), 20_000_000),
+            system_program::transfer(&carol.pubkey(), &alice.pubkey(), 5_000_000),
+        ])
+        .compute_unit_limit(1_000)
+        .compute_unit_price(50_000);
+
+    println!("Without a blockhash: {}", error(builder.sign(&[&alice])));
+    let builder = builder.recent_blockhash(bank.last_blockhash());
+    println!("Signed by alice only: {}", error(builder.sign(&[&alice])));
+    println!(
+        "Signed by alice, bob and carol: {}",
+        error(builder.sign(&[&alice, &bob, &carol]))
+    );
+
+    // The order signers are given in does not matter
+    let tx = builder
+        .sign(&[&carol, &alice])
+        .map_err(|err| err.to_string())?;
+    let message = &tx.message;
+    let header = message.header();
+    println!(
+        "\nSigned by carol and alice: {} bytes, {} instructions, header {} / {} / {}",
+        tx.serialize().len(),
+        message.instructions().len(),
+        header.num_required_signatures,
+        header.num_readonly_signed_accounts,
+        header.num_readonly_unsigned_accounts
+    );
+    println!("Each account once, signers first, then writable before readonly:");
+    let VersionedMessage::Legacy(legacy) = message else {
+        return Err("expected a legacy message".into());
+    };
+    for (i, key) in legacy.account_keys.iter().enumerate() {
+        println!(
+            "  #{} {:<44} {:<6} {}",
+            i,
+            name_of(key),
+            if legacy.is_signer(i) { "signer" } else { "" },
+            if legacy.is_writable(i) {
+                "writable"
+            } else {
+                "readonly"
+            }
+        );
+    }
+    let outcome = bank.execute_versioned_transaction(&tx);
+    match outcome.status {
+        Ok(()) => println!("Sent: ok, fee {}", outcome.fee),
+        Err(err) => println!("Sent: {}", err),
+    }
+
+    // 60 payments will not fit in a packet
+    let recipients: Vec<Pubkey> = (0..60).map(|_| Pubkey::new_unique()).collect();
+    let too_big = TransactionBuilder::new(&alice.pubkey())
+        .add_all(
+            recipients
+                .iter()
+                .map(|to| system_program::transfer(&alice.pubkey(), to, 1_000_000)),
+        )
+        .recent_blockhash(bank.last_blockhash());
+    println!(
+        "\nPaying 60 people at once: {}",
+        error(too_big.sign(&[&alice]))
+    );
+
+    // Put the first 40 in a lookup table and each costs a one-byte index
+    // instead of a 32-byte key, in a v0 message. The table only lives
+    // here; `demo alt` creates one on chain.
+    let forty = TransactionBuilder::new(&alice.pubkey())
+        .add_all(
+            recipients[..40]
+                .iter()
+                .map(|to| system_program::transfer(&alice.pubkey(), to, 1_000_000)),
+        )
+        .recent_blockhash(bank.last_blockhash());
+    println!("Paying 40: {}", error(forty.sign(&[&alice])));
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: recipients[..40].to_vec(),
+    };
+    let tx = forty
+        .lookup_table(table)
+        .sign(&[&alice])
+        .map_err(|err| err.to_string())?;
+    println!(
+        "Paying 40 through a lookup table: {} bytes, {} static keys",
+        tx.serialize().len(),
+        tx.message.static_account_keys().len()
+    );
+    Ok(())
+}
+
+fn error(result: Result<VersionedTransaction, BuildError>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        Err(err) => err.to_string(),
+    }
+}